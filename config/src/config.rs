@@ -7,7 +7,7 @@
 //! - `priv_validator_key.rs`: `config::priv_validator_key::PrivValidatorKey`
 
 use alloc::collections::{btree_map, BTreeMap};
-use core::{fmt, str::FromStr};
+use core::{fmt, str::FromStr, time::Duration};
 use std::{
     fs,
     path::{Path, PathBuf},
@@ -123,13 +123,16 @@ impl TendermintConfig {
         Self::parse_toml(toml_string)
     }
 
-    /// Load `genesis.json` file from the configured location
+    /// Load `genesis.json` file from the configured location, checking its
+    /// contents with [`Genesis::validate`]
     pub fn load_genesis_file(&self, home: impl AsRef<Path>) -> Result<Genesis, Error> {
         let path = home.as_ref().join(&self.genesis_file);
         let genesis_json = fs::read_to_string(&path)
             .map_err(|e| Error::file_io(format!("{}", path.display()), e))?;
 
-        let res = serde_json::from_str(genesis_json.as_ref()).map_err(Error::serde_json)?;
+        let res: Genesis =
+            serde_json::from_str(genesis_json.as_ref()).map_err(Error::serde_json)?;
+        res.validate().map_err(Error::tendermint)?;
 
         Ok(res)
     }
@@ -681,6 +684,26 @@ pub struct StatesyncConfig {
     /// Temporary directory for state sync snapshot chunks, defaults to the OS tempdir (typically
     /// /tmp). Will create a new, randomly named directory within, and remove it when done.
     pub temp_dir: String,
+
+    /// The timeout duration before re-requesting a chunk, possibly from a different peer.
+    #[serde(default = "default_chunk_request_timeout")]
+    pub chunk_request_timeout: Timeout,
+
+    /// The number of concurrent chunk fetchers to run.
+    #[serde(
+        default = "default_chunk_fetchers",
+        serialize_with = "serialize_number_as_string",
+        deserialize_with = "deserialize_number_or_string"
+    )]
+    pub chunk_fetchers: u32,
+}
+
+fn default_chunk_request_timeout() -> Timeout {
+    Duration::from_secs(10).into()
+}
+
+fn default_chunk_fetchers() -> u32 {
+    4
 }
 
 /// fastsync configuration options
@@ -733,6 +756,35 @@ where
     }
 }
 
+/// Deserialize a number which may be quoted, as CometBFT writes some numbers as strings
+fn deserialize_number_or_string<'de, D, T, E>(deserializer: D) -> Result<T, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: FromStr<Err = E> + Deserialize<'de>,
+    E: fmt::Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString<T> {
+        Number(T),
+        String(String),
+    }
+
+    match NumberOrString::<T>::deserialize(deserializer)? {
+        NumberOrString::Number(n) => Ok(n),
+        NumberOrString::String(s) => s.parse().map_err(|e| D::Error::custom(format!("{e}"))),
+    }
+}
+
+/// Serialize a number as a string, as CometBFT does
+fn serialize_number_as_string<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+    T: ToString,
+{
+    value.to_string().serialize(serializer)
+}
+
 /// Deserialize a comma separated list of types that impl `FromStr` as a `Vec`
 fn deserialize_comma_separated_list<'de, D, T, E>(deserializer: D) -> Result<Vec<T>, D::Error>
 where
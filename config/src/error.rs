@@ -1,7 +1,9 @@
 use std::io::Error as IoError;
 
 use flex_error::{define_error, DisplayOnly};
-use tendermint::Error as TendermintError;
+use tendermint::{block, vote, Error as TendermintError};
+
+use crate::SignStep;
 
 define_error! {
     #[derive(Debug, Clone, PartialEq, Eq)]
@@ -34,5 +36,40 @@ define_error! {
         Tendermint
             [ TendermintError ]
             |_| { format_args!("tendermint error") },
+
+        SignRegression
+            {
+                last_height: block::Height,
+                last_round: block::Round,
+                last_step: SignStep,
+                height: block::Height,
+                round: block::Round,
+                step: SignStep,
+            }
+            | e | {
+                format_args!("refusing to sign at height {}, round {}, step {:?}: already signed at height {}, round {}, step {:?}",
+                    e.height, e.round, e.step, e.last_height, e.last_round, e.last_step)
+            },
+
+        MissingSignBytes
+            { height: block::Height, round: block::Round, step: SignStep }
+            | e | {
+                format_args!("no sign bytes for the message signed at height {}, round {}, step {:?}",
+                    e.height, e.round, e.step)
+            },
+
+        ConflictingSignData
+            { height: block::Height, round: block::Round, step: SignStep }
+            | e | {
+                format_args!("conflicting data with the message signed at height {}, round {}, step {:?}",
+                    e.height, e.round, e.step)
+            },
+
+        UnexpectedVoteExtension
+            { vote_type: vote::Type }
+            | e | {
+                format_args!("unexpected vote extension in {}: only non-nil precommits have extensions",
+                    e.vote_type)
+            },
     }
 }
@@ -0,0 +1,172 @@
+//! File-based private validator
+
+use std::path::{Path, PathBuf};
+
+use tendermint::{
+    account, block, chain,
+    privval::{RemoteSignerError, Signer},
+    public_key::{Algorithm, PublicKey},
+    vote, Proposal, Vote,
+};
+
+use crate::{error::Error, LastSignState, PrivValidatorKey};
+
+/// Private validator signing with a key read from `priv_validator_key.json`,
+/// and protected against double signing by the state of
+/// `priv_validator_state.json`, as the `FilePV` of CometBFT
+///
+/// Every signature goes through the [`LastSignState`], which is saved before
+/// the signed message is returned.
+pub struct FilePrivValidator {
+    key: PrivValidatorKey,
+    state: LastSignState,
+    state_path: PathBuf,
+}
+
+impl FilePrivValidator {
+    /// Generate a new validator key with the given algorithm, and write it to
+    /// `key_path` along with an empty sign state at `state_path`
+    pub fn generate<K, S>(algorithm: Algorithm, key_path: &K, state_path: &S) -> Result<Self, Error>
+    where
+        K: AsRef<Path>,
+        S: AsRef<Path>,
+    {
+        let key = PrivValidatorKey::generate(algorithm)?;
+        key.save_json_file(key_path)?;
+
+        let state = LastSignState::default();
+        state.save_json_file(state_path)?;
+
+        Ok(Self {
+            key,
+            state,
+            state_path: state_path.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Load the validator key at `key_path` and the sign state at `state_path`
+    pub fn load<K, S>(key_path: &K, state_path: &S) -> Result<Self, Error>
+    where
+        K: AsRef<Path>,
+        S: AsRef<Path>,
+    {
+        Ok(Self {
+            key: PrivValidatorKey::load_json_file(key_path)?,
+            state: LastSignState::load_json_file(state_path)?,
+            state_path: state_path.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Load the validator key and sign state, or generate them with the given
+    /// algorithm if the key file does not exist
+    pub fn load_or_generate<K, S>(
+        algorithm: Algorithm,
+        key_path: &K,
+        state_path: &S,
+    ) -> Result<Self, Error>
+    where
+        K: AsRef<Path>,
+        S: AsRef<Path>,
+    {
+        if key_path.as_ref().exists() {
+            Self::load(key_path, state_path)
+        } else {
+            Self::generate(algorithm, key_path, state_path)
+        }
+    }
+
+    /// Get the validator key
+    pub fn key(&self) -> &PrivValidatorKey {
+        &self.key
+    }
+
+    /// Get the address of the validator
+    pub fn address(&self) -> account::Id {
+        self.key.address
+    }
+
+    /// Get the public key of the validator
+    pub fn public_key(&self) -> PublicKey {
+        self.key.pub_key
+    }
+
+    /// Get the last sign state
+    pub fn last_sign_state(&self) -> &LastSignState {
+        &self.state
+    }
+
+    /// Sign a vote for the given chain, and its extension if it is a non-nil
+    /// precommit
+    ///
+    /// Vote extensions may differ between two calls for the same vote, so
+    /// they are always signed again. Extensions of other votes are refused.
+    pub fn sign_vote(&mut self, chain_id: &chain::Id, vote: &mut Vote) -> Result<(), Error> {
+        let extension_signature =
+            if vote.vote_type == vote::Type::Precommit && !is_nil(vote.block_id) {
+                let sign_bytes = vote.clone().into_extension_signable_vec(chain_id.clone());
+                Some(self.key.sign(&sign_bytes)?)
+            } else if !vote.extension.is_empty() {
+                return Err(Error::unexpected_vote_extension(vote.vote_type));
+            } else {
+                None
+            };
+
+        let key = &self.key;
+        self.state
+            .sign_vote(chain_id, vote, |sign_bytes| key.sign(sign_bytes))?;
+        self.state.save_json_file(&self.state_path)?;
+
+        vote.extension_signature = extension_signature;
+        Ok(())
+    }
+
+    /// Sign a proposal for the given chain
+    pub fn sign_proposal(
+        &mut self,
+        chain_id: &chain::Id,
+        proposal: &mut Proposal,
+    ) -> Result<(), Error> {
+        let key = &self.key;
+        self.state
+            .sign_proposal(chain_id, proposal, |sign_bytes| key.sign(sign_bytes))?;
+        self.state.save_json_file(&self.state_path)
+    }
+}
+
+impl Signer for FilePrivValidator {
+    fn public_key(&self, _chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError> {
+        Ok(self.public_key())
+    }
+
+    fn sign_vote(
+        &mut self,
+        chain_id: &chain::Id,
+        mut vote: Vote,
+    ) -> Result<Vote, RemoteSignerError> {
+        FilePrivValidator::sign_vote(self, chain_id, &mut vote).map_err(remote_signer_error)?;
+        Ok(vote)
+    }
+
+    fn sign_proposal(
+        &mut self,
+        chain_id: &chain::Id,
+        mut proposal: Proposal,
+    ) -> Result<Proposal, RemoteSignerError> {
+        FilePrivValidator::sign_proposal(self, chain_id, &mut proposal)
+            .map_err(remote_signer_error)?;
+        Ok(proposal)
+    }
+}
+
+/// Whether a block ID is the one of nil votes
+fn is_nil(block_id: Option<block::Id>) -> bool {
+    block_id.is_none_or(|id| id == block::Id::default())
+}
+
+fn remote_signer_error(e: Error) -> RemoteSignerError {
+    // CometBFT reports every signing error with code 0.
+    RemoteSignerError {
+        code: 0,
+        description: e.to_string(),
+    }
+}
@@ -20,11 +20,15 @@ pub mod net;
 
 mod config;
 mod error;
+mod file_priv_validator;
 mod node_key;
 mod prelude;
 mod priv_validator_key;
+mod priv_validator_state;
 
 pub use config::*;
 pub use error::*;
+pub use file_priv_validator::FilePrivValidator;
 pub use node_key::NodeKey;
 pub use priv_validator_key::PrivValidatorKey;
+pub use priv_validator_state::{LastSignState, SignStep};
@@ -2,14 +2,16 @@
 
 use std::{fs, path::Path};
 
+use rand_core::OsRng;
 use serde::{Deserialize, Serialize};
 use tendermint::{
     account,
     private_key::PrivateKey,
-    public_key::{PublicKey, TendermintKey},
+    public_key::{Algorithm, PublicKey, TendermintKey},
+    Error as TendermintError, Signature,
 };
 
-use crate::{error::Error, prelude::*};
+use crate::{error::Error, prelude::*, priv_validator_state::write_private_file};
 
 /// Validator private key
 #[derive(Serialize, Deserialize)] // JSON custom serialization for priv_validator_key.json
@@ -25,6 +27,38 @@ pub struct PrivValidatorKey {
 }
 
 impl PrivValidatorKey {
+    /// Create a validator key from its private key
+    pub fn new(priv_key: PrivateKey) -> Result<Self, Error> {
+        let pub_key = priv_key.public_key();
+        TendermintKey::new_consensus_key(pub_key).map_err(Error::tendermint)?;
+
+        Ok(Self {
+            address: account::Id::from(pub_key),
+            pub_key,
+            priv_key,
+        })
+    }
+
+    /// Generate a new validator key with the given algorithm
+    pub fn generate(algorithm: Algorithm) -> Result<Self, Error> {
+        let priv_key = match algorithm {
+            Algorithm::Ed25519 => ed25519_consensus::SigningKey::new(OsRng).into(),
+            #[cfg(feature = "secp256k1")]
+            Algorithm::Secp256k1 => {
+                PrivateKey::Secp256k1(k256::ecdsa::SigningKey::random(&mut OsRng))
+            },
+            #[cfg(not(feature = "secp256k1"))]
+            Algorithm::Secp256k1 => {
+                return Err(Error::tendermint(TendermintError::unsupported_key_type()))
+            },
+            #[cfg(feature = "bls12381")]
+            Algorithm::Bls12381 => {
+                PrivateKey::Bls12381(tendermint::private_key::Bls12381::generate(OsRng))
+            },
+        };
+        Self::new(priv_key)
+    }
+
     /// Parse `priv_validator_key.json`
     pub fn parse_json<T: AsRef<str>>(json_string: T) -> Result<Self, Error> {
         let result =
@@ -48,6 +82,37 @@ impl PrivValidatorKey {
         Self::parse_json(json_string)
     }
 
+    /// Save `priv_validator_key.json` to a file, only readable by its owner
+    pub fn save_json_file<P>(&self, path: &P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let json_string = serde_json::to_string_pretty(self).map_err(Error::serde_json)?;
+        write_private_file(path.as_ref(), json_string)
+    }
+
+    /// Sign a message with this validator private key
+    pub fn sign(&self, msg: &[u8]) -> Result<Signature, Error> {
+        #[allow(unreachable_patterns)]
+        match &self.priv_key {
+            PrivateKey::Ed25519(signing_key) => {
+                let signing_key = ed25519_consensus::SigningKey::try_from(signing_key.clone())
+                    .map_err(Error::tendermint)?;
+                Ok(signing_key.sign(msg).into())
+            },
+            #[cfg(feature = "secp256k1")]
+            PrivateKey::Secp256k1(signing_key) => {
+                use k256::ecdsa::signature::Signer;
+
+                let signature: k256::ecdsa::Signature = signing_key.sign(msg);
+                Ok(signature.into())
+            },
+            #[cfg(feature = "bls12381")]
+            PrivateKey::Bls12381(signing_key) => Ok(signing_key.sign(msg)),
+            _ => Err(Error::tendermint(TendermintError::unsupported_key_type())),
+        }
+    }
+
     /// Get the consensus public key for this validator private key
     pub fn consensus_pubkey(&self) -> TendermintKey {
         TendermintKey::new_consensus_key(self.priv_key.public_key()).unwrap()
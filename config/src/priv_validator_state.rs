@@ -0,0 +1,344 @@
+//! Validator sign state, protecting against double signing
+
+use std::{cmp::Ordering, fs, io::Write, path::Path};
+
+use serde::{Deserialize, Serialize};
+use tendermint::{
+    block, chain,
+    proposal::CanonicalProposal,
+    serializers,
+    vote::{self, CanonicalVote},
+    Proposal, Signature, Time, Vote,
+};
+use tendermint_proto::{
+    v0_38::types::{CanonicalProposal as RawCanonicalProposal, CanonicalVote as RawCanonicalVote},
+    Protobuf,
+};
+
+use crate::{error::Error, prelude::*};
+
+/// Step of a round at which a validator signs
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SignStep {
+    /// Nothing was signed yet
+    #[default]
+    None = 0,
+
+    /// Proposal
+    Propose = 1,
+
+    /// Prevote
+    Prevote = 2,
+
+    /// Precommit
+    Precommit = 3,
+}
+
+impl From<vote::Type> for SignStep {
+    fn from(vote_type: vote::Type) -> Self {
+        match vote_type {
+            vote::Type::Prevote => Self::Prevote,
+            vote::Type::Precommit => Self::Precommit,
+        }
+    }
+}
+
+impl TryFrom<i8> for SignStep {
+    type Error = Error;
+
+    fn try_from(step: i8) -> Result<Self, Self::Error> {
+        match step {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Propose),
+            2 => Ok(Self::Prevote),
+            3 => Ok(Self::Precommit),
+            _ => Err(Error::parse(format!("invalid sign step: {step}"))),
+        }
+    }
+}
+
+impl From<SignStep> for i8 {
+    fn from(step: SignStep) -> Self {
+        step as i8
+    }
+}
+
+/// Last height, round and step a validator signed at, persisted to
+/// `priv_validator_state.json`
+///
+/// Before signing a vote or proposal, it is checked against this state so that
+/// no conflicting message is signed at the same height, round and step, and
+/// nothing is signed for an earlier one. A message identical to the last one
+/// signed, or differing from it only by its timestamp, gets the last signature
+/// again, as with the `FilePV` of CometBFT.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawLastSignState", into = "RawLastSignState")]
+pub struct LastSignState {
+    /// Height
+    pub height: block::Height,
+
+    /// Round
+    pub round: block::Round,
+
+    /// Step
+    pub step: SignStep,
+
+    /// Last signature
+    pub signature: Option<Signature>,
+
+    /// Last signed bytes
+    pub sign_bytes: Option<Vec<u8>>,
+}
+
+impl Default for LastSignState {
+    fn default() -> Self {
+        Self {
+            height: block::Height::from(0_u32),
+            round: block::Round::default(),
+            step: SignStep::None,
+            signature: None,
+            sign_bytes: None,
+        }
+    }
+}
+
+impl LastSignState {
+    /// Parse `priv_validator_state.json`
+    pub fn parse_json<T: AsRef<str>>(json_string: T) -> Result<Self, Error> {
+        serde_json::from_str(json_string.as_ref()).map_err(Error::serde_json)
+    }
+
+    /// Load `priv_validator_state.json` from a file
+    pub fn load_json_file<P>(path: &P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let json_string = fs::read_to_string(path)
+            .map_err(|e| Error::file_io(format!("{}", path.as_ref().display()), e))?;
+
+        Self::parse_json(json_string)
+    }
+
+    /// Save `priv_validator_state.json` to a file
+    ///
+    /// The state is written to a temporary file first, which then replaces
+    /// the file, so that an interrupted save never leaves a truncated state.
+    /// As with CometBFT, the file is only readable by its owner.
+    pub fn save_json_file<P>(&self, path: &P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let json_string = serde_json::to_string_pretty(self).map_err(Error::serde_json)?;
+        write_private_file(path.as_ref(), json_string)
+    }
+
+    /// Check that a message may be signed at the given height, round and step
+    ///
+    /// Returns whether they are the ones of the last signed message, in which
+    /// case the message may only be signed if it matches the last one.
+    pub fn check_hrs(
+        &self,
+        height: block::Height,
+        round: block::Round,
+        step: SignStep,
+    ) -> Result<bool, Error> {
+        let regression =
+            || Error::sign_regression(self.height, self.round, self.step, height, round, step);
+        match (self.height, self.round, self.step).cmp(&(height, round, step)) {
+            Ordering::Greater => Err(regression()),
+            Ordering::Less => Ok(false),
+            Ordering::Equal => match (&self.sign_bytes, &self.signature) {
+                (Some(_), Some(_)) => Ok(true),
+                _ => Err(Error::missing_sign_bytes(height, round, step)),
+            },
+        }
+    }
+
+    /// Sign a vote for the given chain with `sign`, unless it conflicts with
+    /// the last signed message
+    ///
+    /// The vote extension is not covered. The state is updated in memory, and
+    /// must be saved before the signature is released.
+    pub fn sign_vote<F>(
+        &mut self,
+        chain_id: &chain::Id,
+        vote: &mut Vote,
+        sign: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&[u8]) -> Result<Signature, Error>,
+    {
+        let step = SignStep::from(vote.vote_type);
+        if self.check_hrs(vote.height, vote.round, step)? {
+            let canonical = CanonicalVote::new(vote.clone(), chain_id.clone());
+            vote.timestamp = self
+                .sign_bytes
+                .as_deref()
+                .and_then(|bytes| last_vote_timestamp(bytes, canonical))
+                .ok_or_else(|| self.conflicting())?;
+            vote.signature.clone_from(&self.signature);
+            return Ok(());
+        }
+
+        let sign_bytes = vote.clone().into_signable_vec(chain_id.clone());
+        let signature = sign(&sign_bytes)?;
+        vote.signature = Some(signature.clone());
+        self.update(vote.height, vote.round, step, sign_bytes, signature);
+        Ok(())
+    }
+
+    /// Sign a proposal for the given chain with `sign`, unless it conflicts
+    /// with the last signed message
+    ///
+    /// The state is updated in memory, and must be saved before the signature
+    /// is released.
+    pub fn sign_proposal<F>(
+        &mut self,
+        chain_id: &chain::Id,
+        proposal: &mut Proposal,
+        sign: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&[u8]) -> Result<Signature, Error>,
+    {
+        if self.check_hrs(proposal.height, proposal.round, SignStep::Propose)? {
+            let canonical = CanonicalProposal::new(proposal.clone(), chain_id.clone());
+            proposal.timestamp = self
+                .sign_bytes
+                .as_deref()
+                .and_then(|bytes| last_proposal_timestamp(bytes, canonical))
+                .ok_or_else(|| self.conflicting())?;
+            proposal.signature.clone_from(&self.signature);
+            return Ok(());
+        }
+
+        let sign_bytes = proposal.clone().into_signable_vec(chain_id.clone());
+        let signature = sign(&sign_bytes)?;
+        proposal.signature = Some(signature.clone());
+        self.update(
+            proposal.height,
+            proposal.round,
+            SignStep::Propose,
+            sign_bytes,
+            signature,
+        );
+        Ok(())
+    }
+
+    fn conflicting(&self) -> Error {
+        Error::conflicting_sign_data(self.height, self.round, self.step)
+    }
+
+    fn update(
+        &mut self,
+        height: block::Height,
+        round: block::Round,
+        step: SignStep,
+        sign_bytes: Vec<u8>,
+        signature: Signature,
+    ) {
+        self.height = height;
+        self.round = round;
+        self.step = step;
+        self.signature = Some(signature);
+        self.sign_bytes = Some(sign_bytes);
+    }
+}
+
+/// Write a file only readable by its owner, through a temporary file replacing
+/// it, so that an interrupted write never leaves a truncated file
+pub(crate) fn write_private_file<C: AsRef<[u8]>>(path: &Path, contents: C) -> Result<(), Error> {
+    let file_io = |e| Error::file_io(format!("{}", path.display()), e);
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&tmp).map_err(file_io)?;
+    file.write_all(contents.as_ref()).map_err(file_io)?;
+    file.sync_all().map_err(file_io)?;
+    drop(file);
+
+    fs::rename(&tmp, path).map_err(file_io)
+}
+
+/// Timestamp of the last signed vote, if `vote` only differs from it by its
+/// timestamp
+fn last_vote_timestamp(last_sign_bytes: &[u8], vote: CanonicalVote) -> Option<Option<Time>> {
+    let last: CanonicalVote =
+        Protobuf::<RawCanonicalVote>::decode_length_delimited_vec(last_sign_bytes).ok()?;
+    let timestamp = last.timestamp;
+    (CanonicalVote {
+        timestamp: vote.timestamp,
+        ..last
+    } == vote)
+        .then_some(timestamp)
+}
+
+/// Timestamp of the last signed proposal, if `proposal` only differs from it
+/// by its timestamp
+fn last_proposal_timestamp(
+    last_sign_bytes: &[u8],
+    proposal: CanonicalProposal,
+) -> Option<Option<Time>> {
+    let last: CanonicalProposal =
+        Protobuf::<RawCanonicalProposal>::decode_length_delimited_vec(last_sign_bytes).ok()?;
+    let timestamp = last.timestamp;
+    (CanonicalProposal {
+        timestamp: proposal.timestamp,
+        ..last
+    } == proposal)
+        .then_some(timestamp)
+}
+
+/// JSON encoding of [`LastSignState`], as in `priv_validator_state.json`
+#[derive(Serialize, Deserialize)]
+struct RawLastSignState {
+    height: block::Height,
+    round: i32,
+    step: i8,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "serializers::bytes::base64string"
+    )]
+    signature: Vec<u8>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "serializers::bytes::hexstring"
+    )]
+    signbytes: Vec<u8>,
+}
+
+impl TryFrom<RawLastSignState> for LastSignState {
+    type Error = Error;
+
+    fn try_from(raw: RawLastSignState) -> Result<Self, Self::Error> {
+        Ok(Self {
+            height: raw.height,
+            round: raw.round.try_into().map_err(Error::tendermint)?,
+            step: raw.step.try_into()?,
+            signature: Signature::new(raw.signature).map_err(Error::tendermint)?,
+            sign_bytes: Some(raw.signbytes).filter(|bytes| !bytes.is_empty()),
+        })
+    }
+}
+
+impl From<LastSignState> for RawLastSignState {
+    fn from(state: LastSignState) -> Self {
+        Self {
+            height: state.height,
+            // Rounds are at most `i32::MAX`
+            round: state.round.value() as i32,
+            step: state.step.into(),
+            signature: state
+                .signature
+                .map(Signature::into_bytes)
+                .unwrap_or_default(),
+            signbytes: state.sign_bytes.unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Tests for signing with a `FilePrivValidator`.
+
+use std::{fs, path::PathBuf};
+
+use tendermint::{
+    block::{self, parts},
+    chain,
+    crypto::{default::signature::Verifier, signature::Verifier as _},
+    hash::Hash,
+    proposal,
+    public_key::Algorithm,
+    vote::{self, SignedVote, ValidatorIndex},
+    Proposal, Time, Vote,
+};
+use tendermint_config::{FilePrivValidator, LastSignState, PrivValidatorKey, SignStep};
+
+/// Paths of the key and state files, in a fresh temporary directory
+fn file_paths(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("file-pv-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    (
+        dir.join("priv_validator_key.json"),
+        dir.join("priv_validator_state.json"),
+    )
+}
+
+fn chain_id() -> chain::Id {
+    "test-chain".parse().unwrap()
+}
+
+fn block_id() -> Option<block::Id> {
+    Some(block::Id {
+        hash: Hash::Sha256([7; 32]),
+        part_set_header: parts::Header::new(1, Hash::Sha256([8; 32])).unwrap(),
+    })
+}
+
+fn timestamp(secs: i64) -> Option<Time> {
+    Some(Time::from_unix_timestamp(1_700_000_000 + secs, 0).unwrap())
+}
+
+fn precommit(pv: &FilePrivValidator, height: u32, extension: &[u8]) -> Vote {
+    Vote {
+        vote_type: vote::Type::Precommit,
+        height: height.into(),
+        round: 0_u8.into(),
+        block_id: block_id(),
+        timestamp: timestamp(0),
+        validator_address: pv.address(),
+        validator_index: ValidatorIndex::try_from(0_u32).unwrap(),
+        signature: None,
+        extension: extension.to_vec(),
+        extension_signature: None,
+    }
+}
+
+/// Sign votes and a proposal, checking their signatures and the saved state
+fn check_signing(algorithm: Algorithm) {
+    let (key_path, state_path) = file_paths(algorithm.as_str());
+    let mut pv = FilePrivValidator::generate(algorithm, &key_path, &state_path).unwrap();
+    let public_key = pv.public_key();
+
+    let key = PrivValidatorKey::load_json_file(&key_path).unwrap();
+    assert_eq!(key.address, pv.address());
+    assert_eq!(key.pub_key, public_key);
+    assert_eq!(
+        LastSignState::load_json_file(&state_path).unwrap(),
+        LastSignState::default()
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&key_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let mut vote = precommit(&pv, 5, b"extension");
+    pv.sign_vote(&chain_id(), &mut vote).unwrap();
+    let signed = SignedVote::from_vote(vote.clone(), chain_id()).unwrap();
+    Verifier::verify(public_key, &signed.sign_bytes(), signed.signature()).unwrap();
+    let extension_sign_bytes = vote.clone().into_extension_signable_vec(chain_id());
+    Verifier::verify(
+        public_key,
+        &extension_sign_bytes,
+        vote.extension_signature.as_ref().unwrap(),
+    )
+    .unwrap();
+
+    // The state is saved, and protects the validator once loaded again.
+    let mut pv = FilePrivValidator::load(&key_path, &state_path).unwrap();
+    let state = LastSignState::load_json_file(&state_path).unwrap();
+    assert_eq!(&state, pv.last_sign_state());
+    assert_eq!(state.height.value(), 5);
+    assert_eq!(state.step, SignStep::Precommit);
+
+    let mut resigned = Vote {
+        timestamp: timestamp(3),
+        ..precommit(&pv, 5, b"other extension")
+    };
+    pv.sign_vote(&chain_id(), &mut resigned).unwrap();
+    assert_eq!(resigned.signature, vote.signature);
+    assert_eq!(resigned.timestamp, vote.timestamp);
+    let extension_sign_bytes = resigned.clone().into_extension_signable_vec(chain_id());
+    Verifier::verify(
+        public_key,
+        &extension_sign_bytes,
+        resigned.extension_signature.as_ref().unwrap(),
+    )
+    .unwrap();
+
+    let mut conflicting = Vote {
+        block_id: None,
+        ..precommit(&pv, 5, b"")
+    };
+    assert!(pv.sign_vote(&chain_id(), &mut conflicting).is_err());
+    assert!(conflicting.signature.is_none());
+
+    // Only non-nil precommits have extensions.
+    let mut prevote = Vote {
+        vote_type: vote::Type::Prevote,
+        ..precommit(&pv, 6, b"extension")
+    };
+    assert!(pv.sign_vote(&chain_id(), &mut prevote).is_err());
+    prevote.extension.clear();
+    pv.sign_vote(&chain_id(), &mut prevote).unwrap();
+    assert!(prevote.extension_signature.is_none());
+
+    let mut proposal = Proposal {
+        msg_type: proposal::Type::Proposal,
+        height: 7_u32.into(),
+        round: 1_u8.into(),
+        pol_round: None,
+        block_id: block_id(),
+        timestamp: timestamp(0),
+        signature: None,
+    };
+    pv.sign_proposal(&chain_id(), &mut proposal).unwrap();
+    let sign_bytes = proposal.clone().into_signable_vec(chain_id());
+    Verifier::verify(
+        public_key,
+        &sign_bytes,
+        proposal.signature.as_ref().unwrap(),
+    )
+    .unwrap();
+    let state = LastSignState::load_json_file(&state_path).unwrap();
+    assert_eq!(state.height.value(), 7);
+    assert_eq!(state.step, SignStep::Propose);
+
+    // Earlier heights are refused.
+    let mut earlier = precommit(&pv, 6, b"");
+    assert!(pv.sign_vote(&chain_id(), &mut earlier).is_err());
+
+    fs::remove_dir_all(key_path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn file_priv_validator_ed25519() {
+    check_signing(Algorithm::Ed25519);
+}
+
+#[test]
+#[cfg(feature = "secp256k1")]
+fn file_priv_validator_secp256k1() {
+    check_signing(Algorithm::Secp256k1);
+}
+
+#[test]
+#[cfg(feature = "bls12381")]
+fn file_priv_validator_bls12381() {
+    check_signing(Algorithm::Bls12381);
+}
+
+#[test]
+fn file_priv_validator_load_or_generate() {
+    let (key_path, state_path) = file_paths("load-or-generate");
+    let generated =
+        FilePrivValidator::load_or_generate(Algorithm::Ed25519, &key_path, &state_path).unwrap();
+    let loaded =
+        FilePrivValidator::load_or_generate(Algorithm::Ed25519, &key_path, &state_path).unwrap();
+    assert_eq!(loaded.address(), generated.address());
+    fs::remove_dir_all(key_path.parent().unwrap()).unwrap();
+}
@@ -200,6 +200,36 @@ fn config_toml_parser() {
     assert_eq!(instrumentation.prometheus_listen_addr, ":26660");
     assert_eq!(instrumentation.max_open_connections, 3);
     assert_eq!(instrumentation.namespace, "tendermint");
+
+    // statesync configuration options
+
+    let statesync = &config.statesync;
+    assert!(!statesync.enable);
+    assert!(statesync.rpc_servers.is_empty());
+    assert_eq!(statesync.trust_height, 0);
+    assert_eq!(*statesync.discovery_time, Duration::from_secs(15));
+    assert_eq!(*statesync.chunk_request_timeout, Duration::from_secs(10));
+    assert_eq!(statesync.chunk_fetchers, 4);
+}
+
+/// Parse the chunk options of the statesync section, which CometBFT writes
+/// with a quoted number of chunk fetchers
+#[test]
+fn statesync_chunk_options_parser() {
+    for chunk_fetchers in ["\"2\"", "2"] {
+        let config_toml = read_fixture("config.toml").replace(
+            "temp_dir = \"\"",
+            &format!(
+                "temp_dir = \"\"\nchunk_request_timeout = \"1s\"\nchunk_fetchers = {chunk_fetchers}"
+            ),
+        );
+        let config = TendermintConfig::parse_toml(config_toml).unwrap();
+        assert_eq!(
+            *config.statesync.chunk_request_timeout,
+            Duration::from_secs(1)
+        );
+        assert_eq!(config.statesync.chunk_fetchers, 2);
+    }
 }
 
 /// Parse an example `node_key.json` file to a `NodeKey` struct
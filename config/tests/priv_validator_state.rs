@@ -0,0 +1,255 @@
+//! Tests for the double-sign protection of `LastSignState`.
+
+use std::{fs, path::PathBuf};
+
+use proptest::prelude::*;
+use tendermint::{
+    account,
+    block::{self, parts},
+    hash::Hash,
+    proposal,
+    vote::{self, ValidatorIndex},
+    Proposal, Signature, Time, Vote,
+};
+use tendermint_config::{LastSignState, SignStep};
+
+/// Message to sign: a vote, or a proposal at the `Propose` step
+#[derive(Clone, Debug)]
+struct Message {
+    height: u32,
+    round: u8,
+    step: SignStep,
+    block: Option<u8>,
+    timestamp: i64,
+}
+
+impl Message {
+    fn hrs(&self) -> (u32, u8, SignStep) {
+        (self.height, self.round, self.step)
+    }
+
+    fn block_id(&self) -> Option<block::Id> {
+        self.block.map(|b| block::Id {
+            hash: Hash::Sha256([b; 32]),
+            part_set_header: parts::Header::new(1, Hash::Sha256([b; 32])).unwrap(),
+        })
+    }
+
+    fn timestamp(&self) -> Option<Time> {
+        Some(Time::from_unix_timestamp(1_700_000_000 + self.timestamp, 0).unwrap())
+    }
+
+    /// Sign the message, returning its signature and timestamp
+    fn sign(
+        &self,
+        state: &mut LastSignState,
+        signature: &Signature,
+    ) -> Result<(Option<Signature>, Option<Time>), tendermint_config::Error> {
+        let chain_id = "test-chain".parse().unwrap();
+        let sign = |_: &[u8]| Ok(signature.clone());
+        if let Some(vote_type) = self.vote_type() {
+            let mut vote = Vote {
+                vote_type,
+                height: self.height.into(),
+                round: self.round.into(),
+                block_id: self.block_id(),
+                timestamp: self.timestamp(),
+                validator_address: account::Id::new([1; 20]),
+                validator_index: ValidatorIndex::try_from(0_u32).unwrap(),
+                signature: None,
+                extension: Vec::new(),
+                extension_signature: None,
+            };
+            state.sign_vote(&chain_id, &mut vote, sign)?;
+            Ok((vote.signature, vote.timestamp))
+        } else {
+            let mut proposal = Proposal {
+                msg_type: proposal::Type::Proposal,
+                height: self.height.into(),
+                round: self.round.into(),
+                pol_round: None,
+                block_id: self.block_id(),
+                timestamp: self.timestamp(),
+                signature: None,
+            };
+            state.sign_proposal(&chain_id, &mut proposal, sign)?;
+            Ok((proposal.signature, proposal.timestamp))
+        }
+    }
+
+    fn vote_type(&self) -> Option<vote::Type> {
+        match self.step {
+            SignStep::Prevote => Some(vote::Type::Prevote),
+            SignStep::Precommit => Some(vote::Type::Precommit),
+            _ => None,
+        }
+    }
+}
+
+fn arb_step() -> impl Strategy<Value = SignStep> {
+    prop_oneof![
+        Just(SignStep::Propose),
+        Just(SignStep::Prevote),
+        Just(SignStep::Precommit),
+    ]
+}
+
+fn arb_message() -> impl Strategy<Value = Message> {
+    (
+        1..4_u32,
+        0..3_u8,
+        arb_step(),
+        prop::option::of(0..2_u8),
+        0..3_i64,
+    )
+        .prop_map(|(height, round, step, block, timestamp)| Message {
+            height,
+            round,
+            step,
+            block,
+            timestamp,
+        })
+}
+
+proptest! {
+    #[test]
+    fn json_roundtrip(
+        height in 0..i64::MAX,
+        round in 0..i32::MAX,
+        step in 0..4_i8,
+        signed in prop::option::of((prop::array::uniform32(any::<u8>()), prop::collection::vec(any::<u8>(), 1..200))),
+    ) {
+        let (signature, sign_bytes) = match signed {
+            Some((sig, bytes)) => (Signature::new([sig, sig].concat()).unwrap(), Some(bytes)),
+            None => (None, None),
+        };
+        let state = LastSignState {
+            height: height.try_into().unwrap(),
+            round: round.try_into().unwrap(),
+            step: step.try_into().unwrap(),
+            signature,
+            sign_bytes,
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        prop_assert_eq!(LastSignState::parse_json(json).unwrap(), state);
+    }
+
+    #[test]
+    fn check_hrs_follows_hrs_order(
+        last in (0..3_u32, 0..3_u8, arb_step()),
+        next in (0..3_u32, 0..3_u8, arb_step()),
+    ) {
+        let state = LastSignState {
+            height: last.0.into(),
+            round: last.1.into(),
+            step: last.2,
+            signature: Signature::new([1; 64]).unwrap(),
+            sign_bytes: Some(vec![1]),
+        };
+        let result = state.check_hrs(next.0.into(), next.1.into(), next.2);
+        match last.cmp(&next) {
+            core::cmp::Ordering::Less => prop_assert_eq!(result.unwrap(), false),
+            core::cmp::Ordering::Equal => prop_assert_eq!(result.unwrap(), true),
+            core::cmp::Ordering::Greater => prop_assert!(result.is_err()),
+        }
+    }
+
+    #[test]
+    fn never_signs_conflicting_messages(messages in prop::collection::vec(arb_message(), 1..30)) {
+        let mut state = LastSignState::default();
+        let mut last: Option<(Message, Signature)> = None;
+
+        for (i, message) in messages.into_iter().enumerate() {
+            let fresh = Signature::new([i as u8 + 1; 64]).unwrap().unwrap();
+            let result = message.sign(&mut state, &fresh);
+
+            match &last {
+                // A new height, round or step is signed.
+                None => prop_assert_eq!(result.unwrap().0, Some(fresh.clone())),
+                Some((prev, _)) if prev.hrs() < message.hrs() => {
+                    prop_assert_eq!(result.unwrap().0, Some(fresh.clone()));
+                },
+                Some((prev, _)) if prev.hrs() > message.hrs() => {
+                    prop_assert!(result.is_err());
+                    continue;
+                },
+                // The same message, but for its timestamp, gets the last signature and
+                // timestamp again, and anything else is refused.
+                Some((prev, signature)) => {
+                    if prev.block == message.block {
+                        let (sig, timestamp) = result.unwrap();
+                        prop_assert_eq!(sig.as_ref(), Some(signature));
+                        prop_assert_eq!(timestamp, prev.timestamp());
+                    } else {
+                        prop_assert!(result.is_err());
+                    }
+                    continue;
+                },
+            }
+            last = Some((message, fresh));
+        }
+
+        let state_hrs = last.map(|(message, _)| message.hrs());
+        prop_assert_eq!(
+            state_hrs.map(|(h, r, s)| (block::Height::from(h), block::Round::from(r), s)),
+            Some((state.height, state.round, state.step))
+        );
+    }
+}
+
+/// Read a fixture file from the `support/config` directory
+fn read_fixture(name: &str) -> String {
+    fs::read_to_string(PathBuf::from("./tests/support/config/").join(name)).unwrap()
+}
+
+/// Precommit signed in `priv_validator_state.json`, with another timestamp
+fn fixture_precommit(timestamp: i64) -> Message {
+    Message {
+        height: 5,
+        round: 0,
+        step: SignStep::Precommit,
+        block: Some(7),
+        timestamp,
+    }
+}
+
+/// Parse an example `priv_validator_state.json`, and sign against it
+#[test]
+fn priv_validator_state_json_parser() {
+    let mut state = LastSignState::parse_json(read_fixture("priv_validator_state.json")).unwrap();
+    assert_eq!(state.height.value(), 5);
+    assert_eq!(state.round.value(), 0);
+    assert_eq!(state.step, SignStep::Precommit);
+    let signature = state.signature.clone().unwrap();
+    assert_eq!(signature.as_bytes(), [0x2a; 64]);
+
+    // The state is only advanced by new messages.
+    let other = Signature::new([1; 64]).unwrap().unwrap();
+    let (sig, timestamp) = fixture_precommit(2).sign(&mut state, &other).unwrap();
+    assert_eq!(sig, Some(signature));
+    assert_eq!(timestamp, fixture_precommit(1).timestamp());
+    let conflicting = Message {
+        block: None,
+        ..fixture_precommit(1)
+    };
+    assert!(conflicting.sign(&mut state, &other).is_err());
+    assert_eq!(state.height.value(), 5);
+
+    let empty = LastSignState::parse_json(r#"{"height":"0","round":0,"step":0}"#).unwrap();
+    assert_eq!(empty, LastSignState::default());
+    assert!(LastSignState::parse_json(r#"{"height":"0","round":0,"step":4}"#).is_err());
+}
+
+/// Save a `LastSignState`, and load it again
+#[test]
+fn priv_validator_state_save_and_load() {
+    let path =
+        std::env::temp_dir().join(format!("priv_validator_state-{}.json", std::process::id()));
+    let mut state = LastSignState::default();
+    let signature = Signature::new([3; 64]).unwrap().unwrap();
+    fixture_precommit(1).sign(&mut state, &signature).unwrap();
+    state.save_json_file(&path).unwrap();
+
+    assert_eq!(LastSignState::load_json_file(&path).unwrap(), state);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,69 @@
+//! Compares the time taken to tally the voting power of commits when their
+//! signatures are verified one by one, and as a batch.
+//!
+//! Run with `cargo bench -p tendermint-light-client-verifier`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tendermint::{crypto::signature, PublicKey, Signature, Time};
+use tendermint_light_client_verifier::{
+    operations::{ProdVotingPowerCalculator, ProvidedVotingPowerCalculator, VotingPowerCalculator},
+    types::{SignedHeader, TrustThreshold, ValidatorSet},
+};
+use tendermint_testgen::{Generator, Header, LightBlock, Validator};
+
+/// Verifier checking the signatures of batches one by one.
+struct SequentialVerifier;
+
+impl signature::Verifier for SequentialVerifier {
+    fn verify(
+        pubkey: PublicKey,
+        msg: &[u8],
+        signature: &Signature,
+    ) -> Result<(), signature::Error> {
+        tendermint::crypto::default::signature::Verifier::verify(pubkey, msg, signature)
+    }
+}
+
+/// Signed header of a block committed by all the validators, and their set.
+fn signed_header(validators: usize) -> (SignedHeader, ValidatorSet) {
+    let validators = (0..validators)
+        .map(|i| Validator::new(&i.to_string()).voting_power(10))
+        .collect::<Vec<_>>();
+    let header = Header::new(&validators)
+        .height(10)
+        .chain_id("bench-chain")
+        .next_validators(&validators)
+        .time(Time::from_unix_timestamp(10, 0).unwrap());
+    let light_block = LightBlock::new_default_with_header(header)
+        .generate()
+        .unwrap();
+    (light_block.signed_header, light_block.validators)
+}
+
+fn tally<C: VotingPowerCalculator + Default>(
+    (signed_header, validators): &(SignedHeader, ValidatorSet),
+) {
+    let tally = C::default()
+        .voting_power_in(signed_header, validators, TrustThreshold::TWO_THIRDS)
+        .unwrap();
+    assert!(tally.tallied > 0);
+}
+
+fn batch_verification(c: &mut Criterion) {
+    let mut group = c.benchmark_group("voting_power_in");
+    for validators in [4, 50, 150, 300] {
+        let block = signed_header(validators);
+        group.bench_with_input(
+            BenchmarkId::new("sequential", validators),
+            &block,
+            |b, block| b.iter(|| tally::<ProvidedVotingPowerCalculator<SequentialVerifier>>(block)),
+        );
+        group.bench_with_input(BenchmarkId::new("batch", validators), &block, |b, block| {
+            b.iter(|| tally::<ProdVotingPowerCalculator>(block))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, batch_verification);
+criterion_main!(benches);
@@ -264,17 +264,12 @@ impl NonAbsentCommitVote {
 struct NonAbsentCommitVotes {
     /// Votes sorted by validator address.
     votes: Vec<NonAbsentCommitVote>,
-    /// Internal buffer for storing sign_bytes.
-    ///
-    /// The buffer is reused for each canonical vote so that we allocate it
-    /// once.
-    sign_bytes: Vec<u8>,
 }
 
 impl NonAbsentCommitVotes {
-    /// Initial capacity of the `sign_bytes` buffer.
+    /// Initial capacity of the buffers storing sign bytes.
     ///
-    /// The buffer will be resized if it happens to be too small so this value
+    /// The buffers will be resized if they happen to be too small so this value
     /// isn’t critical for correctness.  It’s a matter of performance to avoid
     /// reallocations.
     ///
@@ -314,52 +309,67 @@ impl NonAbsentCommitVotes {
                 pair[0].validator_id(),
             ))
         } else {
-            Ok(Self {
-                votes,
-                sign_bytes: Vec::with_capacity(Self::SIGN_BYTES_INITIAL_CAPACITY),
-            })
+            Ok(Self { votes })
         }
     }
 
     /// Looks up a vote cast by given validator.
     ///
-    /// If the validator didn’t cast a vote or voted for `nil`, returns `Ok(None)`. Otherwise, if
-    /// the vote had valid signature, returns `Ok(Some(idx))` where idx is the validator's index.
-    /// If the vote had invalid signature, returns `Err`.
-    pub fn has_voted<V: signature::Verifier>(
-        &mut self,
-        validator: &validator::Info,
-    ) -> Result<Option<usize>, VerificationError> {
-        if let Ok(idx) = self
-            .votes
+    /// If the validator didn’t cast a vote or voted for `nil`, returns `None`. Otherwise,
+    /// returns `Some(idx)` where idx is the index of the vote.
+    pub fn find(&self, validator: &validator::Info) -> Option<usize> {
+        self.votes
             .binary_search_by_key(&validator.address, NonAbsentCommitVote::validator_id)
-        {
-            let vote = &mut self.votes[idx];
+            .ok()
+    }
 
-            if !vote.verified {
-                self.sign_bytes.clear();
-                vote.signed_vote
-                    .sign_bytes_into(&mut self.sign_bytes)
+    /// Verifies the signatures of the votes at given indices, cast by the
+    /// given validators, which were not verified yet.
+    ///
+    /// The signatures are verified as a batch with [`signature::Verifier::verify_batch`].
+    /// If one of them is invalid, returns an error for the first invalid one.
+    pub fn verify<V: signature::Verifier>(
+        &mut self,
+        signers: &[(usize, &validator::Info)],
+    ) -> Result<(), VerificationError> {
+        let unverified = signers
+            .iter()
+            .filter(|(idx, _)| !self.votes[*idx].verified)
+            .collect::<Vec<_>>();
+        let sign_bytes = unverified
+            .iter()
+            .map(|(idx, _)| {
+                let mut sign_bytes = Vec::with_capacity(Self::SIGN_BYTES_INITIAL_CAPACITY);
+                self.votes[*idx]
+                    .signed_vote
+                    .sign_bytes_into(&mut sign_bytes)
                     .expect("buffer is resized if needed and encoding never fails");
+                sign_bytes
+            })
+            .collect::<Vec<_>>();
+        let items = unverified
+            .iter()
+            .zip(&sign_bytes)
+            .map(|((idx, validator), sign_bytes)| signature::BatchItem {
+                public_key: validator.pub_key,
+                msg: sign_bytes,
+                signature: self.votes[*idx].signed_vote.signature(),
+            })
+            .collect::<Vec<_>>();
+
+        if let Err(e) = V::verify_batch(&items) {
+            let (_, validator) = unverified[e.index];
+            return Err(VerificationError::invalid_signature(
+                items[e.index].signature.as_bytes().to_vec(),
+                Box::new((*validator).clone()),
+                sign_bytes[e.index].clone(),
+            ));
+        }
 
-                let sign_bytes = self.sign_bytes.as_slice();
-                validator
-                    .verify_signature::<V>(sign_bytes, vote.signed_vote.signature())
-                    .map_err(|_| {
-                        VerificationError::invalid_signature(
-                            vote.signed_vote.signature().as_bytes().to_vec(),
-                            Box::new(validator.clone()),
-                            sign_bytes.to_vec(),
-                        )
-                    })?;
-
-                vote.verified = true;
-            }
-
-            Ok(Some(idx))
-        } else {
-            Ok(None)
+        for (idx, _) in unverified {
+            self.votes[*idx].verified = true;
         }
+        Ok(())
     }
 }
 
@@ -414,17 +424,17 @@ fn voting_power_in_impl<V: signature::Verifier>(
     total_voting_power: u64,
 ) -> Result<VotingPowerTally, VerificationError> {
     let mut power = VotingPowerTally::new(total_voting_power, trust_threshold);
-    let mut seen_vals = Vec::new();
+    let mut signers = Vec::new();
 
     for validator in validator_set.validators() {
-        if let Some(idx) = votes.has_voted::<V>(validator)? {
+        if let Some(idx) = votes.find(validator) {
             // Check if this validator has already voted.
             //
             // O(n) complexity.
-            if seen_vals.contains(&idx) {
+            if signers.iter().any(|(seen, _)| *seen == idx) {
                 return Err(VerificationError::duplicate_validator(validator.address));
             }
-            seen_vals.push(idx);
+            signers.push((idx, validator));
 
             power.tally(validator.power());
 
@@ -435,6 +445,9 @@ fn voting_power_in_impl<V: signature::Verifier>(
         }
     }
 
+    // Only the signatures of the tallied votes are verified, all at once.
+    votes.verify::<V>(&signers)?;
+
     Ok(power)
 }
 
@@ -596,4 +609,78 @@ mod tests {
         // ensure the result matches the expected result
         assert_eq!(result_ok.unwrap(), EXPECTED_RESULT);
     }
+    #[test]
+    #[cfg(feature = "bls12381")]
+    fn test_bls12381_signatures() {
+        use tendermint::{crypto::bls12381::SigningKey, validator, PublicKey};
+
+        use crate::types::ValidatorSet;
+
+        let vp_calculator = ProdVotingPowerCalculator::default();
+        // Both validators are needed to reach the threshold.
+        let trust_threshold = TrustThreshold::TWO_THIRDS;
+
+        // Re-sign the commit with BLS12-381 validators.
+        let mut light_block: LightBlock = TestgenLightBlock::new_default(10)
+            .generate()
+            .unwrap()
+            .into();
+        let signed_header = &mut light_block.signed_header;
+        let mut validators = Vec::new();
+        for (idx, commit_sig) in signed_header.commit.signatures.iter_mut().enumerate() {
+            let CommitSig::BlockIdFlagCommit {
+                validator_address,
+                timestamp,
+                signature,
+            } = commit_sig
+            else {
+                panic!("expected a commit signature");
+            };
+            let key = SigningKey::try_from(&[idx as u8 + 1; 32][..]).unwrap();
+            let pub_key = PublicKey::Bls12381(key.verification_key());
+            *validator_address = account::Id::from(pub_key);
+            let vote = Vote {
+                vote_type: tendermint::vote::Type::Precommit,
+                height: signed_header.commit.height,
+                round: signed_header.commit.round,
+                block_id: Some(signed_header.commit.block_id),
+                timestamp: Some(*timestamp),
+                validator_address: *validator_address,
+                validator_index: ValidatorIndex::try_from(idx).unwrap(),
+                signature: None,
+                extension: Default::default(),
+                extension_signature: None,
+            };
+            *signature =
+                Some(key.sign(&vote.into_signable_vec(signed_header.header.chain_id.clone())));
+            validators.push(validator::Info::new(pub_key, 50_u32.into()));
+        }
+        light_block.validators = ValidatorSet::without_proposer(validators);
+
+        let tally = vp_calculator
+            .voting_power_in(
+                &light_block.signed_header,
+                &light_block.validators,
+                trust_threshold,
+            )
+            .unwrap();
+        assert_eq!(tally.total, tally.tallied);
+
+        // A signature for another message is rejected.
+        let other = SigningKey::try_from(&[0x0A; 32][..]).unwrap();
+        if let CommitSig::BlockIdFlagCommit { signature, .. } =
+            &mut light_block.signed_header.commit.signatures[0]
+        {
+            *signature = Some(other.sign(b"other"));
+        }
+        let result_err = vp_calculator.voting_power_in(
+            &light_block.signed_header,
+            &light_block.validators,
+            trust_threshold,
+        );
+        match result_err {
+            Err(VerificationError(VerificationErrorDetail::InvalidSignature(_), _)) => {},
+            _ => panic!("expected InvalidSignature error"),
+        }
+    }
 }
@@ -0,0 +1,592 @@
+//! Blocksync, through which nodes download historical blocks from their peers.
+//!
+//! The [`BlockSync`] client requests blocks over the
+//! [`StreamId::BlockSync`](crate::transport::StreamId::BlockSync) stream with the messages of
+//! the `CometBFT` blocksync protocol, and delivers them in order once verified with
+//! [`verify_block`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Debug, Display},
+    thread,
+    time::{Duration, Instant},
+};
+
+use prost::Message as _;
+use tendermint::{block, chain, node, validator, Block};
+use tendermint_light_client_verifier::{
+    errors::VerificationError,
+    operations::{ProdCommitValidator, ProdVotingPowerCalculator},
+    predicates::{ProdPredicates, VerificationPredicates},
+    types::SignedHeader,
+};
+use tendermint_proto::v0_38::blocksync::{
+    message::Sum, BlockRequest, BlockResponse, Message as RawMessage, NoBlockResponse,
+    StatusRequest, StatusResponse,
+};
+
+use crate::{
+    error::Error,
+    transport::{Connection, StreamId, StreamSend},
+};
+
+/// Maximum size of a blocksync message: a block of the maximum size, and its encoding
+/// overhead.
+pub const MAX_MESSAGE_SIZE: usize = StreamId::BlockSync.max_message_size();
+
+/// Default maximum number of blocks requested at once.
+const DEFAULT_MAX_PENDING_REQUESTS: usize = 600;
+
+/// Default maximum number of blocks requested at once from a single peer.
+const DEFAULT_MAX_PENDING_REQUESTS_PER_PEER: usize = 20;
+
+/// Configuration of a [`BlockSync`] client.
+#[derive(Clone, Debug)]
+pub struct BlockSyncConfig {
+    /// Maximum number of blocks requested at once, across all peers.
+    pub max_pending_requests: usize,
+    /// Maximum number of blocks requested at once from a single peer.
+    pub max_pending_requests_per_peer: usize,
+    /// Time given to a peer to send a requested block, after which it is disconnected.
+    pub request_timeout: Duration,
+    /// Interval between two requests for the heights of the blocks the peers have.
+    pub status_interval: Duration,
+    /// Height of the last block to download. Without it, blocks are downloaded until the
+    /// client caught up with its peers.
+    pub stop_height: Option<block::Height>,
+}
+
+impl Default for BlockSyncConfig {
+    fn default() -> Self {
+        Self {
+            max_pending_requests: DEFAULT_MAX_PENDING_REQUESTS,
+            max_pending_requests_per_peer: DEFAULT_MAX_PENDING_REQUESTS_PER_PEER,
+            request_timeout: Duration::from_secs(15),
+            status_interval: Duration::from_secs(10),
+            stop_height: None,
+        }
+    }
+}
+
+/// Source of the validator sets which signed the downloaded blocks, such as a light client
+/// or the state of the application.
+pub trait ValidatorSetSource {
+    /// Returns the validator set of the given height.
+    ///
+    /// # Errors
+    ///
+    /// * If the validator set of the height is unknown.
+    fn validator_set(&mut self, height: block::Height) -> eyre::Result<validator::Set>;
+}
+
+/// A single validator set signing every block.
+impl ValidatorSetSource for validator::Set {
+    fn validator_set(&mut self, _height: block::Height) -> eyre::Result<validator::Set> {
+        Ok(self.clone())
+    }
+}
+
+/// Verifies a block against the next one, whose last commit must be signed by more than two
+/// thirds of the voting power of `validators`, the validator set of the block.
+///
+/// As blocks are not split into parts here, only the hash of the IDs of the block is checked.
+///
+/// # Errors
+///
+/// * If the blocks are not consecutive blocks of the chain.
+/// * If the last commit of the next block is not a commit of the block.
+/// * If the transactions, evidence or last commit of the block do not match its header.
+/// * If `validators` is not the validator set of the block.
+/// * If the commit is not signed by enough of the validators, or has invalid signatures.
+pub fn verify_block(
+    chain_id: &chain::Id,
+    block: &Block,
+    next: &Block,
+    validators: &validator::Set,
+) -> Result<(), Error> {
+    check_block(chain_id, block, next, validators)
+        .map_err(|reason| Error::invalid_block(block.header.height, reason))
+}
+
+/// Checks of [`verify_block`], returning why the block is invalid.
+fn check_block(
+    chain_id: &chain::Id,
+    block: &Block,
+    next: &Block,
+    validators: &validator::Set,
+) -> Result<(), String> {
+    let header = &block.header;
+    if header.chain_id != *chain_id {
+        return Err(format!("block is on chain {}", header.chain_id));
+    }
+    if next.header.chain_id != *chain_id || next.header.height != header.height.increment() {
+        return Err(format!(
+            "next block is block {} of chain {}",
+            next.header.height, next.header.chain_id
+        ));
+    }
+    let commit = next
+        .last_commit
+        .clone()
+        .ok_or("next block has no last commit")?;
+    if commit.height != header.height {
+        return Err(format!("last commit is for height {}", commit.height));
+    }
+    if next.header.last_block_id.map(|id| id.hash) != Some(header.hash()) {
+        return Err("next block does not follow the block".into());
+    }
+    // The signatures only cover the header, which must commit to the rest of the block.
+    block.validate_basic().map_err(|e| e.to_string())?;
+
+    let predicates = ProdPredicates;
+    let to_reason = |e: VerificationError| e.to_string();
+    predicates
+        .header_matches_commit(header, commit.block_id.hash)
+        .map_err(to_reason)?;
+    predicates
+        .validator_sets_match(validators, header.validators_hash)
+        .map_err(to_reason)?;
+    let signed_header = SignedHeader::new(header.clone(), commit).map_err(|e| e.to_string())?;
+    predicates
+        .valid_commit(&signed_header, validators, &ProdCommitValidator)
+        .map_err(to_reason)?;
+    predicates
+        .has_sufficient_signers_overlap(
+            &signed_header,
+            validators,
+            &ProdVotingPowerCalculator::default(),
+        )
+        .map_err(to_reason)
+}
+
+/// Client downloading blocks from its peers, and delivering them in order as an iterator,
+/// from a start height.
+///
+/// Blocks are requested from the peers which reported having them, and each block is only
+/// delivered once verified against the next one with [`verify_block`]. Peers sending
+/// invalid or unsolicited messages, or not sending requested blocks in time, are
+/// disconnected and their requests sent to other peers. The errors they were disconnected
+/// with are kept until [`BlockSync::take_removed_peers`] is called.
+///
+/// The iteration ends once the client caught up with its peers, or reached the stop height
+/// of its configuration. It ends with an error if no peers are left, or if a validator set
+/// is missing.
+pub struct BlockSync<C, V>
+where
+    C: Connection,
+{
+    chain_id: chain::Id,
+    config: BlockSyncConfig,
+    validators: V,
+    peers: HashMap<node::Id, Peer<C>>,
+    events: (flume::Sender<Event>, flume::Receiver<Event>),
+    pending: BTreeMap<u64, PendingRequest>,
+    received: BTreeMap<u64, (Block, node::Id)>,
+    next_height: u64,
+    last_status_request: Instant,
+    removed: Vec<(node::Id, Error)>,
+    done: bool,
+}
+
+impl<C, V> BlockSync<C, V>
+where
+    C: Connection,
+    C::StreamRead: 'static,
+    C::Error: Display + Debug + Send + Sync + 'static,
+    V: ValidatorSetSource,
+{
+    /// Creates a client downloading the blocks of the given chain from `start_height`,
+    /// verified with the validator sets of `validators`.
+    pub fn new(
+        chain_id: chain::Id,
+        start_height: block::Height,
+        validators: V,
+        config: BlockSyncConfig,
+    ) -> Self {
+        Self {
+            chain_id,
+            config,
+            validators,
+            peers: HashMap::new(),
+            events: flume::unbounded(),
+            pending: BTreeMap::new(),
+            received: BTreeMap::new(),
+            next_height: start_height.value(),
+            last_status_request: Instant::now(),
+            removed: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Returns the height of the next block to be delivered.
+    #[must_use]
+    pub fn next_height(&self) -> block::Height {
+        block::Height::try_from(self.next_height).unwrap_or_default()
+    }
+
+    /// Returns the IDs of the peers blocks are downloaded from.
+    pub fn peers(&self) -> impl Iterator<Item = &node::Id> {
+        self.peers.keys()
+    }
+
+    /// Adds a connected peer with the given ID to download blocks from, and requests the
+    /// heights of the blocks it has. The connection is closed once the peer is removed, or
+    /// the client dropped.
+    ///
+    /// # Errors
+    ///
+    /// * If the blocksync stream cannot be opened.
+    /// * If the request cannot be sent.
+    pub fn add_peer(&mut self, id: node::Id, conn: C) -> eyre::Result<()> {
+        let (read, send) = conn
+            .open_bidirectional(StreamId::BlockSync)
+            .map_err(eyre::Report::msg)?;
+        send.send(encode(Sum::StatusRequest(StatusRequest {})))?;
+
+        let events = self.events.0.clone();
+        thread::spawn(move || {
+            for msg in read {
+                let Ok(msg) = msg else { break };
+                if events.send((id, Some(msg))).is_err() {
+                    return;
+                }
+            }
+            let _ = events.send((id, None));
+        });
+
+        if let Some(previous) = self.peers.insert(
+            id,
+            Peer {
+                conn,
+                send,
+                range: None,
+                pending: 0,
+            },
+        ) {
+            let _ = previous.conn.close();
+        }
+        Ok(())
+    }
+
+    /// Returns the peers removed since the last call, along with the error each was removed
+    /// with.
+    pub fn take_removed_peers(&mut self) -> Vec<(node::Id, Error)> {
+        std::mem::take(&mut self.removed)
+    }
+
+    /// Delivers the next block if it and the following one were received, or returns `None`.
+    fn deliver(&mut self) -> Option<Result<Block, Error>> {
+        let (next, next_peer) = self.received.get(&(self.next_height + 1))?;
+        let (block, peer) = self.received.get(&self.next_height)?;
+        let (next_peer, peer) = (*next_peer, *peer);
+
+        let height = block.header.height;
+        let validators = match self.validators.validator_set(height) {
+            Ok(validators) => validators,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(Error::validator_set(height, e.to_string())));
+            },
+        };
+
+        if let Err(reason) = check_block(&self.chain_id, block, next, &validators) {
+            // Either of the peers may have sent an invalid block, so both are removed along
+            // with the blocks they sent.
+            for id in [peer, next_peer] {
+                self.received.retain(|_, (_, from)| *from != id);
+                self.remove_peer(id, Error::invalid_block(height, reason.clone()));
+            }
+            return None;
+        }
+
+        let (block, _) = self.received.remove(&self.next_height)?;
+        self.next_height += 1;
+        Some(Ok(block))
+    }
+
+    /// Whether no peer has blocks beyond the ones which can be delivered.
+    fn is_caught_up(&self) -> bool {
+        self.peers.values().all(|peer| peer.range.is_some())
+            && self
+                .peers
+                .values()
+                .filter_map(|peer| peer.range.map(|(_, height)| height))
+                .max()
+                .is_none_or(|height| height <= self.next_height)
+    }
+
+    /// Last height to request, which is the one after the stop height as the commit of a
+    /// block is in the following one.
+    fn last_height(&self) -> u64 {
+        let limit = self.next_height + self.config.max_pending_requests as u64;
+        self.config
+            .stop_height
+            .map_or(limit, |stop| limit.min(stop.value() + 1))
+    }
+
+    /// Requests the blocks which are neither received nor requested, from the peers which
+    /// have them and the fewest requests pending.
+    fn request_blocks(&mut self) {
+        let now = Instant::now();
+        for height in self.next_height..=self.last_height() {
+            if self.pending.contains_key(&height) || self.received.contains_key(&height) {
+                continue;
+            }
+            let Some((&id, peer)) = self
+                .peers
+                .iter_mut()
+                .filter(|(_, peer)| {
+                    peer.pending < self.config.max_pending_requests_per_peer
+                        && peer
+                            .range
+                            .is_some_and(|(base, top)| (base..=top).contains(&height))
+                })
+                .min_by_key(|(_, peer)| peer.pending)
+            else {
+                continue;
+            };
+
+            let request = encode(Sum::BlockRequest(BlockRequest {
+                height: to_i64(height),
+            }));
+            if let Err(e) = peer.send.send(request) {
+                self.remove_peer(id, Error::connection_failed(e.to_string()));
+                continue;
+            }
+            peer.pending += 1;
+            self.pending.insert(
+                height,
+                PendingRequest {
+                    peer: id,
+                    deadline: now + self.config.request_timeout,
+                },
+            );
+        }
+    }
+
+    /// Requests the heights of the blocks the peers have, if the status interval elapsed.
+    fn request_status(&mut self) {
+        if self.last_status_request.elapsed() < self.config.status_interval {
+            return;
+        }
+        self.last_status_request = Instant::now();
+
+        let request = encode(Sum::StatusRequest(StatusRequest {}));
+        let failed = self
+            .peers
+            .iter()
+            .filter_map(|(&id, peer)| peer.send.send(&request).err().map(|e| (id, e)))
+            .collect::<Vec<_>>();
+        for (id, e) in failed {
+            self.remove_peer(id, Error::connection_failed(e.to_string()));
+        }
+    }
+
+    /// Removes the peers which did not send a requested block in time.
+    fn check_timeouts(&mut self) {
+        let now = Instant::now();
+        let expired = self
+            .pending
+            .iter()
+            .filter(|(_, request)| request.deadline <= now)
+            .map(|(&height, request)| (request.peer, height))
+            .collect::<Vec<_>>();
+        for (id, height) in expired {
+            if self.peers.contains_key(&id) {
+                self.remove_peer(id, Error::block_request_timeout(id, to_height(height)));
+            }
+        }
+    }
+
+    /// Waits for the next message of a peer until the next timeout, and handles it.
+    fn wait(&mut self) {
+        let status_deadline = self.last_status_request + self.config.status_interval;
+        let deadline = self
+            .pending
+            .values()
+            .map(|request| request.deadline)
+            .min()
+            .map_or(status_deadline, |deadline| deadline.min(status_deadline));
+
+        let Ok((id, msg)) = self.events.1.recv_deadline(deadline) else {
+            return;
+        };
+        if !self.peers.contains_key(&id) {
+            return;
+        }
+        let result = msg.map_or_else(
+            || Err(Error::connection_closed()),
+            |msg| self.receive(id, &msg),
+        );
+        if let Err(e) = result {
+            self.remove_peer(id, e);
+        }
+    }
+
+    /// Handles a message received from a known peer.
+    fn receive(&mut self, id: node::Id, msg: &[u8]) -> Result<(), Error> {
+        let msg = RawMessage::decode(msg)
+            .map_err(|e| Error::invalid_block_sync_message(e.to_string()))?;
+        let Some(peer) = self.peers.get_mut(&id) else {
+            return Err(Error::unknown_peer(id));
+        };
+
+        let reply = match msg.sum {
+            // Blocks are not served, as they are not stored.
+            Some(Sum::StatusRequest(_)) => {
+                Some(Sum::StatusResponse(StatusResponse { height: 0, base: 0 }))
+            },
+            Some(Sum::BlockRequest(BlockRequest { height })) => {
+                Some(Sum::NoBlockResponse(NoBlockResponse { height }))
+            },
+            Some(Sum::StatusResponse(StatusResponse { height, base })) => {
+                let (Ok(base), Ok(height)) = (u64::try_from(base), u64::try_from(height)) else {
+                    return Err(Error::invalid_block_sync_message(format!(
+                        "invalid status: base {base}, height {height}"
+                    )));
+                };
+                if base > height {
+                    return Err(Error::invalid_block_sync_message(format!(
+                        "base {base} is above height {height}"
+                    )));
+                }
+                peer.range = Some((base, height));
+                None
+            },
+            Some(Sum::NoBlockResponse(NoBlockResponse { height })) => {
+                let height = u64::try_from(height).unwrap_or_default();
+                if self
+                    .pending
+                    .get(&height)
+                    .is_some_and(|request| request.peer == id)
+                {
+                    self.pending.remove(&height);
+                    peer.pending -= 1;
+                    // The peer is not asked for this block, or later ones, until it reports
+                    // having them again.
+                    peer.range = peer
+                        .range
+                        .filter(|&(base, _)| base < height)
+                        .map(|(base, _)| (base, height - 1));
+                }
+                None
+            },
+            Some(Sum::BlockResponse(BlockResponse { block, .. })) => {
+                let block = block
+                    .ok_or_else(|| Error::invalid_block_sync_message("missing block".into()))?;
+                let block = Block::try_from(block)
+                    .map_err(|e| Error::invalid_block_sync_message(e.to_string()))?;
+                let height = block.header.height;
+                if self
+                    .pending
+                    .get(&height.value())
+                    .is_none_or(|request| request.peer != id)
+                {
+                    return Err(Error::unsolicited_block(id, height));
+                }
+                self.pending.remove(&height.value());
+                peer.pending -= 1;
+                self.received.insert(height.value(), (block, id));
+                None
+            },
+            None => return Err(Error::invalid_block_sync_message("empty message".into())),
+        };
+
+        if let Some(reply) = reply {
+            peer.send
+                .send(encode(reply))
+                .map_err(|e| Error::connection_failed(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Disconnects a peer, whose requests are then sent to other peers.
+    fn remove_peer(&mut self, id: node::Id, e: Error) {
+        let Some(peer) = self.peers.remove(&id) else {
+            return;
+        };
+        let _ = peer.conn.close();
+        self.pending.retain(|_, request| request.peer != id);
+        self.removed.push((id, e));
+    }
+}
+
+impl<C, V> Iterator for BlockSync<C, V>
+where
+    C: Connection,
+    C::StreamRead: 'static,
+    C::Error: Display + Debug + Send + Sync + 'static,
+    V: ValidatorSetSource,
+{
+    type Item = Result<Block, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.done
+                || self
+                    .config
+                    .stop_height
+                    .is_some_and(|stop| self.next_height > stop.value())
+            {
+                return None;
+            }
+            if let Some(result) = self.deliver() {
+                return Some(result);
+            }
+            if self.peers.is_empty() {
+                self.done = true;
+                return Some(Err(Error::no_block_sync_peers()));
+            }
+            if self.is_caught_up() {
+                return None;
+            }
+
+            self.request_status();
+            self.request_blocks();
+            self.wait();
+            self.check_timeouts();
+        }
+    }
+}
+
+impl<C, V> Drop for BlockSync<C, V>
+where
+    C: Connection,
+{
+    fn drop(&mut self) {
+        for peer in self.peers.values() {
+            let _ = peer.conn.close();
+        }
+    }
+}
+
+/// Message received from a peer, or `None` once its stream ended.
+type Event = (node::Id, Option<Vec<u8>>);
+
+/// Peer blocks are downloaded from.
+struct Peer<C: Connection> {
+    conn: C,
+    send: C::StreamSend,
+    /// Lowest and highest heights of the blocks the peer has, once it reported them.
+    range: Option<(u64, u64)>,
+    /// Number of blocks requested from the peer, which it did not send yet.
+    pending: usize,
+}
+
+/// Request for a block, which has not been answered yet.
+struct PendingRequest {
+    peer: node::Id,
+    deadline: Instant,
+}
+
+fn encode(sum: Sum) -> Vec<u8> {
+    RawMessage { sum: Some(sum) }.encode_to_vec()
+}
+
+fn to_i64(height: u64) -> i64 {
+    i64::try_from(height).unwrap_or(i64::MAX)
+}
+
+fn to_height(height: u64) -> block::Height {
+    block::Height::try_from(height).unwrap_or_default()
+}
@@ -4,8 +4,11 @@
 // TODO(soares): Update flex-error accordingly to address this.
 #![allow(clippy::use_self)]
 
+use std::net::SocketAddr;
+
 use flex_error::{define_error, DisplayOnly};
 use prost::DecodeError;
+use tendermint::{block, chain, node, AppHash};
 
 define_error! {
     Error {
@@ -57,8 +60,175 @@ define_error! {
 
         TransportClone
             { detail: String }
-            | e | { format_args!("failed to clone underlying transport: {}", e.detail) }
+            | e | { format_args!("failed to clone underlying transport: {}", e.detail) },
+
+        RemotePeerMismatch
+            { expected: node::Id, actual: node::Id }
+            | e | { format_args!("expected remote peer {}, got {}", e.expected, e.actual) },
+
+        PublicKeyMismatch
+            | _ | { "public key to bind with does not match the private key of the transport" },
+
+        NoAddress
+            | _ | { "no address to bind to or connect to" },
+
+        AddressInUse
+            { addr: SocketAddr }
+            | e | { format_args!("address already in use: {}", e.addr) },
+
+        ConnectionRefused
+            { addr: SocketAddr }
+            | e | { format_args!("no endpoint listening on {}", e.addr) },
+
+        InvalidChannel
+            { id: u64 }
+            | e | { format_args!("invalid or duplicate channel ID: {}", e.id) },
+
+        UnknownChannel
+            { id: u64 }
+            | e | { format_args!("unknown channel: {}", e.id) },
+
+        ReceiverTaken
+            { id: u64 }
+            | e | { format_args!("receiver of channel {} was already taken", e.id) },
+
+        ConnectionClosed
+            | _ | { "connection closed" },
+
+        ConnectionFailed
+            { reason: String }
+            | e | { format_args!("connection failed: {}", e.reason) },
+
+        PongTimeout
+            | _ | { "peer did not answer ping in time" },
+
+        RecvQueueFull
+            { id: u64 }
+            | e | { format_args!("messages of channel {} are not received as fast as the peer sends them", e.id) },
+
+        MessageTooLarge
+            { size: u64, max: usize }
+            | e | { format_args!("message of {} bytes exceeds maximum size of {} bytes", e.size, e.max) },
+
+        InvalidNodeInfo
+            { reason: String }
+            | e | { format_args!("invalid node info: {}", e.reason) },
+
+        IncompatibleNetwork
+            { local: chain::Id, remote: chain::Id }
+            | e | { format_args!("peer is on network {}, not {}", e.remote, e.local) },
+
+        IncompatibleBlockVersion
+            { local: u64, remote: u64 }
+            | e | { format_args!("peer has block protocol version {}, not {}", e.remote, e.local) },
+
+        IncompatibleP2pVersion
+            { local: u64, remote: u64 }
+            | e | { format_args!("peer has p2p protocol version {}, not {}", e.remote, e.local) },
+
+        NoCommonChannels
+            | _ | { "peer has no channels in common with the local node" },
+
+        InvalidNetAddress
+            { reason: String }
+            | e | { format_args!("invalid network address: {}", e.reason) },
+
+        AddressRejected
+            { addr: String, reason: String }
+            | e | { format_args!("address {} rejected: {}", e.addr, e.reason) },
+
+        InvalidAddrBook
+            { path: String, reason: String }
+            | e | { format_args!("invalid address book {}: {}", e.path, e.reason) },
+
+        InvalidPexMessage
+            { reason: String }
+            | e | { format_args!("invalid PEX message: {}", e.reason) },
+
+        UnknownPeer
+            { id: node::Id }
+            | e | { format_args!("unknown peer {}", e.id) },
 
+        UnsolicitedPexAddrs
+            { id: node::Id }
+            | e | { format_args!("peer {} sent addresses which were not requested", e.id) },
+
+        PexRequestTooFrequent
+            { id: node::Id }
+            | e | { format_args!("peer {} requested addresses too frequently", e.id) },
+
+        TooManyPexAddrs
+            { count: usize, max: usize }
+            | e | { format_args!("received {} addresses, more than the maximum of {}", e.count, e.max) },
+
+        InvalidPrivvalAddress
+            { addr: String }
+            | e | { format_args!("invalid privval address: {}", e.addr) },
+
+        InvalidPrivvalMessage
+            { reason: String }
+            | e | { format_args!("invalid privval message: {}", e.reason) },
+
+        UnexpectedPrivvalMessage
+            { kind: String }
+            | e | { format_args!("unexpected privval message: {}", e.kind) },
+
+        RemoteSigner
+            { code: i32, description: String }
+            | e | { format_args!("remote signer error {}: {}", e.code, e.description) },
+
+        InvalidBlockSyncMessage
+            { reason: String }
+            | e | { format_args!("invalid blocksync message: {}", e.reason) },
+
+        UnsolicitedBlock
+            { id: node::Id, height: block::Height }
+            | e | { format_args!("peer {} sent block {} which was not requested", e.id, e.height) },
+
+        BlockRequestTimeout
+            { id: node::Id, height: block::Height }
+            | e | { format_args!("peer {} did not send block {} in time", e.id, e.height) },
+
+        InvalidBlock
+            { height: block::Height, reason: String }
+            | e | { format_args!("invalid block {}: {}", e.height, e.reason) },
+
+        ValidatorSet
+            { height: block::Height, reason: String }
+            | e | { format_args!("no validator set for height {}: {}", e.height, e.reason) },
+
+        NoBlockSyncPeers
+            | _ | { "no peers left to download blocks from" },
+
+        InvalidStateSyncMessage
+            { reason: String }
+            | e | { format_args!("invalid statesync message: {}", e.reason) },
+
+        NoStateSyncPeers
+            | _ | { "no peers left to download snapshots from" },
+
+        NoSnapshots
+            | _ | { "no snapshot could be restored" },
+
+        StateSyncAborted
+            { reason: String }
+            | e | { format_args!("state sync aborted: {}", e.reason) },
+
+        UnverifiedAppHash
+            { height: block::Height, reason: String }
+            | e | { format_args!("app hash at height {} cannot be verified: {}", e.height, e.reason) },
+
+        AppHashUnavailable
+            { height: block::Height, reason: String }
+            | e | { format_args!("app hash at height {} is unavailable: {}", e.height, e.reason) },
+
+        AppHashMismatch
+            { height: block::Height, expected: AppHash, actual: AppHash }
+            | e | { format_args!("restored app hash at height {} is {}, not {}", e.height, e.actual, e.expected) },
+
+        Abci
+            { reason: String }
+            | e | { format_args!("ABCI client error: {}", e.reason) },
     }
 }
 
@@ -67,3 +237,10 @@ impl From<std::io::Error> for Error {
         Self::io(e)
     }
 }
+
+impl Error {
+    /// Converts the error into the [`eyre::Report`] returned by the [`crate::transport`] traits.
+    pub fn into_report(self) -> eyre::Report {
+        eyre::Report::msg(self)
+    }
+}
@@ -22,6 +22,14 @@
     html_logo_url = "https://raw.githubusercontent.com/informalsystems/tendermint-rs/master/img/logo-tendermint-rs_3961x4001.png"
 )]
 
+#[cfg(feature = "blocksync")]
+pub mod blocksync;
 pub mod error;
+pub mod mconnection;
+pub mod node_info;
+pub mod pex;
+pub mod privval;
 pub mod secret_connection;
+#[cfg(feature = "statesync")]
+pub mod statesync;
 pub mod transport;
@@ -0,0 +1,733 @@
+//! `MConnection`: multiplexing of message channels over a [`SecretConnection`], as spoken by
+//! `CometBFT` peers.
+//!
+//! Messages are split into `PacketMsg` packets of bounded size, which are interleaved on the
+//! wire and reassembled by the receiver. Every channel has its own send queue, and channels
+//! with a higher priority get a proportionally larger share of the bandwidth. Liveness of the
+//! peer is checked with `PacketPing`/`PacketPong` exchanges.
+//!
+//! [Specification](https://github.com/cometbft/cometbft/blob/main/spec/p2p/legacy-docs/connection.md)
+
+use std::{
+    collections::HashMap,
+    io::{self, BufReader, BufWriter, Read, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use flume::RecvTimeoutError;
+use prost::Message as _;
+use tendermint::channel;
+use tendermint_proto::v0_38::p2p::{packet::Sum, Packet, PacketMsg, PacketPing, PacketPong};
+use tendermint_std_ext::TryClone;
+
+use crate::{
+    error::Error,
+    secret_connection::{SecretConnection, DATA_MAX_SIZE},
+};
+
+/// Default capacity of a channel for a single incoming message, in bytes. Large enough to carry
+/// a block of the default maximum size of 21 MiB.
+pub const DEFAULT_RECV_MESSAGE_CAPACITY: usize = 22_020_096;
+
+/// Default interval at which the peer is pinged, in seconds.
+const DEFAULT_PING_INTERVAL_SECS: u64 = 60;
+
+/// Upper bound on the encoding overhead of a `PacketMsg` carrying a payload.
+const MAX_PACKET_MSG_OVERHEAD: usize = 16;
+
+/// Interval at which the amount of data recently sent on each channel decays.
+const STATS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Time given to the queued messages to be sent when the connection is closed.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// I/O resource underlying a connection, which can be shut down while it is in use.
+pub trait Shutdown: Send + Sync {
+    /// Shuts down both the read and write halves of the resource.
+    ///
+    /// # Errors
+    ///
+    /// * If the resource could not be shut down.
+    fn shutdown(&self) -> io::Result<()>;
+}
+
+impl Shutdown for std::net::TcpStream {
+    fn shutdown(&self) -> io::Result<()> {
+        Self::shutdown(self, std::net::Shutdown::Both)
+    }
+}
+
+/// Properties of a channel of an [`MConnection`].
+#[derive(Clone, Debug)]
+pub struct ChannelDescriptor {
+    /// Channel ID, which must fit in a byte.
+    pub id: channel::Id,
+    /// Relative share of the bandwidth the channel gets when other channels have data to send
+    /// as well.
+    pub priority: u32,
+    /// Number of outgoing messages that can be queued on the channel.
+    pub send_queue_capacity: usize,
+    /// Number of incoming messages that can wait on the channel to be received, which bounds
+    /// the memory the peer can make us use. Once it is reached, the connection fails, as the
+    /// peer sends faster than the messages are received. Until the receiver of the channel is
+    /// taken, further messages are discarded instead.
+    pub recv_queue_capacity: usize,
+    /// Maximum size of a single incoming message, in bytes.
+    pub recv_message_capacity: usize,
+}
+
+impl ChannelDescriptor {
+    /// Describes the channel with the given ID, with priority 1 and default capacities.
+    #[must_use]
+    pub const fn new(id: channel::Id) -> Self {
+        Self {
+            id,
+            priority: 1,
+            send_queue_capacity: 1,
+            recv_queue_capacity: 1,
+            recv_message_capacity: DEFAULT_RECV_MESSAGE_CAPACITY,
+        }
+    }
+}
+
+/// Configuration of an [`MConnection`].
+///
+/// The defaults match those of `CometBFT`'s `[p2p]` configuration section.
+#[derive(Clone, Debug)]
+pub struct MConnectionConfig {
+    /// Rate at which data is sent, in bytes per second. Zero means unlimited.
+    pub send_rate: u64,
+    /// Rate at which data is received, in bytes per second. Zero means unlimited.
+    pub recv_rate: u64,
+    /// Maximum size of the payload of a `PacketMsg`, in bytes.
+    pub max_packet_msg_payload_size: usize,
+    /// Interval at which the peer is pinged.
+    pub ping_interval: Duration,
+    /// Time after which the connection fails if the peer did not answer a ping.
+    pub pong_timeout: Duration,
+}
+
+impl Default for MConnectionConfig {
+    fn default() -> Self {
+        Self {
+            send_rate: 5_120_000,
+            recv_rate: 5_120_000,
+            max_packet_msg_payload_size: DATA_MAX_SIZE,
+            ping_interval: Duration::from_secs(DEFAULT_PING_INTERVAL_SECS),
+            pong_timeout: Duration::from_secs(45),
+        }
+    }
+}
+
+/// Events handled by the routine sending packets.
+enum Signal {
+    /// A message was queued.
+    Data,
+    /// The peer pinged us.
+    SendPong,
+    /// The peer answered our ping.
+    PongReceived,
+    /// The connection is being closed.
+    Close,
+}
+
+/// State shared by the handles and routines of a connection.
+#[derive(Default)]
+struct Shared {
+    /// Set when the connection is closed locally, which is not reported as a failure.
+    closing: bool,
+    /// Why the connection failed, as determined first by either routine.
+    failure: Option<String>,
+}
+
+/// Receiver of the incoming messages of a channel, not taken yet, with the flag telling the
+/// receive routine once it is.
+type Inbox = (flume::Receiver<Vec<u8>>, Arc<AtomicBool>);
+
+/// Connection multiplexing messages of several channels over a [`SecretConnection`].
+///
+/// Two background threads send and receive packets. The connection is closed when dropped, or
+/// when either thread fails.
+pub struct MConnection {
+    senders: HashMap<u64, ChannelSender>,
+    receivers: Mutex<HashMap<u64, Inbox>>,
+    signals: flume::Sender<Signal>,
+    shared: Arc<Mutex<Shared>>,
+    io: Arc<dyn Shutdown>,
+    /// Disconnected once the send routine stopped.
+    send_stopped: flume::Receiver<()>,
+}
+
+impl MConnection {
+    /// Starts multiplexing the given channels over the connection.
+    ///
+    /// `io` must shut down the I/O resource underlying `conn`, to interrupt the threads reading
+    /// from and writing to it when the connection is closed.
+    ///
+    /// # Errors
+    ///
+    /// * If a channel ID does not fit in a byte, or is given more than once.
+    /// * If the connection cannot be split into its sending and receiving halves.
+    pub fn new<IoHandler>(
+        conn: SecretConnection<IoHandler>,
+        io: impl Shutdown + 'static,
+        channels: &[ChannelDescriptor],
+        config: &MConnectionConfig,
+    ) -> Result<Self, Error>
+    where
+        IoHandler: Read + Write + Send + Sync + TryClone + 'static,
+        <IoHandler as TryClone>::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let (signals_tx, signals_rx) = flume::unbounded();
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let mut senders = HashMap::new();
+        let mut receivers = HashMap::new();
+        let mut send_channels = Vec::new();
+        let mut recv_channels = HashMap::new();
+        for desc in channels {
+            let id = desc.id.value();
+            let wire_id = u8::try_from(id).map_err(|_| Error::invalid_channel(id))?;
+            if senders.contains_key(&id) {
+                return Err(Error::invalid_channel(id));
+            }
+
+            let (queue_tx, queue_rx) = flume::bounded(desc.send_queue_capacity.max(1));
+            let (inbox_tx, inbox_rx) = flume::bounded(desc.recv_queue_capacity.max(1));
+            senders.insert(
+                id,
+                ChannelSender {
+                    queue: queue_tx,
+                    signals: signals_tx.clone(),
+                    shared: shared.clone(),
+                },
+            );
+            let taken = Arc::new(AtomicBool::new(false));
+            receivers.insert(id, (inbox_rx, taken.clone()));
+            send_channels.push(SendChannel {
+                id: i32::from(wire_id),
+                priority: desc.priority.max(1),
+                queue: queue_rx,
+                sending: None,
+                recently_sent: 0,
+            });
+            recv_channels.insert(
+                i32::from(wire_id),
+                RecvChannel {
+                    capacity: desc.recv_message_capacity,
+                    buffer: Vec::new(),
+                    inbox: inbox_tx,
+                    taken,
+                },
+            );
+        }
+
+        let (sender, receiver) = conn.split()?;
+        let io: Arc<dyn Shutdown> = Arc::new(io);
+
+        let send_routine = SendRoutine {
+            writer: BufWriter::with_capacity(DATA_MAX_SIZE, sender),
+            channels: send_channels,
+            signals: signals_rx,
+            limiter: RateLimiter::new(config.send_rate),
+            max_payload_size: config.max_packet_msg_payload_size.max(1),
+            ping_interval: config.ping_interval,
+            pong_timeout: config.pong_timeout,
+        };
+        let (send_shared, send_io) = (shared.clone(), io.clone());
+        let (stopping, send_stopped) = flume::bounded::<()>(0);
+        thread::spawn(move || {
+            if let Err(e) = send_routine.run() {
+                fail(&send_shared, &*send_io, &e);
+            }
+            drop(stopping);
+        });
+
+        let recv_routine = RecvRoutine {
+            reader: BufReader::with_capacity(DATA_MAX_SIZE, receiver),
+            channels: recv_channels,
+            signals: signals_tx.clone(),
+            limiter: RateLimiter::new(config.recv_rate),
+            max_packet_size: config.max_packet_msg_payload_size.max(1) + MAX_PACKET_MSG_OVERHEAD,
+        };
+        let (recv_shared, recv_io) = (shared.clone(), io.clone());
+        thread::spawn(move || recv_routine.run(&recv_shared, &*recv_io));
+
+        Ok(Self {
+            senders,
+            receivers: Mutex::new(receivers),
+            signals: signals_tx,
+            shared,
+            io,
+            send_stopped,
+        })
+    }
+
+    /// Returns a handle for sending messages on the given channel.
+    ///
+    /// # Errors
+    ///
+    /// * If the channel is not one of the connection.
+    pub fn sender(&self, id: channel::Id) -> Result<ChannelSender, Error> {
+        self.senders
+            .get(&id.value())
+            .cloned()
+            .ok_or_else(|| Error::unknown_channel(id.value()))
+    }
+
+    /// Returns the messages received on the given channel. Can only be called once per channel.
+    ///
+    /// Messages must be received promptly: the connection fails once more messages than the
+    /// [`ChannelDescriptor::recv_queue_capacity`] of the channel wait to be received. Packets are
+    /// read from the peer regardless, so that one channel cannot hold up the others.
+    ///
+    /// # Errors
+    ///
+    /// * If the channel is not one of the connection.
+    /// * If the receiver of the channel was already taken.
+    pub fn receiver(&self, id: channel::Id) -> Result<ChannelReceiver, Error> {
+        if !self.senders.contains_key(&id.value()) {
+            return Err(Error::unknown_channel(id.value()));
+        }
+        let (messages, taken) = self
+            .receivers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&id.value())
+            .ok_or_else(|| Error::receiver_taken(id.value()))?;
+        taken.store(true, Ordering::Release);
+        Ok(ChannelReceiver {
+            messages: messages.into_iter(),
+            shared: self.shared.clone(),
+            ended: false,
+        })
+    }
+
+    /// Closes the connection. Messages already queued are sent first, unless sending them takes
+    /// too long.
+    ///
+    /// # Errors
+    ///
+    /// * If the underlying I/O resource could not be shut down.
+    pub fn close(&self) -> Result<(), Error> {
+        self.shared
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .closing = true;
+        let _ = self.signals.send(Signal::Close);
+        let _ = self.send_stopped.recv_timeout(FLUSH_TIMEOUT);
+        self.io.shutdown()?;
+        Ok(())
+    }
+}
+
+impl Drop for MConnection {
+    fn drop(&mut self) {
+        // The peer may already be gone, in which case there is nothing left to release.
+        let _ = self.close();
+    }
+}
+
+/// Handle for sending messages on a channel of an [`MConnection`].
+#[derive(Clone)]
+pub struct ChannelSender {
+    queue: flume::Sender<Vec<u8>>,
+    signals: flume::Sender<Signal>,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl ChannelSender {
+    /// Queues the message for sending, waiting for room in the send queue of the channel if it
+    /// is full.
+    ///
+    /// # Errors
+    ///
+    /// * If the connection is closed.
+    pub fn send(&self, msg: Vec<u8>) -> Result<(), Error> {
+        self.check_open()?;
+        self.queue
+            .send(msg)
+            .map_err(|_| Error::connection_closed())?;
+        let _ = self.signals.send(Signal::Data);
+        Ok(())
+    }
+
+    /// Queues the message for sending if there is room in the send queue of the channel.
+    /// Returns whether the message was queued.
+    ///
+    /// # Errors
+    ///
+    /// * If the connection is closed.
+    pub fn try_send(&self, msg: Vec<u8>) -> Result<bool, Error> {
+        self.check_open()?;
+        match self.queue.try_send(msg) {
+            Ok(()) => {
+                let _ = self.signals.send(Signal::Data);
+                Ok(true)
+            },
+            Err(flume::TrySendError::Full(_)) => Ok(false),
+            Err(flume::TrySendError::Disconnected(_)) => Err(Error::connection_closed()),
+        }
+    }
+
+    /// The send routine may still be running for a moment after the connection is closed, so
+    /// the queue being open is not enough.
+    fn check_open(&self) -> Result<(), Error> {
+        let shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+        if shared.closing || shared.failure.is_some() {
+            return Err(Error::connection_closed());
+        }
+        drop(shared);
+        Ok(())
+    }
+}
+
+/// Messages received on a channel of an [`MConnection`].
+///
+/// Ends when the connection is closed. If the connection failed, the error is yielded last.
+pub struct ChannelReceiver {
+    messages: flume::IntoIter<Vec<u8>>,
+    shared: Arc<Mutex<Shared>>,
+    /// Set once the messages ran out and the failure, if any, was yielded.
+    ended: bool,
+}
+
+impl Iterator for ChannelReceiver {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(msg) = self.messages.next() {
+            return Some(Ok(msg));
+        }
+        if std::mem::replace(&mut self.ended, true) {
+            return None;
+        }
+        // The receive routine records the failure before it stops delivering messages.
+        let failure = self
+            .shared
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .failure
+            .clone();
+        failure.map(|failure| Err(Error::connection_failed(failure)))
+    }
+}
+
+/// Shuts down the connection after a routine failed, unless it is already being closed.
+fn fail(shared: &Mutex<Shared>, io: &dyn Shutdown, e: &Error) {
+    let mut shared = shared.lock().unwrap_or_else(PoisonError::into_inner);
+    if !shared.closing && shared.failure.is_none() {
+        shared.failure = Some(e.to_string());
+    }
+    drop(shared);
+    let _ = io.shutdown();
+}
+
+struct SendChannel {
+    id: i32,
+    priority: u32,
+    queue: flume::Receiver<Vec<u8>>,
+    /// Message being sent, and how much of it was sent already.
+    sending: Option<(Vec<u8>, usize)>,
+    /// Bytes sent recently, decaying over time.
+    recently_sent: u64,
+}
+
+impl SendChannel {
+    fn is_pending(&self) -> bool {
+        self.sending.is_some() || !self.queue.is_empty()
+    }
+
+    /// Takes the next packet to send off the current message.
+    fn next_packet(&mut self, max_payload_size: usize) -> Option<PacketMsg> {
+        let (msg, offset) = match self.sending.take() {
+            Some(sending) => sending,
+            None => (self.queue.try_recv().ok()?, 0),
+        };
+        let end = msg.len().min(offset + max_payload_size);
+        let packet = PacketMsg {
+            channel_id: self.id,
+            eof: end == msg.len(),
+            data: msg[offset..end].to_vec(),
+        };
+        if !packet.eof {
+            self.sending = Some((msg, end));
+        }
+        Some(packet)
+    }
+}
+
+struct SendRoutine<W: Write> {
+    writer: BufWriter<W>,
+    channels: Vec<SendChannel>,
+    signals: flume::Receiver<Signal>,
+    limiter: RateLimiter,
+    max_payload_size: usize,
+    ping_interval: Duration,
+    pong_timeout: Duration,
+}
+
+impl<W: Write> SendRoutine<W> {
+    fn run(mut self) -> Result<(), Error> {
+        let mut next_ping = Instant::now() + self.ping_interval;
+        let mut next_decay = Instant::now() + STATS_INTERVAL;
+        let mut pong_deadline = None;
+
+        loop {
+            while self.send_packet_msg()? {}
+            self.writer.flush()?;
+
+            let deadline = pong_deadline.map_or(next_ping, |d: Instant| d.min(next_ping));
+            match self.signals.recv_deadline(deadline.min(next_decay)) {
+                Ok(Signal::Data) | Err(RecvTimeoutError::Timeout) => {},
+                Ok(Signal::SendPong) => {
+                    self.write_packet(Sum::PacketPong(PacketPong {}))?;
+                },
+                Ok(Signal::PongReceived) => pong_deadline = None,
+                Ok(Signal::Close) | Err(RecvTimeoutError::Disconnected) => {
+                    // Send what was queued in the meantime, without touching the connection
+                    // otherwise: it may be closing because the peer is gone.
+                    let mut sent = false;
+                    while self.send_packet_msg()? {
+                        sent = true;
+                    }
+                    if sent {
+                        self.writer.flush()?;
+                    }
+                    return Ok(());
+                },
+            }
+
+            let now = Instant::now();
+            if pong_deadline.is_some_and(|deadline| now >= deadline) {
+                return Err(Error::pong_timeout());
+            }
+            if now >= next_ping {
+                self.write_packet(Sum::PacketPing(PacketPing {}))?;
+                next_ping = now + self.ping_interval;
+                pong_deadline.get_or_insert(now + self.pong_timeout);
+            }
+            if now >= next_decay {
+                for channel in &mut self.channels {
+                    channel.recently_sent = channel.recently_sent * 4 / 5;
+                }
+                next_decay = now + STATS_INTERVAL;
+            }
+        }
+    }
+
+    /// Sends a packet of the pending channel which recently sent the least relative to its
+    /// priority. Returns whether there was anything to send.
+    fn send_packet_msg(&mut self) -> Result<bool, Error> {
+        let Some(index) = self
+            .channels
+            .iter()
+            .enumerate()
+            .filter(|(_, channel)| channel.is_pending())
+            .min_by(|(_, a), (_, b)| {
+                // Compare `a.recently_sent / a.priority` to `b.recently_sent / b.priority`.
+                (u128::from(a.recently_sent) * u128::from(b.priority))
+                    .cmp(&(u128::from(b.recently_sent) * u128::from(a.priority)))
+            })
+            .map(|(index, _)| index)
+        else {
+            return Ok(false);
+        };
+        let Some(packet) = self.channels[index].next_packet(self.max_payload_size) else {
+            return Ok(false);
+        };
+
+        let sent = self.write_packet(Sum::PacketMsg(packet))?;
+        let channel = &mut self.channels[index];
+        channel.recently_sent = channel.recently_sent.saturating_add(sent as u64);
+        Ok(true)
+    }
+
+    /// Writes the packet, and returns its size on the wire.
+    fn write_packet(&mut self, sum: Sum) -> Result<usize, Error> {
+        let bytes = Packet { sum: Some(sum) }.encode_length_delimited_to_vec();
+        self.limiter.acquire(bytes.len());
+        self.writer.write_all(&bytes)?;
+        Ok(bytes.len())
+    }
+}
+
+struct RecvChannel {
+    capacity: usize,
+    /// Message being received.
+    buffer: Vec<u8>,
+    inbox: flume::Sender<Vec<u8>>,
+    /// Whether the receiver of the channel was taken.
+    taken: Arc<AtomicBool>,
+}
+
+struct RecvRoutine<R: Read> {
+    reader: BufReader<R>,
+    channels: HashMap<i32, RecvChannel>,
+    signals: flume::Sender<Signal>,
+    limiter: RateLimiter,
+    max_packet_size: usize,
+}
+
+impl<R: Read> RecvRoutine<R> {
+    fn run(mut self, shared: &Mutex<Shared>, io: &dyn Shutdown) {
+        let result = self.recv_packets();
+        if let Err(e) = &result {
+            fail(shared, io, e);
+        }
+        // Stop the send routine too. The receivers see the failure, if any, once the channels
+        // are dropped.
+        let _ = self.signals.send(Signal::Close);
+    }
+
+    fn recv_packets(&mut self) -> Result<(), Error> {
+        while let Some(packet) = self.read_packet()? {
+            match packet.sum {
+                Some(Sum::PacketPing(_)) => {
+                    let _ = self.signals.send(Signal::SendPong);
+                },
+                Some(Sum::PacketPong(_)) => {
+                    let _ = self.signals.send(Signal::PongReceived);
+                },
+                Some(Sum::PacketMsg(msg)) => {
+                    let channel = self.channels.get_mut(&msg.channel_id).ok_or_else(|| {
+                        Error::unknown_channel(u64::from(msg.channel_id.unsigned_abs()))
+                    })?;
+                    if channel.buffer.len() + msg.data.len() > channel.capacity {
+                        return Err(Error::message_too_large(
+                            (channel.buffer.len() + msg.data.len()) as u64,
+                            channel.capacity,
+                        ));
+                    }
+                    channel.buffer.extend_from_slice(&msg.data);
+                    if msg.eof {
+                        // Never wait for room in the inbox, which would stop reading the other
+                        // channels and the pongs as well.
+                        match channel.inbox.try_send(std::mem::take(&mut channel.buffer)) {
+                            // The receiver was dropped, or nobody took it to read the channel.
+                            Ok(()) | Err(flume::TrySendError::Disconnected(_)) => {},
+                            Err(flume::TrySendError::Full(_))
+                                if !channel.taken.load(Ordering::Acquire) => {},
+                            Err(flume::TrySendError::Full(_)) => {
+                                return Err(Error::recv_queue_full(u64::from(
+                                    msg.channel_id.unsigned_abs(),
+                                )));
+                            },
+                        }
+                    }
+                },
+                None => return Err(Error::protocol()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the next packet, or `None` if the connection was closed between two packets.
+    fn read_packet(&mut self) -> Result<Option<Packet>, Error> {
+        let Some(len) = read_varint(&mut self.reader)? else {
+            return Ok(None);
+        };
+        if len > self.max_packet_size as u64 {
+            return Err(Error::message_too_large(len, self.max_packet_size));
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let mut buf = vec![0; len as usize];
+        self.reader.read_exact(&mut buf)?;
+        self.limiter.acquire(buf.len());
+
+        Packet::decode(buf.as_slice())
+            .map(Some)
+            .map_err(Error::decode)
+    }
+}
+
+/// Reads an unsigned varint, or `None` if the reader is at its end.
+pub(crate) fn read_varint<R: Read>(reader: &mut R) -> Result<Option<u64>, Error> {
+    let mut decoder = VarintDecoder::default();
+    loop {
+        let mut byte = 0_u8;
+        match reader.read_exact(std::slice::from_mut(&mut byte)) {
+            Err(e) if decoder.is_empty() && e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(None)
+            },
+            result => result?,
+        }
+        if let Some(value) = decoder.push(byte)? {
+            return Ok(Some(value));
+        }
+    }
+}
+
+/// Decodes an unsigned varint fed one byte at a time, for readers of any kind.
+#[derive(Default)]
+pub(crate) struct VarintDecoder {
+    value: u64,
+    shift: u32,
+}
+
+impl VarintDecoder {
+    /// Whether no byte was fed yet.
+    pub(crate) const fn is_empty(&self) -> bool {
+        self.shift == 0
+    }
+
+    /// Feeds the next byte, returning the value once it is complete.
+    ///
+    /// # Errors
+    ///
+    /// * If the varint does not fit in 64 bits.
+    pub(crate) fn push(&mut self, byte: u8) -> Result<Option<u64>, Error> {
+        // Only the lowest bit of the tenth byte fits, as in prost's `decode_varint`.
+        if self.shift > 63 || (self.shift == 63 && byte > 1) {
+            return Err(Error::protocol());
+        }
+        self.value |= u64::from(byte & 0x7f) << self.shift;
+        if byte & 0x80 == 0 {
+            return Ok(Some(self.value));
+        }
+        self.shift += 7;
+        Ok(None)
+    }
+}
+
+/// Limits the average rate of a transfer, allowing bursts of up to a second's worth of data.
+struct RateLimiter {
+    /// Bytes per second, or zero if unlimited.
+    rate: u64,
+    /// Bytes that can be transferred without waiting. Negative when overdrawn.
+    allowance: f64,
+    last: Instant,
+}
+
+#[allow(clippy::cast_precision_loss)]
+impl RateLimiter {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate,
+            allowance: rate as f64,
+            last: Instant::now(),
+        }
+    }
+
+    /// Accounts for the transfer of `n` bytes, first waiting as long as needed to stay within
+    /// the rate.
+    fn acquire(&mut self, n: usize) {
+        if self.rate == 0 {
+            return;
+        }
+        let rate = self.rate as f64;
+        let now = Instant::now();
+        let refill = now.duration_since(self.last).as_secs_f64() * rate;
+        self.allowance = (self.allowance + refill).min(rate);
+        self.last = now;
+
+        if self.allowance < n as f64 {
+            thread::sleep(Duration::from_secs_f64((n as f64 - self.allowance) / rate));
+        }
+        self.allowance -= n as f64;
+    }
+}
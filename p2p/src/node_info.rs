@@ -0,0 +1,180 @@
+//! Exchange of the [`node::Info`] of peers, which follows the `SecretConnection` handshake, and
+//! checks that peers can talk to each other.
+//!
+//! [Specification](https://github.com/cometbft/cometbft/blob/main/spec/p2p/legacy-docs/peer.md#cometbft-version-handshake)
+
+use std::{
+    collections::HashSet,
+    io::{self, Read, Write},
+};
+
+use tendermint::node;
+use tendermint_proto::{v0_38::p2p::DefaultNodeInfo as RawDefaultNodeInfo, Protobuf};
+
+use crate::{error::Error, mconnection::read_varint, secret_connection::SecretConnection};
+
+/// Maximum size of an encoded node info, in bytes.
+pub const MAX_NODE_INFO_SIZE: usize = 10_240;
+
+/// Maximum number of channels a node can advertise.
+pub const MAX_NUM_CHANNELS: usize = 16;
+
+/// Sends the local node info over the connection, and receives that of the peer.
+///
+/// The node info of the peer is validated, and checked to be compatible with the local one.
+///
+/// # Errors
+///
+/// * If the node info cannot be sent or received.
+/// * If the node info of the peer is invalid, or was not sent by the authenticated peer.
+/// * If the peer is not compatible with the local node, see [`check_compatible`].
+pub fn exchange<IoHandler>(
+    conn: &mut SecretConnection<IoHandler>,
+    local_info: &node::Info,
+) -> Result<node::Info, Error>
+where
+    IoHandler: Read + Write + Send + Sync,
+{
+    conn.write_all(&encode(local_info))?;
+    conn.flush()?;
+
+    let len = read_varint(conn)?.ok_or_else(|| Error::io(io::ErrorKind::UnexpectedEof.into()))?;
+    let mut buf = vec![0; checked_len(len)?];
+    conn.read_exact(&mut buf)?;
+
+    let remote_info = decode(&buf)?;
+    check_remote(conn.remote_pubkey().peer_id(), local_info, &remote_info)?;
+    Ok(remote_info)
+}
+
+/// Sends the local node info over the connection, and receives that of the peer, like
+/// [`exchange`] does over a blocking connection.
+///
+/// # Errors
+///
+/// * If the node info cannot be sent or received.
+/// * If the node info of the peer is invalid, or was not sent by the authenticated peer.
+/// * If the peer is not compatible with the local node, see [`check_compatible`].
+#[cfg(feature = "tokio")]
+pub async fn exchange_async<IoHandler>(
+    conn: &mut crate::secret_connection::AsyncSecretConnection<IoHandler>,
+    local_info: &node::Info,
+) -> Result<node::Info, Error>
+where
+    IoHandler: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send,
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    conn.write_all(&encode(local_info)).await?;
+    conn.flush().await?;
+
+    let mut decoder = crate::mconnection::VarintDecoder::default();
+    let len = loop {
+        if let Some(len) = decoder.push(conn.read_u8().await?)? {
+            break len;
+        }
+    };
+    let mut buf = vec![0; checked_len(len)?];
+    conn.read_exact(&mut buf).await?;
+
+    let remote_info = decode(&buf)?;
+    check_remote(conn.remote_pubkey().peer_id(), local_info, &remote_info)?;
+    Ok(remote_info)
+}
+
+/// Checks that the node info is well-formed.
+///
+/// # Errors
+///
+/// * If there are more than [`MAX_NUM_CHANNELS`] channels, or duplicate channels.
+/// * If the moniker is empty.
+pub fn validate(info: &node::Info) -> Result<(), Error> {
+    let channels = info.channels.ids();
+    if channels.len() > MAX_NUM_CHANNELS {
+        return Err(Error::invalid_node_info(format!(
+            "{} channels, more than the maximum of {MAX_NUM_CHANNELS}",
+            channels.len()
+        )));
+    }
+    let mut seen = HashSet::new();
+    if let Some(id) = channels.iter().find(|&&id| !seen.insert(id)) {
+        return Err(Error::invalid_node_info(format!(
+            "duplicate channel {id:#04x}"
+        )));
+    }
+    if info.moniker.as_ref().is_empty() {
+        return Err(Error::invalid_node_info("empty moniker".to_owned()));
+    }
+    Ok(())
+}
+
+/// Checks that the remote node can talk to the local one.
+///
+/// # Errors
+///
+/// * If the nodes are on different networks.
+/// * If the nodes have different block or p2p protocol versions.
+/// * If the local node has channels, but none in common with the remote node.
+pub fn check_compatible(local: &node::Info, remote: &node::Info) -> Result<(), Error> {
+    if local.protocol_version.block != remote.protocol_version.block {
+        return Err(Error::incompatible_block_version(
+            local.protocol_version.block,
+            remote.protocol_version.block,
+        ));
+    }
+    if local.protocol_version.p2p != remote.protocol_version.p2p {
+        return Err(Error::incompatible_p2p_version(
+            local.protocol_version.p2p,
+            remote.protocol_version.p2p,
+        ));
+    }
+    if local.network != remote.network {
+        return Err(Error::incompatible_network(
+            local.network.clone(),
+            remote.network.clone(),
+        ));
+    }
+
+    // A node without channels, e.g. a seed crawler, talks to anybody.
+    let local_channels = local.channels.ids();
+    if !local_channels.is_empty()
+        && !remote
+            .channels
+            .ids()
+            .iter()
+            .any(|id| local_channels.contains(id))
+    {
+        return Err(Error::no_common_channels());
+    }
+    Ok(())
+}
+
+fn encode(info: &node::Info) -> Vec<u8> {
+    Protobuf::<RawDefaultNodeInfo>::encode_length_delimited_vec(info.clone())
+}
+
+fn decode(buf: &[u8]) -> Result<node::Info, Error> {
+    <node::Info as Protobuf<RawDefaultNodeInfo>>::decode_vec(buf)
+        .map_err(|e| Error::invalid_node_info(e.to_string()))
+}
+
+fn checked_len(len: u64) -> Result<usize, Error> {
+    usize::try_from(len)
+        .ok()
+        .filter(|&len| len <= MAX_NODE_INFO_SIZE)
+        .ok_or_else(|| Error::message_too_large(len, MAX_NODE_INFO_SIZE))
+}
+
+/// Checks that the node info was sent by the peer it describes, and that the peer is valid and
+/// compatible with the local node.
+fn check_remote(
+    peer_id: node::Id,
+    local_info: &node::Info,
+    remote_info: &node::Info,
+) -> Result<(), Error> {
+    if remote_info.id != peer_id {
+        return Err(Error::remote_peer_mismatch(peer_id, remote_info.id));
+    }
+    validate(remote_info)?;
+    check_compatible(local_info, remote_info)
+}
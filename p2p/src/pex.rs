@@ -0,0 +1,136 @@
+//! Peer exchange (PEX), through which nodes discover the addresses of other peers.
+//!
+//! Known addresses are kept in an [`AddrBook`], and exchanged with other peers over the
+//! [`StreamId::Pex`](crate::transport::StreamId::Pex) stream by the [`PexReactor`], following
+//! the PEX protocol of `CometBFT`.
+
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use tendermint::node;
+use tendermint_proto::v0_38::p2p::NetAddress as RawNetAddress;
+
+pub use self::{
+    addr_book::AddrBook,
+    reactor::{PexConfig, PexReactor},
+};
+use crate::error::Error;
+
+mod addr_book;
+mod reactor;
+
+/// Maximum number of addresses sent in a single `PexAddrs` message.
+pub const MAX_ADDRS_PER_MESSAGE: usize = 250;
+
+/// Address of a peer: its node ID, and the socket address it accepts connections on.
+///
+/// Formatted as `<id>@<ip>:<port>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "RawNetAddress", into = "RawNetAddress")]
+pub struct NetAddress {
+    /// ID of the peer.
+    pub id: node::Id,
+    /// Socket address of the peer.
+    pub addr: SocketAddr,
+}
+
+impl NetAddress {
+    /// Creates the address of the peer with the given ID, listening on `addr`.
+    #[must_use]
+    pub const fn new(id: node::Id, addr: SocketAddr) -> Self {
+        Self { id, addr }
+    }
+
+    /// Whether the address is reachable on the public internet, i.e. it is not a loopback,
+    /// private, link-local, documentation or otherwise reserved address.
+    #[must_use]
+    pub const fn is_routable(&self) -> bool {
+        self.addr.port() != 0 && is_routable_ip(self.addr.ip())
+    }
+}
+
+impl fmt::Display for NetAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.id, self.addr)
+    }
+}
+
+impl FromStr for NetAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, addr) = s
+            .split_once('@')
+            .ok_or_else(|| Error::invalid_net_address(format!("missing node ID in {s}")))?;
+        Ok(Self {
+            id: id
+                .parse()
+                .map_err(|e| Error::invalid_net_address(format!("{e}")))?,
+            addr: addr
+                .parse()
+                .map_err(|e| Error::invalid_net_address(format!("{e}")))?,
+        })
+    }
+}
+
+impl TryFrom<RawNetAddress> for NetAddress {
+    type Error = Error;
+
+    fn try_from(raw: RawNetAddress) -> Result<Self, Self::Error> {
+        let id = raw
+            .id
+            .parse()
+            .map_err(|e| Error::invalid_net_address(format!("{e}")))?;
+        let ip: IpAddr = raw
+            .ip
+            .parse()
+            .map_err(|e| Error::invalid_net_address(format!("{e}")))?;
+        let port = u16::try_from(raw.port)
+            .map_err(|_| Error::invalid_net_address(format!("invalid port {}", raw.port)))?;
+        Ok(Self::new(id, SocketAddr::new(ip, port)))
+    }
+}
+
+impl From<NetAddress> for RawNetAddress {
+    fn from(addr: NetAddress) -> Self {
+        Self {
+            id: addr.id.to_string(),
+            ip: addr.addr.ip().to_string(),
+            port: addr.addr.port().into(),
+        }
+    }
+}
+
+/// Whether the IP address is reachable on the public internet.
+pub(crate) const fn is_routable_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            // RFC 6598 shared address space.
+            let shared = a == 100 && (b & 0xc0) == 64;
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || shared)
+        },
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            // RFC 4193 unique local, RFC 4862 link-local and RFC 3849 documentation.
+            let unique_local = (segments[0] & 0xfe00) == 0xfc00;
+            let link_local = (segments[0] & 0xffc0) == 0xfe80;
+            let documentation = segments[0] == 0x2001 && segments[1] == 0x0db8;
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || unique_local
+                || link_local
+                || documentation)
+        },
+    }
+}
@@ -0,0 +1,639 @@
+//! Book of the known peer addresses, bucketed like the `addrbook` of `CometBFT`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    fs, io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tendermint::node;
+
+use super::{is_routable_ip, NetAddress, MAX_ADDRS_PER_MESSAGE};
+use crate::error::Error;
+
+/// Number of buckets for addresses which were never successfully connected to.
+const NEW_BUCKET_COUNT: usize = 256;
+/// Maximum number of addresses in a bucket of new addresses.
+const NEW_BUCKET_SIZE: usize = 64;
+/// Number of buckets for addresses which were successfully connected to.
+const OLD_BUCKET_COUNT: usize = 64;
+/// Maximum number of addresses in a bucket of old addresses.
+const OLD_BUCKET_SIZE: usize = 64;
+/// Number of new buckets addresses from the same source group can end up in.
+const NEW_BUCKETS_PER_GROUP: u64 = 32;
+/// Number of old buckets addresses from the same group can end up in.
+const OLD_BUCKETS_PER_GROUP: u64 = 4;
+/// Maximum number of new buckets an address can be in.
+const MAX_NEW_BUCKETS_PER_ADDRESS: usize = 4;
+
+/// Addresses not attempted for this many days are considered bad.
+const NUM_MISSING_DAYS: u64 = 7;
+/// Addresses never connected to after this many attempts are considered bad.
+const NUM_RETRIES: u32 = 3;
+/// Addresses failing this many times in a row over `MIN_BAD_DAYS` are considered bad.
+const MAX_FAILURES: u32 = 10;
+const MIN_BAD_DAYS: u64 = 7;
+
+/// Share of the book, in percent, returned by [`AddrBook::selection`].
+const GET_SELECTION_PERCENT: usize = 23;
+/// Minimum number of addresses returned by [`AddrBook::selection`], if the book has as many.
+const MIN_GET_SELECTION: usize = 32;
+
+/// Below this number of addresses, the book needs more of them.
+const NEED_ADDRESS_THRESHOLD: usize = 1000;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Book of the addresses of the peers known to the local node.
+///
+/// As in `CometBFT`, addresses which were never successfully connected to are kept in "new"
+/// buckets, and are moved to "old" buckets once marked as good. The bucket of an address is
+/// derived from its network group, the group of the peer which advertised it and a key secret
+/// to the book, so that a single source cannot fill the book with addresses of its choice.
+///
+/// Banned peers are not persisted.
+pub struct AddrBook {
+    path: Option<PathBuf>,
+    key: String,
+    routability_strict: bool,
+    our_addrs: HashSet<NetAddress>,
+    private_ids: HashSet<node::Id>,
+    addrs: HashMap<node::Id, KnownAddress>,
+    bad_peers: HashMap<node::Id, KnownAddress>,
+    new_buckets: Vec<HashSet<node::Id>>,
+    old_buckets: Vec<HashSet<node::Id>>,
+    n_new: usize,
+    n_old: usize,
+}
+
+impl AddrBook {
+    /// Creates an empty book, kept in memory only.
+    ///
+    /// With `routability_strict`, addresses which are not routable on the public internet are
+    /// rejected.
+    #[must_use]
+    pub fn new(routability_strict: bool) -> Self {
+        let mut key = [0_u8; 12];
+        OsRng.fill_bytes(&mut key);
+        let key = key.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        });
+
+        Self {
+            path: None,
+            key,
+            routability_strict,
+            our_addrs: HashSet::new(),
+            private_ids: HashSet::new(),
+            addrs: HashMap::new(),
+            bad_peers: HashMap::new(),
+            new_buckets: vec![HashSet::new(); NEW_BUCKET_COUNT],
+            old_buckets: vec![HashSet::new(); OLD_BUCKET_COUNT],
+            n_new: 0,
+            n_old: 0,
+        }
+    }
+
+    /// Loads the book persisted as JSON at `path`, which [`AddrBook::save`] then writes to.
+    ///
+    /// The book starts empty if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read.
+    /// * If the file does not hold a valid address book.
+    pub fn load(path: impl Into<PathBuf>, routability_strict: bool) -> Result<Self, Error> {
+        let path = path.into();
+        let mut book = Self::new(routability_strict);
+        match fs::read(&path) {
+            Ok(bytes) => {
+                let file: AddrBookFile = serde_json::from_slice(&bytes).map_err(|e| {
+                    Error::invalid_addr_book(path.display().to_string(), e.to_string())
+                })?;
+                book.restore(file).map_err(|reason| {
+                    Error::invalid_addr_book(path.display().to_string(), reason)
+                })?;
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+            Err(e) => return Err(e.into()),
+        }
+        book.path = Some(path);
+        Ok(book)
+    }
+
+    /// Persists the book as JSON to the file it was loaded from. Does nothing for books kept in
+    /// memory only.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be written.
+    pub fn save(&self) -> Result<(), Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut addrs = self.addrs.values().cloned().collect::<Vec<_>>();
+        addrs.sort_by_key(|ka| ka.addr.id);
+        let file = AddrBookFile {
+            key: self.key.clone(),
+            addrs,
+        };
+        let json = serde_json::to_vec_pretty(&file).map_err(io::Error::from)?;
+
+        // Write to a temporary file first, so that a crash never leaves a truncated book.
+        let tmp = temporary_path(path);
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Records an address of the local node, which is never added to the book.
+    pub fn add_our_address(&mut self, addr: NetAddress) {
+        self.our_addrs.insert(addr);
+    }
+
+    /// Records IDs of peers which must not be gossiped, and are thus never added to the book.
+    pub fn add_private_ids(&mut self, ids: impl IntoIterator<Item = node::Id>) {
+        self.private_ids.extend(ids);
+    }
+
+    /// Adds an address advertised by the peer at `src`.
+    ///
+    /// Addresses already known are added to further buckets with a decreasing probability, so
+    /// that addresses advertised by many peers are more likely to be kept.
+    ///
+    /// # Errors
+    ///
+    /// * If the address is one of the local node.
+    /// * If the ID of the address is private.
+    /// * If the address is not routable, and the book is strict about routability.
+    /// * If the peer is banned.
+    pub fn add_address(&mut self, addr: NetAddress, src: NetAddress) -> Result<(), Error> {
+        let reject = |reason: &str| Err(Error::address_rejected(addr.to_string(), reason.into()));
+        if self.our_addrs.contains(&addr) {
+            return reject("address of the local node");
+        }
+        if self.private_ids.contains(&addr.id) {
+            return reject("private peer ID");
+        }
+        if self.routability_strict && !addr.is_routable() {
+            return reject("non-routable address");
+        }
+        if self.is_banned(addr.id) {
+            return reject("banned peer");
+        }
+
+        match self.addrs.get(&addr.id) {
+            Some(ka) => {
+                if ka.is_old() || ka.buckets.len() >= MAX_NEW_BUCKETS_PER_ADDRESS {
+                    return Ok(());
+                }
+                // The more buckets the address is in, the less likely it is added to another.
+                let factor = 2 * ka.buckets.len() as u64;
+                if !OsRng.next_u64().is_multiple_of(factor) {
+                    return Ok(());
+                }
+            },
+            None => {
+                self.addrs.insert(addr.id, KnownAddress::new(addr, src));
+            },
+        }
+
+        let bucket = self.calc_new_bucket(&addr, &src);
+        self.add_to_new_bucket(addr.id, bucket);
+        Ok(())
+    }
+
+    /// Removes the address of the given peer from the book.
+    pub fn remove_address(&mut self, id: node::Id) {
+        let Some(ka) = self.addrs.remove(&id) else {
+            return;
+        };
+        let buckets = match ka.bucket_type {
+            BucketType::New => {
+                self.n_new -= 1;
+                &mut self.new_buckets
+            },
+            BucketType::Old => {
+                self.n_old -= 1;
+                &mut self.old_buckets
+            },
+        };
+        for &bucket in &ka.buckets {
+            buckets[bucket].remove(&id);
+        }
+    }
+
+    /// Records an attempt to connect to the given peer.
+    pub fn mark_attempt(&mut self, id: node::Id) {
+        if let Some(ka) = self.addrs.get_mut(&id) {
+            ka.attempts += 1;
+            ka.last_attempt = now();
+        }
+    }
+
+    /// Records a successful connection to the given peer, moving its address to the old
+    /// buckets.
+    pub fn mark_good(&mut self, id: node::Id) {
+        let Some(ka) = self.addrs.get_mut(&id) else {
+            return;
+        };
+        let now = now();
+        ka.attempts = 0;
+        ka.last_attempt = now;
+        ka.last_success = now;
+        if !ka.is_old() {
+            self.move_to_old(id);
+        }
+    }
+
+    /// Removes the address of the given peer from the book, and bans the peer for
+    /// `ban_duration`.
+    pub fn mark_bad(&mut self, id: node::Id, ban_duration: Duration) {
+        let Some(mut ka) = self.addrs.get(&id).cloned() else {
+            return;
+        };
+        self.remove_address(id);
+        ka.buckets.clear();
+        ka.bucket_type = BucketType::New;
+        ka.last_ban_time = now().saturating_add(ban_duration.as_secs());
+        self.bad_peers.insert(id, ka);
+    }
+
+    /// Adds the addresses of the peers whose ban expired back to the book.
+    pub fn reinstate_bad_peers(&mut self) {
+        let now = now();
+        let expired = self
+            .bad_peers
+            .values()
+            .filter(|ka| ka.last_ban_time <= now)
+            .map(|ka| ka.addr.id)
+            .collect::<Vec<_>>();
+        for id in expired {
+            if let Some(mut ka) = self.bad_peers.remove(&id) {
+                ka.last_ban_time = 0;
+                let bucket = self.calc_new_bucket(&ka.addr, &ka.src);
+                self.addrs.insert(id, ka);
+                self.add_to_new_bucket(id, bucket);
+            }
+        }
+    }
+
+    /// Whether the given peer is currently banned.
+    #[must_use]
+    pub fn is_banned(&self, id: node::Id) -> bool {
+        self.bad_peers
+            .get(&id)
+            .is_some_and(|ka| ka.last_ban_time > now())
+    }
+
+    /// Whether the address of the given peer is in the book.
+    #[must_use]
+    pub fn has_address(&self, id: node::Id) -> bool {
+        self.addrs.contains_key(&id)
+    }
+
+    /// Whether the given peer was successfully connected to, i.e. its address is in an old
+    /// bucket.
+    #[must_use]
+    pub fn is_good(&self, id: node::Id) -> bool {
+        self.addrs.get(&id).is_some_and(KnownAddress::is_old)
+    }
+
+    /// Returns the last time the given peer was attempted to be connected to, if ever.
+    #[must_use]
+    pub fn last_attempt(&self, id: node::Id) -> Option<SystemTime> {
+        self.addrs
+            .get(&id)
+            .filter(|ka| ka.last_attempt != 0)
+            .map(|ka| UNIX_EPOCH + Duration::from_secs(ka.last_attempt))
+    }
+
+    /// Number of addresses in the book.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.n_new + self.n_old
+    }
+
+    /// Whether the book holds no address.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    /// Whether the book has too few addresses, and more should be requested from peers.
+    #[must_use]
+    pub const fn need_more_addrs(&self) -> bool {
+        self.size() < NEED_ADDRESS_THRESHOLD
+    }
+
+    /// Picks an address at random, to connect to.
+    ///
+    /// `bias_towards_new`, between 0 and 100, is how much addresses never connected to are
+    /// favoured over the ones already connected to.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn pick_address(&self, bias_towards_new: u8) -> Option<NetAddress> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let bias = bias_towards_new.min(100);
+        let new_correlation = (self.n_new as f64).sqrt() * f64::from(bias);
+        let old_correlation = (self.n_old as f64).sqrt() * f64::from(100 - bias);
+        let random = OsRng.next_u64() as f64 / u64::MAX as f64;
+        let pick_old = (new_correlation + old_correlation) * random < old_correlation;
+
+        let buckets = if (pick_old && self.n_old > 0) || self.n_new == 0 {
+            &self.old_buckets
+        } else {
+            &self.new_buckets
+        };
+        let non_empty = buckets
+            .iter()
+            .filter(|bucket| !bucket.is_empty())
+            .collect::<Vec<_>>();
+        let bucket = non_empty[random_index(non_empty.len())];
+        let id = bucket.iter().nth(random_index(bucket.len()))?;
+        self.addrs.get(id).map(|ka| ka.addr)
+    }
+
+    /// Returns a random selection of the addresses in the book, to send to peers.
+    ///
+    /// The selection holds a share of the book, of at most [`MAX_ADDRS_PER_MESSAGE`]
+    /// addresses.
+    #[must_use]
+    pub fn selection(&self) -> Vec<NetAddress> {
+        let size = self.size();
+        let count = MIN_GET_SELECTION
+            .min(size)
+            .max(size * GET_SELECTION_PERCENT / 100)
+            .min(MAX_ADDRS_PER_MESSAGE);
+
+        let mut addrs = self.addrs.values().map(|ka| ka.addr).collect::<Vec<_>>();
+        // Partial Fisher-Yates shuffle of the first `count` addresses.
+        for i in 0..count {
+            let j = i + random_index(addrs.len() - i);
+            addrs.swap(i, j);
+        }
+        addrs.truncate(count);
+        addrs
+    }
+
+    fn add_to_new_bucket(&mut self, id: node::Id, bucket: usize) {
+        if self.new_buckets[bucket].contains(&id) || self.is_good(id) {
+            return;
+        }
+        if self.new_buckets[bucket].len() >= NEW_BUCKET_SIZE {
+            self.expire_new(bucket);
+        }
+
+        self.new_buckets[bucket].insert(id);
+        if let Some(ka) = self.addrs.get_mut(&id) {
+            ka.buckets.push(bucket);
+            if ka.buckets.len() == 1 {
+                self.n_new += 1;
+            }
+        }
+    }
+
+    /// Makes room in a full new bucket, by removing a bad address or else the oldest one.
+    fn expire_new(&mut self, bucket: usize) {
+        let now = now();
+        let ids = &self.new_buckets[bucket];
+        let expired = ids
+            .iter()
+            .find(|id| self.addrs[*id].is_bad(now))
+            .or_else(|| ids.iter().min_by_key(|id| self.addrs[*id].last_attempt))
+            .copied();
+        if let Some(id) = expired {
+            self.remove_from_new_bucket(id, bucket);
+        }
+    }
+
+    fn remove_from_new_bucket(&mut self, id: node::Id, bucket: usize) {
+        self.new_buckets[bucket].remove(&id);
+        if let Some(ka) = self.addrs.get_mut(&id) {
+            ka.buckets.retain(|&b| b != bucket);
+            if ka.buckets.is_empty() {
+                self.addrs.remove(&id);
+                self.n_new -= 1;
+            }
+        }
+    }
+
+    fn move_to_old(&mut self, id: node::Id) {
+        let Some(ka) = self.addrs.get_mut(&id) else {
+            return;
+        };
+        for bucket in ka.buckets.drain(..) {
+            self.new_buckets[bucket].remove(&id);
+        }
+        self.n_new -= 1;
+        let addr = ka.addr;
+
+        let bucket = self.calc_old_bucket(&addr);
+        if self.old_buckets[bucket].len() >= OLD_BUCKET_SIZE {
+            // Make room by moving the oldest address of the bucket back to a new bucket.
+            let oldest = self.old_buckets[bucket]
+                .iter()
+                .min_by_key(|id| self.addrs[*id].last_attempt)
+                .copied();
+            if let Some(oldest) = oldest {
+                self.old_buckets[bucket].remove(&oldest);
+                self.n_old -= 1;
+                let ka = self.addrs.get_mut(&oldest).expect("address in bucket");
+                ka.buckets.clear();
+                ka.bucket_type = BucketType::New;
+                let (addr, src) = (ka.addr, ka.src);
+                let new_bucket = self.calc_new_bucket(&addr, &src);
+                self.add_to_new_bucket(oldest, new_bucket);
+            }
+        }
+
+        self.old_buckets[bucket].insert(id);
+        if let Some(ka) = self.addrs.get_mut(&id) {
+            ka.bucket_type = BucketType::Old;
+            ka.buckets = vec![bucket];
+        }
+        self.n_old += 1;
+    }
+
+    fn restore(&mut self, file: AddrBookFile) -> Result<(), String> {
+        self.key = file.key;
+        for ka in file.addrs {
+            let id = ka.addr.id;
+            let (buckets, count) = match ka.bucket_type {
+                BucketType::New => (&mut self.new_buckets, &mut self.n_new),
+                BucketType::Old => (&mut self.old_buckets, &mut self.n_old),
+            };
+            let valid = match ka.bucket_type {
+                BucketType::New => !ka.buckets.is_empty(),
+                BucketType::Old => ka.buckets.len() == 1,
+            };
+            if !valid || ka.buckets.iter().any(|&bucket| bucket >= buckets.len()) {
+                return Err(format!("invalid buckets for address {}", ka.addr));
+            }
+            if self.addrs.contains_key(&id) {
+                return Err(format!("duplicate address {}", ka.addr));
+            }
+
+            for &bucket in &ka.buckets {
+                buckets[bucket].insert(id);
+            }
+            *count += 1;
+            self.addrs.insert(id, ka);
+        }
+        Ok(())
+    }
+
+    fn calc_new_bucket(&self, addr: &NetAddress, src: &NetAddress) -> usize {
+        let src_group = self.group_key(src.addr.ip());
+
+        let mut data = self.key.as_bytes().to_vec();
+        data.extend_from_slice(self.group_key(addr.addr.ip()).as_bytes());
+        data.extend_from_slice(src_group.as_bytes());
+        let hash = double_sha256(&data) % NEW_BUCKETS_PER_GROUP;
+
+        let mut data = self.key.as_bytes().to_vec();
+        data.extend_from_slice(src_group.as_bytes());
+        data.extend_from_slice(&hash.to_be_bytes());
+        bucket_index(double_sha256(&data), NEW_BUCKET_COUNT)
+    }
+
+    fn calc_old_bucket(&self, addr: &NetAddress) -> usize {
+        let mut data = self.key.as_bytes().to_vec();
+        data.extend_from_slice(addr.to_string().as_bytes());
+        let hash = double_sha256(&data) % OLD_BUCKETS_PER_GROUP;
+
+        let mut data = self.key.as_bytes().to_vec();
+        data.extend_from_slice(self.group_key(addr.addr.ip()).as_bytes());
+        data.extend_from_slice(&hash.to_be_bytes());
+        bucket_index(double_sha256(&data), OLD_BUCKET_COUNT)
+    }
+
+    /// Returns the network group of the IP address: its /16 prefix for IPv4, and its /32 prefix
+    /// for IPv6.
+    fn group_key(&self, ip: IpAddr) -> String {
+        if self.routability_strict {
+            if ip.is_loopback() {
+                return "local".into();
+            }
+            if !is_routable_ip(ip) {
+                return "unroutable".into();
+            }
+        }
+        match ip {
+            IpAddr::V4(ip) => {
+                let [a, b, ..] = ip.octets();
+                format!("{a}.{b}.0.0/16")
+            },
+            IpAddr::V6(ip) => {
+                let [a, b, ..] = ip.segments();
+                format!("{a:x}:{b:x}::/32")
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum BucketType {
+    New,
+    Old,
+}
+
+/// Address in the book, along with what is known about connecting to it. Times are seconds
+/// since the Unix epoch, zero meaning never.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct KnownAddress {
+    addr: NetAddress,
+    src: NetAddress,
+    buckets: Vec<usize>,
+    bucket_type: BucketType,
+    attempts: u32,
+    last_attempt: u64,
+    last_success: u64,
+    last_ban_time: u64,
+}
+
+impl KnownAddress {
+    const fn new(addr: NetAddress, src: NetAddress) -> Self {
+        Self {
+            addr,
+            src,
+            buckets: Vec::new(),
+            bucket_type: BucketType::New,
+            attempts: 0,
+            last_attempt: 0,
+            last_success: 0,
+            last_ban_time: 0,
+        }
+    }
+
+    fn is_old(&self) -> bool {
+        self.bucket_type == BucketType::Old
+    }
+
+    /// Whether the address is worth replacing by another, as in `CometBFT`.
+    const fn is_bad(&self, now: u64) -> bool {
+        // Attempted recently: give it a chance.
+        if self.last_attempt + 60 > now {
+            return false;
+        }
+        // Not attempted for too long, or never.
+        if self.last_attempt + NUM_MISSING_DAYS * SECS_PER_DAY < now {
+            return true;
+        }
+        // Never succeeded in a few attempts.
+        if self.last_success == 0 && self.attempts >= NUM_RETRIES {
+            return true;
+        }
+        // Failed too many times since the last success.
+        self.last_success + MIN_BAD_DAYS * SECS_PER_DAY < now && self.attempts >= MAX_FAILURES
+    }
+}
+
+/// Address book as persisted to JSON.
+#[derive(Serialize, Deserialize)]
+struct AddrBookFile {
+    key: String,
+    addrs: Vec<KnownAddress>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    tmp.into()
+}
+
+fn double_sha256(data: &[u8]) -> u64 {
+    let hash = Sha256::digest(Sha256::digest(data));
+    let mut first = [0_u8; 8];
+    first.copy_from_slice(&hash[..8]);
+    u64::from_be_bytes(first)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn bucket_index(hash: u64, count: usize) -> usize {
+    (hash % count as u64) as usize
+}
+
+/// Returns a random index below `len`, which must not be zero.
+#[allow(clippy::cast_possible_truncation)]
+fn random_index(len: usize) -> usize {
+    (OsRng.next_u64() % len as u64) as usize
+}
@@ -0,0 +1,372 @@
+//! Reactor exchanging peer addresses over the PEX stream.
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use eyre::Result;
+use prost::Message as _;
+use tendermint::node;
+use tendermint_proto::v0_38::p2p::{
+    message::Sum, Message as RawMessage, NetAddress as RawNetAddress, PexAddrs, PexRequest,
+};
+
+use super::{AddrBook, NetAddress, MAX_ADDRS_PER_MESSAGE};
+use crate::{
+    error::Error,
+    transport::{ConnectInfo, Connection, Endpoint, StreamId, StreamSend},
+};
+
+/// Default minimum interval between two requests of the same peer, in seconds.
+const DEFAULT_MIN_RECEIVE_REQUEST_INTERVAL_SECS: u64 = 10;
+
+/// Default minimum interval between two crawls of the same address, in seconds.
+const DEFAULT_CRAWL_INTERVAL_SECS: u64 = 120;
+
+/// Configuration of a [`PexReactor`].
+#[derive(Clone, Debug)]
+pub struct PexConfig {
+    /// Whether the node is a seed node. Seed nodes disconnect from inbound peers once they
+    /// answered their request, and learn addresses by crawling the network with
+    /// [`PexReactor::crawl`].
+    pub seed_mode: bool,
+    /// Minimum interval between two requests of the same peer. Peers requesting addresses
+    /// more often are disconnected.
+    pub min_receive_request_interval: Duration,
+    /// Minimum interval between two crawls of the same address.
+    pub crawl_interval: Duration,
+    /// Time given to a crawled peer to answer the request for its addresses.
+    pub crawl_timeout: Duration,
+}
+
+impl Default for PexConfig {
+    fn default() -> Self {
+        Self {
+            seed_mode: false,
+            min_receive_request_interval: Duration::from_secs(
+                DEFAULT_MIN_RECEIVE_REQUEST_INTERVAL_SECS,
+            ),
+            crawl_interval: Duration::from_secs(DEFAULT_CRAWL_INTERVAL_SECS),
+            crawl_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Reactor requesting addresses from peers and serving the ones of its [`AddrBook`], with
+/// the messages of the `CometBFT` PEX protocol.
+///
+/// Peers sending addresses which were not requested, or requesting addresses too often, are
+/// rejected with an error, upon which they should be disconnected.
+pub struct PexReactor {
+    book: Arc<Mutex<AddrBook>>,
+    config: PexConfig,
+    peers: Mutex<HashMap<node::Id, PeerState>>,
+}
+
+impl PexReactor {
+    /// Creates a reactor learning and serving the addresses of the given book.
+    #[must_use]
+    pub fn new(book: Arc<Mutex<AddrBook>>, config: PexConfig) -> Self {
+        Self {
+            book,
+            config,
+            peers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the address book of the reactor.
+    #[must_use]
+    pub const fn book(&self) -> &Arc<Mutex<AddrBook>> {
+        &self.book
+    }
+
+    /// Registers a connected peer, which listens at `addr`.
+    ///
+    /// The address of the peer is added to the book, and marked as good if the local node
+    /// dialed it. If the book needs more addresses, a request for addresses is returned for
+    /// outbound peers.
+    pub fn add_peer(&self, addr: NetAddress, outbound: bool) -> Option<Vec<u8>> {
+        lock(&self.peers).insert(addr.id, PeerState::new(addr));
+
+        let mut book = lock(&self.book);
+        // Rejected addresses, such as non-routable ones, are only not remembered.
+        let _ = book.add_address(addr, addr);
+        if !outbound {
+            return None;
+        }
+        book.mark_good(addr.id);
+        let need_more_addrs = book.need_more_addrs();
+        drop(book);
+
+        if need_more_addrs {
+            self.request_addrs(addr.id)
+        } else {
+            None
+        }
+    }
+
+    /// Unregisters a disconnected peer.
+    pub fn remove_peer(&self, id: node::Id) {
+        lock(&self.peers).remove(&id);
+    }
+
+    /// Returns a request for the addresses known to the given peer, to send it.
+    ///
+    /// Returns `None` if the peer is unknown, or a request to it is still pending.
+    pub fn request_addrs(&self, id: node::Id) -> Option<Vec<u8>> {
+        let mut peers = lock(&self.peers);
+        let peer = peers.get_mut(&id)?;
+        if peer.request_sent {
+            return None;
+        }
+        peer.request_sent = true;
+        drop(peers);
+
+        Some(encode(Sum::PexRequest(PexRequest {})))
+    }
+
+    /// Handles a message received from the given peer, returning the reply to send it if any.
+    ///
+    /// # Errors
+    ///
+    /// * If the message cannot be decoded.
+    /// * If the peer is unknown.
+    /// * If the peer requests addresses more often than allowed.
+    /// * If the peer sends addresses which were not requested, too many or invalid ones.
+    pub fn receive(&self, id: node::Id, msg: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let msg = RawMessage::decode(msg).map_err(|e| Error::invalid_pex_message(e.to_string()))?;
+        match msg.sum {
+            Some(Sum::PexRequest(_)) => self.receive_request(id).map(Some),
+            Some(Sum::PexAddrs(PexAddrs { addrs })) => self.receive_addrs(id, addrs).map(|()| None),
+            None => Err(Error::invalid_pex_message("empty message".into())),
+        }
+    }
+
+    /// Runs the reactor for a connected peer listening at `addr`, until the connection is
+    /// closed.
+    ///
+    /// Seed nodes close inbound connections once they answered the request of the peer. The
+    /// connection is also closed if the peer misbehaves.
+    ///
+    /// # Errors
+    ///
+    /// * If the PEX stream cannot be opened.
+    /// * If the peer misbehaves, or the connection fails.
+    pub fn serve<C>(&self, conn: &C, addr: NetAddress, outbound: bool) -> Result<()>
+    where
+        C: Connection,
+        C::Error: Display + Debug + Send + Sync + 'static,
+    {
+        let (read, send) = conn
+            .open_bidirectional(StreamId::Pex)
+            .map_err(eyre::Report::msg)?;
+
+        let result = self
+            .add_peer(addr, outbound)
+            .map_or(Ok(()), |request| send.send(request))
+            .and_then(|()| self.serve_messages(conn, read, &send, addr.id, outbound));
+        self.remove_peer(addr.id);
+
+        if result.is_err() {
+            let _ = conn.close();
+        }
+        result
+    }
+
+    /// Crawls the network once: connects to the addresses of the book which were not
+    /// attempted recently, and requests their addresses. Meant for seed nodes.
+    ///
+    /// Returns the number of peers crawled.
+    pub fn crawl<E>(&self, endpoint: &E) -> usize
+    where
+        E: Endpoint<SocketAddr> + Sync,
+        E::Connection: Connection + Sync,
+        <E::Connection as Connection>::Error: Display + Debug + Send + Sync + 'static,
+    {
+        let candidates = {
+            let mut book = lock(&self.book);
+            book.reinstate_bad_peers();
+            let now = SystemTime::now();
+            book.selection()
+                .into_iter()
+                .filter(|addr| {
+                    book.last_attempt(addr.id).is_none_or(|last| {
+                        now.duration_since(last).unwrap_or_default() >= self.config.crawl_interval
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        thread::scope(|scope| {
+            for &addr in &candidates {
+                scope.spawn(move || self.crawl_peer(endpoint, addr));
+            }
+        });
+        candidates.len()
+    }
+
+    fn receive_request(&self, id: node::Id) -> Result<Vec<u8>, Error> {
+        let mut peers = lock(&self.peers);
+        let peer = peers.get_mut(&id).ok_or_else(|| Error::unknown_peer(id))?;
+        let now = Instant::now();
+        if peer
+            .last_request
+            .is_some_and(|last| now.duration_since(last) < self.config.min_receive_request_interval)
+        {
+            return Err(Error::pex_request_too_frequent(id));
+        }
+        peer.last_request = Some(now);
+        drop(peers);
+
+        let addrs = lock(&self.book).selection();
+        Ok(encode(Sum::PexAddrs(PexAddrs {
+            addrs: addrs.into_iter().map(Into::into).collect(),
+        })))
+    }
+
+    fn receive_addrs(&self, id: node::Id, addrs: Vec<RawNetAddress>) -> Result<(), Error> {
+        let mut peers = lock(&self.peers);
+        let peer = peers.get_mut(&id).ok_or_else(|| Error::unknown_peer(id))?;
+        if !peer.request_sent {
+            return Err(Error::unsolicited_pex_addrs(id));
+        }
+        peer.request_sent = false;
+        let src = peer.addr;
+        drop(peers);
+
+        if addrs.len() > MAX_ADDRS_PER_MESSAGE {
+            return Err(Error::too_many_pex_addrs(
+                addrs.len(),
+                MAX_ADDRS_PER_MESSAGE,
+            ));
+        }
+        let addrs = addrs
+            .into_iter()
+            .map(NetAddress::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut book = lock(&self.book);
+        for addr in addrs {
+            // Addresses of the local node, of private peers and the like are skipped.
+            let _ = book.add_address(addr, src);
+        }
+        drop(book);
+        Ok(())
+    }
+
+    fn serve_messages<C: Connection>(
+        &self,
+        conn: &C,
+        read: C::StreamRead,
+        send: &C::StreamSend,
+        id: node::Id,
+        outbound: bool,
+    ) -> Result<()> {
+        for msg in read {
+            if let Some(reply) = self.receive(id, &msg?).map_err(Error::into_report)? {
+                send.send(reply)?;
+                // Seed nodes only answer a single request of the peers connecting to them.
+                if self.config.seed_mode && !outbound {
+                    return conn.close();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn crawl_peer<E>(&self, endpoint: &E, addr: NetAddress)
+    where
+        E: Endpoint<SocketAddr>,
+        E::Connection: Connection + Sync,
+        <E::Connection as Connection>::Error: Display + Debug + Send + Sync + 'static,
+    {
+        lock(&self.book).mark_attempt(addr.id);
+        let Ok(conn) = endpoint.connect(ConnectInfo {
+            addrs: addr.addr,
+            id: addr.id,
+        }) else {
+            return;
+        };
+
+        lock(&self.peers).insert(addr.id, PeerState::new(addr));
+        let answered = self.query(&conn, addr.id);
+        self.remove_peer(addr.id);
+        let _ = conn.close();
+
+        if answered {
+            lock(&self.book).mark_good(addr.id);
+        }
+    }
+
+    /// Requests the addresses known to a crawled peer, and waits for its answer until the
+    /// crawl timeout. Returns whether the peer answered.
+    fn query<C>(&self, conn: &C, id: node::Id) -> bool
+    where
+        C: Connection + Sync,
+        C::Error: Display + Debug + Send + Sync + 'static,
+    {
+        let Ok((mut read, send)) = conn.open_bidirectional(StreamId::Pex) else {
+            return false;
+        };
+        let Some(request) = self.request_addrs(id) else {
+            return false;
+        };
+        if send.send(request).is_err() {
+            return false;
+        }
+
+        thread::scope(|scope| {
+            let (done, wait) = flume::bounded::<()>(1);
+            // Closing the connection ends the stream, should the peer not answer in time.
+            scope.spawn(move || {
+                if wait.recv_timeout(self.config.crawl_timeout).is_err() {
+                    let _ = conn.close();
+                }
+            });
+
+            let mut answered = false;
+            for msg in read.by_ref() {
+                match msg.map(|msg| self.receive(id, &msg)) {
+                    Ok(Ok(None)) => {
+                        answered = true;
+                        break;
+                    },
+                    Ok(Ok(Some(reply))) if send.send(&reply).is_ok() => {},
+                    _ => break,
+                }
+            }
+            let _ = done.send(());
+            answered
+        })
+    }
+}
+
+/// State of a peer connected to the reactor.
+struct PeerState {
+    addr: NetAddress,
+    last_request: Option<Instant>,
+    request_sent: bool,
+}
+
+impl PeerState {
+    const fn new(addr: NetAddress) -> Self {
+        Self {
+            addr,
+            last_request: None,
+            request_sent: false,
+        }
+    }
+}
+
+fn encode(sum: Sum) -> Vec<u8> {
+    RawMessage { sum: Some(sum) }.encode_to_vec()
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,244 @@
+//! Privval protocol, through which a node has its consensus messages signed by a remote
+//! signer.
+//!
+//! The signer is served by a [`SignerServer`], backed by a [`Signer`], and the node sends its
+//! requests with a [`SignerClient`]. Either end may dial the other, over TCP secured with a
+//! [`SecretConnection`](crate::secret_connection::SecretConnection) or over a Unix domain
+//! socket. Messages are length-delimited `privval.Message`s.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+};
+
+use prost::Message as _;
+use tendermint::{
+    node,
+    proposal::{SignProposalRequest, SignedProposalResponse},
+    public_key::{PubKeyRequest, PubKeyResponse},
+    vote::{SignVoteRequest, SignedVoteResponse},
+};
+use tendermint_proto::v0_38::privval::{
+    message::Sum, Message as RawMessage, PingRequest, PingResponse,
+};
+
+pub use self::{
+    client::SignerClient,
+    connection::{connect, Connection, Listener},
+    server::SignerServer,
+};
+use crate::{error::Error, mconnection::read_varint};
+pub use tendermint::privval::Signer;
+
+mod client;
+mod connection;
+mod server;
+
+/// Maximum size of a privval message, as in `CometBFT`.
+pub const MAX_MESSAGE_SIZE: usize = 10_240;
+
+/// Address of the other end of a privval connection.
+///
+/// Formatted as `tcp://[<id>@]<ip>:<port>` or `unix://<path>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Address {
+    /// TCP address, the connection being secured with a `SecretConnection`.
+    Tcp {
+        /// ID the peer must authenticate as, if known.
+        peer_id: Option<node::Id>,
+        /// Socket address of the peer.
+        addr: SocketAddr,
+    },
+    /// Path of a Unix domain socket.
+    Unix(PathBuf),
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp {
+                peer_id: Some(peer_id),
+                addr,
+            } => write!(f, "tcp://{peer_id}@{addr}"),
+            Self::Tcp {
+                peer_id: None,
+                addr,
+            } => write!(f, "tcp://{addr}"),
+            Self::Unix(path) => write!(f, "unix://{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::invalid_privval_address(s.to_owned());
+        if let Some(path) = s.strip_prefix("unix://") {
+            return Ok(Self::Unix(path.into()));
+        }
+        let rest = s.strip_prefix("tcp://").ok_or_else(invalid)?;
+        let (peer_id, addr) = match rest.split_once('@') {
+            Some((peer_id, addr)) => (Some(peer_id.parse().map_err(|_| invalid())?), addr),
+            None => (None, rest),
+        };
+        Ok(Self::Tcp {
+            peer_id,
+            addr: addr.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Request sent by a node to its signer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Request {
+    /// Request for the public key of the validator.
+    PubKey(PubKeyRequest),
+    /// Request to sign a vote.
+    SignVote(SignVoteRequest),
+    /// Request to sign a proposal.
+    SignProposal(SignProposalRequest),
+    /// Check that the connection is alive.
+    Ping,
+}
+
+/// Response of a signer to a [`Request`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Response {
+    /// Public key of the validator, or the error getting it.
+    PubKey(PubKeyResponse),
+    /// Signed vote, or the error signing it.
+    SignedVote(SignedVoteResponse),
+    /// Signed proposal, or the error signing it.
+    SignedProposal(SignedProposalResponse),
+    /// Answer to a ping.
+    Ping,
+}
+
+impl From<Request> for RawMessage {
+    fn from(request: Request) -> Self {
+        let sum = match request {
+            Request::PubKey(request) => Sum::PubKeyRequest(request.into()),
+            Request::SignVote(request) => Sum::SignVoteRequest(request.into()),
+            Request::SignProposal(request) => Sum::SignProposalRequest(request.into()),
+            Request::Ping => Sum::PingRequest(PingRequest {}),
+        };
+        Self { sum: Some(sum) }
+    }
+}
+
+impl TryFrom<RawMessage> for Request {
+    type Error = Error;
+
+    fn try_from(message: RawMessage) -> Result<Self, Self::Error> {
+        let invalid = |e: tendermint::Error| Error::invalid_privval_message(e.to_string());
+        match message.sum {
+            Some(Sum::PubKeyRequest(request)) => {
+                request.try_into().map(Self::PubKey).map_err(invalid)
+            },
+            Some(Sum::SignVoteRequest(request)) => {
+                request.try_into().map(Self::SignVote).map_err(invalid)
+            },
+            Some(Sum::SignProposalRequest(request)) => {
+                request.try_into().map(Self::SignProposal).map_err(invalid)
+            },
+            Some(Sum::PingRequest(_)) => Ok(Self::Ping),
+            other => Err(Error::unexpected_privval_message(describe(other.as_ref()))),
+        }
+    }
+}
+
+impl From<Response> for RawMessage {
+    fn from(response: Response) -> Self {
+        let sum = match response {
+            Response::PubKey(response) => Sum::PubKeyResponse(response.into()),
+            Response::SignedVote(response) => Sum::SignedVoteResponse(response.into()),
+            Response::SignedProposal(response) => Sum::SignedProposalResponse(response.into()),
+            Response::Ping => Sum::PingResponse(PingResponse {}),
+        };
+        Self { sum: Some(sum) }
+    }
+}
+
+impl TryFrom<RawMessage> for Response {
+    type Error = Error;
+
+    fn try_from(message: RawMessage) -> Result<Self, Self::Error> {
+        let invalid = |e: tendermint::Error| Error::invalid_privval_message(e.to_string());
+        match message.sum {
+            Some(Sum::PubKeyResponse(response)) => {
+                response.try_into().map(Self::PubKey).map_err(invalid)
+            },
+            Some(Sum::SignedVoteResponse(response)) => {
+                response.try_into().map(Self::SignedVote).map_err(invalid)
+            },
+            Some(Sum::SignedProposalResponse(response)) => response
+                .try_into()
+                .map(Self::SignedProposal)
+                .map_err(invalid),
+            Some(Sum::PingResponse(_)) => Ok(Self::Ping),
+            other => Err(Error::unexpected_privval_message(describe(other.as_ref()))),
+        }
+    }
+}
+
+/// Writes a length-delimited message.
+///
+/// # Errors
+///
+/// * If the message cannot be written.
+pub fn write_message<W: Write>(
+    writer: &mut W,
+    message: impl Into<RawMessage>,
+) -> Result<(), Error> {
+    writer.write_all(&message.into().encode_length_delimited_to_vec())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a length-delimited message, or `None` if the connection was closed in between
+/// messages.
+///
+/// # Errors
+///
+/// * If the message cannot be read, or is larger than [`MAX_MESSAGE_SIZE`].
+/// * If the message cannot be decoded.
+pub fn read_message<R: Read>(reader: &mut R) -> Result<Option<RawMessage>, Error> {
+    let Some(len) = read_varint(reader)? else {
+        return Ok(None);
+    };
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|&len| len <= MAX_MESSAGE_SIZE)
+        .ok_or_else(|| Error::message_too_large(len, MAX_MESSAGE_SIZE))?;
+
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+    RawMessage::decode(buf.as_slice())
+        .map(Some)
+        .map_err(|e| Error::invalid_privval_message(e.to_string()))
+}
+
+/// Names the kind of a message, for error reporting.
+fn describe(sum: Option<&Sum>) -> String {
+    match sum {
+        Some(Sum::PubKeyRequest(_)) => "public key request",
+        Some(Sum::PubKeyResponse(_)) => "public key response",
+        Some(Sum::SignVoteRequest(_)) => "vote signing request",
+        Some(Sum::SignedVoteResponse(_)) => "signed vote response",
+        Some(Sum::SignProposalRequest(_)) => "proposal signing request",
+        Some(Sum::SignedProposalResponse(_)) => "signed proposal response",
+        Some(Sum::PingRequest(_)) => "ping request",
+        Some(Sum::PingResponse(_)) => "ping response",
+        None => "empty message",
+    }
+    .to_owned()
+}
+
+/// Returns the error for a connection closed while a message was expected.
+fn unexpected_eof() -> Error {
+    Error::io(io::ErrorKind::UnexpectedEof.into())
+}
@@ -0,0 +1,162 @@
+//! Node end of the privval protocol.
+
+use std::io::{Read, Write};
+
+use tendermint::{
+    chain,
+    privval::RemoteSignerError,
+    proposal::{SignProposalRequest, SignedProposalResponse},
+    public_key::{PubKeyRequest, PubKeyResponse},
+    vote::{SignVoteRequest, SignedVoteResponse},
+    Proposal, PublicKey, Vote,
+};
+
+use super::{
+    connect, read_message, unexpected_eof, write_message, Address, Connection, Listener, Request,
+    Response,
+};
+use crate::error::Error;
+
+/// Sends the requests of a node to its remote signer.
+pub struct SignerClient<IoHandler = Connection> {
+    io: IoHandler,
+}
+
+impl SignerClient<Connection> {
+    /// Connects to the signer listening at `addr`, authenticating with `identity` over TCP.
+    ///
+    /// # Errors
+    ///
+    /// * If the signer cannot be connected to.
+    pub fn connect(
+        addr: &Address,
+        identity: &ed25519_consensus::SigningKey,
+    ) -> Result<Self, Error> {
+        connect(addr, identity).map(Self::new)
+    }
+
+    /// Waits for the signer to connect to the listener, as `CometBFT` nodes do.
+    ///
+    /// # Errors
+    ///
+    /// * If no connection can be accepted.
+    pub fn accept(listener: &Listener) -> Result<Self, Error> {
+        listener.accept().map(Self::new)
+    }
+}
+
+impl<IoHandler: Read + Write> SignerClient<IoHandler> {
+    /// Creates a client sending requests over the given connection.
+    pub const fn new(io: IoHandler) -> Self {
+        Self { io }
+    }
+
+    /// Returns the connection to the signer.
+    pub const fn inner(&self) -> &IoHandler {
+        &self.io
+    }
+
+    /// Sends a request to the signer, and returns its response.
+    ///
+    /// # Errors
+    ///
+    /// * If the connection fails.
+    /// * If the response is invalid.
+    pub fn request(&mut self, request: Request) -> Result<Response, Error> {
+        write_message(&mut self.io, request)?;
+        read_message(&mut self.io)?
+            .ok_or_else(unexpected_eof)?
+            .try_into()
+    }
+
+    /// Returns the public key of the validator on the given chain.
+    ///
+    /// # Errors
+    ///
+    /// * If the request fails, or the signer returns an error.
+    pub fn public_key(&mut self, chain_id: &chain::Id) -> Result<PublicKey, Error> {
+        let request = Request::PubKey(PubKeyRequest {
+            chain_id: chain_id.clone(),
+        });
+        match self.request(request)? {
+            Response::PubKey(PubKeyResponse { pub_key, error }) => {
+                into_result(pub_key, error, "public key")
+            },
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// Has the signer sign the vote for the given chain.
+    ///
+    /// # Errors
+    ///
+    /// * If the request fails, or the signer returns an error.
+    pub fn sign_vote(&mut self, chain_id: &chain::Id, vote: Vote) -> Result<Vote, Error> {
+        let request = Request::SignVote(SignVoteRequest {
+            vote,
+            chain_id: chain_id.clone(),
+        });
+        match self.request(request)? {
+            Response::SignedVote(SignedVoteResponse { vote, error }) => {
+                into_result(vote, error, "vote")
+            },
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// Has the signer sign the proposal for the given chain.
+    ///
+    /// # Errors
+    ///
+    /// * If the request fails, or the signer returns an error.
+    pub fn sign_proposal(
+        &mut self,
+        chain_id: &chain::Id,
+        proposal: Proposal,
+    ) -> Result<Proposal, Error> {
+        let request = Request::SignProposal(SignProposalRequest {
+            proposal,
+            chain_id: chain_id.clone(),
+        });
+        match self.request(request)? {
+            Response::SignedProposal(SignedProposalResponse { proposal, error }) => {
+                into_result(proposal, error, "proposal")
+            },
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// Checks that the signer is still connected.
+    ///
+    /// # Errors
+    ///
+    /// * If the request fails.
+    pub fn ping(&mut self) -> Result<(), Error> {
+        match self.request(Request::Ping)? {
+            Response::Ping => Ok(()),
+            other => Err(unexpected(&other)),
+        }
+    }
+}
+
+fn into_result<T>(
+    value: Option<T>,
+    error: Option<RemoteSignerError>,
+    name: &str,
+) -> Result<T, Error> {
+    match (value, error) {
+        (_, Some(e)) => Err(Error::remote_signer(e.code, e.description)),
+        (Some(value), None) => Ok(value),
+        (None, None) => Err(Error::invalid_privval_message(format!("missing {name}"))),
+    }
+}
+
+fn unexpected(response: &Response) -> Error {
+    let kind = match response {
+        Response::PubKey(_) => "public key response",
+        Response::SignedVote(_) => "signed vote response",
+        Response::SignedProposal(_) => "signed proposal response",
+        Response::Ping => "ping response",
+    };
+    Error::unexpected_privval_message(kind.to_owned())
+}
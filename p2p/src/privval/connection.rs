@@ -0,0 +1,194 @@
+//! Connections between a node and its remote signer.
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use tendermint::node;
+
+use super::Address;
+use crate::{
+    error::Error,
+    secret_connection::{SecretConnection, Version},
+    transport::tcp::{DEFAULT_DIAL_TIMEOUT, DEFAULT_HANDSHAKE_TIMEOUT},
+};
+
+/// Connection between a node and its remote signer.
+pub enum Connection {
+    /// TCP connection, secured with a `SecretConnection`.
+    Tcp(Box<SecretConnection<TcpStream>>),
+    /// Unix domain socket connection.
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Connection {
+    /// Returns the ID the peer authenticated as, for TCP connections.
+    #[must_use]
+    pub fn peer_id(&self) -> Option<node::Id> {
+        match self {
+            Self::Tcp(conn) => Some(conn.remote_pubkey().peer_id()),
+            #[cfg(unix)]
+            Self::Unix(_) => None,
+        }
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(conn) => conn.read(buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(conn) => conn.write(buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tcp(conn) => conn.flush(),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Connects to the given address. TCP connections are secured with a `SecretConnection`,
+/// authenticating the local end with `identity`.
+///
+/// # Errors
+///
+/// * If the address cannot be connected to.
+/// * If the `SecretConnection` handshake fails, or the peer is not the one expected.
+/// * If the address is a Unix domain socket on a platform without them.
+pub fn connect(
+    addr: &Address,
+    identity: &ed25519_consensus::SigningKey,
+) -> Result<Connection, Error> {
+    match addr {
+        Address::Tcp { peer_id, addr } => {
+            let stream = TcpStream::connect_timeout(addr, DEFAULT_DIAL_TIMEOUT)?;
+            secure(&stream, identity, *peer_id)
+        },
+        #[cfg(unix)]
+        Address::Unix(path) => Ok(Connection::Unix(UnixStream::connect(path)?)),
+        #[cfg(not(unix))]
+        Address::Unix(_) => Err(Error::invalid_privval_address(addr.to_string())),
+    }
+}
+
+/// Listener accepting privval connections.
+pub struct Listener {
+    inner: ListenerInner,
+    identity: ed25519_consensus::SigningKey,
+    peer_id: Option<node::Id>,
+}
+
+enum ListenerInner {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, std::path::PathBuf),
+}
+
+impl Listener {
+    /// Listens on the given address. TCP connections are secured with a `SecretConnection`,
+    /// authenticating the local end with `identity`, and only accepted from the peer of the
+    /// address if it has an ID.
+    ///
+    /// # Errors
+    ///
+    /// * If the address cannot be bound, or is the path of a file that cannot be removed.
+    /// * If the address is a Unix domain socket on a platform without them.
+    pub fn bind(addr: &Address, identity: ed25519_consensus::SigningKey) -> Result<Self, Error> {
+        let (inner, peer_id) = match addr {
+            Address::Tcp { peer_id, addr } => {
+                (ListenerInner::Tcp(TcpListener::bind(addr)?), *peer_id)
+            },
+            #[cfg(unix)]
+            Address::Unix(path) => {
+                // A socket left behind by a process that did not exit cleanly would make binding
+                // fail, so it is removed first, as `CometBFT` does.
+                match std::fs::remove_file(path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {},
+                }
+                (
+                    ListenerInner::Unix(UnixListener::bind(path)?, path.clone()),
+                    None,
+                )
+            },
+            #[cfg(not(unix))]
+            Address::Unix(_) => return Err(Error::invalid_privval_address(addr.to_string())),
+        };
+        Ok(Self {
+            inner,
+            identity,
+            peer_id,
+        })
+    }
+
+    /// Returns the address the listener is bound to.
+    ///
+    /// # Errors
+    ///
+    /// * If the local address of a TCP listener cannot be retrieved.
+    pub fn local_addr(&self) -> Result<Address, Error> {
+        match &self.inner {
+            ListenerInner::Tcp(listener) => Ok(Address::Tcp {
+                peer_id: None,
+                addr: listener.local_addr()?,
+            }),
+            #[cfg(unix)]
+            ListenerInner::Unix(_, path) => Ok(Address::Unix(path.clone())),
+        }
+    }
+
+    /// Waits for the next connection.
+    ///
+    /// # Errors
+    ///
+    /// * If no connection can be accepted.
+    /// * If the `SecretConnection` handshake fails, or the peer is not the one expected.
+    pub fn accept(&self) -> Result<Connection, Error> {
+        match &self.inner {
+            ListenerInner::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                secure(&stream, &self.identity, self.peer_id)
+            },
+            #[cfg(unix)]
+            ListenerInner::Unix(listener, _) => Ok(Connection::Unix(listener.accept()?.0)),
+        }
+    }
+}
+
+/// Secures a TCP connection with a `SecretConnection`, checking the ID of the peer if given.
+fn secure(
+    stream: &TcpStream,
+    identity: &ed25519_consensus::SigningKey,
+    peer_id: Option<node::Id>,
+) -> Result<Connection, Error> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(DEFAULT_HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(DEFAULT_HANDSHAKE_TIMEOUT))?;
+    let conn = SecretConnection::new(stream.try_clone()?, identity.clone(), Version::V0_34)?;
+    stream.set_read_timeout(None)?;
+    stream.set_write_timeout(None)?;
+
+    let actual = conn.remote_pubkey().peer_id();
+    match peer_id {
+        Some(expected) if expected != actual => Err(Error::remote_peer_mismatch(expected, actual)),
+        _ => Ok(Connection::Tcp(Box::new(conn))),
+    }
+}
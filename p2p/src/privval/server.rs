@@ -0,0 +1,102 @@
+//! Signer end of the privval protocol.
+
+use std::io::{Read, Write};
+
+use tendermint::{
+    proposal::SignedProposalResponse, public_key::PubKeyResponse, vote::SignedVoteResponse,
+};
+
+use super::{connect, read_message, write_message, Address, Listener, Request, Response, Signer};
+use crate::error::Error;
+
+/// Serves the requests of a node with a [`Signer`].
+pub struct SignerServer<S> {
+    signer: S,
+}
+
+impl<S: Signer> SignerServer<S> {
+    /// Creates a server answering requests with the given signer.
+    pub const fn new(signer: S) -> Self {
+        Self { signer }
+    }
+
+    /// Returns the signer of the server.
+    pub const fn signer(&self) -> &S {
+        &self.signer
+    }
+
+    /// Returns the signer of the server, consuming it.
+    pub fn into_signer(self) -> S {
+        self.signer
+    }
+
+    /// Dials the node at `addr`, authenticating with `identity` over TCP, and serves its
+    /// requests until it disconnects.
+    ///
+    /// # Errors
+    ///
+    /// * If the node cannot be connected to.
+    /// * If the connection fails, or the node sends an invalid request.
+    pub fn dial(
+        &mut self,
+        addr: &Address,
+        identity: &ed25519_consensus::SigningKey,
+    ) -> Result<(), Error> {
+        let conn = connect(addr, identity)?;
+        self.serve(conn)
+    }
+
+    /// Waits for the node to connect to the listener, and serves its requests until it
+    /// disconnects.
+    ///
+    /// # Errors
+    ///
+    /// * If no connection can be accepted.
+    /// * If the connection fails, or the node sends an invalid request.
+    pub fn accept(&mut self, listener: &Listener) -> Result<(), Error> {
+        let conn = listener.accept()?;
+        self.serve(conn)
+    }
+
+    /// Serves the requests received over the connection, until it is closed.
+    ///
+    /// # Errors
+    ///
+    /// * If the connection fails, or the node sends an invalid request.
+    pub fn serve<IoHandler: Read + Write>(&mut self, mut io: IoHandler) -> Result<(), Error> {
+        while let Some(message) = read_message(&mut io)? {
+            let response = self.handle(message.try_into()?);
+            write_message(&mut io, response)?;
+        }
+        Ok(())
+    }
+
+    /// Answers a request. Errors of the signer are returned to the node in the response.
+    pub fn handle(&mut self, request: Request) -> Response {
+        match request {
+            Request::PubKey(request) => {
+                let (pub_key, error) = split(self.signer.public_key(&request.chain_id));
+                Response::PubKey(PubKeyResponse { pub_key, error })
+            },
+            Request::SignVote(request) => {
+                let (vote, error) = split(self.signer.sign_vote(&request.chain_id, request.vote));
+                Response::SignedVote(SignedVoteResponse { vote, error })
+            },
+            Request::SignProposal(request) => {
+                let (proposal, error) = split(
+                    self.signer
+                        .sign_proposal(&request.chain_id, request.proposal),
+                );
+                Response::SignedProposal(SignedProposalResponse { proposal, error })
+            },
+            Request::Ping => Response::Ping,
+        }
+    }
+}
+
+fn split<T, E>(result: Result<T, E>) -> (Option<T>, Option<E>) {
+    match result {
+        Ok(value) => (Some(value), None),
+        Err(e) => (None, Some(e)),
+    }
+}
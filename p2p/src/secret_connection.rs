@@ -30,11 +30,17 @@ pub use self::{
     protocol::Version,
     public_key::PublicKey,
 };
+
+#[cfg(feature = "tokio")]
+pub use self::async_connection::{AsyncReceiver, AsyncSecretConnection, AsyncSender};
 use crate::error::Error;
 
 #[cfg(feature = "amino")]
 mod amino_types;
 
+#[cfg(feature = "tokio")]
+mod async_connection;
+
 mod kdf;
 mod nonce;
 mod protocol;
@@ -50,6 +56,9 @@ pub const DATA_MAX_SIZE: usize = 1024;
 const DATA_LEN_SIZE: usize = 4;
 const TOTAL_FRAME_SIZE: usize = DATA_MAX_SIZE + DATA_LEN_SIZE;
 
+/// Size of an encrypted frame on the wire
+const SEALED_FRAME_SIZE: usize = TAG_SIZE + TOTAL_FRAME_SIZE;
+
 /// Handshake is a process of establishing the `SecretConnection` between two peers.
 /// [Specification](https://github.com/tendermint/spec/blob/master/spec/p2p/peer.md#authenticated-encryption-handshake)
 pub struct Handshake<S> {
@@ -190,7 +199,8 @@ impl Handshake<AwaitingAuthSig> {
                 ed25519_consensus::VerificationKey::try_from(&bytes[..])
                     .map_err(|_| Error::signature())
             },
-            proto::crypto::public_key::Sum::Secp256k1(_) => Err(Error::unsupported_key()),
+            proto::crypto::public_key::Sum::Secp256k1(_)
+            | proto::crypto::public_key::Sum::Bls12381(_) => Err(Error::unsupported_key()),
         }?;
 
         let remote_sig = ed25519_consensus::Signature::try_from(auth_sig_msg.sig.as_slice())
@@ -220,8 +230,7 @@ impl Handshake<AwaitingAuthSig> {
 macro_rules! checked_io {
     ($term:expr, $f:expr) => {{
         if $term.load(Ordering::SeqCst) {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
+            return Err(io::Error::other(
                 "secret connection was terminated elsewhere by previous error",
             ));
         }
@@ -405,6 +414,16 @@ struct ReceiveState {
     buffer: Vec<u8>,
 }
 
+impl ReceiveState {
+    /// Moves as much of the decrypted data not read yet as fits into `data`.
+    fn read_buffered(&mut self, data: &mut [u8]) -> usize {
+        let n = cmp::min(data.len(), self.buffer.len());
+        data[..n].copy_from_slice(&self.buffer[..n]);
+        self.buffer.drain(..n);
+        n
+    }
+}
+
 /// The sending end of a [`SecretConnection`].
 pub struct Sender<IoHandler> {
     io_handler: IoHandler,
@@ -511,7 +530,7 @@ fn encrypt(
     chunk: &[u8],
     send_cipher: &ChaCha20Poly1305,
     send_nonce: &Nonce,
-    sealed_frame: &mut [u8; TAG_SIZE + TOTAL_FRAME_SIZE],
+    sealed_frame: &mut [u8; SEALED_FRAME_SIZE],
 ) -> Result<(), Error> {
     assert!(!chunk.is_empty(), "chunk is empty");
     assert!(
@@ -536,6 +555,20 @@ fn encrypt(
     Ok(())
 }
 
+/// Encrypts the next frame sent on the connection, which carries `chunk`
+fn seal_frame(send_state: &mut SendState, chunk: &[u8]) -> io::Result<[u8; SEALED_FRAME_SIZE]> {
+    let mut sealed_frame = [0_u8; SEALED_FRAME_SIZE];
+    encrypt(
+        chunk,
+        &send_state.cipher,
+        &send_state.nonce,
+        &mut sealed_frame,
+    )
+    .map_err(|e| io::Error::other(e.to_string()))?;
+    send_state.nonce.increment();
+    Ok(sealed_frame)
+}
+
 // Writes encrypted frames of `TAG_SIZE` + `TOTAL_FRAME_SIZE`
 fn encrypt_and_write<IoHandler: Write>(
     io_handler: &mut IoHandler,
@@ -544,13 +577,8 @@ fn encrypt_and_write<IoHandler: Write>(
 ) -> io::Result<usize> {
     let mut n = 0_usize;
     for chunk in data.chunks(DATA_MAX_SIZE) {
-        let sealed_frame = &mut [0_u8; TAG_SIZE + TOTAL_FRAME_SIZE];
-        encrypt(chunk, &send_state.cipher, &send_state.nonce, sealed_frame)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
-        send_state.nonce.increment();
-        // end encryption
-
-        io_handler.write_all(&sealed_frame[..])?;
+        let sealed_frame = seal_frame(send_state, chunk)?;
+        io_handler.write_all(&sealed_frame)?;
         n = n
             .checked_add(chunk.len())
             .expect("overflow when adding chunk lengths");
@@ -592,67 +620,46 @@ fn decrypt(
     Ok(in_out.len())
 }
 
-fn read_and_decrypt<IoHandler: Read>(
-    io_handler: &mut IoHandler,
+/// Decrypts the next frame received on the connection, and returns the chunk it carries
+fn open_frame(
     recv_state: &mut ReceiveState,
-    data: &mut [u8],
-) -> io::Result<usize> {
-    if !recv_state.buffer.is_empty() {
-        let n = cmp::min(data.len(), recv_state.buffer.len());
-        data.copy_from_slice(&recv_state.buffer[..n]);
-        let mut leftover_portion = vec![
-            0;
-            recv_state
-                .buffer
-                .len()
-                .checked_sub(n)
-                .expect("leftover calculation failed")
-        ];
-        leftover_portion.clone_from_slice(&recv_state.buffer[n..]);
-        recv_state.buffer = leftover_portion;
-
-        return Ok(n);
-    }
-
-    let mut sealed_frame = [0_u8; TAG_SIZE + TOTAL_FRAME_SIZE];
-    io_handler.read_exact(&mut sealed_frame)?;
-
-    // decrypt the frame
+    sealed_frame: &[u8; SEALED_FRAME_SIZE],
+) -> io::Result<Vec<u8>> {
     let mut frame = [0_u8; TOTAL_FRAME_SIZE];
-    let res = decrypt(
-        &sealed_frame,
+    decrypt(
+        sealed_frame,
         &recv_state.cipher,
         &recv_state.nonce,
         &mut frame,
-    );
-
-    if let Err(err) = res {
-        return Err(io::Error::new(io::ErrorKind::Other, err.to_string()));
-    }
-
+    )
+    .map_err(|e| io::Error::other(e.to_string()))?;
     recv_state.nonce.increment();
-    // end decryption
 
     let chunk_length = u32::from_le_bytes(frame[..4].try_into().expect("chunk framing failed"));
 
     if chunk_length as usize > DATA_MAX_SIZE {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("chunk is too big: {chunk_length}! max: {DATA_MAX_SIZE}"),
-        ));
+        return Err(io::Error::other(format!(
+            "chunk is too big: {chunk_length}! max: {DATA_MAX_SIZE}"
+        )));
     }
 
-    let mut chunk = vec![0; chunk_length as usize];
-    chunk.clone_from_slice(
-        &frame[DATA_LEN_SIZE
-            ..(DATA_LEN_SIZE
-                .checked_add(chunk_length as usize)
-                .expect("chunk size addition overflow"))],
-    );
+    Ok(frame[DATA_LEN_SIZE
+        ..(DATA_LEN_SIZE
+            .checked_add(chunk_length as usize)
+            .expect("chunk size addition overflow"))]
+        .to_vec())
+}
 
-    let n = cmp::min(data.len(), chunk.len());
-    data[..n].copy_from_slice(&chunk[..n]);
-    recv_state.buffer.copy_from_slice(&chunk[n..]);
+fn read_and_decrypt<IoHandler: Read>(
+    io_handler: &mut IoHandler,
+    recv_state: &mut ReceiveState,
+    data: &mut [u8],
+) -> io::Result<usize> {
+    if recv_state.buffer.is_empty() {
+        let mut sealed_frame = [0_u8; SEALED_FRAME_SIZE];
+        io_handler.read_exact(&mut sealed_frame)?;
+        recv_state.buffer = open_frame(recv_state, &sealed_frame)?;
+    }
 
-    Ok(n)
+    Ok(recv_state.read_buffered(data))
 }
@@ -0,0 +1,424 @@
+//! `SecretConnection` over asynchronous I/O handlers.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf, ReadHalf, WriteHalf};
+
+use super::{
+    open_frame, seal_frame, Handshake, Nonce, PublicKey, ReceiveState, SendState, Version,
+    DATA_MAX_SIZE, SEALED_FRAME_SIZE,
+};
+use crate::error::Error;
+
+/// Encrypted connection between peers in a Tendermint network, over an I/O handler
+/// implementing [`AsyncRead`] and [`AsyncWrite`].
+///
+/// This is the asynchronous counterpart of [`SecretConnection`](super::SecretConnection): the
+/// handshake and the wire format are the same, so either can talk to the other.
+///
+/// ## Connection integrity and failures
+///
+/// As with the blocking connection, once a read or write fails the connection is unusable, and
+/// the peer must be disconnected from.
+///
+/// ## Buffering
+///
+/// Data is sent in frames of up to [`DATA_MAX_SIZE`] bytes. A write may return before its frame
+/// was fully written to the I/O handler, so the connection must be flushed for the data to be
+/// guaranteed to be sent.
+///
+/// ## Full-duplex connections
+///
+/// [`AsyncSecretConnection::split`] and [`AsyncSecretConnection::split_with`] split the
+/// connection into sending and receiving halves, which can be used from separate tasks. No data
+/// is copied between the halves and the I/O handler.
+pub struct AsyncSecretConnection<IoHandler> {
+    io_handler: IoHandler,
+    remote_pubkey: PublicKey,
+    send_state: AsyncSendState,
+    recv_state: AsyncReceiveState,
+    terminate: Arc<AtomicBool>,
+}
+
+impl<IoHandler> AsyncSecretConnection<IoHandler>
+where
+    IoHandler: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    /// Performs a handshake and returns a new `AsyncSecretConnection`.
+    ///
+    /// As with the blocking connection, each peer sends its handshake messages before reading
+    /// those of the other, so the I/O handler must be able to buffer a frame.
+    ///
+    /// # Errors
+    ///
+    /// * if sharing of the pubkey fails
+    /// * if sharing of the signature fails
+    /// * if receiving the signature fails
+    pub async fn new(
+        mut io_handler: IoHandler,
+        local_privkey: ed25519_consensus::SigningKey,
+        protocol_version: Version,
+    ) -> Result<Self, Error> {
+        // Start a handshake process.
+        let local_pubkey = PublicKey::from(&local_privkey);
+        let (mut h, local_eph_pubkey) = Handshake::new(local_privkey, protocol_version);
+
+        // Write local ephemeral pubkey and receive one too.
+        io_handler
+            .write_all(&protocol_version.encode_initial_handshake(&local_eph_pubkey))
+            .await?;
+        io_handler.flush().await?;
+        let response_len = io_handler.read_u8().await?;
+        let mut buf = vec![0; response_len as usize];
+        io_handler.read_exact(&mut buf).await?;
+        let remote_eph_pubkey = protocol_version.decode_initial_handshake(&buf)?;
+
+        // Compute a local signature (also recv_cipher & send_cipher)
+        let h = h.got_key(remote_eph_pubkey)?;
+
+        let mut sc = Self {
+            io_handler,
+            // Replaced once the remote peer is authenticated.
+            remote_pubkey: local_pubkey,
+            send_state: AsyncSendState::new(SendState {
+                cipher: h.state.send_cipher.clone(),
+                nonce: Nonce::default(),
+            }),
+            recv_state: AsyncReceiveState::new(ReceiveState {
+                cipher: h.state.recv_cipher.clone(),
+                nonce: Nonce::default(),
+                buffer: vec![],
+            }),
+            terminate: Arc::new(AtomicBool::new(false)),
+        };
+
+        // Share each other's pubkey & challenge signature.
+        // NOTE: the data must be encrypted/decrypted using ciphers.
+        let buf = match local_pubkey {
+            PublicKey::Ed25519(ref pk) => {
+                protocol_version.encode_auth_signature(pk, &h.state.local_signature)
+            },
+        };
+        sc.write_all(&buf).await?;
+        sc.flush().await?;
+        let mut buf = vec![0; protocol_version.auth_sig_msg_response_len()];
+        sc.read_exact(&mut buf).await?;
+        let auth_sig_msg = protocol_version.decode_auth_signature(&buf)?;
+
+        // Authenticate remote pubkey.
+        sc.remote_pubkey = h.got_signature(auth_sig_msg)?;
+
+        // All good!
+        Ok(sc)
+    }
+
+    /// Splits the connection into its sending and receiving halves, with [`tokio::io::split`].
+    ///
+    /// I/O handlers which can be split without synchronizing the halves, such as
+    /// `tokio::net::TcpStream`, are better split with [`AsyncSecretConnection::split_with`].
+    pub fn split(
+        self,
+    ) -> (
+        AsyncSender<WriteHalf<IoHandler>>,
+        AsyncReceiver<ReadHalf<IoHandler>>,
+    ) {
+        self.split_with(tokio::io::split)
+    }
+}
+
+impl<IoHandler> AsyncSecretConnection<IoHandler> {
+    /// Returns the remote pubkey.
+    pub const fn remote_pubkey(&self) -> PublicKey {
+        self.remote_pubkey
+    }
+
+    /// Splits the connection into its sending and receiving halves, splitting the I/O handler
+    /// with `split`, e.g. `tokio::net::TcpStream::into_split`.
+    pub fn split_with<Reader, Writer>(
+        self,
+        split: impl FnOnce(IoHandler) -> (Reader, Writer),
+    ) -> (AsyncSender<Writer>, AsyncReceiver<Reader>) {
+        let (reader, writer) = split(self.io_handler);
+        (
+            AsyncSender {
+                io_handler: writer,
+                remote_pubkey: self.remote_pubkey,
+                state: self.send_state,
+                terminate: self.terminate.clone(),
+            },
+            AsyncReceiver {
+                io_handler: reader,
+                remote_pubkey: self.remote_pubkey,
+                state: self.recv_state,
+                terminate: self.terminate,
+            },
+        )
+    }
+}
+
+impl<IoHandler: AsyncRead + Unpin> AsyncRead for AsyncSecretConnection<IoHandler> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_checked(&this.terminate, || {
+            this.recv_state.poll_read(&mut this.io_handler, cx, buf)
+        })
+    }
+}
+
+impl<IoHandler: AsyncWrite + Unpin> AsyncWrite for AsyncSecretConnection<IoHandler> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        poll_checked(&this.terminate, || {
+            this.send_state.poll_write(&mut this.io_handler, cx, buf)
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_checked(&this.terminate, || {
+            this.send_state.poll_flush(&mut this.io_handler, cx)
+        })
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_checked(&this.terminate, || {
+            this.send_state.poll_shutdown(&mut this.io_handler, cx)
+        })
+    }
+}
+
+/// The sending end of an [`AsyncSecretConnection`].
+pub struct AsyncSender<IoHandler> {
+    io_handler: IoHandler,
+    remote_pubkey: PublicKey,
+    state: AsyncSendState,
+    terminate: Arc<AtomicBool>,
+}
+
+impl<IoHandler> AsyncSender<IoHandler> {
+    /// Returns the remote pubkey.
+    pub const fn remote_pubkey(&self) -> PublicKey {
+        self.remote_pubkey
+    }
+}
+
+impl<IoHandler: AsyncWrite + Unpin> AsyncWrite for AsyncSender<IoHandler> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        poll_checked(&this.terminate, || {
+            this.state.poll_write(&mut this.io_handler, cx, buf)
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_checked(&this.terminate, || {
+            this.state.poll_flush(&mut this.io_handler, cx)
+        })
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_checked(&this.terminate, || {
+            this.state.poll_shutdown(&mut this.io_handler, cx)
+        })
+    }
+}
+
+/// The receiving end of an [`AsyncSecretConnection`].
+pub struct AsyncReceiver<IoHandler> {
+    io_handler: IoHandler,
+    remote_pubkey: PublicKey,
+    state: AsyncReceiveState,
+    terminate: Arc<AtomicBool>,
+}
+
+impl<IoHandler> AsyncReceiver<IoHandler> {
+    /// Returns the remote pubkey.
+    pub const fn remote_pubkey(&self) -> PublicKey {
+        self.remote_pubkey
+    }
+}
+
+impl<IoHandler: AsyncRead + Unpin> AsyncRead for AsyncReceiver<IoHandler> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_checked(&this.terminate, || {
+            this.state.poll_read(&mut this.io_handler, cx, buf)
+        })
+    }
+}
+
+/// Fails if the connection was terminated by a previous error, and terminates it if `poll`
+/// fails, like `checked_io!` does for blocking connections.
+fn poll_checked<T>(
+    terminate: &AtomicBool,
+    poll: impl FnOnce() -> Poll<io::Result<T>>,
+) -> Poll<io::Result<T>> {
+    if terminate.load(Ordering::SeqCst) {
+        return Poll::Ready(Err(io::Error::other(
+            "secret connection was terminated elsewhere by previous error",
+        )));
+    }
+    let result = poll();
+    if matches!(result, Poll::Ready(Err(_))) {
+        terminate.store(true, Ordering::SeqCst);
+    }
+    result
+}
+
+/// Sending state of an [`AsyncSecretConnection`], with the frame being written.
+struct AsyncSendState {
+    state: SendState,
+    sealed_frame: Box<[u8; SEALED_FRAME_SIZE]>,
+    /// How much of `sealed_frame` was written. All of it when there is no frame to write.
+    written: usize,
+}
+
+impl AsyncSendState {
+    fn new(state: SendState) -> Self {
+        Self {
+            state,
+            sealed_frame: Box::new([0; SEALED_FRAME_SIZE]),
+            written: SEALED_FRAME_SIZE,
+        }
+    }
+
+    /// Writes what is left of the current frame.
+    fn poll_write_frame<W: AsyncWrite + Unpin>(
+        &mut self,
+        io_handler: &mut W,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        while self.written < SEALED_FRAME_SIZE {
+            let n = ready!(
+                Pin::new(&mut *io_handler).poll_write(cx, &self.sealed_frame[self.written..])
+            )?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.written += n;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Encrypts up to a frame's worth of `data` once the previous frame is written.
+    fn poll_write<W: AsyncWrite + Unpin>(
+        &mut self,
+        io_handler: &mut W,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.poll_write_frame(io_handler, cx))?;
+        if data.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let chunk = &data[..data.len().min(DATA_MAX_SIZE)];
+        *self.sealed_frame = seal_frame(&mut self.state, chunk)?;
+        self.written = 0;
+
+        // The chunk is accepted either way: whatever is left of the frame is written by the
+        // next write or flush.
+        if let Poll::Ready(Err(e)) = self.poll_write_frame(io_handler, cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(chunk.len()))
+    }
+
+    fn poll_flush<W: AsyncWrite + Unpin>(
+        &mut self,
+        io_handler: &mut W,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_frame(io_handler, cx))?;
+        Pin::new(io_handler).poll_flush(cx)
+    }
+
+    fn poll_shutdown<W: AsyncWrite + Unpin>(
+        &mut self,
+        io_handler: &mut W,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_frame(io_handler, cx))?;
+        Pin::new(io_handler).poll_shutdown(cx)
+    }
+}
+
+/// Receiving state of an [`AsyncSecretConnection`], with the frame being read.
+struct AsyncReceiveState {
+    state: ReceiveState,
+    sealed_frame: Box<[u8; SEALED_FRAME_SIZE]>,
+    /// How much of `sealed_frame` was read.
+    filled: usize,
+}
+
+impl AsyncReceiveState {
+    fn new(state: ReceiveState) -> Self {
+        Self {
+            state,
+            sealed_frame: Box::new([0; SEALED_FRAME_SIZE]),
+            filled: 0,
+        }
+    }
+
+    /// Reads and decrypts frames until there is data to return. Ends the stream if the I/O
+    /// handler ends between two frames.
+    fn poll_read<R: AsyncRead + Unpin>(
+        &mut self,
+        io_handler: &mut R,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        while self.state.buffer.is_empty() {
+            while self.filled < SEALED_FRAME_SIZE {
+                let mut frame_buf = ReadBuf::new(&mut self.sealed_frame[self.filled..]);
+                ready!(Pin::new(&mut *io_handler).poll_read(cx, &mut frame_buf))?;
+                let n = frame_buf.filled().len();
+                if n == 0 {
+                    return Poll::Ready(if self.filled == 0 {
+                        Ok(())
+                    } else {
+                        Err(io::ErrorKind::UnexpectedEof.into())
+                    });
+                }
+                self.filled += n;
+            }
+            self.filled = 0;
+            self.state.buffer = open_frame(&mut self.state, &self.sealed_frame)?;
+        }
+
+        let n = self.state.read_buffered(buf.initialize_unfilled());
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,791 @@
+//! State sync, through which nodes restore the state of their application from a snapshot
+//! taken by their peers.
+//!
+//! The [`StateSync`] client discovers snapshots over the
+//! [`StreamId::Snapshot`](crate::transport::StreamId::Snapshot) stream, and fetches their chunks
+//! over the [`StreamId::Chunk`](crate::transport::StreamId::Chunk) stream, with the messages of
+//! the `CometBFT` statesync protocol. Snapshots are restored through the snapshot calls of an
+//! ABCI [`Client`], once their app hash is verified by an [`AppHashSource`] such as a light
+//! client.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::{Debug, Display},
+    fs,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use prost::Message as _;
+use rand_core::{OsRng, RngCore};
+use tendermint::{block, node, AppHash};
+use tendermint_abci::Client;
+use tendermint_config::StatesyncConfig;
+use tendermint_light_client::{errors::ErrorDetail as LightClientErrorDetail, instance::Instance};
+use tendermint_proto::v0_38::{
+    abci::{
+        response_apply_snapshot_chunk::Result as ApplyResult,
+        response_offer_snapshot::Result as OfferResult, RequestApplySnapshotChunk, RequestInfo,
+        RequestOfferSnapshot, Snapshot as RawSnapshot,
+    },
+    statesync::{
+        message::Sum, ChunkRequest, ChunkResponse, Message as RawMessage, SnapshotsRequest,
+        SnapshotsResponse,
+    },
+};
+
+use crate::{
+    error::{Error, ErrorDetail},
+    transport::{Connection, StreamId, StreamSend},
+};
+
+/// Maximum size of a message of the snapshot stream.
+pub const MAX_SNAPSHOT_MESSAGE_SIZE: usize = StreamId::Snapshot.max_message_size();
+
+/// Maximum size of a message of the chunk stream.
+pub const MAX_CHUNK_MESSAGE_SIZE: usize = StreamId::Chunk.max_message_size();
+
+/// Snapshot of the state of an application, as advertised by peers.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+    /// Height at which the snapshot was taken.
+    pub height: block::Height,
+    /// Format of the snapshot, specific to the application.
+    pub format: u32,
+    /// Number of chunks of the snapshot.
+    pub chunks: u32,
+    /// Hash of the snapshot, specific to the application.
+    pub hash: Vec<u8>,
+    /// Metadata of the snapshot, specific to the application.
+    pub metadata: Vec<u8>,
+}
+
+impl TryFrom<SnapshotsResponse> for Snapshot {
+    type Error = Error;
+
+    fn try_from(raw: SnapshotsResponse) -> Result<Self, Self::Error> {
+        if raw.chunks == 0 {
+            return Err(Error::invalid_state_sync_message(
+                "snapshot has no chunks".into(),
+            ));
+        }
+        let height = block::Height::try_from(raw.height)
+            .ok()
+            .filter(|height| height.value() > 0)
+            .ok_or_else(|| {
+                Error::invalid_state_sync_message(format!("invalid snapshot height {}", raw.height))
+            })?;
+        Ok(Self {
+            height,
+            format: raw.format,
+            chunks: raw.chunks,
+            hash: raw.hash,
+            metadata: raw.metadata,
+        })
+    }
+}
+
+impl From<Snapshot> for RawSnapshot {
+    fn from(snapshot: Snapshot) -> Self {
+        Self {
+            height: snapshot.height.value(),
+            format: snapshot.format,
+            chunks: snapshot.chunks,
+            hash: snapshot.hash.into(),
+            metadata: snapshot.metadata.into(),
+        }
+    }
+}
+
+/// Source of the trusted app hashes snapshots are verified against, such as a light client.
+pub trait AppHashSource {
+    /// Returns the app hash of the state at the given height, which is the one of the header
+    /// of the next block.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::unverified_app_hash`] if the header of the next block cannot be verified, in
+    ///   which case the snapshot is rejected.
+    /// * Any other error, such as [`Error::app_hash_unavailable`], if the app hash cannot be
+    ///   obtained at all, in which case the restoration fails.
+    fn app_hash(&mut self, height: block::Height) -> Result<AppHash, Error>;
+}
+
+/// Verifies the header of the next block with the light client.
+///
+/// Failing to reach the peers of the light client, or a light client missing its trusted
+/// state or outside of its trusting period, makes the app hash unavailable rather than
+/// unverified.
+impl AppHashSource for Instance {
+    fn app_hash(&mut self, height: block::Height) -> Result<AppHash, Error> {
+        self.light_client
+            .verify_to_target(height.increment(), &mut self.state)
+            .map(|light_block| light_block.signed_header.header.app_hash)
+            .map_err(|e| match e.detail() {
+                LightClientErrorDetail::InvalidLightBlock(_)
+                | LightClientErrorDetail::InvalidAdjacentHeaders(_)
+                | LightClientErrorDetail::MissingLastBlockId(_)
+                | LightClientErrorDetail::BisectionFailed(_)
+                | LightClientErrorDetail::ForkDetected(_)
+                | LightClientErrorDetail::HeightTooHigh(_)
+                | LightClientErrorDetail::TargetLowerThanTrustedState(_) => {
+                    Error::unverified_app_hash(height, e.to_string())
+                },
+                _ => Error::app_hash_unavailable(height, e.to_string()),
+            })
+    }
+}
+
+/// Client restoring the state of an application from a snapshot of its peers, configured by
+/// the statesync section of the node configuration.
+///
+/// Snapshots are discovered from the peers for the discovery time of the configuration, and
+/// the most recent one is offered to the application, along with its app hash. Its chunks
+/// are then fetched from the peers having the snapshot, stored in a directory of the
+/// temporary directory of the configuration, and applied in order. The results of the
+/// application decide whether chunks are applied again or fetched again, and whether
+/// snapshots, formats or peers are rejected, after which other snapshots are tried.
+///
+/// Peers sending invalid messages are disconnected. The errors they were disconnected with
+/// are kept until [`StateSync::take_removed_peers`] is called.
+pub struct StateSync<C, A>
+where
+    C: Connection,
+{
+    discovery_time: Duration,
+    chunk_request_timeout: Duration,
+    chunk_fetchers: usize,
+    temp_dir: PathBuf,
+    app_hashes: A,
+    peers: HashMap<node::Id, Peer<C>>,
+    events: (flume::Sender<Event>, flume::Receiver<Event>),
+    snapshots: HashMap<Snapshot, HashSet<node::Id>>,
+    rejected_snapshots: HashSet<Snapshot>,
+    rejected_formats: HashSet<u32>,
+    rejected_peers: HashSet<node::Id>,
+    restore: Option<Restore>,
+    removed: Vec<(node::Id, Error)>,
+}
+
+impl<C, A> StateSync<C, A>
+where
+    C: Connection,
+    C::StreamRead: 'static,
+    C::Error: Display + Debug + Send + Sync + 'static,
+    A: AppHashSource,
+{
+    /// Creates a client configured by the given statesync section, verifying snapshots with
+    /// the app hashes of `app_hashes`.
+    pub fn new(config: &StatesyncConfig, app_hashes: A) -> Self {
+        let temp_dir = if config.temp_dir.is_empty() {
+            std::env::temp_dir()
+        } else {
+            PathBuf::from(&config.temp_dir)
+        };
+        Self {
+            discovery_time: *config.discovery_time,
+            chunk_request_timeout: *config.chunk_request_timeout,
+            chunk_fetchers: usize::try_from(config.chunk_fetchers)
+                .unwrap_or(usize::MAX)
+                .max(1),
+            temp_dir,
+            app_hashes,
+            peers: HashMap::new(),
+            events: flume::unbounded(),
+            snapshots: HashMap::new(),
+            rejected_snapshots: HashSet::new(),
+            rejected_formats: HashSet::new(),
+            rejected_peers: HashSet::new(),
+            restore: None,
+            removed: Vec::new(),
+        }
+    }
+
+    /// Adds a connected peer with the given ID to download snapshots from, and requests the
+    /// snapshots it has. The connection is closed once the peer is removed, or the client
+    /// dropped.
+    ///
+    /// # Errors
+    ///
+    /// * If the statesync streams cannot be opened.
+    /// * If the request cannot be sent.
+    pub fn add_peer(&mut self, id: node::Id, conn: C) -> eyre::Result<()> {
+        let (snapshot_read, snapshot_send) = conn
+            .open_bidirectional(StreamId::Snapshot)
+            .map_err(eyre::Report::msg)?;
+        let (chunk_read, chunk_send) = conn
+            .open_bidirectional(StreamId::Chunk)
+            .map_err(eyre::Report::msg)?;
+        snapshot_send.send(encode(Sum::SnapshotsRequest(SnapshotsRequest {})))?;
+
+        spawn_reader(id, snapshot_read, self.events.0.clone());
+        spawn_reader(id, chunk_read, self.events.0.clone());
+
+        if let Some(previous) = self.peers.insert(
+            id,
+            Peer {
+                conn,
+                snapshot_send,
+                chunk_send,
+            },
+        ) {
+            let _ = previous.conn.close();
+        }
+        Ok(())
+    }
+
+    /// Returns the discovered snapshots which were not rejected.
+    pub fn snapshots(&self) -> impl Iterator<Item = &Snapshot> {
+        self.snapshots.keys()
+    }
+
+    /// Returns the peers removed since the last call, along with the error each was removed
+    /// with.
+    pub fn take_removed_peers(&mut self) -> Vec<(node::Id, Error)> {
+        std::mem::take(&mut self.removed)
+    }
+
+    /// Discovers snapshots from the peers, and restores the application from the most recent
+    /// one which can be restored. Returns the snapshot, and the app hash of the restored state.
+    ///
+    /// # Errors
+    ///
+    /// * If no snapshot can be restored, or no peers are left.
+    /// * If the application aborts the restoration, or the ABCI connection fails.
+    /// * If the [`AppHashSource`] fails other than by not verifying an app hash.
+    /// * If the app hash of the restored application is not the verified one.
+    /// * If the chunks cannot be stored in the temporary directory.
+    pub fn sync(&mut self, client: &mut Client) -> Result<(Snapshot, AppHash), Error> {
+        self.request_snapshots();
+        let discovery_end = Instant::now() + self.discovery_time;
+        while self.wait(discovery_end) {}
+
+        loop {
+            if self.peers.is_empty() {
+                return Err(Error::no_state_sync_peers());
+            }
+            let Some(snapshot) = self.best_snapshot() else {
+                return Err(Error::no_snapshots());
+            };
+            // Snapshots whose app hash cannot be verified are not offered.
+            let app_hash = match self.app_hashes.app_hash(snapshot.height) {
+                Ok(app_hash) => app_hash,
+                Err(e) if matches!(e.detail(), ErrorDetail::UnverifiedAppHash(_)) => {
+                    tracing::warn!(
+                        height = %snapshot.height,
+                        error = %e,
+                        "rejecting snapshot whose app hash cannot be verified"
+                    );
+                    self.reject_snapshot(&snapshot);
+                    continue;
+                },
+                Err(e) => return Err(e),
+            };
+            if !self.offer(client, &snapshot, &app_hash)? {
+                continue;
+            }
+
+            self.restore = Some(Restore::new(snapshot.clone(), &self.temp_dir)?);
+            let restored = self.restore_chunks(client);
+            self.restore = None;
+            match restored? {
+                Restored::Done => {},
+                Restored::Retry => continue,
+                Restored::Rejected => {
+                    self.reject_snapshot(&snapshot);
+                    continue;
+                },
+            }
+
+            verify_app(client, &snapshot, &app_hash)?;
+            return Ok((snapshot, app_hash));
+        }
+    }
+
+    /// Requests the snapshots of every peer.
+    fn request_snapshots(&mut self) {
+        let request = encode(Sum::SnapshotsRequest(SnapshotsRequest {}));
+        let failed = self
+            .peers
+            .iter()
+            .filter_map(|(&id, peer)| peer.snapshot_send.send(&request).err().map(|e| (id, e)))
+            .collect::<Vec<_>>();
+        for (id, e) in failed {
+            self.remove_peer(id, Error::connection_failed(e.to_string()));
+        }
+    }
+
+    /// Most recent snapshot of the highest format, available from the most peers.
+    fn best_snapshot(&self) -> Option<Snapshot> {
+        self.snapshots
+            .keys()
+            .map(|snapshot| (snapshot, self.snapshot_peers(snapshot).len()))
+            .filter(|(_, peers)| *peers > 0)
+            .max_by_key(|(snapshot, peers)| (snapshot.height, snapshot.format, *peers))
+            .map(|(snapshot, _)| snapshot.clone())
+    }
+
+    /// Connected peers having the snapshot.
+    fn snapshot_peers(&self, snapshot: &Snapshot) -> Vec<node::Id> {
+        self.snapshots
+            .get(snapshot)
+            .into_iter()
+            .flatten()
+            .filter(|id| self.peers.contains_key(id) && !self.rejected_peers.contains(id))
+            .copied()
+            .collect()
+    }
+
+    /// Offers a snapshot to the application, returning whether it accepted it.
+    fn offer(
+        &mut self,
+        client: &mut Client,
+        snapshot: &Snapshot,
+        app_hash: &AppHash,
+    ) -> Result<bool, Error> {
+        let response = client
+            .offer_snapshot(RequestOfferSnapshot {
+                snapshot: Some(snapshot.clone().into()),
+                app_hash: app_hash.as_bytes().to_vec().into(),
+            })
+            .map_err(|e| Error::abci(e.to_string()))?;
+        match response.result() {
+            OfferResult::Accept => return Ok(true),
+            OfferResult::Reject => self.reject_snapshot(snapshot),
+            OfferResult::RejectFormat => self.reject_format(snapshot.format),
+            OfferResult::RejectSender => {
+                for id in self.snapshot_peers(snapshot) {
+                    self.reject_peer(id);
+                }
+            },
+            OfferResult::Abort => {
+                return Err(Error::state_sync_aborted(
+                    "application aborted the snapshot offer".into(),
+                ))
+            },
+            OfferResult::Unknown => {
+                return Err(Error::state_sync_aborted(
+                    "unknown result of the snapshot offer".into(),
+                ))
+            },
+        }
+        Ok(false)
+    }
+
+    /// Fetches the chunks of the snapshot being restored, and applies them in order.
+    fn restore_chunks(&mut self, client: &mut Client) -> Result<Restored, Error> {
+        loop {
+            let Some(restore) = &self.restore else {
+                return Ok(Restored::Rejected);
+            };
+            let snapshot = restore.snapshot.clone();
+            let Some(index) = (0..snapshot.chunks).find(|i| !restore.applied.contains(i)) else {
+                return Ok(Restored::Done);
+            };
+            if let Some(&sender) = restore.received.get(&index) {
+                if let Some(restored) = self.apply_chunk(client, index, sender)? {
+                    return Ok(restored);
+                }
+                continue;
+            }
+
+            if self.peers.is_empty() {
+                return Err(Error::no_state_sync_peers());
+            }
+            if self.snapshot_peers(&snapshot).is_empty() {
+                return Ok(Restored::Rejected);
+            }
+            self.request_chunks();
+            let deadline = self.restore.as_ref().and_then(|restore| {
+                restore
+                    .pending
+                    .values()
+                    .map(|request| request.deadline)
+                    .min()
+            });
+            if let Some(deadline) = deadline {
+                self.wait(deadline);
+            }
+            self.expire_chunk_requests();
+        }
+    }
+
+    /// Applies a received chunk, returning how the restoration ended if it did.
+    fn apply_chunk(
+        &mut self,
+        client: &mut Client,
+        index: u32,
+        sender: node::Id,
+    ) -> Result<Option<Restored>, Error> {
+        let Some(restore) = &mut self.restore else {
+            return Ok(Some(Restored::Rejected));
+        };
+        let chunk = fs::read(restore.chunk_path(index))?;
+        let response = client
+            .apply_snapshot_chunk(RequestApplySnapshotChunk {
+                index,
+                chunk: chunk.into(),
+                sender: sender.to_string(),
+            })
+            .map_err(|e| Error::abci(e.to_string()))?;
+
+        let result = response.result();
+        if result == ApplyResult::Accept {
+            restore.applied.insert(index);
+        }
+        for &index in &response.refetch_chunks {
+            restore.discard(index);
+        }
+        for sender in &response.reject_senders {
+            if let Ok(id) = sender.parse() {
+                self.reject_peer(id);
+            }
+        }
+
+        match result {
+            // Chunks to retry are applied again.
+            ApplyResult::Accept | ApplyResult::Retry => Ok(None),
+            ApplyResult::RetrySnapshot => Ok(Some(Restored::Retry)),
+            ApplyResult::RejectSnapshot => Ok(Some(Restored::Rejected)),
+            ApplyResult::Abort => Err(Error::state_sync_aborted(format!(
+                "application aborted applying chunk {index}"
+            ))),
+            ApplyResult::Unknown => Err(Error::state_sync_aborted(format!(
+                "unknown result applying chunk {index}"
+            ))),
+        }
+    }
+
+    /// Requests the chunks which are neither received nor requested, up to the number of
+    /// chunk fetchers, preferring the peers which were not asked for the chunk yet.
+    fn request_chunks(&mut self) {
+        let Some(restore) = &self.restore else {
+            return;
+        };
+        let candidates = self.snapshot_peers(&restore.snapshot);
+        let Some(restore) = &mut self.restore else {
+            return;
+        };
+
+        let now = Instant::now();
+        let mut failed = None;
+        for index in 0..restore.snapshot.chunks {
+            if restore.pending.len() >= self.chunk_fetchers {
+                break;
+            }
+            if restore.applied.contains(&index)
+                || restore.received.contains_key(&index)
+                || restore.pending.contains_key(&index)
+            {
+                continue;
+            }
+
+            let load = |id: &&node::Id| {
+                restore
+                    .pending
+                    .values()
+                    .filter(|request| request.peer == **id)
+                    .count()
+            };
+            let untried = candidates
+                .iter()
+                .filter(|id| !restore.tried.contains(&(index, **id)))
+                .min_by_key(load);
+            let Some(&id) = untried.or_else(|| candidates.iter().min_by_key(load)) else {
+                break;
+            };
+            let Some(peer) = self.peers.get(&id) else {
+                break;
+            };
+
+            let request = encode(Sum::ChunkRequest(ChunkRequest {
+                height: restore.snapshot.height.value(),
+                format: restore.snapshot.format,
+                index,
+            }));
+            if let Err(e) = peer.chunk_send.send(request) {
+                failed = Some((id, e));
+                break;
+            }
+            restore.pending.insert(
+                index,
+                PendingRequest {
+                    peer: id,
+                    deadline: now + self.chunk_request_timeout,
+                },
+            );
+            restore.tried.insert((index, id));
+        }
+
+        if let Some((id, e)) = failed {
+            self.remove_peer(id, Error::connection_failed(e.to_string()));
+        }
+    }
+
+    /// Drops the chunk requests which were not answered in time, for them to be sent again,
+    /// possibly to other peers.
+    fn expire_chunk_requests(&mut self) {
+        let now = Instant::now();
+        if let Some(restore) = &mut self.restore {
+            restore.pending.retain(|_, request| request.deadline > now);
+        }
+    }
+
+    /// Waits for the next message of a peer until the deadline, and handles it. Returns
+    /// whether a message was received.
+    fn wait(&mut self, deadline: Instant) -> bool {
+        let Ok((id, msg)) = self.events.1.recv_deadline(deadline) else {
+            return false;
+        };
+        if !self.peers.contains_key(&id) {
+            return true;
+        }
+        let result = msg.map_or_else(
+            || Err(Error::connection_closed()),
+            |msg| self.receive(id, &msg),
+        );
+        if let Err(e) = result {
+            self.remove_peer(id, e);
+        }
+        true
+    }
+
+    /// Handles a message received from a known peer.
+    fn receive(&mut self, id: node::Id, msg: &[u8]) -> Result<(), Error> {
+        let msg = RawMessage::decode(msg)
+            .map_err(|e| Error::invalid_state_sync_message(e.to_string()))?;
+        match msg.sum {
+            // Snapshots are not served, as none are taken.
+            Some(Sum::SnapshotsRequest(_)) => Ok(()),
+            Some(Sum::SnapshotsResponse(response)) => {
+                let snapshot = Snapshot::try_from(response)?;
+                if !self.rejected_snapshots.contains(&snapshot)
+                    && !self.rejected_formats.contains(&snapshot.format)
+                    && !self.rejected_peers.contains(&id)
+                {
+                    self.snapshots.entry(snapshot).or_default().insert(id);
+                }
+                Ok(())
+            },
+            Some(Sum::ChunkRequest(ChunkRequest {
+                height,
+                format,
+                index,
+            })) => {
+                let Some(peer) = self.peers.get(&id) else {
+                    return Err(Error::unknown_peer(id));
+                };
+                let response = encode(Sum::ChunkResponse(ChunkResponse {
+                    height,
+                    format,
+                    index,
+                    chunk: Vec::new(),
+                    missing: true,
+                }));
+                peer.chunk_send
+                    .send(response)
+                    .map_err(|e| Error::connection_failed(e.to_string()))
+            },
+            Some(Sum::ChunkResponse(response)) => self.receive_chunk(id, &response),
+            None => Err(Error::invalid_state_sync_message("empty message".into())),
+        }
+    }
+
+    /// Stores a chunk of the snapshot being restored, if it was requested from the peer.
+    /// Chunks of other snapshots, or answering requests which timed out, are ignored.
+    fn receive_chunk(&mut self, id: node::Id, response: &ChunkResponse) -> Result<(), Error> {
+        let Some(restore) = &mut self.restore else {
+            return Ok(());
+        };
+        if response.height != restore.snapshot.height.value()
+            || response.format != restore.snapshot.format
+            || restore
+                .pending
+                .get(&response.index)
+                .is_none_or(|request| request.peer != id)
+        {
+            return Ok(());
+        }
+
+        restore.pending.remove(&response.index);
+        // Peers missing the chunk are only not asked for it again.
+        if !response.missing {
+            fs::write(restore.chunk_path(response.index), &response.chunk)?;
+            restore.received.insert(response.index, id);
+        }
+        Ok(())
+    }
+
+    fn reject_snapshot(&mut self, snapshot: &Snapshot) {
+        self.snapshots.remove(snapshot);
+        self.rejected_snapshots.insert(snapshot.clone());
+    }
+
+    fn reject_format(&mut self, format: u32) {
+        self.snapshots
+            .retain(|snapshot, _| snapshot.format != format);
+        self.rejected_formats.insert(format);
+    }
+
+    /// Rejects the snapshots of a peer, and the chunks it sent which were not applied.
+    fn reject_peer(&mut self, id: node::Id) {
+        for peers in self.snapshots.values_mut() {
+            peers.remove(&id);
+        }
+        self.snapshots.retain(|_, peers| !peers.is_empty());
+        self.rejected_peers.insert(id);
+
+        if let Some(restore) = &mut self.restore {
+            let sent = restore
+                .received
+                .iter()
+                .filter(|(index, sender)| **sender == id && !restore.applied.contains(index))
+                .map(|(&index, _)| index)
+                .collect::<Vec<_>>();
+            for index in sent {
+                restore.discard(index);
+            }
+            restore.pending.retain(|_, request| request.peer != id);
+        }
+    }
+
+    /// Disconnects a peer, whose chunk requests are then sent to other peers.
+    fn remove_peer(&mut self, id: node::Id, e: Error) {
+        let Some(peer) = self.peers.remove(&id) else {
+            return;
+        };
+        let _ = peer.conn.close();
+        if let Some(restore) = &mut self.restore {
+            restore.pending.retain(|_, request| request.peer != id);
+        }
+        self.removed.push((id, e));
+    }
+}
+
+impl<C, A> Drop for StateSync<C, A>
+where
+    C: Connection,
+{
+    fn drop(&mut self) {
+        for peer in self.peers.values() {
+            let _ = peer.conn.close();
+        }
+    }
+}
+
+/// Checks that the application restored the state of the snapshot.
+fn verify_app(client: &mut Client, snapshot: &Snapshot, app_hash: &AppHash) -> Result<(), Error> {
+    let info = client
+        .info(RequestInfo::default())
+        .map_err(|e| Error::abci(e.to_string()))?;
+    if u64::try_from(info.last_block_height).ok() != Some(snapshot.height.value()) {
+        return Err(Error::state_sync_aborted(format!(
+            "restored height is {}, not {}",
+            info.last_block_height, snapshot.height
+        )));
+    }
+    let actual = AppHash::try_from(info.last_block_app_hash)
+        .map_err(|e| Error::state_sync_aborted(e.to_string()))?;
+    if actual != *app_hash {
+        return Err(Error::app_hash_mismatch(
+            snapshot.height,
+            app_hash.clone(),
+            actual,
+        ));
+    }
+    Ok(())
+}
+
+/// How the restoration of a snapshot ended.
+enum Restored {
+    /// Every chunk was applied.
+    Done,
+    /// The snapshot is to be offered again.
+    Retry,
+    /// The snapshot was rejected.
+    Rejected,
+}
+
+/// Message received from a peer, or `None` once its stream ended.
+type Event = (node::Id, Option<Vec<u8>>);
+
+/// Peer snapshots are downloaded from.
+struct Peer<C: Connection> {
+    conn: C,
+    snapshot_send: C::StreamSend,
+    chunk_send: C::StreamSend,
+}
+
+/// Snapshot being restored, whose received chunks are stored in a temporary directory until
+/// applied.
+struct Restore {
+    snapshot: Snapshot,
+    dir: PathBuf,
+    /// Peers which sent the chunks stored in the directory.
+    received: HashMap<u32, node::Id>,
+    pending: HashMap<u32, PendingRequest>,
+    applied: BTreeSet<u32>,
+    /// Chunks and the peers they were requested from.
+    tried: HashSet<(u32, node::Id)>,
+}
+
+impl Restore {
+    fn new(snapshot: Snapshot, temp_dir: &std::path::Path) -> Result<Self, Error> {
+        let dir = temp_dir.join(format!("statesync-{:016x}", OsRng.next_u64()));
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            snapshot,
+            dir,
+            received: HashMap::new(),
+            pending: HashMap::new(),
+            applied: BTreeSet::new(),
+            tried: HashSet::new(),
+        })
+    }
+
+    fn chunk_path(&self, index: u32) -> PathBuf {
+        self.dir.join(index.to_string())
+    }
+
+    /// Forgets a chunk, for it to be fetched and applied again.
+    fn discard(&mut self, index: u32) {
+        if self.received.remove(&index).is_some() {
+            let _ = fs::remove_file(self.chunk_path(index));
+        }
+        self.applied.remove(&index);
+        self.tried.retain(|(tried, _)| *tried != index);
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Request for a chunk, which has not been answered yet.
+struct PendingRequest {
+    peer: node::Id,
+    deadline: Instant,
+}
+
+/// Forwards the messages of a stream to the events of the client, until it ends.
+fn spawn_reader<R>(id: node::Id, read: R, events: flume::Sender<Event>)
+where
+    R: Iterator<Item = eyre::Result<Vec<u8>>> + Send + 'static,
+{
+    thread::spawn(move || {
+        for msg in read {
+            let Ok(msg) = msg else { break };
+            if events.send((id, Some(msg))).is_err() {
+                return;
+            }
+        }
+        let _ = events.send((id, None));
+    });
+}
+
+fn encode(sum: Sum) -> Vec<u8> {
+    RawMessage { sum: Some(sum) }.encode_to_vec()
+}
@@ -4,7 +4,13 @@
 use std::net::{SocketAddr, ToSocketAddrs};
 
 use eyre::Result;
-use tendermint::{node, public_key::PublicKey};
+use tendermint::{channel, node, public_key::PublicKey};
+
+pub use self::connection::{EncryptedConnection, StreamReceiver, StreamSender};
+
+mod connection;
+pub mod memory;
+pub mod tcp;
 
 /// Information which resources to bind to and how to identify on the network.
 pub struct BindInfo<A>
@@ -37,10 +43,42 @@ where
 }
 
 /// Known list of typed streams.
-#[derive(Clone, Copy, Hash, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
 pub enum StreamId {
     /// Stream to exchange message concerning Peer Exchange.
     Pex,
+    /// Stream to request and serve historical blocks.
+    BlockSync,
+    /// Stream to discover the state snapshots of peers.
+    Snapshot,
+    /// Stream to request and serve the chunks of state snapshots.
+    Chunk,
+}
+
+impl StreamId {
+    /// Returns the ID of the `MConnection` channel carrying the stream.
+    #[must_use]
+    pub const fn channel_id(self) -> channel::Id {
+        match self {
+            Self::Pex => channel::Id(0x00),
+            Self::BlockSync => channel::Id(0x40),
+            Self::Snapshot => channel::Id(0x60),
+            Self::Chunk => channel::Id(0x61),
+        }
+    }
+
+    /// Returns the maximum size of a message of the stream.
+    #[must_use]
+    pub const fn max_message_size(self) -> usize {
+        match self {
+            // Up to 250 addresses of up to 256 bytes each.
+            Self::Pex => 64_000,
+            // A block of the maximum size, and its encoding overhead.
+            Self::BlockSync => 104_857_600 + 5,
+            Self::Snapshot => 4_000_000,
+            Self::Chunk => 16_000_000,
+        }
+    }
 }
 
 /// Envelope to trace the original direction of an established connection.
@@ -51,6 +89,63 @@ pub enum Direction<Conn> {
     Outgoing(Conn),
 }
 
+impl<Conn> Direction<Conn> {
+    /// Returns a reference to the wrapped connection.
+    pub const fn inner(&self) -> &Conn {
+        match self {
+            Self::Incoming(conn) | Self::Outgoing(conn) => conn,
+        }
+    }
+
+    /// Unwraps the connection, discarding its direction.
+    pub fn into_inner(self) -> Conn {
+        match self {
+            Self::Incoming(conn) | Self::Outgoing(conn) => conn,
+        }
+    }
+
+    /// Whether the remote peer connected to the local node.
+    pub const fn is_incoming(&self) -> bool {
+        matches!(self, Self::Incoming(_))
+    }
+}
+
+impl<Conn> Connection for Direction<Conn>
+where
+    Conn: Connection,
+{
+    type Error = Conn::Error;
+    type StreamRead = Conn::StreamRead;
+    type StreamSend = Conn::StreamSend;
+
+    fn advertised_addrs(&self) -> Vec<SocketAddr> {
+        self.inner().advertised_addrs()
+    }
+
+    fn close(&self) -> Result<()> {
+        self.inner().close()
+    }
+
+    fn local_addr(&self) -> SocketAddr {
+        self.inner().local_addr()
+    }
+
+    fn open_bidirectional(
+        &self,
+        stream_id: StreamId,
+    ) -> Result<(Self::StreamRead, Self::StreamSend), Self::Error> {
+        self.inner().open_bidirectional(stream_id)
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.inner().public_key()
+    }
+
+    fn remote_addr(&self) -> SocketAddr {
+        self.inner().remote_addr()
+    }
+}
+
 /// Trait that describes the send end of a stream.
 pub trait StreamSend {
     /// Sends the message to the peer over the open stream. `msg` should be a valid and properly
@@ -61,7 +156,7 @@ pub trait StreamSend {
     /// * If the underlying I/O operations fail.
     /// * If the stream is closed.
     /// * If the peer is gone
-    fn send<B: AsRef<[u8]>>(msg: B) -> Result<()>;
+    fn send<B: AsRef<[u8]>>(&self, msg: B) -> Result<()>;
 }
 
 /// Trait which describes the core concept of a connection between two peers established by
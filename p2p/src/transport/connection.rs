@@ -0,0 +1,195 @@
+//! Connections secured with a [`SecretConnection`], as established by the transports of this
+//! crate.
+
+use std::{
+    io::{Read, Write},
+    net::SocketAddr,
+};
+
+use eyre::Result;
+use tendermint::{node, public_key::PublicKey};
+use tendermint_std_ext::TryClone;
+
+use super::{Connection, StreamId, StreamSend};
+use crate::{
+    error::Error,
+    mconnection::{
+        ChannelDescriptor, ChannelReceiver, ChannelSender, MConnection, MConnectionConfig, Shutdown,
+    },
+    secret_connection::SecretConnection,
+};
+
+/// Connection to a remote peer, encrypted and authenticated with a [`SecretConnection`].
+///
+/// Streams are multiplexed over the connection with an [`MConnection`], each stream being
+/// carried by the channel given by [`StreamId::channel_id`].
+///
+/// The connection is closed when dropped.
+pub struct EncryptedConnection {
+    remote_id: node::Id,
+    public_key: PublicKey,
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+    advertised_addrs: Vec<SocketAddr>,
+    mconn: MConnection,
+}
+
+impl EncryptedConnection {
+    /// Wraps a `SecretConnection` whose handshake has completed, and starts multiplexing the
+    /// streams over it.
+    pub(crate) fn new<IoHandler>(
+        conn: SecretConnection<IoHandler>,
+        io: impl Shutdown + 'static,
+        config: &MConnectionConfig,
+        local_addr: SocketAddr,
+        remote_addr: SocketAddr,
+        advertised_addrs: Vec<SocketAddr>,
+    ) -> Result<Self, Error>
+    where
+        IoHandler: Read + Write + Send + Sync + TryClone + 'static,
+        <IoHandler as TryClone>::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let remote_pubkey = conn.remote_pubkey();
+        let public_key = remote_pubkey
+            .ed25519()
+            .and_then(|pk| PublicKey::from_raw_ed25519(pk.as_bytes()))
+            .ok_or_else(Error::invalid_key)?;
+
+        Ok(Self {
+            remote_id: remote_pubkey.peer_id(),
+            public_key,
+            local_addr,
+            remote_addr,
+            advertised_addrs,
+            mconn: MConnection::new(conn, io, &stream_channels(), config)?,
+        })
+    }
+
+    /// Returns the id of the remote peer, derived from its public key.
+    #[must_use]
+    pub const fn remote_id(&self) -> node::Id {
+        self.remote_id
+    }
+
+    /// Checks that the remote peer is the one expected.
+    ///
+    /// # Errors
+    ///
+    /// * If the id of the remote peer differs from `expected`.
+    pub(crate) fn verify_remote_id(&self, expected: node::Id) -> Result<(), Error> {
+        if self.remote_id == expected {
+            Ok(())
+        } else {
+            Err(Error::remote_peer_mismatch(expected, self.remote_id))
+        }
+    }
+}
+
+impl Connection for EncryptedConnection {
+    type Error = Error;
+    type StreamRead = StreamReceiver;
+    type StreamSend = StreamSender;
+
+    /// For outgoing connections, these are the addresses the peer was dialed at. Peers
+    /// connecting to the local node do not advertise any addresses at this layer.
+    fn advertised_addrs(&self) -> Vec<SocketAddr> {
+        self.advertised_addrs.clone()
+    }
+
+    fn close(&self) -> Result<()> {
+        self.mconn.close().map_err(Error::into_report)
+    }
+
+    fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn open_bidirectional(
+        &self,
+        stream_id: StreamId,
+    ) -> Result<(Self::StreamRead, Self::StreamSend), Self::Error> {
+        let channel_id = stream_id.channel_id();
+        Ok((
+            StreamReceiver {
+                messages: self.mconn.receiver(channel_id)?,
+            },
+            StreamSender {
+                sender: self.mconn.sender(channel_id)?,
+            },
+        ))
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+}
+
+/// Read end of a stream of an [`EncryptedConnection`].
+///
+/// Ends when the connection is closed. If the connection failed, the error is yielded last.
+pub struct StreamReceiver {
+    messages: ChannelReceiver,
+}
+
+impl Iterator for StreamReceiver {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.messages
+            .next()
+            .map(|msg| msg.map_err(Error::into_report))
+    }
+}
+
+/// Send end of a stream of an [`EncryptedConnection`].
+pub struct StreamSender {
+    sender: ChannelSender,
+}
+
+impl StreamSend for StreamSender {
+    fn send<B: AsRef<[u8]>>(&self, msg: B) -> Result<()> {
+        self.sender
+            .send(msg.as_ref().to_vec())
+            .map_err(Error::into_report)
+    }
+}
+
+/// Channels carrying the streams, with the same properties as in `CometBFT`.
+///
+/// The incoming queues leave room for the bursts of responses to the requests a client may
+/// have in flight, such as the blocks requested from a peer by blocksync.
+const fn stream_channels() -> [ChannelDescriptor; 4] {
+    [
+        ChannelDescriptor {
+            send_queue_capacity: 10,
+            recv_queue_capacity: 10,
+            recv_message_capacity: StreamId::Pex.max_message_size(),
+            ..ChannelDescriptor::new(StreamId::Pex.channel_id())
+        },
+        ChannelDescriptor {
+            priority: 5,
+            send_queue_capacity: 1000,
+            recv_queue_capacity: 100,
+            recv_message_capacity: StreamId::BlockSync.max_message_size(),
+            ..ChannelDescriptor::new(StreamId::BlockSync.channel_id())
+        },
+        ChannelDescriptor {
+            priority: 6,
+            send_queue_capacity: 10,
+            recv_queue_capacity: 10,
+            recv_message_capacity: StreamId::Snapshot.max_message_size(),
+            ..ChannelDescriptor::new(StreamId::Snapshot.channel_id())
+        },
+        ChannelDescriptor {
+            priority: 3,
+            send_queue_capacity: 10,
+            recv_queue_capacity: 10,
+            recv_message_capacity: StreamId::Chunk.max_message_size(),
+            ..ChannelDescriptor::new(StreamId::Chunk.channel_id())
+        },
+    ]
+}
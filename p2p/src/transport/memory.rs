@@ -0,0 +1,393 @@
+//! In-memory [`Transport`], connecting endpoints within the same process.
+//!
+//! Meant for tests: connections go through the same `SecretConnection` handshake as with the
+//! [`TcpTransport`](super::tcp::TcpTransport), but bytes are exchanged over in-memory pipes
+//! rather than sockets.
+
+use std::{
+    cmp,
+    collections::HashMap,
+    convert::Infallible,
+    io::{self, Read, Write},
+    net::{SocketAddr, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use eyre::Result;
+use tendermint::public_key::PublicKey;
+use tendermint_std_ext::TryClone;
+
+use super::{BindInfo, ConnectInfo, Direction, EncryptedConnection, Endpoint, Transport};
+use crate::{
+    error::Error,
+    mconnection::{MConnectionConfig, Shutdown},
+    secret_connection::{SecretConnection, Version},
+};
+
+/// Connection established by a [`MemoryTransport`].
+pub type MemoryConnection = Direction<EncryptedConnection>;
+
+/// First port assigned to endpoints bound to port 0.
+const FIRST_EPHEMERAL_PORT: u16 = 49152;
+
+/// Registry of the endpoints bound to a set of [`MemoryTransport`]s, which can connect to each
+/// other by address.
+#[derive(Clone)]
+pub struct MemoryNetwork {
+    listeners: Arc<Mutex<Listeners>>,
+}
+
+struct Listeners {
+    by_addr: HashMap<SocketAddr, flume::Sender<(Pipe, SocketAddr)>>,
+    next_port: u16,
+}
+
+impl Listeners {
+    /// Picks an unused port for the given address if it has port 0.
+    fn assign_port(&mut self, mut addr: SocketAddr) -> SocketAddr {
+        if addr.port() == 0 {
+            loop {
+                addr.set_port(self.next_port);
+                self.next_port = self
+                    .next_port
+                    .checked_add(1)
+                    .unwrap_or(FIRST_EPHEMERAL_PORT);
+                if !self.by_addr.contains_key(&addr) {
+                    break;
+                }
+            }
+        }
+        addr
+    }
+}
+
+impl Default for MemoryNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryNetwork {
+    /// Creates an empty network.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            listeners: Arc::new(Mutex::new(Listeners {
+                by_addr: HashMap::new(),
+                next_port: FIRST_EPHEMERAL_PORT,
+            })),
+        }
+    }
+
+    /// Creates a transport on this network, which identifies the local node with the given
+    /// private key.
+    #[must_use]
+    pub fn transport(&self, private_key: ed25519_consensus::SigningKey) -> MemoryTransport {
+        MemoryTransport {
+            network: self.clone(),
+            private_key,
+            protocol_version: Version::V0_34,
+        }
+    }
+
+    fn listeners(&self) -> std::sync::MutexGuard<'_, Listeners> {
+        self.listeners
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// [`Transport`] over in-memory pipes, created with [`MemoryNetwork::transport`].
+pub struct MemoryTransport {
+    network: MemoryNetwork,
+    private_key: ed25519_consensus::SigningKey,
+    protocol_version: Version,
+}
+
+impl MemoryTransport {
+    /// Sets the `SecretConnection` protocol version to use. Defaults to [`Version::V0_34`].
+    #[must_use]
+    pub const fn protocol_version(mut self, version: Version) -> Self {
+        self.protocol_version = version;
+        self
+    }
+}
+
+impl<A> Transport<A> for MemoryTransport
+where
+    A: ToSocketAddrs,
+{
+    type Connection = MemoryConnection;
+    type Endpoint = MemoryEndpoint;
+    type Incoming = MemoryIncoming;
+
+    /// Binds to the first of the addresses to bind to. Port 0 is replaced with an unused port.
+    fn bind(self, bind_info: BindInfo<A>) -> Result<(Self::Endpoint, Self::Incoming)> {
+        let local_public_key =
+            PublicKey::from_raw_ed25519(self.private_key.verification_key().as_bytes());
+        if local_public_key != Some(bind_info.public_key) {
+            return Err(Error::public_key_mismatch().into_report());
+        }
+
+        let addr = bind_info
+            .bind_addrs
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::no_address().into_report())?;
+
+        let (tx, rx) = flume::unbounded();
+        let listen_addr = {
+            let mut listeners = self.network.listeners();
+            let addr = listeners.assign_port(addr);
+            if listeners.by_addr.contains_key(&addr) {
+                return Err(Error::address_in_use(addr).into_report());
+            }
+            listeners.by_addr.insert(addr, tx);
+            addr
+        };
+
+        Ok((
+            MemoryEndpoint {
+                network: self.network,
+                private_key: self.private_key.clone(),
+                protocol_version: self.protocol_version,
+                listen_addr,
+            },
+            MemoryIncoming {
+                private_key: self.private_key,
+                protocol_version: self.protocol_version,
+                listen_addr,
+                pipes: rx,
+            },
+        ))
+    }
+}
+
+/// Local handle of a bound [`MemoryTransport`], used to connect to other endpoints of the same
+/// [`MemoryNetwork`].
+///
+/// Dropping the endpoint releases its address, and ends its [`MemoryIncoming`].
+pub struct MemoryEndpoint {
+    network: MemoryNetwork,
+    private_key: ed25519_consensus::SigningKey,
+    protocol_version: Version,
+    listen_addr: SocketAddr,
+}
+
+impl<A> Endpoint<A> for MemoryEndpoint
+where
+    A: ToSocketAddrs,
+{
+    type Connection = MemoryConnection;
+
+    /// Connects to the first address of the peer that an endpoint is bound to.
+    ///
+    /// The handshake only completes once the remote endpoint accepts the connection by
+    /// advancing its [`MemoryIncoming`].
+    fn connect(&self, info: ConnectInfo<A>) -> Result<Self::Connection> {
+        let addrs = info.addrs.to_socket_addrs()?.collect::<Vec<_>>();
+
+        let (pipe, local_addr, remote_addr) = {
+            let mut listeners = self.network.listeners();
+            let (remote_addr, listener) = addrs
+                .iter()
+                .find_map(|addr| Some((*addr, listeners.by_addr.get(addr)?.clone())))
+                .ok_or_else(|| {
+                    addrs
+                        .first()
+                        .map_or_else(Error::no_address, |addr| Error::connection_refused(*addr))
+                        .into_report()
+                })?;
+            let mut local_addr = self.listen_addr;
+            local_addr.set_port(0);
+            let local_addr = listeners.assign_port(local_addr);
+
+            let (local, remote) = pipe();
+            listener
+                .send((remote, local_addr))
+                .map_err(|_| Error::connection_refused(remote_addr).into_report())?;
+            drop(listeners);
+            (local, local_addr, remote_addr)
+        };
+
+        let conn = handshake(
+            pipe,
+            &self.private_key,
+            self.protocol_version,
+            local_addr,
+            remote_addr,
+            addrs,
+        )
+        .map_err(Error::into_report)?;
+        conn.verify_remote_id(info.id).map_err(Error::into_report)?;
+        Ok(Direction::Outgoing(conn))
+    }
+
+    fn listen_addrs(&self) -> Vec<SocketAddr> {
+        vec![self.listen_addr]
+    }
+}
+
+impl Drop for MemoryEndpoint {
+    fn drop(&mut self) {
+        self.network.listeners().by_addr.remove(&self.listen_addr);
+    }
+}
+
+/// Inbound connections of a bound [`MemoryTransport`].
+///
+/// Each connection goes through the handshake before it is yielded. Connections failing the
+/// handshake are yielded as errors. Ends once the [`MemoryEndpoint`] is dropped.
+pub struct MemoryIncoming {
+    private_key: ed25519_consensus::SigningKey,
+    protocol_version: Version,
+    listen_addr: SocketAddr,
+    pipes: flume::Receiver<(Pipe, SocketAddr)>,
+}
+
+impl Iterator for MemoryIncoming {
+    type Item = Result<MemoryConnection>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (pipe, remote_addr) = self.pipes.recv().ok()?;
+        let result = handshake(
+            pipe,
+            &self.private_key,
+            self.protocol_version,
+            self.listen_addr,
+            remote_addr,
+            Vec::new(),
+        );
+        Some(result.map(Direction::Incoming).map_err(Error::into_report))
+    }
+}
+
+fn handshake(
+    pipe: Pipe,
+    private_key: &ed25519_consensus::SigningKey,
+    protocol_version: Version,
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+    advertised_addrs: Vec<SocketAddr>,
+) -> Result<EncryptedConnection, Error> {
+    let shutdown = pipe.clone();
+    let conn = SecretConnection::new(pipe, private_key.clone(), protocol_version)?;
+    EncryptedConnection::new(
+        conn,
+        shutdown,
+        &MConnectionConfig::default(),
+        local_addr,
+        remote_addr,
+        advertised_addrs,
+    )
+}
+
+/// One end of a bidirectional in-memory byte stream.
+///
+/// Clones share the underlying stream, but each buffers what it has read on its own.
+struct Pipe {
+    tx: flume::Sender<Vec<u8>>,
+    rx: flume::Receiver<Vec<u8>>,
+    /// Wakes up a read blocked on `rx` on shutdown, without keeping the channel open.
+    wake: flume::WeakSender<Vec<u8>>,
+    /// Shared by both ends.
+    closed: Arc<AtomicBool>,
+    buffer: Vec<u8>,
+    pos: usize,
+}
+
+/// Creates the two ends of a pipe.
+fn pipe() -> (Pipe, Pipe) {
+    let (tx1, rx1) = flume::unbounded();
+    let (tx2, rx2) = flume::unbounded();
+    let closed = Arc::new(AtomicBool::new(false));
+    let end = |tx: flume::Sender<Vec<u8>>, rx, wake: &flume::Sender<Vec<u8>>| Pipe {
+        tx,
+        rx,
+        wake: wake.downgrade(),
+        closed: closed.clone(),
+        buffer: Vec::new(),
+        pos: 0,
+    };
+    (end(tx1.clone(), rx2, &tx2), end(tx2, rx1, &tx1))
+}
+
+impl Clone for Pipe {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            rx: self.rx.clone(),
+            wake: self.wake.clone(),
+            closed: self.closed.clone(),
+            buffer: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buffer.len() {
+            // Like with a socket, data sent before the pipe was shut down can still be read.
+            if self.closed.load(Ordering::SeqCst) && self.rx.is_empty() {
+                return Ok(0);
+            }
+            // Empty chunks are only sent on shutdown, and disconnection means the other end is
+            // gone: both are the end of the stream.
+            match self.rx.recv() {
+                Ok(chunk) if !chunk.is_empty() => {
+                    self.buffer = chunk;
+                    self.pos = 0;
+                },
+                _ => return Ok(0),
+            }
+        }
+
+        let n = cmp::min(buf.len(), self.buffer.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buffer[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.tx
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl TryClone for Pipe {
+    type Error = Infallible;
+
+    fn try_clone(&self) -> Result<Self, Self::Error> {
+        Ok(self.clone())
+    }
+}
+
+impl Shutdown for Pipe {
+    fn shutdown(&self) -> io::Result<()> {
+        self.closed.store(true, Ordering::SeqCst);
+        // Wake up blocked reads on both ends.
+        if let Some(wake) = self.wake.upgrade() {
+            let _ = wake.send(Vec::new());
+        }
+        let _ = self.tx.send(Vec::new());
+        Ok(())
+    }
+}
@@ -0,0 +1,291 @@
+//! [`Transport`] over TCP, securing every connection with a [`SecretConnection`].
+
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use eyre::Result;
+use tendermint::public_key::PublicKey;
+
+use super::{BindInfo, ConnectInfo, Direction, EncryptedConnection, Endpoint, Transport};
+use crate::{
+    error::Error,
+    mconnection::MConnectionConfig,
+    secret_connection::{SecretConnection, Version},
+};
+
+/// Default timeout for establishing a TCP connection to a peer.
+pub const DEFAULT_DIAL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Default timeout for completing the `SecretConnection` handshake with a peer.
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Maximum number of inbound connections going through the handshake, or waiting to be yielded
+/// once it completed, at the same time.
+pub const MAX_PENDING_INBOUND: usize = 64;
+
+/// Connection established by a [`TcpTransport`].
+pub type TcpConnection = Direction<EncryptedConnection>;
+
+/// [`Transport`] over TCP.
+///
+/// Every inbound and outbound connection is secured with a [`SecretConnection`] using the
+/// private key of the transport. Outbound connections are rejected if the remote peer is not
+/// the one given in the [`ConnectInfo`].
+pub struct TcpTransport {
+    private_key: ed25519_consensus::SigningKey,
+    protocol_version: Version,
+    dial_timeout: Duration,
+    handshake_timeout: Duration,
+    mconnection_config: MConnectionConfig,
+}
+
+impl TcpTransport {
+    /// Creates a transport which identifies the local node with the given private key.
+    #[must_use]
+    pub fn new(private_key: ed25519_consensus::SigningKey) -> Self {
+        Self {
+            private_key,
+            protocol_version: Version::V0_34,
+            dial_timeout: DEFAULT_DIAL_TIMEOUT,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            mconnection_config: MConnectionConfig::default(),
+        }
+    }
+
+    /// Sets the `SecretConnection` protocol version to use. Defaults to [`Version::V0_34`].
+    #[must_use]
+    pub const fn protocol_version(mut self, version: Version) -> Self {
+        self.protocol_version = version;
+        self
+    }
+
+    /// Sets the timeout for establishing a TCP connection to a peer.
+    #[must_use]
+    pub const fn dial_timeout(mut self, timeout: Duration) -> Self {
+        self.dial_timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout for completing the `SecretConnection` handshake with a peer.
+    #[must_use]
+    pub const fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Sets the configuration of the `MConnection` multiplexing the streams of every
+    /// connection.
+    #[must_use]
+    pub const fn mconnection_config(mut self, config: MConnectionConfig) -> Self {
+        self.mconnection_config = config;
+        self
+    }
+}
+
+impl<A> Transport<A> for TcpTransport
+where
+    A: ToSocketAddrs,
+{
+    type Connection = TcpConnection;
+    type Endpoint = TcpEndpoint;
+    type Incoming = TcpIncoming;
+
+    fn bind(self, bind_info: BindInfo<A>) -> Result<(Self::Endpoint, Self::Incoming)> {
+        let local_public_key =
+            PublicKey::from_raw_ed25519(self.private_key.verification_key().as_bytes());
+        if local_public_key != Some(bind_info.public_key) {
+            return Err(Error::public_key_mismatch().into_report());
+        }
+
+        let listener = TcpListener::bind(bind_info.bind_addrs)?;
+        let listen_addr = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let handshaker = Handshaker {
+            private_key: self.private_key,
+            protocol_version: self.protocol_version,
+            handshake_timeout: self.handshake_timeout,
+            mconnection_config: self.mconnection_config,
+        };
+
+        let (results_tx, results_rx) = flume::bounded(MAX_PENDING_INBOUND);
+        let acceptor = Acceptor {
+            handshaker: handshaker.clone(),
+            listener,
+            stopped: stopped.clone(),
+            results: results_tx,
+        };
+        thread::spawn(move || acceptor.run());
+
+        Ok((
+            TcpEndpoint {
+                handshaker,
+                dial_timeout: self.dial_timeout,
+                listen_addr,
+                stopped,
+            },
+            TcpIncoming {
+                results: results_rx.into_iter(),
+            },
+        ))
+    }
+}
+
+/// Local handle of a bound [`TcpTransport`], used to connect to remote peers.
+///
+/// Dropping the endpoint stops accepting inbound connections.
+pub struct TcpEndpoint {
+    handshaker: Handshaker,
+    dial_timeout: Duration,
+    listen_addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+}
+
+impl<A> Endpoint<A> for TcpEndpoint
+where
+    A: ToSocketAddrs,
+{
+    type Connection = TcpConnection;
+
+    /// Tries the addresses of the peer in turn, until a connection is established with the
+    /// expected peer. Fails with the error of the last address otherwise.
+    fn connect(&self, info: ConnectInfo<A>) -> Result<Self::Connection> {
+        let addrs = info.addrs.to_socket_addrs()?.collect::<Vec<_>>();
+
+        let mut last_err = None;
+        for addr in &addrs {
+            let result = TcpStream::connect_timeout(addr, self.dial_timeout)
+                .map_err(Error::from)
+                .and_then(|stream| self.handshaker.handshake(stream, addrs.clone()))
+                .and_then(|conn| conn.verify_remote_id(info.id).map(|()| conn));
+            match result {
+                Ok(conn) => return Ok(Direction::Outgoing(conn)),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(Error::no_address).into_report())
+    }
+
+    fn listen_addrs(&self) -> Vec<SocketAddr> {
+        vec![self.listen_addr]
+    }
+}
+
+impl Drop for TcpEndpoint {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake up the listener if it is waiting for a connection, so that it notices.
+        let _ = TcpStream::connect_timeout(&self.listen_addr, self.dial_timeout);
+    }
+}
+
+/// Inbound connections of a bound [`TcpTransport`].
+///
+/// Each accepted connection goes through the handshake before it is yielded. Handshakes run
+/// concurrently, up to [`MAX_PENDING_INBOUND`] at once, and connections are yielded as their
+/// handshake completes, so that a slow peer does not hold up the others. Connections failing
+/// the handshake are yielded as errors. Ends once the [`TcpEndpoint`] is dropped and the
+/// pending handshakes completed.
+pub struct TcpIncoming {
+    results: flume::IntoIter<Result<TcpConnection>>,
+}
+
+impl Iterator for TcpIncoming {
+    type Item = Result<TcpConnection>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.results.next()
+    }
+}
+
+/// Accepts the inbound connections of a [`TcpTransport`] in the background, and runs their
+/// handshakes on threads of their own.
+struct Acceptor {
+    handshaker: Handshaker,
+    listener: TcpListener,
+    stopped: Arc<AtomicBool>,
+    results: flume::Sender<Result<TcpConnection>>,
+}
+
+impl Acceptor {
+    fn run(self) {
+        // Holds a token per pending connection, which is released once it was yielded.
+        let (pending, done) = flume::bounded::<()>(MAX_PENDING_INBOUND);
+        loop {
+            let accepted = self.listener.accept();
+            if self.stopped.load(Ordering::SeqCst) || self.results.is_disconnected() {
+                return;
+            }
+            let stream = match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    if self.results.send(Err(e.into())).is_err() {
+                        return;
+                    }
+                    continue;
+                },
+            };
+
+            // Waits while too many connections are pending.
+            if pending.send(()).is_err() {
+                return;
+            }
+            let (handshaker, results, done) =
+                (self.handshaker.clone(), self.results.clone(), done.clone());
+            thread::spawn(move || {
+                let result = handshaker
+                    .handshake(stream, Vec::new())
+                    .map(Direction::Incoming)
+                    .map_err(Error::into_report);
+                let _ = results.send(result);
+                let _ = done.recv();
+            });
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Handshaker {
+    private_key: ed25519_consensus::SigningKey,
+    protocol_version: Version,
+    handshake_timeout: Duration,
+    mconnection_config: MConnectionConfig,
+}
+
+impl Handshaker {
+    fn handshake(
+        &self,
+        stream: TcpStream,
+        advertised_addrs: Vec<SocketAddr>,
+    ) -> Result<EncryptedConnection, Error> {
+        let local_addr = stream.local_addr()?;
+        let remote_addr = stream.peer_addr()?;
+
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(self.handshake_timeout))?;
+        stream.set_write_timeout(Some(self.handshake_timeout))?;
+        let conn = SecretConnection::new(
+            stream.try_clone()?,
+            self.private_key.clone(),
+            self.protocol_version,
+        )?;
+        stream.set_read_timeout(None)?;
+        stream.set_write_timeout(None)?;
+
+        EncryptedConnection::new(
+            conn,
+            stream,
+            &self.mconnection_config,
+            local_addr,
+            remote_addr,
+            advertised_addrs,
+        )
+    }
+}
@@ -4,7 +4,8 @@
 //! Conditions for inclusion in this crate are:
 //!
 //! 1. The utilities are relatively general.
-//! 2. The utilities don't rely on any code internal to the other crates of this repository.
+//! 2. The utilities don't rely on any code internal to the other crates of this repository,
+//!    except for the generators of their public domain types.
 //!
 //! The each module of this crate (and the module's dependencies) are guarded by
 //! a feature, documented along with the module.
@@ -16,3 +17,7 @@
 /// Enabled with the "time" feature:
 #[cfg(feature = "time")]
 pub mod time;
+
+/// Enabled with the "tendermint" feature, which also enables "time":
+#[cfg(feature = "tendermint")]
+pub mod tendermint;
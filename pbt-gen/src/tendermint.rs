@@ -0,0 +1,282 @@
+//! Provides [proptest](https://github.com/AltSysrq/proptest) generators for
+//! the domain types of the `tendermint` crate.
+//!
+//! The generated values are well-formed, i.e. they can be encoded in the
+//! protobuf and JSON formats of every supported protocol version and decoded
+//! back to an equal value, but they are not otherwise consistent: hashes,
+//! signatures and addresses are arbitrary bytes.
+
+use ::tendermint::{
+    account,
+    block::{self, parts, Commit, CommitSig, Header, Height, Round},
+    chain,
+    evidence::{self, DuplicateVoteEvidence, Evidence},
+    proposal::{self, Proposal},
+    validator::{self, ProposerPriority},
+    vote::{self, ValidatorIndex, Vote},
+    AppHash, Block, Hash, PublicKey, Signature, Time,
+};
+use proptest::{collection::vec, option, prelude::*};
+
+use crate::time::arb_protobuf_safe_datetime;
+
+/// An arbitrary SHA-256 [`Hash`].
+pub fn arb_hash() -> impl Strategy<Value = Hash> {
+    any::<[u8; 32]>().prop_map(Hash::Sha256)
+}
+
+/// An arbitrary [`AppHash`] of up to 64 bytes.
+pub fn arb_app_hash() -> impl Strategy<Value = AppHash> {
+    vec(any::<u8>(), 0..=64).prop_map(|bytes| AppHash::try_from(bytes).unwrap())
+}
+
+/// An arbitrary [`account::Id`].
+pub fn arb_account_id() -> impl Strategy<Value = account::Id> {
+    any::<[u8; 20]>().prop_map(account::Id::new)
+}
+
+/// An arbitrary [`chain::Id`], made of the characters allowed in chain
+/// identifiers.
+pub fn arb_chain_id() -> impl Strategy<Value = chain::Id> {
+    "[a-zA-Z0-9._-]{1,50}".prop_map(|id| chain::Id::try_from(id).unwrap())
+}
+
+/// An arbitrary [`Time`] that can be represented in protobuf.
+pub fn arb_time() -> impl Strategy<Value = Time> {
+    arb_protobuf_safe_datetime().prop_map(|t| Time::try_from(t).unwrap())
+}
+
+/// An arbitrary [`Height`].
+pub fn arb_height() -> impl Strategy<Value = Height> {
+    (1..=i64::MAX as u64).prop_map(|h| Height::try_from(h).unwrap())
+}
+
+/// An arbitrary [`Round`].
+pub fn arb_round() -> impl Strategy<Value = Round> {
+    (0..=i32::MAX as u32).prop_map(|r| Round::try_from(r).unwrap())
+}
+
+/// An arbitrary Ed25519 [`Signature`].
+pub fn arb_signature() -> impl Strategy<Value = Signature> {
+    vec(any::<u8>(), 64).prop_map(|bytes| Signature::new(bytes).unwrap().unwrap())
+}
+
+/// An arbitrary Ed25519 [`PublicKey`].
+pub fn arb_public_key() -> impl Strategy<Value = PublicKey> {
+    any::<[u8; 32]>().prop_map(|bytes| PublicKey::from_raw_ed25519(&bytes).unwrap())
+}
+
+/// An arbitrary [`vote::Power`].
+pub fn arb_power() -> impl Strategy<Value = vote::Power> {
+    (0..=i64::MAX as u64).prop_map(|p| vote::Power::try_from(p).unwrap())
+}
+
+prop_compose! {
+    /// An arbitrary [`block::Id`] of a block of at least one part.
+    pub fn arb_block_id()(
+        hash in arb_hash(),
+        total in 1..=u32::MAX,
+        parts_hash in arb_hash(),
+    ) -> block::Id {
+        block::Id {
+            hash,
+            part_set_header: parts::Header::new(total, parts_hash).unwrap(),
+        }
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`Header`]. Headers at the first height have no last
+    /// block ID, as required by the protobuf decoding.
+    pub fn arb_header()(
+        (block_version, app_version) in (any::<u64>(), any::<u64>()),
+        chain_id in arb_chain_id(),
+        height in arb_height(),
+        time in arb_time(),
+        last_block_id in option::of(arb_block_id()),
+        (last_commit_hash, data_hash, last_results_hash, evidence_hash) in (
+            option::of(arb_hash()),
+            option::of(arb_hash()),
+            option::of(arb_hash()),
+            option::of(arb_hash()),
+        ),
+        (validators_hash, next_validators_hash, consensus_hash) in (
+            arb_hash(),
+            arb_hash(),
+            arb_hash(),
+        ),
+        app_hash in arb_app_hash(),
+        proposer_address in arb_account_id(),
+    ) -> Header {
+        Header {
+            version: block::header::Version {
+                block: block_version,
+                app: app_version,
+            },
+            chain_id,
+            height,
+            time,
+            last_block_id: last_block_id.filter(|_| height.value() > 1),
+            last_commit_hash,
+            data_hash,
+            validators_hash,
+            next_validators_hash,
+            consensus_hash,
+            app_hash,
+            last_results_hash,
+            evidence_hash,
+            proposer_address,
+        }
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`Vote`], without a vote extension.
+    pub fn arb_vote()(
+        vote_type in prop_oneof![Just(vote::Type::Prevote), Just(vote::Type::Precommit)],
+        height in arb_height(),
+        round in arb_round(),
+        block_id in option::of(arb_block_id()),
+        timestamp in arb_time(),
+        validator_address in arb_account_id(),
+        validator_index in (0..=i32::MAX as u32),
+        signature in option::of(arb_signature()),
+    ) -> Vote {
+        Vote {
+            vote_type,
+            height,
+            round,
+            block_id,
+            timestamp: Some(timestamp),
+            validator_address,
+            validator_index: ValidatorIndex::try_from(validator_index).unwrap(),
+            signature,
+            extension: vec![],
+            extension_signature: None,
+        }
+    }
+}
+
+/// An arbitrary [`CommitSig`].
+pub fn arb_commit_sig() -> impl Strategy<Value = CommitSig> {
+    prop_oneof![
+        Just(CommitSig::BlockIdFlagAbsent),
+        (arb_account_id(), arb_time(), arb_signature()).prop_map(
+            |(validator_address, timestamp, signature)| CommitSig::BlockIdFlagCommit {
+                validator_address,
+                timestamp,
+                signature: Some(signature),
+            }
+        ),
+        (arb_account_id(), arb_time(), arb_signature()).prop_map(
+            |(validator_address, timestamp, signature)| CommitSig::BlockIdFlagNil {
+                validator_address,
+                timestamp,
+                signature: Some(signature),
+            }
+        ),
+    ]
+}
+
+prop_compose! {
+    /// An arbitrary [`Commit`] with up to 10 signatures.
+    pub fn arb_commit()(
+        height in arb_height(),
+        round in arb_round(),
+        block_id in arb_block_id(),
+        signatures in vec(arb_commit_sig(), 0..=10),
+    ) -> Commit {
+        Commit {
+            height,
+            round,
+            block_id,
+            signatures,
+        }
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`DuplicateVoteEvidence`] of two votes at the same height.
+    pub fn arb_duplicate_vote_evidence()(
+        vote_a in arb_vote(),
+        vote_b in arb_vote(),
+        total_voting_power in arb_power(),
+        validator_power in arb_power(),
+        timestamp in arb_time(),
+    ) -> DuplicateVoteEvidence {
+        DuplicateVoteEvidence {
+            vote_b: Vote {
+                height: vote_a.height,
+                ..vote_b
+            },
+            vote_a,
+            total_voting_power,
+            validator_power,
+            timestamp,
+        }
+    }
+}
+
+/// An arbitrary [`evidence::List`] of up to 3 pieces of duplicate vote
+/// evidence.
+pub fn arb_evidence_list() -> impl Strategy<Value = evidence::List> {
+    vec(
+        arb_duplicate_vote_evidence().prop_map(Evidence::from),
+        0..=3,
+    )
+    .prop_map(evidence::List::new)
+}
+
+prop_compose! {
+    /// An arbitrary [`Block`] with up to 10 transactions. Blocks at the first
+    /// height have no last commit.
+    pub fn arb_block()(
+        header in arb_header(),
+        data in vec(vec(any::<u8>(), 0..=64), 0..=10),
+        evidence in arb_evidence_list(),
+        last_commit in option::of(arb_commit()),
+    ) -> Block {
+        let last_commit = last_commit.filter(|_| header.height.value() > 1);
+        Block::new(header, data, evidence, last_commit)
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`validator::Info`], with its address derived from its
+    /// public key and without a name.
+    pub fn arb_validator_info()(
+        pub_key in arb_public_key(),
+        power in arb_power(),
+        proposer_priority in any::<i64>(),
+    ) -> validator::Info {
+        validator::Info {
+            address: account::Id::from(pub_key),
+            pub_key,
+            power,
+            name: None,
+            proposer_priority: ProposerPriority::from(proposer_priority),
+        }
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`Proposal`].
+    pub fn arb_proposal()(
+        height in arb_height(),
+        round in arb_round(),
+        pol_round in option::of(arb_round()),
+        block_id in option::of(arb_block_id()),
+        timestamp in option::of(arb_time()),
+        signature in option::of(arb_signature()),
+    ) -> Proposal {
+        Proposal {
+            msg_type: proposal::Type::Proposal,
+            height,
+            round,
+            pol_round,
+            block_id,
+            timestamp,
+            signature,
+        }
+    }
+}
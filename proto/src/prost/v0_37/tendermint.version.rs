@@ -21,6 +21,6 @@ pub struct Consensus {
     #[serde(with = "crate::serializers::from_str")]
     pub block: u64,
     #[prost(uint64, tag = "2")]
-    #[serde(with = "crate::serializers::from_str", default)]
+    #[serde(with = "crate::serializers::from_str", default, skip_serializing_if = "crate::serializers::is_default")]
     pub app: u64,
 }
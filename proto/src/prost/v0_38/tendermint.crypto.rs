@@ -62,7 +62,7 @@ pub struct ProofOps {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PublicKey {
-    #[prost(oneof = "public_key::Sum", tags = "1, 2")]
+    #[prost(oneof = "public_key::Sum", tags = "1, 2, 3")]
     pub sum: ::core::option::Option<public_key::Sum>,
 }
 /// Nested message and enum types in `PublicKey`.
@@ -84,5 +84,11 @@ pub mod public_key {
             with = "crate::serializers::bytes::base64string"
         )]
         Secp256k1(::prost::alloc::vec::Vec<u8>),
+        #[prost(bytes, tag = "3")]
+        #[serde(
+            rename = "tendermint/PubKeyBls12_381",
+            with = "crate::serializers::bytes::base64string"
+        )]
+        Bls12381(::prost::alloc::vec::Vec<u8>),
     }
 }
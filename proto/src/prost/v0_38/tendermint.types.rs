@@ -236,7 +236,7 @@ pub struct Commit {
     #[prost(message, optional, tag = "3")]
     pub block_id: ::core::option::Option<BlockId>,
     #[prost(message, repeated, tag = "4")]
-    #[serde(with = "crate::serializers::nullable")]
+    #[serde(with = "crate::serializers::allow_null")]
     pub signatures: ::prost::alloc::vec::Vec<CommitSig>,
 }
 /// CommitSig is a part of the Vote included in a Commit.
@@ -452,7 +452,7 @@ pub struct LightClientAttackEvidence {
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EvidenceList {
     #[prost(message, repeated, tag = "1")]
-    #[serde(with = "crate::serializers::nullable")]
+    #[serde(with = "crate::serializers::allow_null")]
     pub evidence: ::prost::alloc::vec::Vec<Evidence>,
 }
 #[derive(::serde::Deserialize, ::serde::Serialize)]
@@ -68,3 +68,9 @@ pub mod timestamp;
 pub mod txs;
 
 mod public_key;
+
+/// Whether `value` is the default of its type, for `skip_serializing_if` to
+/// omit it like `omitempty` does in Go.
+pub fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    value == &T::default()
+}
@@ -29,10 +29,6 @@ pub fn serialize<S>(value: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    if value.is_empty() {
-        let whatevs: Option<Vec<u8>> = None;
-        return whatevs.serialize(serializer);
-    }
     let value_base64string: Result<Vec<String>, S::Error> = value
         .iter()
         .map(|v| String::from_utf8(base64::encode(v)).map_err(serde::ser::Error::custom))
@@ -23,6 +23,250 @@ pub use subscription::{Subscription, SubscriptionClient};
 ))]
 pub mod sync;
 
+/// Implements [`Client`] for a wrapper `$ty<C>` around another client `C`.
+///
+/// The wrapper must provide an inherent method
+/// `async fn dispatch<'a, T, F, Fut>(&'a self, f: F) -> Result<T, Error>`
+/// (with `F: Fn(&'a C) -> Fut` and `Fut: Future<Output = Result<T, Error>>`)
+/// that runs `f` against the wrapped client(s) as many times as it sees fit.
+///
+/// Broadcasting a transaction is not idempotent, so the broadcast methods go
+/// through the method given as second argument instead, which defaults to
+/// `dispatch` as well.
+///
+/// All methods that transports override to account for the protocol
+/// dialect are forwarded to the wrapped client as a whole, so that the
+/// wrapped client's compatibility mode is honored.
+#[cfg(any(
+    feature = "http-client",
+    feature = "websocket-client",
+    feature = "mock-client"
+))]
+macro_rules! impl_client_by_dispatch {
+    ($ty:ident) => {
+        impl_client_by_dispatch!($ty, dispatch);
+    };
+    ($ty:ident, $dispatch_broadcast:ident) => {
+        #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+        #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+        impl<C> $crate::client::Client for $ty<C>
+        where
+            C: $crate::client::Client + Send + Sync,
+        {
+            async fn perform<R>(&self, request: R) -> Result<R::Output, $crate::Error>
+            where
+                R: $crate::SimpleRequest,
+            {
+                // Requests are not `Clone`, so each attempt gets its own copy
+                // rebuilt from the serialized parameters.
+                let is_broadcast = matches!(
+                    request.method(),
+                    $crate::Method::BroadcastTxAsync
+                        | $crate::Method::BroadcastTxSync
+                        | $crate::Method::BroadcastTxCommit
+                );
+                let params = serde_json::to_value(&request).map_err($crate::Error::serde)?;
+                let request =
+                    || serde_json::from_value::<R>(params.clone()).map_err($crate::Error::serde);
+                if is_broadcast {
+                    self.$dispatch_broadcast(|c| {
+                        let request = request();
+                        async move { c.perform(request?).await }
+                    })
+                    .await
+                } else {
+                    self.dispatch(|c| {
+                        let request = request();
+                        async move { c.perform(request?).await }
+                    })
+                    .await
+                }
+            }
+
+            async fn block<H>(
+                &self,
+                height: H,
+            ) -> Result<$crate::endpoint::block::Response, $crate::Error>
+            where
+                H: Into<tendermint::block::Height> + Send,
+            {
+                let height = height.into();
+                self.dispatch(|c| c.block(height)).await
+            }
+
+            async fn block_by_hash(
+                &self,
+                hash: tendermint::Hash,
+            ) -> Result<$crate::endpoint::block_by_hash::Response, $crate::Error> {
+                self.dispatch(|c| c.block_by_hash(hash)).await
+            }
+
+            async fn latest_block(
+                &self,
+            ) -> Result<$crate::endpoint::block::Response, $crate::Error> {
+                self.dispatch(|c| c.latest_block()).await
+            }
+
+            async fn header<H>(
+                &self,
+                height: H,
+            ) -> Result<$crate::endpoint::header::Response, $crate::Error>
+            where
+                H: Into<tendermint::block::Height> + Send,
+            {
+                let height = height.into();
+                self.dispatch(|c| c.header(height)).await
+            }
+
+            async fn header_by_hash(
+                &self,
+                hash: tendermint::Hash,
+            ) -> Result<$crate::endpoint::header_by_hash::Response, $crate::Error> {
+                self.dispatch(|c| c.header_by_hash(hash)).await
+            }
+
+            async fn block_results<H>(
+                &self,
+                height: H,
+            ) -> Result<$crate::endpoint::block_results::Response, $crate::Error>
+            where
+                H: Into<tendermint::block::Height> + Send,
+            {
+                let height = height.into();
+                self.dispatch(|c| c.block_results(height)).await
+            }
+
+            async fn latest_block_results(
+                &self,
+            ) -> Result<$crate::endpoint::block_results::Response, $crate::Error> {
+                self.dispatch(|c| c.latest_block_results()).await
+            }
+
+            async fn block_search(
+                &self,
+                query: $crate::query::Query,
+                page: u32,
+                per_page: u8,
+                order: $crate::Order,
+            ) -> Result<$crate::endpoint::block_search::Response, $crate::Error> {
+                self.dispatch(|c| c.block_search(query.clone(), page, per_page, order.clone()))
+                    .await
+            }
+
+            async fn broadcast_tx_async<T>(
+                &self,
+                tx: T,
+            ) -> Result<$crate::endpoint::broadcast::tx_async::Response, $crate::Error>
+            where
+                T: Into<alloc::vec::Vec<u8>> + Send,
+            {
+                let tx = tx.into();
+                self.$dispatch_broadcast(|c| c.broadcast_tx_async(tx.clone()))
+                    .await
+            }
+
+            async fn broadcast_tx_sync<T>(
+                &self,
+                tx: T,
+            ) -> Result<$crate::endpoint::broadcast::tx_sync::Response, $crate::Error>
+            where
+                T: Into<alloc::vec::Vec<u8>> + Send,
+            {
+                let tx = tx.into();
+                self.$dispatch_broadcast(|c| c.broadcast_tx_sync(tx.clone()))
+                    .await
+            }
+
+            async fn broadcast_tx_commit<T>(
+                &self,
+                tx: T,
+            ) -> Result<$crate::endpoint::broadcast::tx_commit::Response, $crate::Error>
+            where
+                T: Into<alloc::vec::Vec<u8>> + Send,
+            {
+                let tx = tx.into();
+                self.$dispatch_broadcast(|c| c.broadcast_tx_commit(tx.clone()))
+                    .await
+            }
+
+            async fn validators<H>(
+                &self,
+                height: H,
+                paging: $crate::Paging,
+            ) -> Result<$crate::endpoint::validators::Response, $crate::Error>
+            where
+                H: Into<tendermint::block::Height> + Send,
+            {
+                // Fetch all pages within the same attempt, so that they come
+                // from the same endpoint and are consistent with each other.
+                let height = height.into();
+                self.dispatch(|c| c.validators(height, paging)).await
+            }
+
+            async fn broadcast_evidence(
+                &self,
+                e: tendermint::evidence::Evidence,
+            ) -> Result<$crate::endpoint::evidence::Response, $crate::Error> {
+                self.dispatch(|c| c.broadcast_evidence(e.clone())).await
+            }
+
+            async fn tx(
+                &self,
+                hash: tendermint::Hash,
+                prove: bool,
+            ) -> Result<$crate::endpoint::tx::Response, $crate::Error> {
+                self.dispatch(|c| c.tx(hash, prove)).await
+            }
+
+            async fn tx_search(
+                &self,
+                query: $crate::query::Query,
+                prove: bool,
+                page: u32,
+                per_page: u8,
+                order: $crate::Order,
+            ) -> Result<$crate::endpoint::tx_search::Response, $crate::Error> {
+                self.dispatch(|c| c.tx_search(query.clone(), prove, page, per_page, order.clone()))
+                    .await
+            }
+        }
+    };
+}
+
+#[cfg(any(
+    feature = "http-client",
+    feature = "websocket-client",
+    feature = "mock-client"
+))]
+pub mod pool;
+#[cfg(any(
+    feature = "http-client",
+    feature = "websocket-client",
+    feature = "mock-client"
+))]
+pub use pool::ClientPool;
+
+#[cfg(all(test, feature = "mock-client"))]
+mod testing {
+    use std::{fs, path::PathBuf};
+
+    use crate::prelude::*;
+
+    /// Read the given response of a CometBFT 0.38 node to the kvstore
+    /// application.
+    pub fn read_json_fixture(name: &str) -> String {
+        let path = PathBuf::from("./tests/kvstore_fixtures/v0_38/incoming");
+        fs::read_to_string(path.join(name.to_owned() + ".json")).unwrap()
+    }
+}
+
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod history;
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod middleware;
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+mod paginate;
+
 #[cfg(any(
     feature = "http-client",
     feature = "websocket-client",
@@ -36,6 +280,11 @@ pub use transport::http::{self, HttpClient, HttpClientUrl};
 pub use transport::websocket::{
     self, WebSocketClient, WebSocketClientDriver, WebSocketClientUrl, WebSocketConfig,
 };
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub use transport::{
+    auth::{self, AuthProvider},
+    tls::TlsConfig,
+};
 
 #[cfg(feature = "mock-client")]
 pub use transport::mock::{MockClient, MockRequestMatcher, MockRequestMethodMatcher};
@@ -149,6 +398,33 @@ pub trait Client {
             .await
     }
 
+    /// `/block_search`: stream all blocks matching the query, in the given
+    /// order, fetching pages lazily as the stream is polled.
+    ///
+    /// Blocks that move to a later page while the stream is consumed are
+    /// yielded only once, unless they moved by more than a batch of
+    /// `per_page * max_concurrency` blocks: only the heights of that many
+    /// last blocks are kept.
+    #[cfg(any(feature = "http-client", feature = "websocket-client"))]
+    fn block_search_stream(
+        &self,
+        query: Query,
+        order: Order,
+        config: crate::PageStreamConfig,
+    ) -> paginate::PageStream<'_, block::Response>
+    where
+        Self: Sync,
+    {
+        paginate::paginate(
+            config,
+            |block| block.block.header.height,
+            move |page, per_page| {
+                let response = self.block_search(query.clone(), page, per_page, order.clone());
+                async move { response.await.map(|r| (r.blocks, r.total_count)) }
+            },
+        )
+    }
+
     /// `/blockchain`: get block headers for `min` <= `height` <= `max`.
     ///
     /// Block headers are returned in descending order (highest first).
@@ -261,6 +537,42 @@ pub trait Client {
         }
     }
 
+    /// `/validators`: stream the validators at the given height, fetching
+    /// pages lazily as the stream is polled.
+    ///
+    /// Unlike [`Client::validators`] with [`Paging::All`], this does not
+    /// collect all validators in memory.
+    #[cfg(any(feature = "http-client", feature = "websocket-client"))]
+    fn validators_stream<H>(
+        &self,
+        height: H,
+        config: crate::PageStreamConfig,
+    ) -> paginate::PageStream<'_, tendermint::validator::Info>
+    where
+        H: Into<Height>,
+        Self: Sync,
+    {
+        let height = height.into();
+        paginate::paginate(
+            config,
+            |validator| validator.address,
+            move |page, per_page| {
+                let response = self.validators(
+                    height,
+                    Paging::Specific {
+                        page_number: (page as usize).into(),
+                        per_page: per_page.into(),
+                    },
+                );
+                async move {
+                    let response = response.await?;
+                    let total = response.total.try_into().map_err(Error::out_of_range)?;
+                    Ok((response.validators, total))
+                }
+            },
+        )
+    }
+
     /// `/consensus_params`: get the latest consensus parameters.
     async fn latest_consensus_params(&self) -> Result<consensus_params::Response, Error> {
         self.perform(consensus_params::Request::new(None)).await
@@ -349,6 +661,35 @@ pub trait Client {
             .await
     }
 
+    /// `/tx_search`: stream all transactions matching the query, in the given
+    /// order, fetching pages lazily as the stream is polled.
+    ///
+    /// Transactions that move to a later page while the stream is consumed,
+    /// because matching transactions were committed in the meantime, are
+    /// yielded only once, unless they moved by more than a batch of
+    /// `per_page * max_concurrency` transactions: only the heights and
+    /// indices of that many last transactions are kept.
+    #[cfg(any(feature = "http-client", feature = "websocket-client"))]
+    fn tx_search_stream(
+        &self,
+        query: Query,
+        prove: bool,
+        order: Order,
+        config: crate::PageStreamConfig,
+    ) -> paginate::PageStream<'_, tx::Response>
+    where
+        Self: Sync,
+    {
+        paginate::paginate(
+            config,
+            |tx| (tx.height, tx.index),
+            move |page, per_page| {
+                let response = self.tx_search(query.clone(), prove, page, per_page, order.clone());
+                async move { response.await.map(|r| (r.txs, r.total_count)) }
+            },
+        )
+    }
+
     #[cfg(any(feature = "http-client", feature = "websocket-client"))]
     /// Poll the `/health` endpoint until it returns a successful result or
     /// the given `timeout` has elapsed.
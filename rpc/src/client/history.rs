@@ -0,0 +1,741 @@
+//! Streaming the events of every block from a given height onward.
+//!
+//! An [`EventStream`] first fetches the blocks that were already committed
+//! through the `/block` and `/block_results` endpoints, and then follows the
+//! chain as new blocks are committed, either by polling the node or, if one
+//! is given, through a `NewBlock` [`Subscription`].
+
+use alloc::collections::{BTreeMap as HashMap, VecDeque};
+use core::pin::Pin;
+use core::time::Duration;
+
+use futures::{
+    stream,
+    task::{Context, Poll},
+    Stream, StreamExt,
+};
+use sha2::{Digest, Sha256};
+use tendermint::{abci, block::Height, Hash};
+
+use crate::{
+    client::{Client, Subscription, SubscriptionClient},
+    endpoint::{block, block_results},
+    event::{Event, EventData, TxInfo, TxResult},
+    prelude::*,
+    query::{EventType, Query},
+    Error,
+};
+
+/// A stream of the events of every block from a given height onward, with
+/// no gaps.
+///
+/// For every block, a `NewBlock` event is yielded first, followed by a `Tx`
+/// event for each of its transactions in the order of the block. These are
+/// the same [`Event`]s that subscriptions to `tm.event = 'NewBlock'` and
+/// `tm.event = 'Tx'` produce.
+///
+/// When a request fails, the error is yielded and the stream carries on
+/// from the same block after the poll interval. The stream never ends.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use futures::StreamExt;
+/// use tendermint_rpc::{client::history::EventStream, HttpClient, WebSocketClient};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = HttpClient::new("http://127.0.0.1:26657").unwrap();
+///     let (ws_client, driver) = WebSocketClient::new("ws://127.0.0.1:26657/websocket")
+///         .await
+///         .unwrap();
+///     tokio::spawn(async move { driver.run().await });
+///
+///     let mut events = EventStream::builder(client, 1_u32.into())
+///         .subscribe_with(&ws_client)
+///         .await
+///         .build();
+///
+///     while let Some(event) = events.next().await {
+///         println!("{:?}", event.unwrap());
+///     }
+/// }
+/// ```
+pub struct EventStream {
+    inner: Pin<Box<dyn Stream<Item = Result<Event, Error>> + Send>>,
+}
+
+/// The builder pattern constructor for [`EventStream`].
+pub struct Builder<C> {
+    client: C,
+    from: Height,
+    poll_interval: Duration,
+    subscription: Option<Subscription>,
+}
+
+impl EventStream {
+    /// Initiate a builder for a stream of the events of every block from the
+    /// given height onward, fetched with the given client.
+    pub fn builder<C>(client: C, from: Height) -> Builder<C>
+    where
+        C: Client + Send + Sync + 'static,
+    {
+        Builder {
+            client,
+            from,
+            poll_interval: Duration::from_secs(1),
+            subscription: None,
+        }
+    }
+}
+
+impl<C> Builder<C>
+where
+    C: Client + Send + Sync + 'static,
+{
+    /// How long to wait before polling the node again when it has no new
+    /// block, and before retrying a failed request.
+    ///
+    /// The default is one second.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Learn about new blocks from the given subscription to `NewBlock`
+    /// events instead of polling for them.
+    ///
+    /// The `NewBlock` events of the subscription are yielded as they are,
+    /// and the node is still asked for the transaction results of each
+    /// block. Should the subscription fail or end, the stream falls back to
+    /// polling.
+    pub fn subscription(mut self, subscription: Subscription) -> Self {
+        self.subscription = Some(subscription);
+        self
+    }
+
+    /// Subscribe to `NewBlock` events with the given client, and learn about
+    /// new blocks from that subscription (see [`Builder::subscription`]).
+    ///
+    /// If the subscription cannot be established, e.g. because the node has
+    /// no WebSocket endpoint, the stream polls for new blocks instead.
+    pub async fn subscribe_with<S>(self, subscriber: &S) -> Self
+    where
+        S: SubscriptionClient + Sync,
+    {
+        match subscriber.subscribe(EventType::NewBlock.into()).await {
+            Ok(subscription) => self.subscription(subscription),
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to subscribe to new blocks, polling instead");
+                self
+            },
+        }
+    }
+
+    /// Create the stream with the options specified for this builder.
+    pub fn build(self) -> EventStream {
+        let state = State {
+            client: self.client,
+            next: self.from,
+            poll_interval: self.poll_interval,
+            live: self.subscription,
+            caught_up: false,
+            pending: None,
+            buffer: VecDeque::new(),
+            failed: false,
+            latest: None,
+        };
+
+        let inner = stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(event) = state.buffer.pop_front() {
+                    return Some((Ok(event), state));
+                }
+                if let Err(e) = state.advance().await {
+                    state.failed = true;
+                    return Some((Err(e), state));
+                }
+            }
+        });
+
+        EventStream {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Event, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl core::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EventStream").finish_non_exhaustive()
+    }
+}
+
+struct State<C> {
+    client: C,
+    /// The height of the next block to yield the events of.
+    next: Height,
+    poll_interval: Duration,
+    /// The subscription to `NewBlock` events, if any.
+    live: Option<Subscription>,
+    /// Whether all blocks committed before the subscription started have been
+    /// fetched, so that new blocks can be awaited from the subscription.
+    caught_up: bool,
+    /// A `NewBlock` event received from the subscription ahead of the blocks
+    /// still to be fetched, with its height.
+    pending: Option<(Height, Event)>,
+    /// Events to yield.
+    buffer: VecDeque<Event>,
+    /// Whether the last request failed, so that the next one is delayed.
+    failed: bool,
+    /// The latest height of the node, as of the last time it was asked.
+    latest: Option<Height>,
+}
+
+impl<C> State<C>
+where
+    C: Client + Sync,
+{
+    /// Fetch the events of the next block into the buffer, or wait for the
+    /// next block to be committed.
+    async fn advance(&mut self) -> Result<(), Error> {
+        if core::mem::take(&mut self.failed) {
+            tokio::time::sleep(self.poll_interval).await;
+        }
+
+        if let Some((height, event)) = self.pending.take() {
+            if height == self.next {
+                return self.fetch_block(Some(event)).await;
+            }
+            // Fill the gap before the block of the event first.
+            self.pending = Some((height, event));
+            return self.fetch_block(None).await;
+        }
+
+        if self.live.is_none() || !self.caught_up {
+            // Only ask the node for its latest height once the blocks known
+            // to be committed have all been fetched.
+            let latest = match self.latest {
+                Some(latest) if latest >= self.next => latest,
+                _ => {
+                    let latest = self.client.status().await?.sync_info.latest_block_height;
+                    self.latest = Some(latest);
+                    latest
+                },
+            };
+            if latest >= self.next {
+                return self.fetch_block(None).await;
+            }
+            if self.live.is_none() {
+                tokio::time::sleep(self.poll_interval).await;
+                return Ok(());
+            }
+            self.caught_up = true;
+        }
+
+        let Some(live) = &mut self.live else {
+            return Ok(());
+        };
+        match live.next().await {
+            Some(Ok(event)) => match new_block_height(&event) {
+                Some(height) if height < self.next => {
+                    // Already yielded while catching up.
+                },
+                Some(height) => self.pending = Some((height, event)),
+                // Without the block, look up the latest height instead.
+                None if event.event_type() == Some(EventType::NewBlock) => {
+                    self.caught_up = false;
+                },
+                None => {},
+            },
+            Some(Err(e)) => {
+                tracing::warn!(error = %e, "new block subscription failed, polling instead");
+                self.live = None;
+            },
+            None => {
+                tracing::warn!("new block subscription ended, polling instead");
+                self.live = None;
+            },
+        }
+        Ok(())
+    }
+
+    /// Fetch the events of the next block into the buffer, taking the
+    /// `NewBlock` event from the subscription if given.
+    async fn fetch_block(&mut self, new_block: Option<Event>) -> Result<(), Error> {
+        let height = self.next;
+        let (new_block, txs) = match new_block {
+            Some(event) => {
+                let txs = match &event.data {
+                    EventData::NewBlock {
+                        block: Some(block), ..
+                    }
+                    | EventData::LegacyNewBlock {
+                        block: Some(block), ..
+                    } => block.data.clone(),
+                    _ => Vec::new(),
+                };
+                let results = self.client.block_results(height).await?;
+                (event, tx_events(height, txs, results)?)
+            },
+            None => {
+                let block = self.client.block(height).await?;
+                let results = self.client.block_results(height).await?;
+                let txs = block.block.data.clone();
+                let new_block = new_block_event(block, &results);
+                (new_block, tx_events(height, txs, results)?)
+            },
+        };
+
+        self.buffer.push_back(new_block);
+        self.buffer.extend(txs);
+        self.next = height.increment();
+        Ok(())
+    }
+}
+
+/// The height of the block of the given `NewBlock` event, if it has one.
+fn new_block_height(event: &Event) -> Option<Height> {
+    match &event.data {
+        EventData::NewBlock {
+            block: Some(block), ..
+        }
+        | EventData::LegacyNewBlock {
+            block: Some(block), ..
+        } => Some(block.header.height),
+        _ => None,
+    }
+}
+
+/// The `NewBlock` event that a subscription produces for the given block.
+///
+/// Nodes that send `BeginBlock` or `EndBlock` events along with the block
+/// results predate CometBFT 0.38, and produce legacy `NewBlock` events.
+fn new_block_event(response: block::Response, results: &block_results::Response) -> Event {
+    let block = Some(Box::new(response.block));
+    let (data, events) =
+        if results.begin_block_events.is_some() || results.end_block_events.is_some() {
+            let begin_block_events = results.begin_block_events.clone().unwrap_or_default();
+            let end_block_events = results.end_block_events.clone().unwrap_or_default();
+            let events = flatten_events(begin_block_events.iter().chain(&end_block_events));
+            let data = EventData::LegacyNewBlock {
+                block,
+                result_begin_block: Some(abci::response::BeginBlock {
+                    events: begin_block_events,
+                }),
+                result_end_block: Some(abci::response::EndBlock {
+                    validator_updates: results.validator_updates.clone(),
+                    consensus_param_updates: results.consensus_param_updates.clone(),
+                    events: end_block_events,
+                }),
+            };
+            (data, events)
+        } else {
+            let data = EventData::NewBlock {
+                block,
+                block_id: response.block_id,
+                result_finalize_block: Some(abci::response::FinalizeBlock {
+                    events: results.finalize_block_events.clone(),
+                    tx_results: results.txs_results.clone().unwrap_or_default(),
+                    validator_updates: results.validator_updates.clone(),
+                    consensus_param_updates: results.consensus_param_updates.clone(),
+                    app_hash: results.app_hash.clone(),
+                }),
+            };
+            (data, flatten_events(&results.finalize_block_events))
+        };
+
+    event(EventType::NewBlock, data, events)
+}
+
+/// The `Tx` events that a subscription produces for the given transactions
+/// of the block at the given height.
+fn tx_events(
+    height: Height,
+    txs: Vec<Vec<u8>>,
+    results: block_results::Response,
+) -> Result<Vec<Event>, Error> {
+    let results = results.txs_results.unwrap_or_default();
+    if txs.len() != results.len() {
+        return Err(Error::server(format!(
+            "block {height} has {} transactions but {} transaction results",
+            txs.len(),
+            results.len()
+        )));
+    }
+    let events = txs
+        .into_iter()
+        .zip(results)
+        .enumerate()
+        .map(|(index, (tx, result))| {
+            let hash = Hash::Sha256(Sha256::digest(&tx).into());
+            let mut events = flatten_events(&result.events);
+            events.insert("tx.hash".to_string(), vec![hash.to_string()]);
+            events.insert("tx.height".to_string(), vec![height.to_string()]);
+
+            // Empty fields are omitted from the JSON of subscription events.
+            let non_zero = |gas: i64| (gas != 0).then(|| gas.to_string());
+            let data = EventData::Tx {
+                tx_result: TxInfo {
+                    height: height.value() as i64,
+                    index: Some(index as i64),
+                    tx,
+                    result: TxResult {
+                        log: (!result.log.is_empty()).then_some(result.log),
+                        gas_wanted: non_zero(result.gas_wanted),
+                        gas_used: non_zero(result.gas_used),
+                        events: result.events,
+                    },
+                },
+            };
+            event(EventType::Tx, data, events)
+        })
+        .collect();
+    Ok(events)
+}
+
+fn event(kind: EventType, data: EventData, mut events: HashMap<String, Vec<String>>) -> Event {
+    events.insert("tm.event".to_string(), vec![kind.to_string()]);
+    Event {
+        query: Query::from(kind).to_string(),
+        data,
+        events: Some(events),
+    }
+}
+
+/// Index the attributes of the given events by `<event type>.<attribute key>`,
+/// like the events of a subscription.
+fn flatten_events<'e>(
+    events: impl IntoIterator<Item = &'e abci::Event>,
+) -> HashMap<String, Vec<String>> {
+    let mut flattened = HashMap::<String, Vec<String>>::new();
+    for event in events {
+        for attribute in &event.attributes {
+            if let (Ok(key), Ok(value)) = (attribute.key_str(), attribute.value_str()) {
+                flattened
+                    .entry(format!("{}.{}", event.kind, key))
+                    .or_default()
+                    .push(value.to_string());
+            }
+        }
+    }
+    flattened
+}
+
+#[cfg(all(test, feature = "mock-client"))]
+mod tests {
+    use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+
+    use super::super::testing::read_json_fixture;
+    use super::*;
+    use crate::{endpoint::status, Method, MockClient, MockRequestMethodMatcher, SimpleRequest};
+
+    /// A chain whose blocks up to the latest height have one transaction each.
+    struct Chain {
+        latest: Arc<AtomicU64>,
+        fixtures: MockClient<MockRequestMethodMatcher>,
+        status_calls: Arc<AtomicU32>,
+    }
+
+    impl Chain {
+        /// A chain, and a handle to set its latest height.
+        fn new(latest: u64) -> (Self, Arc<AtomicU64>) {
+            let latest = Arc::new(AtomicU64::new(latest));
+            (Self::with_latest(latest.clone()), latest)
+        }
+
+        fn with_latest(latest: Arc<AtomicU64>) -> Self {
+            let matcher = MockRequestMethodMatcher::default()
+                .map(Method::Block, Ok(read_json_fixture("block_at_height_10")))
+                .map(
+                    Method::BlockResults,
+                    Ok(read_json_fixture("block_results_at_height_10")),
+                )
+                .map(Method::Status, Ok(read_json_fixture("status")));
+            Self {
+                latest,
+                fixtures: MockClient::new(matcher).0,
+                status_calls: Arc::new(AtomicU32::new(0)),
+            }
+        }
+
+        fn check(&self, height: Height) -> Result<(), Error> {
+            if height.value() > self.latest.load(Ordering::SeqCst) {
+                return Err(Error::invalid_params(format!("no block at {height}")));
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Client for Chain {
+        async fn perform<R>(&self, _request: R) -> Result<R::Output, Error>
+        where
+            R: SimpleRequest,
+        {
+            Err(Error::mismatch_response())
+        }
+
+        async fn block<H>(&self, height: H) -> Result<block::Response, Error>
+        where
+            H: Into<Height> + Send,
+        {
+            let height = height.into();
+            self.check(height)?;
+            let mut response = self.fixtures.block(height).await?;
+            response.block.header.height = height;
+            response.block.data = vec![format!("tx-{height}").into_bytes()];
+            Ok(response)
+        }
+
+        async fn block_results<H>(&self, height: H) -> Result<block_results::Response, Error>
+        where
+            H: Into<Height> + Send,
+        {
+            let height = height.into();
+            self.check(height)?;
+            let mut response = self.fixtures.block_results(height).await?;
+            response.height = height;
+            response.txs_results = Some(vec![abci::types::ExecTxResult {
+                log: "ok".to_string(),
+                gas_used: 42,
+                ..Default::default()
+            }]);
+            Ok(response)
+        }
+
+        async fn status(&self) -> Result<status::Response, Error> {
+            self.status_calls.fetch_add(1, Ordering::SeqCst);
+            let mut response = self.fixtures.status().await?;
+            response.sync_info.latest_block_height =
+                self.latest.load(Ordering::SeqCst).try_into().unwrap();
+            Ok(response)
+        }
+    }
+
+    /// The type and height of the next `count` events.
+    async fn next_events(stream: &mut EventStream, count: usize) -> Vec<(EventType, u64)> {
+        let mut events = Vec::new();
+        for _ in 0..count {
+            let event = tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .expect("timed out waiting for event")
+                .unwrap()
+                .unwrap();
+            let height = match &event.data {
+                EventData::Tx { tx_result } => tx_result.height as u64,
+                _ => new_block_height(&event).unwrap().value(),
+            };
+            events.push((event.event_type().unwrap(), height));
+        }
+        events
+    }
+
+    async fn new_block(chain: &Chain, height: u32) -> Event {
+        let height = Height::from(height);
+        let block = chain.block(height).await.unwrap();
+        let results = chain.block_results(height).await.unwrap();
+        new_block_event(block, &results)
+    }
+
+    #[tokio::test]
+    async fn backfills_then_polls_for_new_blocks() {
+        let (chain, latest) = Chain::new(3);
+        let mut stream = EventStream::builder(chain, 2_u32.into())
+            .poll_interval(Duration::from_millis(10))
+            .build();
+
+        assert_eq!(
+            next_events(&mut stream, 4).await,
+            [
+                (EventType::NewBlock, 2),
+                (EventType::Tx, 2),
+                (EventType::NewBlock, 3),
+                (EventType::Tx, 3)
+            ]
+        );
+
+        latest.store(4, Ordering::SeqCst);
+        assert_eq!(
+            next_events(&mut stream, 2).await,
+            [(EventType::NewBlock, 4), (EventType::Tx, 4)]
+        );
+    }
+
+    #[tokio::test]
+    async fn asks_for_latest_height_once_per_backfill() {
+        let (chain, _) = Chain::new(5);
+        let status_calls = chain.status_calls.clone();
+        let mut stream = EventStream::builder(chain, 1_u32.into()).build();
+
+        next_events(&mut stream, 10).await;
+        assert_eq!(status_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_tx_results() {
+        let (chain, _) = Chain::new(1);
+        let block = chain.block(1_u32).await.unwrap();
+        let mut results = chain.block_results(1_u32).await.unwrap();
+        results.txs_results = Some(Vec::new());
+
+        let err = tx_events(1_u32.into(), block.block.data, results).unwrap_err();
+        assert!(matches!(err.detail(), crate::error::ErrorDetail::Server(_)));
+    }
+
+    #[tokio::test]
+    async fn produces_subscription_events() {
+        let (chain, _) = Chain::new(1);
+        let mut stream = EventStream::builder(chain, 1_u32.into()).build();
+
+        let new_block = stream.next().await.unwrap().unwrap();
+        assert_eq!(new_block.query, "tm.event = 'NewBlock'");
+        assert!(matches!(
+            new_block.data,
+            EventData::NewBlock {
+                result_finalize_block: Some(_),
+                ..
+            }
+        ));
+
+        let tx = stream.next().await.unwrap().unwrap();
+        assert_eq!(tx.query, "tm.event = 'Tx'");
+        let EventData::Tx { tx_result } = tx.data else {
+            panic!("unexpected event: {tx:?}");
+        };
+        assert_eq!(tx_result.index, Some(0));
+        assert_eq!(tx_result.tx, b"tx-1");
+        assert_eq!(tx_result.result.log.as_deref(), Some("ok"));
+        assert_eq!(tx_result.result.gas_wanted, None);
+        assert_eq!(tx_result.result.gas_used.as_deref(), Some("42"));
+
+        let events = tx.events.unwrap();
+        assert_eq!(events["tm.event"], ["Tx"]);
+        assert_eq!(events["tx.height"], ["1"]);
+        let hash = Hash::Sha256(Sha256::digest(b"tx-1").into());
+        assert_eq!(events["tx.hash"], [hash.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn follows_subscription_without_gaps() {
+        let (chain, latest) = Chain::new(2);
+        let (subscriber, driver) = MockClient::new(MockRequestMethodMatcher::default());
+        let driver = tokio::spawn(driver.run());
+
+        let mut stream = EventStream::builder(chain, 2_u32.into())
+            .subscribe_with(&subscriber)
+            .await
+            .build();
+
+        assert_eq!(
+            next_events(&mut stream, 2).await,
+            [(EventType::NewBlock, 2), (EventType::Tx, 2)]
+        );
+
+        // A block that was already yielded, and one that skips height 3.
+        latest.store(4, Ordering::SeqCst);
+        let chain = Chain::with_latest(latest);
+        subscriber.publish(&new_block(&chain, 2).await);
+        subscriber.publish(&new_block(&chain, 4).await);
+
+        assert_eq!(
+            next_events(&mut stream, 4).await,
+            [
+                (EventType::NewBlock, 3),
+                (EventType::Tx, 3),
+                (EventType::NewBlock, 4),
+                (EventType::Tx, 4)
+            ]
+        );
+
+        subscriber.close();
+        driver.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_polling_when_subscription_ends() {
+        let (chain, latest) = Chain::new(1);
+        let (subscriber, driver) = MockClient::new(MockRequestMethodMatcher::default());
+        let driver = tokio::spawn(driver.run());
+
+        let mut stream = EventStream::builder(chain, 1_u32.into())
+            .poll_interval(Duration::from_millis(10))
+            .subscribe_with(&subscriber)
+            .await
+            .build();
+        assert_eq!(
+            next_events(&mut stream, 2).await,
+            [(EventType::NewBlock, 1), (EventType::Tx, 1)]
+        );
+
+        subscriber.close();
+        driver.await.unwrap().unwrap();
+
+        latest.store(2, Ordering::SeqCst);
+        assert_eq!(
+            next_events(&mut stream, 2).await,
+            [(EventType::NewBlock, 2), (EventType::Tx, 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn carries_on_after_errors() {
+        let (chain, latest) = Chain::new(1);
+        let mut stream = EventStream::builder(BrokenStatus(chain), 2_u32.into())
+            .poll_interval(Duration::from_millis(10))
+            .build();
+
+        // The node claims block 2 before it can serve it.
+        assert!(stream.next().await.unwrap().is_err());
+
+        latest.store(2, Ordering::SeqCst);
+        assert_eq!(
+            next_events(&mut stream, 2).await,
+            [(EventType::NewBlock, 2), (EventType::Tx, 2)]
+        );
+    }
+
+    /// Reports one more block than the chain can serve.
+    struct BrokenStatus(Chain);
+
+    #[async_trait]
+    impl Client for BrokenStatus {
+        async fn perform<R>(&self, _request: R) -> Result<R::Output, Error>
+        where
+            R: SimpleRequest,
+        {
+            Err(Error::mismatch_response())
+        }
+
+        async fn block<H>(&self, height: H) -> Result<block::Response, Error>
+        where
+            H: Into<Height> + Send,
+        {
+            self.0.block(height).await
+        }
+
+        async fn block_results<H>(&self, height: H) -> Result<block_results::Response, Error>
+        where
+            H: Into<Height> + Send,
+        {
+            self.0.block_results(height).await
+        }
+
+        async fn status(&self) -> Result<status::Response, Error> {
+            let mut response = self.0.status().await?;
+            response.sync_info.latest_block_height =
+                response.sync_info.latest_block_height.increment();
+            Ok(response)
+        }
+    }
+}
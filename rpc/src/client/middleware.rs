@@ -0,0 +1,94 @@
+//! Middleware that wraps a [`Client`] to make it more resilient against
+//! unreliable or rate-limited RPC endpoints.
+//!
+//! Each middleware wraps another client and is itself a [`Client`], so they
+//! can be stacked in any order. For instance, the following client retries
+//! failed requests, stops sending requests for a while after repeated
+//! failures, and never sends more than 10 requests per second:
+//!
+//! ```rust,ignore
+//! use core::num::NonZeroU32;
+//!
+//! use tendermint_rpc::client::middleware::{CircuitBreaker, RateLimit, Retry};
+//! use tendermint_rpc::HttpClient;
+//!
+//! let client = HttpClient::new("https://rpc.example.com").unwrap();
+//! let client = Retry::new(CircuitBreaker::new(RateLimit::new(
+//!     client,
+//!     NonZeroU32::new(10).unwrap(),
+//! )));
+//! ```
+//!
+//! Errors are classified with [`ErrorDetail::is_retryable`]: only errors
+//! that may go away when the request is sent again are retried and count as
+//! failures for the circuit breaker. Errors returned by the node itself, such
+//! as invalid parameters, are passed through as-is.
+//!
+//! [`Client`]: crate::Client
+//! [`ErrorDetail::is_retryable`]: crate::error::ErrorDetail::is_retryable
+
+mod circuit_breaker;
+mod limit;
+mod retry;
+
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+pub use limit::{ConcurrencyLimit, RateLimit};
+pub use retry::{Retry, RetryPolicy};
+
+#[cfg(test)]
+mod testing {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use async_trait::async_trait;
+
+    use crate::{client::Client, prelude::*, Error, Response, SimpleRequest};
+
+    const EMPTY_RESPONSE: &str = r#"{"jsonrpc":"2.0","id":"","result":{}}"#;
+
+    /// A client that fails with the given error a number of times before
+    /// answering every request with an empty result.
+    ///
+    /// Only suitable for requests with an empty response, such as `/health`.
+    pub struct Flaky {
+        error: Error,
+        failures: AtomicU32,
+        calls: AtomicU32,
+    }
+
+    impl Flaky {
+        pub fn new(error: Error, failures: u32) -> Self {
+            Self {
+                error,
+                failures: AtomicU32::new(failures),
+                calls: AtomicU32::new(0),
+            }
+        }
+
+        pub fn calls(&self) -> u32 {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Client for Flaky {
+        async fn perform<R>(&self, _request: R) -> Result<R::Output, Error>
+        where
+            R: SimpleRequest,
+        {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failing {
+                Err(self.error.clone())
+            } else {
+                R::Response::from_string(EMPTY_RESPONSE).map(Into::into)
+            }
+        }
+    }
+
+    pub fn server_error() -> Error {
+        Error::http_request_failed(503_u16.try_into().unwrap(), None)
+    }
+}
@@ -0,0 +1,270 @@
+//! Rejecting requests to an endpoint that keeps failing.
+
+use core::future::Future;
+use core::time::Duration;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use tokio::time::Instant;
+
+use crate::{prelude::*, Error};
+
+/// Determines when a [`CircuitBreaker`] opens and for how long.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive failed requests after which the circuit
+    /// opens.
+    pub failure_threshold: u32,
+
+    /// How long the circuit stays open before a request is let through to
+    /// probe whether the endpoint has recovered.
+    pub reset_timeout: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Requests go through; counts consecutive failures.
+    Closed(u32),
+    /// Requests are rejected until the given instant.
+    Open(Instant),
+    /// A single request is let through to probe the endpoint, and its
+    /// outcome decides whether the circuit closes or opens again. Other
+    /// requests are rejected in the meantime.
+    HalfOpen,
+}
+
+/// A [`Client`] that stops sending requests for a while once a number of
+/// consecutive requests have failed with a retryable error (see
+/// [`ErrorDetail::is_retryable`]).
+///
+/// While the circuit is open, requests fail immediately with
+/// [`ErrorDetail::CircuitOpen`], which tells how long the circuit remains
+/// open. Once that time has passed, a single request is let through to probe
+/// the endpoint while other requests keep being rejected: the circuit closes
+/// if the probe succeeds and opens again if it fails.
+///
+/// [`Client`]: crate::Client
+/// [`ErrorDetail::is_retryable`]: crate::error::ErrorDetail::is_retryable
+/// [`ErrorDetail::CircuitOpen`]: crate::error::ErrorDetail::CircuitOpen
+#[derive(Debug)]
+pub struct CircuitBreaker<C> {
+    inner: C,
+    config: CircuitBreakerConfig,
+    state: Mutex<State>,
+}
+
+impl<C> CircuitBreaker<C> {
+    /// Protect the given client with the default [`CircuitBreakerConfig`].
+    pub fn new(inner: C) -> Self {
+        Self::with_config(inner, CircuitBreakerConfig::default())
+    }
+
+    /// Protect the given client with the given configuration.
+    pub fn with_config(inner: C, config: CircuitBreakerConfig) -> Self {
+        Self {
+            inner,
+            config,
+            state: Mutex::new(State::Closed(0)),
+        }
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Unwrap the wrapped client.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// Whether requests are currently rejected.
+    pub fn is_open(&self) -> bool {
+        matches!(*self.state(), State::Open(until) if Instant::now() < until)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Admit a request, returning whether it is the probe of a half-open
+    /// circuit.
+    fn admit(&self) -> Result<bool, Error> {
+        let mut state = self.state();
+        match *state {
+            State::Closed(_) => Ok(false),
+            State::Open(until) => {
+                let now = Instant::now();
+                if now < until {
+                    return Err(Error::circuit_open(until - now));
+                }
+                *state = State::HalfOpen;
+                Ok(true)
+            },
+            // The probe may complete at any time.
+            State::HalfOpen => Err(Error::circuit_open(Duration::ZERO)),
+        }
+    }
+
+    fn record(&self, failed: bool) {
+        let mut state = self.state();
+        *state = match (*state, failed) {
+            (_, false) => State::Closed(0),
+            (State::Closed(failures), true) if failures + 1 < self.config.failure_threshold => {
+                State::Closed(failures + 1)
+            },
+            // Requests that were admitted before the circuit opened do not
+            // extend the time it stays open.
+            (State::Open(until), true) => State::Open(until),
+            (_, true) => {
+                tracing::debug!(
+                    timeout = ?self.config.reset_timeout,
+                    "too many failed RPC requests, opening circuit"
+                );
+                State::Open(Instant::now() + self.config.reset_timeout)
+            },
+        };
+    }
+
+    async fn dispatch<'a, T, F, Fut>(&'a self, f: F) -> Result<T, Error>
+    where
+        F: Fn(&'a C) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let probe = self.admit()?.then_some(Probe(&self.state));
+        let result = f(&self.inner).await;
+        self.record(matches!(&result, Err(e) if e.detail().is_retryable()));
+        drop(probe);
+        result
+    }
+}
+
+/// Lets the next request probe the endpoint if the probe is cancelled before
+/// its outcome is recorded.
+struct Probe<'a>(&'a Mutex<State>);
+
+impl Drop for Probe<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if *state == State::HalfOpen {
+            *state = State::Open(Instant::now());
+        }
+    }
+}
+
+impl_client_by_dispatch!(CircuitBreaker);
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{server_error, Flaky};
+    use super::*;
+    use crate::{client::Client, error::ErrorDetail, response_error::ResponseError};
+
+    fn config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_threshold: 3,
+            reset_timeout: Duration::from_millis(50),
+        }
+    }
+
+    #[tokio::test]
+    async fn opens_after_consecutive_failures() {
+        let client = CircuitBreaker::with_config(Flaky::new(server_error(), 3), config());
+
+        for _ in 0..3 {
+            client.health().await.unwrap_err();
+        }
+        assert!(client.is_open());
+
+        let err = client.health().await.unwrap_err();
+        assert!(matches!(err.detail(), ErrorDetail::CircuitOpen(_)));
+        assert!(err.detail().retry_after().unwrap() <= Duration::from_millis(50));
+        assert_eq!(client.inner().calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn closes_after_successful_probe() {
+        let client = CircuitBreaker::with_config(Flaky::new(server_error(), 3), config());
+        for _ in 0..3 {
+            client.health().await.unwrap_err();
+        }
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        client.health().await.unwrap();
+        assert!(!client.is_open());
+        assert_eq!(*client.state(), State::Closed(0));
+    }
+
+    #[tokio::test]
+    async fn reopens_after_failed_probe() {
+        let client = CircuitBreaker::with_config(Flaky::new(server_error(), 4), config());
+        for _ in 0..3 {
+            client.health().await.unwrap_err();
+        }
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        client.health().await.unwrap_err();
+        assert!(client.is_open());
+    }
+
+    #[tokio::test]
+    async fn lets_a_single_probe_through() {
+        let client = CircuitBreaker::with_config(Flaky::new(server_error(), 3), config());
+        for _ in 0..3 {
+            client.health().await.unwrap_err();
+        }
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        assert!(client.admit().unwrap());
+        let err = client.health().await.unwrap_err();
+        assert!(matches!(err.detail(), ErrorDetail::CircuitOpen(_)));
+        assert_eq!(client.inner().calls(), 3);
+
+        client.record(false);
+        client.health().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_probe_lets_next_request_through() {
+        let client = CircuitBreaker::with_config(Flaky::new(server_error(), 3), config());
+        for _ in 0..3 {
+            client.health().await.unwrap_err();
+        }
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        let probe = client.dispatch(|_| core::future::pending::<Result<(), Error>>());
+        tokio::time::timeout(Duration::from_millis(10), probe)
+            .await
+            .unwrap_err();
+        assert!(client.admit().unwrap());
+    }
+
+    #[tokio::test]
+    async fn ignores_node_errors() {
+        let error = Error::response(ResponseError::invalid_params("bad"));
+        let client = CircuitBreaker::with_config(Flaky::new(error, 5), config());
+
+        for _ in 0..5 {
+            client.health().await.unwrap_err();
+        }
+        assert!(!client.is_open());
+    }
+
+    #[tokio::test]
+    async fn success_resets_failure_count() {
+        let client = CircuitBreaker::with_config(Flaky::new(server_error(), 2), config());
+        client.health().await.unwrap_err();
+        client.health().await.unwrap_err();
+        client.health().await.unwrap();
+
+        assert_eq!(*client.state(), State::Closed(0));
+    }
+}
@@ -0,0 +1,187 @@
+//! Limiting the number and rate of requests sent to an endpoint.
+
+use core::future::Future;
+use core::num::{NonZeroU32, NonZeroUsize};
+use core::time::Duration;
+use std::sync::{Mutex, PoisonError};
+
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+use crate::{prelude::*, Error};
+
+/// A [`Client`] that caps the number of requests in flight at any time.
+///
+/// Requests beyond the limit wait until an earlier request completes.
+///
+/// [`Client`]: crate::Client
+#[derive(Debug)]
+pub struct ConcurrencyLimit<C> {
+    inner: C,
+    semaphore: Semaphore,
+}
+
+impl<C> ConcurrencyLimit<C> {
+    /// Allow at most `max_concurrent` requests of the given client to be in
+    /// flight at any time.
+    pub fn new(inner: C, max_concurrent: NonZeroUsize) -> Self {
+        Self {
+            inner,
+            semaphore: Semaphore::new(max_concurrent.get()),
+        }
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Unwrap the wrapped client.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    async fn dispatch<'a, T, F, Fut>(&'a self, f: F) -> Result<T, Error>
+    where
+        F: Fn(&'a C) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let _permit = self
+            .semaphore
+            .acquire()
+            .await
+            .map_err(|_| Error::client_internal("concurrency limit closed".to_string()))?;
+        f(&self.inner).await
+    }
+}
+
+impl_client_by_dispatch!(ConcurrencyLimit);
+
+/// A [`Client`] that sends at most a given number of requests per second.
+///
+/// Requests are admitted by a token bucket that holds up to one second's
+/// worth of requests, so short bursts are let through at once. Requests
+/// beyond the limit wait until they are admitted.
+///
+/// [`Client`]: crate::Client
+#[derive(Debug)]
+pub struct RateLimit<C> {
+    inner: C,
+    per_second: NonZeroU32,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl<C> RateLimit<C> {
+    /// Allow at most `per_second` requests of the given client per second.
+    pub fn new(inner: C, per_second: NonZeroU32) -> Self {
+        Self {
+            inner,
+            per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(per_second.get()),
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Unwrap the wrapped client.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// Take a token from the bucket, or return how long to wait until one
+    /// becomes available.
+    fn try_acquire(&self) -> Result<(), Duration> {
+        let rate = f64::from(self.per_second.get());
+        let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(rate);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+
+    async fn dispatch<'a, T, F, Fut>(&'a self, f: F) -> Result<T, Error>
+    where
+        F: Fn(&'a C) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        while let Err(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+        f(&self.inner).await
+    }
+}
+
+impl_client_by_dispatch!(RateLimit);
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::super::testing::Flaky;
+    use super::*;
+    use crate::client::Client;
+
+    #[tokio::test]
+    async fn rate_limit_admits_burst_then_spaces_requests() {
+        let client = RateLimit::new(
+            Flaky::new(Error::channel_send(), 0),
+            NonZeroU32::new(20).unwrap(),
+        );
+
+        let started = std::time::Instant::now();
+        for _ in 0..20 {
+            client.health().await.unwrap();
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        for _ in 0..4 {
+            client.health().await.unwrap();
+        }
+        // Four more requests take at least three intervals of 50ms.
+        assert!(started.elapsed() >= Duration::from_millis(150));
+        assert_eq!(client.inner().calls(), 24);
+    }
+
+    #[tokio::test]
+    async fn concurrency_limit_caps_requests_in_flight() {
+        let client = Arc::new(ConcurrencyLimit::new(
+            Flaky::new(Error::channel_send(), 0),
+            NonZeroUsize::new(2).unwrap(),
+        ));
+
+        let permit = client.semaphore.acquire().await.unwrap();
+        let second = client.semaphore.acquire().await.unwrap();
+
+        let request = tokio::spawn({
+            let client = client.clone();
+            async move { client.health().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(client.inner().calls(), 0);
+
+        drop(permit);
+        request.await.unwrap().unwrap();
+        assert_eq!(client.inner().calls(), 1);
+        drop(second);
+    }
+}
@@ -0,0 +1,246 @@
+//! Retrying failed requests with exponential backoff.
+
+use core::future::Future;
+use core::time::Duration;
+
+use crate::{prelude::*, Error};
+
+/// Determines how often and after how long [`Retry`] sends a failed request
+/// again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How many times a request is retried after the initial attempt.
+    pub max_retries: u32,
+
+    /// How long to wait before the first retry.
+    pub initial_backoff: Duration,
+
+    /// The factor by which the wait grows after each retry.
+    pub multiplier: u32,
+
+    /// The longest to wait before a retry.
+    ///
+    /// If the server asks to wait longer than this through a `Retry-After`
+    /// header, the request fails immediately instead.
+    pub max_backoff: Duration,
+
+    /// Whether to also retry broadcasting transactions.
+    ///
+    /// A broadcast that failed with a timeout may still have reached the
+    /// node, in which case sending it again submits the transaction twice.
+    /// Only enable this for transactions that are safe to submit twice, for
+    /// instance because the application rejects replays.
+    pub retry_broadcasts: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            multiplier: 2,
+            max_backoff: Duration::from_secs(10),
+            retry_broadcasts: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait before the given retry (counting from 0), unless the
+    /// server says otherwise.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.multiplier
+            .checked_pow(retry)
+            .and_then(|factor| self.initial_backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+/// A [`Client`] that retries requests that failed with a retryable error
+/// (see [`ErrorDetail::is_retryable`]), with exponential backoff.
+///
+/// Transactions are broadcast only once unless
+/// [`RetryPolicy::retry_broadcasts`] is set.
+///
+/// A `Retry-After` header sent along with a failed HTTP response takes
+/// precedence over the backoff computed from the [`RetryPolicy`], as does the
+/// remaining time of an open [`CircuitBreaker`] further down the stack.
+///
+/// [`Client`]: crate::Client
+/// [`ErrorDetail::is_retryable`]: crate::error::ErrorDetail::is_retryable
+/// [`CircuitBreaker`]: super::CircuitBreaker
+#[derive(Debug, Clone)]
+pub struct Retry<C> {
+    inner: C,
+    policy: RetryPolicy,
+}
+
+impl<C> Retry<C> {
+    /// Retry requests of the given client according to the default
+    /// [`RetryPolicy`].
+    pub fn new(inner: C) -> Self {
+        Self::with_policy(inner, RetryPolicy::default())
+    }
+
+    /// Retry requests of the given client according to the given policy.
+    pub fn with_policy(inner: C, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Unwrap the wrapped client.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    async fn dispatch<'a, T, F, Fut>(&'a self, f: F) -> Result<T, Error>
+    where
+        F: Fn(&'a C) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut retry = 0;
+        loop {
+            let e = match f(&self.inner).await {
+                Ok(output) => return Ok(output),
+                Err(e) => e,
+            };
+
+            if retry >= self.policy.max_retries || !e.detail().is_retryable() {
+                return Err(e);
+            }
+
+            let backoff = self.policy.backoff(retry);
+            let delay = match e.detail().retry_after() {
+                Some(delay) if delay > self.policy.max_backoff => return Err(e),
+                Some(delay) => delay.max(backoff),
+                None => backoff,
+            };
+
+            tracing::debug!(retry, ?delay, error = %e, "retrying failed RPC request");
+
+            tokio::time::sleep(delay).await;
+            retry += 1;
+        }
+    }
+
+    async fn dispatch_broadcast<'a, T, F, Fut>(&'a self, f: F) -> Result<T, Error>
+    where
+        F: Fn(&'a C) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        if self.policy.retry_broadcasts {
+            self.dispatch(f).await
+        } else {
+            f(&self.inner).await
+        }
+    }
+}
+
+impl_client_by_dispatch!(Retry, dispatch_broadcast);
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{server_error, Flaky};
+    use super::*;
+    use crate::{client::Client, response_error::ResponseError};
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_max() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(10), Duration::from_secs(10));
+        assert_eq!(policy.backoff(100), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let client = Retry::with_policy(Flaky::new(server_error(), 2), fast_policy());
+
+        client.health().await.unwrap();
+        assert_eq!(client.inner().calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let client = Retry::with_policy(Flaky::new(server_error(), 10), fast_policy());
+
+        client.health().await.unwrap_err();
+        assert_eq!(client.inner().calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_node_errors() {
+        let error = Error::response(ResponseError::invalid_params("bad"));
+        let client = Retry::with_policy(Flaky::new(error, 1), fast_policy());
+
+        client.health().await.unwrap_err();
+        assert_eq!(client.inner().calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let error = Error::http_request_failed(403_u16.try_into().unwrap(), None);
+        let client = Retry::with_policy(Flaky::new(error, 1), fast_policy());
+
+        client.health().await.unwrap_err();
+        assert_eq!(client.inner().calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_broadcasts_by_default() {
+        let client = Retry::with_policy(Flaky::new(server_error(), 1), fast_policy());
+
+        client.broadcast_tx_sync(vec![1, 2, 3]).await.unwrap_err();
+        assert_eq!(client.inner().calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_broadcasts_if_enabled() {
+        let policy = RetryPolicy {
+            retry_broadcasts: true,
+            ..fast_policy()
+        };
+        let client = Retry::with_policy(Flaky::new(server_error(), 1), policy);
+
+        // The empty response of the flaky client does not parse as a
+        // broadcast response, but is only returned once retried.
+        client.broadcast_tx_async(vec![1, 2, 3]).await.unwrap_err();
+        assert_eq!(client.inner().calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn respects_retry_after() {
+        let error = Error::http_request_failed(
+            429_u16.try_into().unwrap(),
+            Some(Duration::from_millis(50)),
+        );
+        let client = Retry::with_policy(Flaky::new(error, 1), fast_policy());
+
+        let started = std::time::Instant::now();
+        client.health().await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn gives_up_if_retry_after_exceeds_max_backoff() {
+        let error =
+            Error::http_request_failed(429_u16.try_into().unwrap(), Some(Duration::from_secs(60)));
+        let client = Retry::with_policy(Flaky::new(error, 1), fast_policy());
+
+        client.health().await.unwrap_err();
+        assert_eq!(client.inner().calls(), 1);
+    }
+}
@@ -0,0 +1,348 @@
+//! Streaming all pages of a paginated endpoint.
+
+use alloc::collections::{BTreeSet, VecDeque};
+use core::future::Future;
+use core::pin::Pin;
+
+use futures::{future::join_all, stream, Stream};
+
+use crate::{paging::PageStreamConfig, prelude::*, Error};
+
+/// A stream of the items of all pages of a paginated endpoint.
+pub(crate) type PageStream<'a, T> = Pin<Box<dyn Stream<Item = Result<T, Error>> + Send + 'a>>;
+
+/// The most items per page that nodes return, whatever is requested.
+const MAX_PER_PAGE: u8 = 100;
+
+struct State<T, K, F> {
+    fetch: F,
+    key: fn(&T) -> K,
+    per_page: u8,
+    max_concurrency: u32,
+    next_page: u32,
+    /// Unknown until the first page has been fetched.
+    last_page: Option<u32>,
+    /// Keys of the last items yielded, at most a batch of pages worth.
+    seen: BTreeSet<K>,
+    /// The keys of `seen`, oldest first.
+    seen_order: VecDeque<K>,
+    buffer: VecDeque<T>,
+    done: bool,
+}
+
+/// Stream the items of all pages obtained by calling `fetch` with the page
+/// number (starting at 1) and the number of items per page. `fetch` returns
+/// the items of the page and the total number of items.
+///
+/// Pages are fetched lazily, in batches of up to `max_concurrency` pages
+/// requested at the same time. Items are yielded in the order of the pages.
+///
+/// Items matching the query may be added while the pages are walked, and
+/// shift items already yielded onto later pages. Such items are recognized
+/// by the given `key` and yielded only once, and the pages added by new items
+/// are walked as well. To keep the memory used independent of the number of
+/// items, only the keys of the items of the last batch of pages are kept, so
+/// items shifted by more than a batch of pages may be yielded again.
+pub(crate) fn paginate<'a, T, K, F, Fut>(
+    config: PageStreamConfig,
+    key: fn(&T) -> K,
+    fetch: F,
+) -> PageStream<'a, T>
+where
+    T: Send + 'a,
+    K: Ord + Clone + Send + 'a,
+    F: Fn(u32, u8) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<(Vec<T>, u32), Error>> + Send + 'a,
+{
+    let state = State {
+        fetch,
+        key,
+        // The number of pages is derived from the number of items per page,
+        // which must thus not exceed what nodes return.
+        per_page: config.per_page.get().clamp(1, MAX_PER_PAGE),
+        max_concurrency: config.max_concurrency.clamp(1, u32::MAX as usize) as u32,
+        next_page: 1,
+        last_page: None,
+        seen: BTreeSet::new(),
+        seen_order: VecDeque::new(),
+        buffer: VecDeque::new(),
+        done: false,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.buffer.pop_front() {
+                return Some((Ok(item), state));
+            }
+            if state.done {
+                return None;
+            }
+            if let Err(e) = state.fetch_next().await {
+                state.done = true;
+                return Some((Err(e), state));
+            }
+        }
+    }))
+}
+
+impl<T, K, F, Fut> State<T, K, F>
+where
+    K: Ord + Clone,
+    F: Fn(u32, u8) -> Fut,
+    Fut: Future<Output = Result<(Vec<T>, u32), Error>>,
+{
+    /// Fetch the next batch of pages into the buffer.
+    async fn fetch_next(&mut self) -> Result<(), Error> {
+        let first = self.next_page;
+        let last = match self.last_page {
+            // The total is only known once the first page has been fetched.
+            None => first,
+            Some(last_page) => last_page.min(first.saturating_add(self.max_concurrency - 1)),
+        };
+
+        let pages = join_all((first..=last).map(|page| (self.fetch)(page, self.per_page))).await;
+
+        let mut total = 0;
+        for page in pages {
+            let (items, page_total) = page?;
+            // Guard against endpoints that report more items than they return.
+            if items.is_empty() {
+                self.done = true;
+            }
+            total = total.max(page_total);
+            for item in items {
+                if self.see((self.key)(&item)) {
+                    self.buffer.push_back(item);
+                }
+            }
+        }
+
+        let last_page = total.div_ceil(u32::from(self.per_page));
+        self.last_page = Some(last_page);
+        self.next_page = last + 1;
+        if self.next_page > last_page {
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    /// Remember the key of an item, forgetting the oldest key once more
+    /// than a batch of pages worth are kept. Returns `false` if the key was
+    /// already known.
+    fn see(&mut self, key: K) -> bool {
+        if !self.seen.insert(key.clone()) {
+            return false;
+        }
+        self.seen_order.push_back(key);
+        let capacity = usize::from(self.per_page).saturating_mul(self.max_concurrency as usize);
+        if self.seen_order.len() > capacity {
+            if let Some(oldest) = self.seen_order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::paging::PerPage;
+
+    fn config(per_page: u8, max_concurrency: usize) -> PageStreamConfig {
+        PageStreamConfig {
+            per_page: PerPage::from(per_page),
+            max_concurrency,
+        }
+    }
+
+    /// Serve the page of the given items.
+    fn page(items: &[u32], page: u32, per_page: u8) -> Result<(Vec<u32>, u32), Error> {
+        let start = ((page - 1) * u32::from(per_page)) as usize;
+        let end = (start + usize::from(per_page)).min(items.len());
+        Ok((items[start.min(end)..end].to_vec(), items.len() as u32))
+    }
+
+    async fn collect<'a>(stream: PageStream<'a, u32>) -> Result<Vec<u32>, Error> {
+        stream.collect::<Vec<_>>().await.into_iter().collect()
+    }
+
+    #[tokio::test]
+    async fn walks_all_pages_in_order() {
+        let items: Vec<u32> = (0..25).collect();
+        let stream = paginate(
+            config(10, 2),
+            |i| *i,
+            |p, n| {
+                let items = items.clone();
+                async move { page(&items, p, n) }
+            },
+        );
+
+        assert_eq!(collect(stream).await.unwrap(), items);
+    }
+
+    #[tokio::test]
+    async fn caps_items_per_page() {
+        let items: Vec<u32> = (0..250).collect();
+        let stream = paginate(
+            config(200, 2),
+            |i| *i,
+            |p, n| {
+                // Like a node, serve at most 100 items per page.
+                let items = items.clone();
+                async move { page(&items, p, n.min(100)) }
+            },
+        );
+
+        assert_eq!(collect(stream).await.unwrap(), items);
+    }
+
+    #[tokio::test]
+    async fn can_be_spawned() {
+        let stream = paginate(
+            config(10, 2),
+            |i| *i,
+            |p, n| async move { page(&[1, 2, 3], p, n) },
+        );
+
+        let items = tokio::spawn(collect(stream)).await.unwrap();
+        assert_eq!(items.unwrap(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn empty_result() {
+        let stream = paginate(
+            config(10, 2),
+            |i: &u32| *i,
+            |p, n| async move { page(&[], p, n) },
+        );
+
+        assert_eq!(collect(stream).await.unwrap(), Vec::<u32>::new());
+    }
+
+    #[tokio::test]
+    async fn fetches_lazily() {
+        let items: Vec<u32> = (0..100).collect();
+        let fetched = AtomicU32::new(0);
+        let mut stream = paginate(
+            config(10, 3),
+            |i| *i,
+            |p, n| {
+                fetched.fetch_add(1, Ordering::SeqCst);
+                let items = items.clone();
+                async move { page(&items, p, n) }
+            },
+        );
+
+        for _ in 0..15 {
+            stream.next().await.unwrap().unwrap();
+        }
+        // The first page, then a batch of three.
+        assert_eq!(fetched.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn caps_concurrency() {
+        let items: Vec<u32> = (0..100).collect();
+        let in_flight = AtomicU32::new(0);
+        let max_in_flight = AtomicU32::new(0);
+        let stream = paginate(
+            config(10, 3),
+            |i| *i,
+            |p, n| {
+                let items = items.clone();
+                let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(now, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    page(&items, p, n)
+                }
+            },
+        );
+
+        assert_eq!(collect(stream).await.unwrap(), items);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn handles_pages_shifted_by_new_items() {
+        // Newest first, like a descending search: every fetched page sees
+        // two new items at the front.
+        let items = Mutex::new((0..10).rev().collect::<Vec<u32>>());
+        let stream = paginate(
+            config(4, 1),
+            |i| *i,
+            |p, n| {
+                let mut items = items.lock().unwrap();
+                let newest = items[0];
+                items.insert(0, newest + 1);
+                items.insert(0, newest + 2);
+                let result = page(&items, p, n);
+                async move { result }
+            },
+        );
+
+        let result = collect(stream).await.unwrap();
+        let mut expected: Vec<u32> = result.clone();
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        expected.dedup();
+        assert_eq!(result.len(), expected.len(), "no duplicates");
+        // Every item that existed before the stream started is yielded.
+        for i in 0..10 {
+            assert!(result.contains(&i), "missing {i}");
+        }
+    }
+
+    #[test]
+    fn keeps_keys_of_last_batch_only() {
+        let mut state = State {
+            fetch: |p, n| async move { page(&[], p, n) },
+            key: |i: &u32| *i,
+            per_page: 2,
+            max_concurrency: 2,
+            next_page: 1,
+            last_page: None,
+            seen: BTreeSet::new(),
+            seen_order: VecDeque::new(),
+            buffer: VecDeque::new(),
+            done: false,
+        };
+
+        for i in 0..10 {
+            assert!(state.see(i));
+        }
+        assert_eq!(state.seen.len(), 4);
+        assert_eq!(state.seen_order.len(), 4);
+        // The keys of the last batch are known, older ones are forgotten.
+        assert!(!state.see(6));
+        assert!(state.see(0));
+    }
+
+    #[tokio::test]
+    async fn stops_at_first_error() {
+        let stream = paginate(
+            config(10, 1),
+            |i| *i,
+            |p, n| async move {
+                if p == 2 {
+                    Err(Error::channel_send())
+                } else {
+                    page(&(0..30).collect::<Vec<_>>(), p, n)
+                }
+            },
+        );
+
+        let results: Vec<_> = stream.collect().await;
+        assert_eq!(results.len(), 11);
+        assert!(results[..10].iter().all(Result::is_ok));
+        assert!(results[10].is_err());
+    }
+}
@@ -0,0 +1,684 @@
+//! A [`Client`] that spreads requests over a pool of RPC endpoints.
+//!
+//! [`ClientPool`] wraps any number of clients that implement [`Client`]
+//! (e.g. one [`HttpClient`] per full node) and is itself a [`Client`], so it
+//! can be used anywhere a single client is accepted today.
+//!
+//! Requests are sent to one endpoint at a time, selected according to the
+//! pool's [`Strategy`]. When an endpoint fails with a transport-level error,
+//! or reports that the requested height is beyond its latest block, the
+//! endpoint is marked as unavailable and the request is retried against the
+//! next one. Errors returned by the node itself (e.g. an invalid query) are
+//! returned to the caller as-is.
+//!
+//! Endpoints marked as unavailable are only tried as a last resort until they
+//! either serve a request successfully or pass a health check. Health checks
+//! are not scheduled by the pool itself; call [`ClientPool::check_health`]
+//! periodically to refresh the pool's view of its endpoints.
+//!
+//! [`HttpClient`]: crate::HttpClient
+
+use core::future::Future;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+
+use futures::future::join_all;
+use tendermint::{block::Height, chain};
+
+use crate::{
+    client::Client,
+    error::{Error, ErrorDetail},
+    prelude::*,
+};
+
+/// Weight given to the most recent sample when updating an endpoint's
+/// smoothed latency.
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// How a [`ClientPool`] picks the endpoint a request is sent to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Cycle through the available endpoints in turn.
+    #[default]
+    RoundRobin,
+
+    /// Prefer the available endpoint with the lowest smoothed latency.
+    ///
+    /// Endpoints for which no latency has been observed yet are preferred
+    /// over all others, so that every endpoint gets measured.
+    LowestLatency,
+}
+
+/// What a [`ClientPool`] currently knows about one of its endpoints.
+#[derive(Clone, Debug, Default)]
+pub struct EndpointStatus {
+    /// Whether requests are currently routed to this endpoint.
+    pub available: bool,
+
+    /// Smoothed round-trip time of successful requests.
+    pub latency: Option<Duration>,
+
+    /// Latest block height reported during the last health check.
+    pub latest_height: Option<Height>,
+
+    /// Chain ID reported during the last health check.
+    pub chain_id: Option<chain::Id>,
+
+    /// The error that caused this endpoint to become unavailable, if any.
+    pub error: Option<Error>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The last request or health check failed with a transport error.
+    down: bool,
+    /// The endpoint is too far behind the rest of the pool.
+    lagging: bool,
+    /// The endpoint serves a different chain than the one expected.
+    wrong_chain: bool,
+    latency: Option<Duration>,
+    latest_height: Option<Height>,
+    chain_id: Option<chain::Id>,
+    error: Option<Error>,
+}
+
+impl State {
+    fn is_available(&self) -> bool {
+        !self.down && !self.lagging && !self.wrong_chain
+    }
+
+    fn record_latency(&mut self, sample: Duration) {
+        self.latency = Some(match self.latency {
+            None => sample,
+            Some(latency) => {
+                latency.mul_f64(1.0 - LATENCY_SMOOTHING) + sample.mul_f64(LATENCY_SMOOTHING)
+            },
+        });
+    }
+}
+
+#[derive(Debug)]
+struct Member<C> {
+    client: C,
+    state: Mutex<State>,
+}
+
+impl<C> Member<C> {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The builder pattern constructor for [`ClientPool`].
+pub struct Builder<C> {
+    clients: Vec<C>,
+    strategy: Strategy,
+    chain_id: Option<chain::Id>,
+    same_chain_id: bool,
+    max_lag: Option<u64>,
+}
+
+impl<C> Builder<C> {
+    /// Use the given strategy to select endpoints.
+    ///
+    /// The default is [`Strategy::RoundRobin`].
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Exclude endpoints that do not serve the chain with the given ID.
+    ///
+    /// The chain ID of each endpoint is learned during health checks.
+    pub fn chain_id(mut self, chain_id: chain::Id) -> Self {
+        self.chain_id = Some(chain_id);
+        self.same_chain_id = true;
+        self
+    }
+
+    /// Exclude endpoints that do not serve the same chain as the rest of the
+    /// pool.
+    ///
+    /// Unless a chain ID is given with [`Builder::chain_id`], the expected
+    /// chain ID is the one reported by the first endpoint (in the order given
+    /// to the builder) that passes a health check.
+    pub fn ensure_same_chain_id(mut self) -> Self {
+        self.same_chain_id = true;
+        self
+    }
+
+    /// Mark endpoints whose latest block is more than `blocks` behind the
+    /// highest latest block in the pool as unavailable.
+    ///
+    /// Lag is assessed during health checks.
+    pub fn max_lag(mut self, blocks: u64) -> Self {
+        self.max_lag = Some(blocks);
+        self
+    }
+
+    /// Try to create a client pool with the options specified for this
+    /// builder.
+    pub fn build(self) -> Result<ClientPool<C>, Error> {
+        if self.clients.is_empty() {
+            return Err(Error::no_available_endpoint());
+        }
+
+        Ok(ClientPool {
+            members: self
+                .clients
+                .into_iter()
+                .map(|client| Member {
+                    client,
+                    state: Mutex::default(),
+                })
+                .collect(),
+            strategy: self.strategy,
+            chain_id: Mutex::new(self.chain_id),
+            same_chain_id: self.same_chain_id,
+            max_lag: self.max_lag,
+            next: AtomicUsize::new(0),
+        })
+    }
+}
+
+/// A Tendermint RPC client that fails over between several endpoints
+/// (implements [`crate::Client`]).
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint_rpc::{client::pool::Strategy, Client, ClientPool, HttpClient};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let pool = ClientPool::builder(vec![
+///         HttpClient::new("http://node-1:26657").unwrap(),
+///         HttpClient::new("http://node-2:26657").unwrap(),
+///     ])
+///     .strategy(Strategy::LowestLatency)
+///     .ensure_same_chain_id()
+///     .max_lag(10)
+///     .build()
+///     .unwrap();
+///
+///     pool.check_health().await;
+///
+///     let status = pool.status().await.unwrap();
+///     println!("Got status: {:?}", status);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ClientPool<C> {
+    members: Vec<Member<C>>,
+    strategy: Strategy,
+    chain_id: Mutex<Option<chain::Id>>,
+    same_chain_id: bool,
+    max_lag: Option<u64>,
+    next: AtomicUsize,
+}
+
+impl<C> ClientPool<C> {
+    /// Construct a pool of the given clients using round-robin selection.
+    pub fn new(clients: Vec<C>) -> Result<Self, Error> {
+        Self::builder(clients).build()
+    }
+
+    /// Initiate a builder for a pool of the given clients, so that more
+    /// configuration options can be specified with the builder.
+    pub fn builder(clients: Vec<C>) -> Builder<C> {
+        Builder {
+            clients,
+            strategy: Strategy::default(),
+            chain_id: None,
+            same_chain_id: false,
+            max_lag: None,
+        }
+    }
+
+    /// The clients in this pool, in the order they were given.
+    pub fn clients(&self) -> impl Iterator<Item = &C> {
+        self.members.iter().map(|m| &m.client)
+    }
+
+    /// The current status of each endpoint, in the order the clients were
+    /// given.
+    pub fn endpoint_status(&self) -> Vec<EndpointStatus> {
+        self.members
+            .iter()
+            .map(|m| {
+                let state = m.state();
+                EndpointStatus {
+                    available: state.is_available(),
+                    latency: state.latency,
+                    latest_height: state.latest_height,
+                    chain_id: state.chain_id.clone(),
+                    error: state.error.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Indices of the members to try, in order.
+    ///
+    /// Available members come first, ordered according to the strategy,
+    /// followed by members that are down or lagging. Members serving the
+    /// wrong chain are never tried.
+    fn candidates(&self) -> Vec<usize> {
+        let n = self.members.len();
+        let mut order: Vec<usize> = match self.strategy {
+            Strategy::RoundRobin => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed) % n;
+                (0..n).map(|i| (start + i) % n).collect()
+            },
+            Strategy::LowestLatency => {
+                let mut order: Vec<usize> = (0..n).collect();
+                order.sort_by_cached_key(|&i| self.members[i].state().latency.unwrap_or_default());
+                order
+            },
+        };
+
+        let states: Vec<(bool, bool)> = self
+            .members
+            .iter()
+            .map(|m| {
+                let state = m.state();
+                (state.is_available(), state.wrong_chain)
+            })
+            .collect();
+        order.retain(|&i| !states[i].1);
+        order.sort_by_key(|&i| !states[i].0);
+        order
+    }
+
+    /// Run `f` against each candidate endpoint in turn until one of them
+    /// succeeds or fails with an error that should not be failed over.
+    async fn dispatch<'a, T, F, Fut>(&'a self, f: F) -> Result<T, Error>
+    where
+        F: Fn(&'a C) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut last_error = None;
+
+        for i in self.candidates() {
+            let member = &self.members[i];
+            let started = Instant::now();
+
+            match f(&member.client).await {
+                Ok(output) => {
+                    let mut state = member.state();
+                    state.record_latency(started.elapsed());
+                    state.down = false;
+                    state.error = None;
+                    return Ok(output);
+                },
+                Err(e) => {
+                    if e.detail().is_transport() {
+                        member.state().down = true;
+                    } else if is_height_too_high(&e) {
+                        member.state().lagging = true;
+                    } else {
+                        return Err(e);
+                    }
+
+                    tracing::debug!(endpoint = i, error = %e, "failing over to next RPC endpoint");
+
+                    member.state().error = Some(e.clone());
+                    last_error = Some(e);
+                },
+            }
+        }
+
+        Err(last_error.unwrap_or_else(Error::no_available_endpoint))
+    }
+}
+
+impl<C> ClientPool<C>
+where
+    C: Client + Sync,
+{
+    /// Check the health of every endpoint in the pool and update which of
+    /// them are available.
+    ///
+    /// Endpoints are probed concurrently through `/health`, or through
+    /// `/status` if the pool checks chain IDs or lag. An endpoint that passes
+    /// the check becomes available again, unless it serves the wrong chain or
+    /// lags too far behind the rest of the pool.
+    ///
+    /// Returns the updated status of each endpoint.
+    pub async fn check_health(&self) -> Vec<EndpointStatus> {
+        let use_status = self.same_chain_id || self.max_lag.is_some();
+
+        let results = join_all(self.members.iter().map(|m| async move {
+            let started = Instant::now();
+            let result = if use_status {
+                m.client.status().await.map(Some)
+            } else {
+                m.client.health().await.map(|_| None)
+            };
+            (result, started.elapsed())
+        }))
+        .await;
+
+        for (member, (result, elapsed)) in self.members.iter().zip(results) {
+            let mut state = member.state();
+            match result {
+                Ok(status) => {
+                    state.record_latency(elapsed);
+                    state.down = false;
+                    state.lagging = false;
+                    state.error = None;
+                    if let Some(status) = status {
+                        state.latest_height = Some(status.sync_info.latest_block_height);
+                        state.chain_id = Some(status.node_info.network);
+                    }
+                },
+                Err(e) => {
+                    state.down = true;
+                    state.error = Some(e);
+                },
+            }
+        }
+
+        if self.same_chain_id {
+            self.check_chain_ids();
+        }
+        if let Some(max_lag) = self.max_lag {
+            self.check_lag(max_lag);
+        }
+
+        self.endpoint_status()
+    }
+
+    fn check_chain_ids(&self) {
+        let mut expected = self.chain_id.lock().unwrap_or_else(PoisonError::into_inner);
+        if expected.is_none() {
+            *expected = self.members.iter().find_map(|m| {
+                let state = m.state();
+                if state.down {
+                    None
+                } else {
+                    state.chain_id.clone()
+                }
+            });
+        }
+
+        let Some(expected) = expected.as_ref() else {
+            return;
+        };
+
+        for member in &self.members {
+            let mut state = member.state();
+            let Some(actual) = state.chain_id.clone() else {
+                continue;
+            };
+            state.wrong_chain = &actual != expected;
+            if state.wrong_chain {
+                state.error = Some(Error::chain_id_mismatch(
+                    expected.to_string(),
+                    actual.to_string(),
+                ));
+            }
+        }
+    }
+
+    fn check_lag(&self, max_lag: u64) {
+        let heights: Vec<Option<Height>> = self
+            .members
+            .iter()
+            .map(|m| {
+                let state = m.state();
+                if state.down || state.wrong_chain {
+                    None
+                } else {
+                    state.latest_height
+                }
+            })
+            .collect();
+
+        let Some(highest) = heights.iter().flatten().max().copied() else {
+            return;
+        };
+
+        for (member, height) in self.members.iter().zip(heights) {
+            if let Some(height) = height {
+                member.state().lagging = height.value().saturating_add(max_lag) < highest.value();
+            }
+        }
+    }
+}
+
+/// Whether the error means that the endpoint has not yet reached the
+/// requested height.
+fn is_height_too_high(e: &Error) -> bool {
+    match e.detail() {
+        ErrorDetail::Response(e) => e.source.data().is_some_and(|data| {
+            data.contains("must be less than or equal to the current blockchain height")
+        }),
+        _ => false,
+    }
+}
+
+impl_client_by_dispatch!(ClientPool);
+
+#[cfg(all(test, feature = "mock-client"))]
+mod tests {
+    use super::super::testing::read_json_fixture;
+    use super::*;
+    use crate::{
+        response_error::ResponseError, Method, MockClient, MockRequestMatcher,
+        MockRequestMethodMatcher,
+    };
+
+    const HEALTH_RESPONSE: &str = r#"{"jsonrpc":"2.0","id":"","result":{}}"#;
+
+    fn mock<M: MockRequestMatcher>(matcher: M) -> MockClient<M> {
+        // The driver is only needed for subscriptions.
+        MockClient::new(matcher).0
+    }
+
+    fn healthy() -> MockClient<MockRequestMethodMatcher> {
+        mock(
+            MockRequestMethodMatcher::default()
+                .map(Method::AbciInfo, Ok(read_json_fixture("abci_info")))
+                .map(Method::Block, Ok(read_json_fixture("block_at_height_10")))
+                .map(Method::Health, Ok(HEALTH_RESPONSE.to_string()))
+                .map(Method::Status, Ok(read_json_fixture("status"))),
+        )
+    }
+
+    fn unreachable() -> MockClient<MockRequestMethodMatcher> {
+        let error = || Error::io(std::io::ErrorKind::ConnectionRefused.into());
+        mock(
+            MockRequestMethodMatcher::default()
+                .map(Method::AbciInfo, Err(error()))
+                .map(Method::Block, Err(error()))
+                .map(Method::Health, Err(error()))
+                .map(Method::Status, Err(error())),
+        )
+    }
+
+    fn with_status(status: String) -> MockClient<MockRequestMethodMatcher> {
+        mock(
+            MockRequestMethodMatcher::default()
+                .map(Method::AbciInfo, Ok(read_json_fixture("abci_info")))
+                .map(Method::Status, Ok(status)),
+        )
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_transport_error() {
+        let pool = ClientPool::new(vec![unreachable(), healthy()]).unwrap();
+
+        pool.abci_info().await.unwrap();
+
+        let status = pool.endpoint_status();
+        assert!(!status[0].available);
+        assert!(status[0].error.is_some());
+        assert!(status[1].available);
+
+        // The unreachable endpoint is only tried once all others have failed.
+        pool.abci_info().await.unwrap();
+        pool.abci_info().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn returns_node_errors_without_failing_over() {
+        let invalid = mock(MockRequestMethodMatcher::default().map(
+            Method::AbciInfo,
+            Err(Error::response(ResponseError::invalid_params("bad"))),
+        ));
+        let pool = ClientPool::new(vec![invalid, healthy()]).unwrap();
+
+        let err = pool.abci_info().await.unwrap_err();
+        assert!(matches!(err.detail(), ErrorDetail::Response(_)));
+        assert!(pool.endpoint_status()[0].available);
+    }
+
+    #[tokio::test]
+    async fn fails_over_when_height_too_high() {
+        let behind = mock(
+            MockRequestMethodMatcher::default().map(
+                Method::Block,
+                Err(Error::response(ResponseError::new(
+                    crate::Code::InternalError,
+                    Some(
+                        "height 10 must be less than or equal to the current blockchain height 9"
+                            .to_string(),
+                    ),
+                ))),
+            ),
+        );
+        let pool = ClientPool::new(vec![behind, healthy()]).unwrap();
+
+        let block = pool.block(10_u32).await.unwrap().block;
+        assert_eq!(block.header.height.value(), 10);
+        assert!(!pool.endpoint_status()[0].available);
+    }
+
+    #[tokio::test]
+    async fn all_endpoints_failing() {
+        let pool = ClientPool::new(vec![unreachable(), unreachable()]).unwrap();
+
+        let err = pool.abci_info().await.unwrap_err();
+        assert!(matches!(err.detail(), ErrorDetail::Io(_)));
+    }
+
+    #[test]
+    fn empty_pool() {
+        let err = ClientPool::<MockClient<MockRequestMethodMatcher>>::new(vec![]).unwrap_err();
+        assert!(matches!(err.detail(), ErrorDetail::NoAvailableEndpoint(_)));
+    }
+
+    #[tokio::test]
+    async fn round_robin_uses_every_endpoint() {
+        let pool = ClientPool::new(vec![healthy(), healthy(), healthy()]).unwrap();
+
+        for _ in 0..3 {
+            pool.abci_info().await.unwrap();
+        }
+
+        assert!(pool.endpoint_status().iter().all(|s| s.latency.is_some()));
+    }
+
+    #[tokio::test]
+    async fn health_check_probes_every_endpoint() {
+        let pool = ClientPool::new(vec![unreachable(), healthy()]).unwrap();
+
+        let status = pool.check_health().await;
+        assert!(!status[0].available);
+        assert!(status[1].available);
+        // Without chain ID or lag checks, only `/health` is queried.
+        assert_eq!(status[1].latest_height, None);
+    }
+
+    #[tokio::test]
+    async fn excludes_endpoints_on_other_chains() {
+        let status = read_json_fixture("status");
+        let other_chain = status.replace("\"dockerchain\"", "\"otherchain\"");
+        let pool = ClientPool::builder(vec![with_status(other_chain), with_status(status)])
+            .chain_id("dockerchain".parse().unwrap())
+            .build()
+            .unwrap();
+
+        let status = pool.check_health().await;
+        assert!(!status[0].available);
+        assert!(matches!(
+            status[0].error.as_ref().unwrap().detail(),
+            ErrorDetail::ChainIdMismatch(_)
+        ));
+        assert!(status[1].available);
+
+        // Endpoints on the wrong chain are never tried.
+        let pool = ClientPool::builder(vec![with_status(read_json_fixture("status"))])
+            .chain_id("otherchain".parse().unwrap())
+            .build()
+            .unwrap();
+        pool.check_health().await;
+        let err = pool.abci_info().await.unwrap_err();
+        assert!(matches!(err.detail(), ErrorDetail::NoAvailableEndpoint(_)));
+    }
+
+    #[tokio::test]
+    async fn ensure_same_chain_id_follows_first_endpoint() {
+        let status = read_json_fixture("status");
+        let other_chain = status.replace("\"dockerchain\"", "\"otherchain\"");
+        let pool = ClientPool::builder(vec![
+            unreachable(),
+            with_status(status.clone()),
+            with_status(other_chain),
+            with_status(status),
+        ])
+        .ensure_same_chain_id()
+        .build()
+        .unwrap();
+
+        let available: Vec<bool> = pool
+            .check_health()
+            .await
+            .into_iter()
+            .map(|s| s.available)
+            .collect();
+        assert_eq!(available, [false, true, false, true]);
+    }
+
+    #[tokio::test]
+    async fn excludes_lagging_endpoints() {
+        let status = read_json_fixture("status");
+        let behind = status.replace(
+            "\"latest_block_height\": \"232\"",
+            "\"latest_block_height\": \"200\"",
+        );
+        let slightly_behind = status.replace(
+            "\"latest_block_height\": \"232\"",
+            "\"latest_block_height\": \"230\"",
+        );
+        let pool = ClientPool::builder(vec![
+            with_status(behind),
+            with_status(slightly_behind),
+            with_status(status),
+        ])
+        .max_lag(5)
+        .build()
+        .unwrap();
+
+        let status = pool.check_health().await;
+        assert_eq!(status[0].latest_height, Some(200_u32.into()));
+        assert!(!status[0].available);
+        assert!(status[1].available);
+        assert!(status[2].available);
+    }
+
+    #[tokio::test]
+    async fn lowest_latency_prefers_measured_fastest() {
+        let pool = ClientPool::builder(vec![healthy(), healthy()])
+            .strategy(Strategy::LowestLatency)
+            .build()
+            .unwrap();
+        pool.members[0].state().latency = Some(Duration::from_secs(1));
+        pool.members[1].state().latency = Some(Duration::from_millis(1));
+
+        assert_eq!(pool.candidates(), [1, 0]);
+    }
+}
@@ -1,7 +1,10 @@
 //! Tendermint RPC client implementations for different transports.
 
-mod auth;
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod auth;
 mod router;
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod tls;
 
 macro_rules! perform_with_compat {
     ($self:expr, $request:expr) => {{
@@ -0,0 +1,126 @@
+//! TLS configuration for `https://` and `wss://` connections.
+
+use core::fmt;
+
+use crate::prelude::*;
+
+/// TLS options for secure HTTP and WebSocket RPC connections.
+///
+/// By default, the server certificate is verified against the platform's
+/// native root certificates, and no client certificate is presented.
+#[derive(Clone)]
+pub struct TlsConfig {
+    pub(crate) native_roots: bool,
+    pub(crate) root_certificates: Vec<Vec<u8>>,
+    pub(crate) client_identity: Option<ClientIdentity>,
+}
+
+#[derive(Clone)]
+pub(crate) struct ClientIdentity {
+    pub(crate) cert_chain: Vec<u8>,
+    pub(crate) key: Vec<u8>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            native_roots: true,
+            root_certificates: Vec::new(),
+            client_identity: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    /// The default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also trust the PEM-encoded CA certificate(s) in `pem`, e.g. the CA of
+    /// a private network.
+    pub fn add_root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Whether to trust the platform's native root certificates.
+    ///
+    /// Disable this to trust only the certificates added with
+    /// [`TlsConfig::add_root_certificate_pem`].
+    pub fn native_roots(mut self, enabled: bool) -> Self {
+        self.native_roots = enabled;
+        self
+    }
+
+    /// Authenticate to the server with the PEM-encoded client certificate
+    /// chain and private key (mTLS).
+    pub fn client_identity_pem(
+        mut self,
+        cert_chain: impl Into<Vec<u8>>,
+        key: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.client_identity = Some(ClientIdentity {
+            cert_chain: cert_chain.into(),
+            key: key.into(),
+        });
+        self
+    }
+}
+
+impl fmt::Debug for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsConfig")
+            .field("native_roots", &self.native_roots)
+            .field("root_certificates", &self.root_certificates.len())
+            .field("client_identity", &self.client_identity.is_some())
+            .finish()
+    }
+}
+
+#[cfg(feature = "websocket-client")]
+impl TlsConfig {
+    /// Build a `rustls`-based connector for secure WebSocket connections.
+    pub(crate) fn connector(&self) -> Result<tokio_rustls::TlsConnector, crate::Error> {
+        use alloc::sync::Arc;
+
+        use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+        use crate::Error;
+
+        let mut roots = RootCertStore::empty();
+        if self.native_roots {
+            let certs = rustls_native_certs::load_native_certs()
+                .map_err(|e| Error::invalid_tls_config(e.to_string()))?;
+            // Like `async-tungstenite`, skip native certificates that cannot
+            // be parsed rather than failing altogether.
+            roots.add_parsable_certificates(certs);
+        }
+        for pem in &self.root_certificates {
+            for cert in rustls_pemfile::certs(&mut pem.as_slice()) {
+                let cert = cert.map_err(|e| Error::invalid_tls_config(e.to_string()))?;
+                roots
+                    .add(cert)
+                    .map_err(|e| Error::invalid_tls_config(e.to_string()))?;
+            }
+        }
+
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+        let config = match &self.client_identity {
+            None => builder.with_no_client_auth(),
+            Some(identity) => {
+                let cert_chain = rustls_pemfile::certs(&mut identity.cert_chain.as_slice())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| Error::invalid_tls_config(e.to_string()))?;
+                let key = rustls_pemfile::private_key(&mut identity.key.as_slice())
+                    .map_err(|e| Error::invalid_tls_config(e.to_string()))?
+                    .ok_or_else(|| Error::invalid_tls_config("no private key found".to_string()))?;
+                builder
+                    .with_client_auth_cert(cert_chain, key)
+                    .map_err(|e| Error::invalid_tls_config(e.to_string()))?
+            },
+        };
+
+        Ok(tokio_rustls::TlsConnector::from(Arc::new(config)))
+    }
+}
@@ -1,6 +1,6 @@
 //! WebSocket-based clients for accessing Tendermint RPC functionality.
 
-use alloc::{borrow::Cow, collections::BTreeMap as HashMap, fmt};
+use alloc::{borrow::Cow, collections::BTreeMap as HashMap, fmt, sync::Arc};
 use core::{ops::Add, str::FromStr};
 
 use async_trait::async_trait;
@@ -20,7 +20,9 @@ use tracing::{debug, error};
 use tendermint::{block::Height, Hash};
 use tendermint_config::net;
 
+use super::auth::AuthProvider;
 use super::router::{SubscriptionId, SubscriptionIdRef};
+use super::tls::TlsConfig;
 use crate::{
     client::{
         subscription::SubscriptionTx,
@@ -144,7 +146,16 @@ pub struct WebSocketClient {
 pub struct Builder {
     url: WebSocketClientUrl,
     compat: CompatMode,
+    options: ConnectOptions,
+}
+
+/// Options used when opening the WebSocket connection.
+#[derive(Debug, Default)]
+struct ConnectOptions {
     transport_config: Option<WebSocketConfig>,
+    headers: Vec<(String, String)>,
+    auth: Option<Arc<dyn AuthProvider>>,
+    tls: Option<TlsConfig>,
 }
 
 impl Builder {
@@ -158,7 +169,34 @@ impl Builder {
 
     /// Use the specified low-level WebSocket configuration options.
     pub fn config(mut self, config: WebSocketConfig) -> Self {
-        self.transport_config = Some(config);
+        self.options.transport_config = Some(config);
+        self
+    }
+
+    /// Send the given header with the WebSocket handshake request.
+    ///
+    /// Headers with the same name are all sent, in the order in which they
+    /// were added.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Authenticate the WebSocket handshake request with the headers obtained
+    /// from the given provider.
+    ///
+    /// If the server rejects the handshake with 401 Unauthorized, the provider
+    /// is asked to [refresh](AuthProvider::refresh) its credentials, and the
+    /// connection is attempted once more.
+    pub fn auth(mut self, provider: impl AuthProvider + 'static) -> Self {
+        self.options.auth = Some(Arc::new(provider));
+        self
+    }
+
+    /// Use the given root certificates and client identity for `wss://`
+    /// connections.
+    pub fn tls_config(mut self, config: TlsConfig) -> Self {
+        self.options.tls = Some(config);
         self
     }
 
@@ -167,9 +205,9 @@ impl Builder {
         let url = self.url.0;
         let compat = self.compat;
         let (inner, driver) = if url.is_secure() {
-            sealed::WebSocketClient::new_secure(url, compat, self.transport_config).await?
+            sealed::WebSocketClient::new_secure(url, compat, self.options).await?
         } else {
-            sealed::WebSocketClient::new_unsecure(url, compat, self.transport_config).await?
+            sealed::WebSocketClient::new_unsecure(url, compat, self.options).await?
         };
 
         Ok((WebSocketClient { inner, compat }, driver))
@@ -212,7 +250,7 @@ impl WebSocketClient {
         Builder {
             url,
             compat: Default::default(),
-            transport_config: Default::default(),
+            options: Default::default(),
         }
     }
 
@@ -433,20 +471,29 @@ impl From<WebSocketClientUrl> for Url {
 }
 
 mod sealed {
+    use core::future::Future;
+
     use async_tungstenite::{
-        tokio::{connect_async_with_config, connect_async_with_tls_connector_and_config},
-        tungstenite::client::IntoClientRequest,
+        tokio::{
+            connect_async_with_config, connect_async_with_tls_connector_and_config, ConnectStream,
+        },
+        tungstenite::{
+            client::IntoClientRequest,
+            handshake::client::{Request, Response as HandshakeResponse},
+            http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+        },
+        WebSocketStream,
     };
     use tracing::debug;
 
     use super::{
-        DriverCommand, SimpleRequestCommand, SubscribeCommand, UnsubscribeCommand,
-        WebSocketClientDriver, WebSocketConfig,
+        ConnectOptions, DriverCommand, SimpleRequestCommand, SubscribeCommand, UnsubscribeCommand,
+        WebSocketClientDriver,
     };
     use crate::{
         client::{
             sync::{unbounded, ChannelTx},
-            transport::auth::authorize,
+            transport::auth::{authorize, to_header},
             CompatMode,
         },
         dialect::Dialect,
@@ -491,13 +538,15 @@ mod sealed {
         pub async fn new(
             url: Url,
             compat: CompatMode,
-            config: Option<WebSocketConfig>,
+            options: ConnectOptions,
         ) -> Result<(Self, WebSocketClientDriver), Error> {
             debug!("Connecting to unsecure WebSocket endpoint: {}", url);
 
-            let (stream, _response) = connect_async_with_config(url, config)
-                .await
-                .map_err(Error::tungstenite)?;
+            let config = options.transport_config;
+            let stream = connect(url, &options, |request| {
+                connect_async_with_config(request, config)
+            })
+            .await?;
 
             let (cmd_tx, cmd_rx) = unbounded();
             let driver = WebSocketClientDriver::new(stream, cmd_rx, compat);
@@ -523,16 +572,22 @@ mod sealed {
         pub async fn new(
             url: Url,
             compat: CompatMode,
-            config: Option<WebSocketConfig>,
+            options: ConnectOptions,
         ) -> Result<(Self, WebSocketClientDriver), Error> {
             debug!("Connecting to secure WebSocket endpoint: {}", url);
 
-            // Not supplying a connector means async_tungstenite will create the
-            // connector for us.
-            let (stream, _response) =
-                connect_async_with_tls_connector_and_config(url, None, config)
-                    .await
-                    .map_err(Error::tungstenite)?;
+            // Without a TLS configuration, not supplying a connector means
+            // async_tungstenite will create the connector for us.
+            let connector = options
+                .tls
+                .as_ref()
+                .map(|tls| tls.connector())
+                .transpose()?;
+            let config = options.transport_config;
+            let stream = connect(url, &options, |request| {
+                connect_async_with_tls_connector_and_config(request, connector.clone(), config)
+            })
+            .await?;
 
             let (cmd_tx, cmd_rx) = unbounded();
             let driver = WebSocketClientDriver::new(stream, cmd_rx, compat);
@@ -545,6 +600,69 @@ mod sealed {
         }
     }
 
+    /// Build the handshake request for the given URL, with the headers
+    /// configured in the given options.
+    ///
+    /// Credentials embedded in the URL take precedence over the default
+    /// headers, and the headers of the auth provider over both.
+    pub async fn client_request(url: &Url, options: &ConnectOptions) -> Result<Request, Error> {
+        let mut request = url
+            .clone()
+            .into_client_request()
+            .map_err(Error::tungstenite)?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &options.headers {
+            let (name, value): (HeaderName, HeaderValue) = to_header(name, value)?;
+            headers.append(name, value);
+        }
+        headers.extend(request.headers().clone());
+
+        if let Some(provider) = &options.auth {
+            for (name, value) in provider.headers().await? {
+                let (name, value): (HeaderName, HeaderValue) = to_header(&name, &value)?;
+                headers.insert(name, value);
+            }
+        }
+
+        *request.headers_mut() = headers;
+        Ok(request)
+    }
+
+    /// Open a connection with the given function, refreshing the credentials
+    /// of the auth provider and trying once more if the server rejects the
+    /// handshake with 401 Unauthorized.
+    async fn connect<F, Fut>(
+        url: Url,
+        options: &ConnectOptions,
+        open: F,
+    ) -> Result<WebSocketStream<ConnectStream>, Error>
+    where
+        F: Fn(Request) -> Fut,
+        Fut: Future<
+            Output = Result<
+                (WebSocketStream<ConnectStream>, HandshakeResponse),
+                tungstenite::Error,
+            >,
+        >,
+    {
+        let request = client_request(&url, options).await?;
+        let result = match (open(request).await, &options.auth) {
+            (Err(tungstenite::Error::Http(response)), Some(provider))
+                if response.status() == StatusCode::UNAUTHORIZED =>
+            {
+                debug!("WebSocket handshake unauthorized, refreshing credentials");
+                provider.refresh().await?;
+                let request = client_request(&url, options).await?;
+                open(request).await
+            },
+            (result, _) => result,
+        };
+        result
+            .map(|(stream, _response)| stream)
+            .map_err(Error::tungstenite)
+    }
+
     impl<C> AsyncTungsteniteClient<C> {
         fn send_cmd(&self, cmd: DriverCommand) -> Result<(), Error> {
             self.cmd_tx.send(cmd)
@@ -628,20 +746,20 @@ mod sealed {
         pub async fn new_unsecure(
             url: Url,
             compat: CompatMode,
-            config: Option<WebSocketConfig>,
+            options: ConnectOptions,
         ) -> Result<(Self, WebSocketClientDriver), Error> {
             let (client, driver) =
-                AsyncTungsteniteClient::<Unsecure>::new(url, compat, config).await?;
+                AsyncTungsteniteClient::<Unsecure>::new(url, compat, options).await?;
             Ok((Self::Unsecure(client), driver))
         }
 
         pub async fn new_secure(
             url: Url,
             compat: CompatMode,
-            config: Option<WebSocketConfig>,
+            options: ConnectOptions,
         ) -> Result<(Self, WebSocketClientDriver), Error> {
             let (client, driver) =
-                AsyncTungsteniteClient::<Secure>::new(url, compat, config).await?;
+                AsyncTungsteniteClient::<Secure>::new(url, compat, options).await?;
             Ok((Self::Secure(client), driver))
         }
 
@@ -1603,4 +1721,30 @@ mod test {
 
         assert_eq!(authorization(&req), None);
     }
+
+    #[tokio::test]
+    async fn with_headers_and_auth_provider() {
+        let url = Url::from_str("ws://toto:tata@example.com/websocket").unwrap();
+        let options = ConnectOptions {
+            headers: vec![
+                ("X-Tenant".to_string(), "a".to_string()),
+                ("Authorization".to_string(), "Bearer ignored".to_string()),
+            ],
+            ..Default::default()
+        };
+        let req = sealed::client_request(&url, &options).await.unwrap();
+
+        assert_eq!(req.headers().get("x-tenant").unwrap(), "a");
+        // Credentials embedded in the URL take precedence.
+        assert_eq!(authorization(&req), Some("Basic dG90bzp0YXRh"));
+
+        let options = ConnectOptions {
+            auth: Some(Arc::new(super::super::auth::Authorization::bearer("token"))),
+            ..options
+        };
+        let req = sealed::client_request(&url, &options).await.unwrap();
+
+        assert_eq!(authorization(&req), Some("Bearer token"));
+        assert!(req.headers().contains_key("sec-websocket-key"));
+    }
 }
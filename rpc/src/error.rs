@@ -86,6 +86,7 @@ define_error! {
         HttpRequestFailed
             {
                 status: HttpStatusCode,
+                retry_after: Option<Duration>,
             }
             | e | {
                 format_args!("HTTP request failed with non-200 status code: {}", e.status)
@@ -226,6 +227,44 @@ define_error! {
                 format_args!("invalid compatibility mode: '{}' (supported: {})",
                     e.mode, e.supported)
             },
+
+        CircuitOpen
+            {
+                retry_in: Duration,
+            }
+            | e | {
+                format_args!("circuit breaker is open, rejecting requests for another {}ms",
+                    e.retry_in.as_millis())
+            },
+
+        NoAvailableEndpoint
+            | _ | { "no usable endpoint in client pool" },
+
+        ChainIdMismatch
+            {
+                expected: String,
+                actual: String,
+            }
+            | e | {
+                format_args!("endpoint reports chain ID '{}', expected '{}'",
+                    e.actual, e.expected)
+            },
+
+        InvalidHeader
+            {
+                name: String,
+            }
+            | e | {
+                format_args!("invalid HTTP header: {}", e.name)
+            },
+
+        InvalidTlsConfig
+            {
+                reason: String,
+            }
+            | e | {
+                format_args!("invalid TLS configuration: {}", e.reason)
+            },
     }
 }
 
@@ -244,3 +283,65 @@ impl Error {
         Error::channel_send()
     }
 }
+
+impl ErrorDetail {
+    /// Whether this error means that the remote endpoint could not be
+    /// reached, or did not produce a well-formed JSON-RPC response, as opposed
+    /// to an error returned by the node itself.
+    ///
+    /// Requests rejected by an open circuit breaker count as such.
+    pub fn is_transport(&self) -> bool {
+        matches!(
+            self,
+            Self::Io(_)
+                | Self::Http(_)
+                | Self::HttpRequestFailed(_)
+                | Self::WebSocket(_)
+                | Self::WebSocketTimeout(_)
+                | Self::Tungstenite(_)
+                | Self::ChannelSend(_)
+                | Self::Timeout(_)
+                | Self::Join(_)
+                | Self::MalformedJson(_)
+                | Self::CircuitOpen(_)
+        )
+    }
+
+    /// Whether the request that failed with this error may succeed if it is
+    /// sent again later.
+    ///
+    /// This holds for transport errors, except for HTTP responses whose
+    /// status code is neither 429 (Too Many Requests) nor a server error, and
+    /// for malformed responses, which the node would most likely send again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::HttpRequestFailed(e) => {
+                let status = status_code(&e.status);
+                status == 429 || (500..600).contains(&status)
+            },
+            Self::MalformedJson(_) => false,
+            _ => self.is_transport(),
+        }
+    }
+
+    /// How long to wait before sending the request again, as given by the
+    /// `Retry-After` header of a failed HTTP request, or by the time an open
+    /// circuit breaker remains open.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::HttpRequestFailed(e) => e.retry_after,
+            Self::CircuitOpen(e) => Some(e.retry_in),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+fn status_code(status: &HttpStatusCode) -> u16 {
+    status.as_u16()
+}
+
+#[cfg(not(feature = "reqwest"))]
+fn status_code(status: &HttpStatusCode) -> u16 {
+    status.get()
+}
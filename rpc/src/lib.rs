@@ -15,6 +15,20 @@
 //!   including general RPC functionality as well as [`event::Event`] subscription functionality.
 //!   Can be used over secure (`wss://`) and unsecure (`ws://`) connections.
 //!
+//! Both clients can send custom headers, authenticate with any
+//! [`client::AuthProvider`] and use custom root certificates or a client
+//! certificate through a [`client::TlsConfig`], all configured on their
+//! builders.
+//!
+//! Any of these clients can be combined into a [`ClientPool`], which fails
+//! over between several endpoints and spreads requests across them, and
+//! wrapped in the [`client::middleware`] to retry failed requests and to
+//! limit the load put on an endpoint.
+//!
+//! For indexers, [`client::history::EventStream`] yields the events of every
+//! block from a given height onward, switching from fetching past blocks to
+//! following new ones without gaps.
+//!
 //! ### Mock Clients
 //!
 //! Mock clients are included when either of the `http-client` or
@@ -39,7 +53,7 @@ pub mod client;
     feature = "websocket-client",
     feature = "mock-client"
 ))]
-pub use client::{Client, Subscription, SubscriptionClient};
+pub use client::{Client, ClientPool, Subscription, SubscriptionClient};
 #[cfg(feature = "http-client")]
 pub use client::{HttpClient, HttpClientUrl};
 #[cfg(feature = "mock-client")]
@@ -68,7 +82,7 @@ pub use error::Error;
 pub use id::Id;
 pub use method::Method;
 pub use order::Order;
-pub use paging::{PageNumber, Paging, PerPage};
+pub use paging::{PageNumber, PageStreamConfig, Paging, PerPage};
 pub use request::{Request, SimpleRequest};
 pub use response::Response;
 pub use response_error::{Code, ResponseError};
@@ -73,3 +73,35 @@ impl From<u8> for PerPage {
         Self(value)
     }
 }
+
+/// Determines how the paginated streams of a `Client` fetch their pages,
+/// e.g. `Client::tx_search_stream`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PageStreamConfig {
+    /// The number of items to fetch per page.
+    ///
+    /// The default is 100, the most that Tendermint nodes return. Larger
+    /// values are lowered to 100.
+    pub per_page: PerPage,
+
+    /// The maximum number of pages requested at the same time.
+    ///
+    /// The default is 4.
+    pub max_concurrency: usize,
+}
+
+impl Default for PageStreamConfig {
+    fn default() -> Self {
+        Self {
+            per_page: PerPage(100),
+            max_concurrency: 4,
+        }
+    }
+}
+
+impl PerPage {
+    #[cfg(any(feature = "http-client", feature = "websocket-client"))]
+    pub(crate) fn get(self) -> u8 {
+        self.0
+    }
+}
@@ -440,6 +440,15 @@ fn incoming_fixtures() {
                 assert_eq!(result.total_count as usize, result.blocks.len());
                 for response in result.blocks {
                     assert!(response.block.header.height.value() > 1);
+                    response.block.validate_basic().unwrap();
+                    // The kvstore app returns the default result for its transactions.
+                    if response.block.header.height.value() == 45 {
+                        response
+                            .block
+                            .header
+                            .validate_last_results(&[Default::default()])
+                            .unwrap();
+                    }
                 }
             },
             "block_search_evidence" => {
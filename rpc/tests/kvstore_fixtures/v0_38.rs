@@ -368,6 +368,9 @@ fn incoming_fixtures() {
                     tendermint::block::header::Version { block: 11, app: 1 }
                 );
                 assert!(result.block.last_commit.is_none());
+                tendermint::Block::from(result.block.clone())
+                    .validate_basic()
+                    .unwrap();
                 assert!(!result.block_id.hash.is_empty());
                 assert!(!result.block_id.part_set_header.hash.is_empty());
                 assert_eq!(result.block_id.part_set_header.total, 1);
@@ -405,6 +408,23 @@ fn incoming_fixtures() {
                     result.block.header.version,
                     tendermint::block::header::Version { block: 11, app: 1 }
                 );
+                tendermint::Block::from(result.block.clone())
+                    .validate_basic()
+                    .unwrap();
+                result.block.header.validate_last_results(&[]).unwrap();
+
+                let mut tampered = tendermint::Block::from(result.block.clone());
+                tampered.data.push(b"tx".to_vec());
+                assert!(tampered.validate_basic().is_err());
+                let mut tampered = tendermint::Block::from(result.block.clone());
+                tampered.last_commit.as_mut().unwrap().signatures.pop();
+                assert!(tampered.validate_basic().is_err());
+
+                let block = tendermint::Block::from(result.block.clone());
+                let part_set = block.make_part_set().unwrap();
+                assert_eq!(part_set.header(), result.block_id.part_set_header);
+                assert_eq!(tendermint::Block::from_part_set(&part_set).unwrap(), block);
+
                 let last_commit = result.block.last_commit.unwrap();
                 assert!(!last_commit.block_id.hash.is_empty());
                 assert!(!last_commit.block_id.part_set_header.hash.is_empty());
@@ -451,6 +471,9 @@ fn incoming_fixtures() {
 
                 // Test a few selected attributes of the results.
                 for block in result.blocks {
+                    tendermint::Block::from(block.block.clone())
+                        .validate_basic()
+                        .unwrap();
                     let evidence = block.block.evidence.iter().next().unwrap();
 
                     use tendermint::vote;
@@ -1389,6 +1412,11 @@ fn incoming_fixtures() {
                     assert_eq!(proof.proof.total, 1);
                     assert_eq!(proof.proof.index, 0);
                     assert_ne!(proof.root_hash.as_bytes(), [0; 32]);
+                    use tendermint::crypto::{default::Sha256, Sha256 as _};
+                    proof
+                        .proof
+                        .verify::<Sha256>(&proof.root_hash, &Sha256::digest(&proof.data))
+                        .unwrap();
                 }
             },
             _ => {
@@ -10,9 +10,12 @@ use serde::{Deserialize, Serialize};
 
 use super::{Code, Event};
 use crate::{
+    account,
     block::{self, BlockIdFlag},
+    chain,
+    crypto::signature::Verifier,
     prelude::*,
-    serializers, vote, Signature, Time,
+    serializers, validator, vote, Error, Signature, Time,
 };
 
 /// A validator address with voting power.
@@ -164,6 +167,79 @@ pub struct ExtendedCommitInfo {
     pub votes: Vec<ExtendedVoteInfo>,
 }
 
+impl ExtendedCommitInfo {
+    /// Verifies the vote extensions of the commit of the block at `height`,
+    /// as provided to `PrepareProposal`, against the validator set of that
+    /// height.
+    ///
+    /// Each vote must be from a validator of the set with its voting power.
+    /// The votes for the block must carry a valid extension signature, the
+    /// other votes no extension data, and validators with more than 2/3 of
+    /// the voting power must have voted for the block.
+    pub fn verify_vote_extensions<V>(
+        &self,
+        chain_id: &chain::Id,
+        height: block::Height,
+        validator_set: &validator::Set,
+    ) -> Result<(), Error>
+    where
+        V: Verifier,
+    {
+        let mut seen = Vec::with_capacity(self.votes.len());
+        let mut signed_power = 0u64;
+        for vote in &self.votes {
+            let address = account::Id::new(vote.validator.address);
+            let validator = validator_set.validator(address).ok_or_else(|| {
+                Error::invalid_vote_extension(format!(
+                    "vote from {address}, which is not a validator"
+                ))
+            })?;
+            if seen.contains(&address) {
+                return Err(Error::invalid_vote_extension(format!(
+                    "duplicate vote from validator {address}"
+                )));
+            }
+            seen.push(address);
+            if vote.validator.power != validator.power {
+                return Err(Error::invalid_vote_extension(format!(
+                    "voting power of validator {address} does not match: {} vs. {}",
+                    vote.validator.power, validator.power
+                )));
+            }
+
+            if vote.sig_info != BlockSignatureInfo::Flag(BlockIdFlag::Commit) {
+                if !vote.vote_extension.is_empty() || vote.extension_signature.is_some() {
+                    return Err(Error::invalid_vote_extension(format!(
+                        "unexpected vote extension data from validator {address}"
+                    )));
+                }
+                continue;
+            }
+            let extension_signature = vote.extension_signature.as_ref().ok_or_else(|| {
+                Error::invalid_vote_extension(format!(
+                    "vote extension signature is missing for validator {address}"
+                ))
+            })?;
+            let sign_bytes = vote::extension_sign_bytes(
+                vote.vote_extension.to_vec(),
+                height,
+                self.round,
+                chain_id,
+            );
+            validator.verify_signature::<V>(&sign_bytes, extension_signature)?;
+            signed_power += validator.power();
+        }
+
+        let total_power = validator_set.total_voting_power().value();
+        if signed_power * 3 <= total_power * 2 {
+            return Err(Error::invalid_vote_extension(format!(
+                "insufficient voting power with vote extensions: got {signed_power}, needed more than 2/3 of {total_power}"
+            )));
+        }
+        Ok(())
+    }
+}
+
 /// Used for state sync snapshots.
 ///
 /// When sent across the network, a `Snapshot` can be at most 4 MB.
@@ -110,29 +110,61 @@ tendermint_pb_modules! {
     use crate::{prelude::*, Error};
     use sha2::Sha256;
 
-    impl TryFrom<PublicKey> for Id {
-        type Error = Error;
-
-        fn try_from(value: PublicKey) -> Result<Self, Self::Error> {
-            let sum = &value
-                .sum
-                .ok_or_else(|| Error::invalid_key("empty sum".to_string()))?;
-            if let Sum::Ed25519(b) = sum {
-                let digest = Sha256::digest(b);
-                return Ok(Id(digest[..LENGTH].try_into().unwrap()))
-            }
-            #[cfg(feature = "secp256k1")]
-            if let Sum::Secp256k1(b) = sum {
-                use ripemd::Ripemd160;
-
-                let sha_digest = Sha256::digest(b);
-                let ripemd_digest = Ripemd160::digest(&sha_digest[..]);
-                let mut bytes = [0u8; LENGTH];
-                bytes.copy_from_slice(&ripemd_digest[..LENGTH]);
-                return Ok(Id(bytes))
-            }
-            Err(Error::invalid_key("not an ed25519 key".to_string()))
+    /// The ID of an Ed25519 or secp256k1 key, which every protocol version
+    /// has.
+    pub(super) fn key_id(value: &PublicKey) -> Result<Id, Error> {
+        let sum = value
+            .sum
+            .as_ref()
+            .ok_or_else(|| Error::invalid_key("empty sum".to_string()))?;
+        if let Sum::Ed25519(b) = sum {
+            let digest = Sha256::digest(b);
+            return Ok(Id(digest[..LENGTH].try_into().unwrap()))
+        }
+        #[cfg(feature = "secp256k1")]
+        if let Sum::Secp256k1(b) = sum {
+            use ripemd::Ripemd160;
+
+            let sha_digest = Sha256::digest(b);
+            let ripemd_digest = Ripemd160::digest(&sha_digest[..]);
+            let mut bytes = [0u8; LENGTH];
+            bytes.copy_from_slice(&ripemd_digest[..LENGTH]);
+            return Ok(Id(bytes))
         }
+        Err(Error::invalid_key("not an ed25519 key".to_string()))
+    }
+}
+
+impl TryFrom<tendermint_proto::v0_34::crypto::PublicKey> for Id {
+    type Error = Error;
+
+    fn try_from(value: tendermint_proto::v0_34::crypto::PublicKey) -> Result<Self, Self::Error> {
+        v0_34::key_id(&value)
+    }
+}
+
+impl TryFrom<tendermint_proto::v0_37::crypto::PublicKey> for Id {
+    type Error = Error;
+
+    fn try_from(value: tendermint_proto::v0_37::crypto::PublicKey) -> Result<Self, Self::Error> {
+        v0_37::key_id(&value)
+    }
+}
+
+/// Only this protocol version has BLS12-381 keys, whose ID is derived like
+/// the one of Ed25519 keys.
+impl TryFrom<tendermint_proto::v0_38::crypto::PublicKey> for Id {
+    type Error = Error;
+
+    fn try_from(value: tendermint_proto::v0_38::crypto::PublicKey) -> Result<Self, Self::Error> {
+        #[cfg(feature = "bls12381")]
+        if let Some(tendermint_proto::v0_38::crypto::public_key::Sum::Bls12381(b)) = &value.sum {
+            use digest::Digest;
+
+            let digest = sha2::Sha256::digest(b);
+            return Ok(Id(digest[..LENGTH].try_into().unwrap()));
+        }
+        v0_38::key_id(&value)
     }
 }
 
@@ -140,6 +172,8 @@ tendermint_pb_modules! {
 mod key_conversions {
     use super::{Id, LENGTH};
     use crate::crypto::default::Sha256;
+    #[cfg(feature = "bls12381")]
+    use crate::public_key::Bls12381;
     #[cfg(feature = "secp256k1")]
     use crate::public_key::Secp256k1;
     use crate::public_key::{Ed25519, PublicKey};
@@ -167,12 +201,23 @@ mod key_conversions {
         }
     }
 
+    // SHA256(pk)[:20]
+    #[cfg(feature = "bls12381")]
+    impl From<Bls12381> for Id {
+        fn from(pk: Bls12381) -> Id {
+            let digest = Sha256::digest(pk.as_bytes());
+            Id(digest[..LENGTH].try_into().unwrap())
+        }
+    }
+
     impl From<PublicKey> for Id {
         fn from(pub_key: PublicKey) -> Id {
             match pub_key {
                 PublicKey::Ed25519(pk) => Id::from(pk),
                 #[cfg(feature = "secp256k1")]
                 PublicKey::Secp256k1(pk) => Id::from(pk),
+                #[cfg(feature = "bls12381")]
+                PublicKey::Bls12381(pk) => Id::from(pk),
             }
         }
     }
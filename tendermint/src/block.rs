@@ -3,6 +3,7 @@
 mod block_id_flag;
 mod commit;
 pub mod commit_sig;
+mod extended_commit;
 pub mod header;
 mod height;
 mod id;
@@ -13,12 +14,16 @@ pub mod signed_header;
 mod size;
 
 use serde::{Deserialize, Serialize};
-use tendermint_proto::v0_37::types::Block as RawBlock;
+use tendermint_proto::{
+    v0_37::types::Block as RawBlock, v0_38::types::Block as RawBlockV38, Protobuf,
+};
 
+use self::parts::PartSet;
 pub use self::{
     block_id_flag::BlockIdFlag,
     commit::*,
     commit_sig::*,
+    extended_commit::{ExtendedCommit, ExtendedCommitSig},
     header::Header,
     height::*,
     id::{Id, ParseId},
@@ -26,7 +31,15 @@ pub use self::{
     round::*,
     size::Size,
 };
-use crate::{evidence, prelude::*};
+use crate::{
+    abci::types::ExecTxResult,
+    crypto::Sha256,
+    error::Error,
+    evidence,
+    merkle::{self, MerkleHash},
+    prelude::*,
+    Hash,
+};
 
 /// Blocks consist of a header, transactions, votes (the commit), and a list of
 /// evidence of malfeasance (i.e. signing conflicting votes).
@@ -86,7 +99,9 @@ tendermint_pb_modules! {
                 header: Some(value.header.into()),
                 data: Some(RawData { txs: value.data }),
                 evidence: Some(value.evidence.into()),
-                last_commit: value.last_commit.map(Into::into),
+                // Go always encodes the last commit, filling it with the
+                // default for the first block.
+                last_commit: Some(value.last_commit.unwrap_or_default().into()),
             }
         }
     }
@@ -127,4 +142,156 @@ impl Block {
     pub fn last_commit(&self) -> &Option<Commit> {
         &self.last_commit
     }
+
+    /// Splits the protobuf encoding of this block into parts, whose header is
+    /// the `part_set_header` of the ID of the block.
+    #[cfg(feature = "rust-crypto")]
+    pub fn make_part_set(&self) -> Result<PartSet, Error> {
+        self.make_part_set_with::<crate::crypto::default::Sha256>()
+    }
+
+    /// Splits the protobuf encoding of this block into parts, hashed with a
+    /// Merkle hasher provided by a crypto provider.
+    pub fn make_part_set_with<H>(&self) -> Result<PartSet, Error>
+    where
+        H: MerkleHash + Default,
+    {
+        let bytes = Protobuf::<RawBlockV38>::encode_vec(self.clone());
+        PartSet::from_data_with::<H>(&bytes)
+    }
+
+    /// Decodes a block from the data of a complete part set.
+    pub fn from_part_set(part_set: &PartSet) -> Result<Self, Error> {
+        let data = part_set.data()?;
+        <Self as Protobuf<RawBlockV38>>::decode_vec(&data)
+            .map_err(|e| Error::invalid_part(format!("cannot decode block: {e}")))
+    }
+
+    /// Computes the hash of the transactions of this block, which is the
+    /// `data_hash` of its header.
+    pub fn data_hash_with<H>(&self) -> Hash
+    where
+        H: MerkleHash + Sha256 + Default,
+    {
+        data_hash_with::<H>(&self.data)
+    }
+
+    /// Computes the hash of the evidence of this block, which is the
+    /// `evidence_hash` of its header.
+    pub fn evidence_hash_with<H>(&self) -> Hash
+    where
+        H: MerkleHash + Sha256 + Default,
+    {
+        self.evidence.hash_with::<H>()
+    }
+
+    /// Computes the hash of the last commit of this block, which is the
+    /// `last_commit_hash` of its header.
+    ///
+    /// The initial block has no last commit, and is hashed as an empty commit.
+    pub fn last_commit_hash_with<H>(&self) -> Hash
+    where
+        H: MerkleHash + Sha256 + Default,
+    {
+        self.last_commit.as_ref().map_or_else(
+            || Commit::default().hash_with::<H>(),
+            Commit::hash_with::<H>,
+        )
+    }
+
+    /// Checks that the transactions, evidence and last commit of this block
+    /// match the hashes its header commits to.
+    #[cfg(feature = "rust-crypto")]
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        self.validate_basic_with::<crate::crypto::default::Sha256>()
+    }
+
+    /// Checks that the transactions, evidence and last commit of this block
+    /// match the hashes its header commits to, computing the hashes with a
+    /// Merkle hasher provided by a crypto provider.
+    ///
+    /// Together with a verified header, this proves that the contents of a
+    /// block, such as one returned by an RPC node, are the committed ones.
+    pub fn validate_basic_with<H>(&self) -> Result<(), Error>
+    where
+        H: MerkleHash + Sha256 + Default,
+    {
+        if let Some(last_commit) = &self.last_commit {
+            if last_commit.height.increment() != self.header.height {
+                return Err(Error::invalid_block(format!(
+                    "last commit is for height {}, expected {}",
+                    last_commit.height,
+                    self.header.height.value().saturating_sub(1),
+                )));
+            }
+        }
+
+        check_hash(
+            "last_commit_hash",
+            self.header.last_commit_hash,
+            self.last_commit_hash_with::<H>(),
+        )?;
+        check_hash(
+            "data_hash",
+            self.header.data_hash,
+            self.data_hash_with::<H>(),
+        )?;
+        check_hash(
+            "evidence_hash",
+            self.header.evidence_hash,
+            self.evidence_hash_with::<H>(),
+        )
+    }
+}
+
+/// Computes the hash of a list of transactions, as committed to by the
+/// `data_hash` of block headers.
+///
+/// The leaves of the tree are the SHA256 hashes of the transactions.
+pub fn data_hash_with<H>(txs: &[impl AsRef<[u8]>]) -> Hash
+where
+    H: MerkleHash + Sha256 + Default,
+{
+    let tx_hashes = txs.iter().map(H::digest).collect::<Vec<_>>();
+    Hash::Sha256(merkle::simple_hash_from_byte_vectors::<H>(&tx_hashes))
+}
+
+/// Computes the hash of the results of the transactions of a block, as
+/// committed to by the `last_results_hash` of the header of the next block.
+///
+/// Only the deterministic fields of the results are hashed: the code, data,
+/// and gas wanted and used.
+pub fn results_hash_with<H>(results: &[ExecTxResult]) -> Hash
+where
+    H: MerkleHash + Sha256 + Default,
+{
+    use prost::Message as _;
+    use tendermint_proto::v0_38::abci::ExecTxResult as RawExecTxResult;
+
+    let results_bytes = results
+        .iter()
+        .map(|result| {
+            RawExecTxResult {
+                code: result.code.value(),
+                data: result.data.clone(),
+                gas_wanted: result.gas_wanted,
+                gas_used: result.gas_used,
+                ..Default::default()
+            }
+            .encode_to_vec()
+        })
+        .collect::<Vec<_>>();
+    Hash::Sha256(merkle::simple_hash_from_byte_vectors::<H>(&results_bytes))
+}
+
+fn check_hash(field: &str, expected: Option<Hash>, actual: Hash) -> Result<(), Error> {
+    if expected != Some(actual) {
+        return Err(Error::invalid_block(format!(
+            "{} mismatch: header has {}, computed {}",
+            field,
+            expected.unwrap_or_default(),
+            actual,
+        )));
+    }
+    Ok(())
 }
@@ -1,11 +1,15 @@
 //! Commits to a Tendermint blockchain
 
+use prost::Message as _;
 use serde::{Deserialize, Serialize};
-use tendermint_proto::v0_37::types::Commit as RawCommit;
+use tendermint_proto::v0_37::types::{Commit as RawCommit, CommitSig as RawCommitSig};
 
 use crate::{
     block::{commit_sig::CommitSig, Height, Id, Round},
+    crypto::Sha256,
+    merkle::{self, MerkleHash},
     prelude::*,
+    Hash,
 };
 
 /// Commit contains the justification (ie. a set of signatures) that a block was committed by a set
@@ -38,6 +42,8 @@ tendermint_pb_modules! {
     };
     use pb::types::Commit as RawCommit;
 
+    impl Protobuf<RawCommit> for Commit {}
+
     impl TryFrom<RawCommit> for Commit {
         type Error = Error;
 
@@ -71,6 +77,32 @@ tendermint_pb_modules! {
     }
 }
 
+impl Commit {
+    /// Computes the hash of this commit, as committed to by the
+    /// `last_commit_hash` of the header of the next block.
+    #[cfg(feature = "rust-crypto")]
+    pub fn hash(&self) -> Hash {
+        self.hash_with::<crate::crypto::default::Sha256>()
+    }
+
+    /// Hash this commit with a Merkle hasher provided by a crypto provider.
+    ///
+    /// The leaves of the tree are the protobuf encodings of the signatures.
+    pub fn hash_with<H>(&self) -> Hash
+    where
+        H: MerkleHash + Sha256 + Default,
+    {
+        let signatures_bytes = self
+            .signatures
+            .iter()
+            .map(|sig| RawCommitSig::from(sig.clone()).encode_to_vec())
+            .collect::<Vec<_>>();
+        Hash::Sha256(merkle::simple_hash_from_byte_vectors::<H>(
+            &signatures_bytes,
+        ))
+    }
+}
+
 impl Default for Commit {
     fn default() -> Self {
         Commit {
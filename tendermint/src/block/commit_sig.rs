@@ -206,3 +206,64 @@ tendermint_pb_modules! {
         assert_eq!(commit_sig, CommitSig::BlockIdFlagAbsent);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use tendermint_pbt_gen as pbt;
+
+    use super::CommitSig;
+    use crate::{account, signature::SIGNATURE_LENGTH, Signature, Time};
+
+    fn arb_commit_sig() -> impl Strategy<Value = CommitSig> {
+        let vote = (
+            any::<bool>(),
+            any::<[u8; account::LENGTH]>(),
+            pbt::time::arb_protobuf_safe_datetime(),
+            prop::collection::vec(any::<u8>(), SIGNATURE_LENGTH),
+        )
+            .prop_map(|(for_block, address, datetime, signature)| {
+                let validator_address = account::Id::new(address);
+                let timestamp = Time::try_from(datetime).unwrap();
+                let signature = Signature::new(signature).unwrap();
+                if for_block {
+                    CommitSig::BlockIdFlagCommit {
+                        validator_address,
+                        timestamp,
+                        signature,
+                    }
+                } else {
+                    CommitSig::BlockIdFlagNil {
+                        validator_address,
+                        timestamp,
+                        signature,
+                    }
+                }
+            });
+        prop_oneof![Just(CommitSig::BlockIdFlagAbsent), vote]
+    }
+
+    tendermint_pb_modules! {
+        use prost::Message;
+        use proptest::prelude::*;
+
+        use super::arb_commit_sig;
+        use crate::{block::CommitSig, prelude::*};
+        use pb::types::CommitSig as RawCommitSig;
+
+        proptest! {
+            #[test]
+            fn encodings_round_trip(commit_sig in arb_commit_sig()) {
+                let raw = RawCommitSig::from(commit_sig.clone());
+
+                let json = serde_json::to_string(&raw).unwrap();
+                let decoded = serde_json::from_str::<RawCommitSig>(&json).unwrap();
+                prop_assert_eq!(CommitSig::try_from(decoded).unwrap(), commit_sig.clone());
+
+                let bytes = raw.encode_to_vec();
+                let decoded = RawCommitSig::decode(bytes.as_slice()).unwrap();
+                prop_assert_eq!(CommitSig::try_from(decoded).unwrap(), commit_sig);
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Commits with the vote extensions of the precommits, as stored by CometBFT
+//! 0.38 and later to provide the extensions to the proposer of the next block.
+
+use crate::{
+    block::{commit_sig::CommitSig, Commit, Height, Id, Round},
+    chain,
+    crypto::signature::Verifier,
+    error::Error,
+    prelude::*,
+    validator, vote, Signature,
+};
+
+/// A [`CommitSig`] with the vote extension of the precommit.
+///
+/// Only precommits for the committed block carry vote extensions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtendedCommitSig {
+    /// Signature of the precommit.
+    pub commit_sig: CommitSig,
+    /// Vote extension provided by the application.
+    pub extension: Vec<u8>,
+    /// Signature of the vote extension.
+    pub extension_signature: Option<Signature>,
+}
+
+impl ExtendedCommitSig {
+    /// Checks the presence of the vote extension data, depending on whether
+    /// vote extensions are enabled at the height of the commit.
+    ///
+    /// When they are, precommits for the committed block must carry an
+    /// extension signature, and other signatures no extension data at all.
+    pub fn ensure_extension(&self, extensions_enabled: bool) -> Result<(), Error> {
+        let has_extension_data = !self.extension.is_empty() || self.extension_signature.is_some();
+        if extensions_enabled && self.commit_sig.is_commit() {
+            if self.extension_signature.is_none() {
+                return Err(Error::invalid_vote_extension(format!(
+                    "vote extension signature is missing for validator {}",
+                    self.validator_address_display()
+                )));
+            }
+        } else if has_extension_data {
+            return Err(Error::invalid_vote_extension(format!(
+                "unexpected vote extension data for validator {}",
+                self.validator_address_display()
+            )));
+        }
+        Ok(())
+    }
+
+    fn validator_address_display(&self) -> String {
+        self.commit_sig
+            .validator_address()
+            .map_or_else(|| "(absent)".to_string(), |address| address.to_string())
+    }
+}
+
+impl From<CommitSig> for ExtendedCommitSig {
+    fn from(commit_sig: CommitSig) -> Self {
+        Self {
+            commit_sig,
+            extension: Vec::new(),
+            extension_signature: None,
+        }
+    }
+}
+
+impl From<ExtendedCommitSig> for CommitSig {
+    fn from(extended: ExtendedCommitSig) -> Self {
+        extended.commit_sig
+    }
+}
+
+/// A [`Commit`] with the vote extensions of the precommits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtendedCommit {
+    /// Block height
+    pub height: Height,
+
+    /// Round
+    pub round: Round,
+
+    /// Block ID
+    pub block_id: Id,
+
+    /// Signatures, with vote extensions
+    pub extended_signatures: Vec<ExtendedCommitSig>,
+}
+
+impl ExtendedCommit {
+    /// Strips the vote extensions off this commit.
+    pub fn to_commit(&self) -> Commit {
+        Commit {
+            height: self.height,
+            round: self.round,
+            block_id: self.block_id,
+            signatures: self
+                .extended_signatures
+                .iter()
+                .map(|sig| sig.commit_sig.clone())
+                .collect(),
+        }
+    }
+
+    /// Checks the presence of the vote extension data of all signatures with
+    /// [`ExtendedCommitSig::ensure_extension`].
+    pub fn ensure_extensions(&self, extensions_enabled: bool) -> Result<(), Error> {
+        self.extended_signatures
+            .iter()
+            .try_for_each(|sig| sig.ensure_extension(extensions_enabled))
+    }
+
+    /// Verifies the extension signatures of the precommits for the committed
+    /// block against the validator set which signed the commit.
+    ///
+    /// The signatures of the precommits themselves are not verified.
+    pub fn verify_extensions<V>(
+        &self,
+        chain_id: &chain::Id,
+        validator_set: &validator::Set,
+    ) -> Result<(), Error>
+    where
+        V: Verifier,
+    {
+        if validator_set.validators().len() != self.extended_signatures.len() {
+            return Err(Error::invalid_vote_extension(format!(
+                "wrong number of signatures: {} validators, {} signatures",
+                validator_set.validators().len(),
+                self.extended_signatures.len()
+            )));
+        }
+        self.ensure_extensions(true)?;
+
+        for (sig, validator) in self
+            .extended_signatures
+            .iter()
+            .zip(validator_set.validators())
+        {
+            let extension_signature = match (&sig.commit_sig, &sig.extension_signature) {
+                (CommitSig::BlockIdFlagCommit { .. }, Some(signature)) => signature,
+                _ => continue,
+            };
+            if sig.commit_sig.validator_address() != Some(validator.address) {
+                return Err(Error::invalid_vote_extension(format!(
+                    "signature of validator {} in the place of validator {}",
+                    sig.validator_address_display(),
+                    validator.address
+                )));
+            }
+            let sign_bytes = vote::extension_sign_bytes(
+                sig.extension.clone(),
+                self.height,
+                self.round,
+                chain_id,
+            );
+            validator.verify_signature::<V>(&sign_bytes, extension_signature)?;
+        }
+        Ok(())
+    }
+}
+
+mod v0_38 {
+    use tendermint_proto::v0_38::types::{
+        CommitSig as RawCommitSig, ExtendedCommit as RawExtendedCommit,
+        ExtendedCommitSig as RawExtendedCommitSig,
+    };
+    use tendermint_proto::Protobuf;
+
+    use super::{ExtendedCommit, ExtendedCommitSig};
+    use crate::{block::commit_sig::CommitSig, error::Error, prelude::*, Signature};
+
+    impl Protobuf<RawExtendedCommitSig> for ExtendedCommitSig {}
+
+    impl TryFrom<RawExtendedCommitSig> for ExtendedCommitSig {
+        type Error = Error;
+
+        fn try_from(value: RawExtendedCommitSig) -> Result<Self, Self::Error> {
+            let commit_sig = CommitSig::try_from(RawCommitSig {
+                block_id_flag: value.block_id_flag,
+                validator_address: value.validator_address,
+                timestamp: value.timestamp,
+                signature: value.signature,
+            })?;
+            let extended = Self {
+                commit_sig,
+                extension: value.extension,
+                extension_signature: Signature::new(value.extension_signature)?,
+            };
+            if !extended.commit_sig.is_commit()
+                && (!extended.extension.is_empty() || extended.extension_signature.is_some())
+            {
+                return Err(Error::invalid_vote_extension(
+                    "vote extension data on a signature not for the committed block".into(),
+                ));
+            }
+            Ok(extended)
+        }
+    }
+
+    impl From<ExtendedCommitSig> for RawExtendedCommitSig {
+        fn from(value: ExtendedCommitSig) -> Self {
+            let raw = RawCommitSig::from(value.commit_sig);
+            Self {
+                block_id_flag: raw.block_id_flag,
+                validator_address: raw.validator_address,
+                timestamp: raw.timestamp,
+                signature: raw.signature,
+                extension: value.extension,
+                extension_signature: value
+                    .extension_signature
+                    .map(Signature::into_bytes)
+                    .unwrap_or_default(),
+            }
+        }
+    }
+
+    impl Protobuf<RawExtendedCommit> for ExtendedCommit {}
+
+    impl TryFrom<RawExtendedCommit> for ExtendedCommit {
+        type Error = Error;
+
+        fn try_from(value: RawExtendedCommit) -> Result<Self, Self::Error> {
+            Ok(Self {
+                height: value.height.try_into()?,
+                round: value.round.try_into()?,
+                block_id: value
+                    .block_id
+                    .ok_or_else(|| Error::invalid_block("missing block id".to_string()))?
+                    .try_into()?, // gogoproto.nullable = false
+                extended_signatures: value
+                    .extended_signatures
+                    .into_iter()
+                    .map(TryFrom::try_from)
+                    .collect::<Result<_, _>>()?,
+            })
+        }
+    }
+
+    impl From<ExtendedCommit> for RawExtendedCommit {
+        fn from(value: ExtendedCommit) -> Self {
+            Self {
+                height: value.height.into(),
+                round: value.round.into(),
+                block_id: Some(value.block_id.into()),
+                extended_signatures: value
+                    .extended_signatures
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            }
+        }
+    }
+}
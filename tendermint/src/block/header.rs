@@ -8,8 +8,10 @@ use tendermint_proto::v0_37::{
 use tendermint_proto::Protobuf;
 
 use crate::{
+    abci::types::ExecTxResult,
     account, block, chain,
     crypto::Sha256,
+    error::Error,
     merkle::{self, MerkleHash},
     prelude::*,
     AppHash, Hash, Time,
@@ -102,6 +104,28 @@ impl Header {
 
         Hash::Sha256(merkle::simple_hash_from_byte_vectors::<H>(&fields_bytes))
     }
+
+    /// Checks that the results of the transactions of the previous block, as
+    /// returned by the `/block_results` RPC endpoint, match the
+    /// `last_results_hash` of this header.
+    #[cfg(feature = "rust-crypto")]
+    pub fn validate_last_results(&self, results: &[ExecTxResult]) -> Result<(), Error> {
+        self.validate_last_results_with::<crate::crypto::default::Sha256>(results)
+    }
+
+    /// Checks that the results of the transactions of the previous block match
+    /// the `last_results_hash` of this header, computing the hash with a Merkle
+    /// hasher provided by a crypto provider.
+    pub fn validate_last_results_with<H>(&self, results: &[ExecTxResult]) -> Result<(), Error>
+    where
+        H: MerkleHash + Sha256 + Default,
+    {
+        block::check_hash(
+            "last_results_hash",
+            self.last_results_hash,
+            block::results_hash_with::<H>(results),
+        )
+    }
 }
 
 /// `Version` contains the protocol version for the blockchain and the
@@ -215,7 +239,9 @@ tendermint_pb_modules! {
                 chain_id: value.chain_id.into(),
                 height: value.height.into(),
                 time: Some(value.time.into()),
-                last_block_id: value.last_block_id.map(Into::into),
+                // Go always encodes the last block id, filling it with the
+                // default for the first block.
+                last_block_id: Some(value.last_block_id.unwrap_or_default().into()),
                 last_commit_hash: value.last_commit_hash.unwrap_or_default().into(),
                 data_hash: value.data_hash.unwrap_or_default().into(),
                 validators_hash: value.validators_hash.into(),
@@ -168,7 +168,47 @@ pub trait ParseId {
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
+    use crate::hash::SHA256_HASH_SIZE;
+
+    fn arb_id() -> impl Strategy<Value = Id> {
+        let id = (
+            any::<[u8; SHA256_HASH_SIZE]>(),
+            1..=u32::MAX,
+            any::<[u8; SHA256_HASH_SIZE]>(),
+        )
+            .prop_map(|(hash, total, parts_hash)| Id {
+                hash: Hash::Sha256(hash),
+                part_set_header: PartSetHeader::new(total, Hash::Sha256(parts_hash)).unwrap(),
+            });
+        prop_oneof![Just(Id::default()), id]
+    }
+
+    proptest! {
+        #[test]
+        fn json_round_trips(id in arb_id()) {
+            let json = serde_json::to_string(&id).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Id>(&json).unwrap(), id);
+        }
+    }
+
+    tendermint_pb_modules! {
+        use proptest::prelude::*;
+
+        use super::arb_id;
+        use crate::{block::Id, prelude::*};
+        use pb::types::BlockId as RawBlockId;
+
+        proptest! {
+            #[test]
+            fn protobuf_round_trips(id in arb_id()) {
+                let bytes = Protobuf::<RawBlockId>::encode_vec(id);
+                prop_assert_eq!(<Id as Protobuf<RawBlockId>>::decode_vec(&bytes).unwrap(), id);
+            }
+        }
+    }
 
     const EXAMPLE_SHA256_ID: &str =
         "26C0A41F3243C6BCD7AD2DFF8A8D83A71D29D307B5326C227F734A1A512FE47D";
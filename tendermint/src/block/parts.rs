@@ -3,7 +3,21 @@
 use serde::{Deserialize, Serialize};
 use tendermint_proto::v0_37::types::PartSetHeader as RawPartSetHeader;
 
-use crate::{error::Error, prelude::*, Hash};
+use crate::{
+    error::Error,
+    merkle::{self, MerkleHash, Proof},
+    prelude::*,
+    Hash,
+};
+
+/// Size of the parts blocks are split into, in bytes.
+pub const BLOCK_PART_SIZE_BYTES: u32 = 65536;
+
+/// Maximum number of parts of a block of at most [`Size::MAX_BYTES`] bytes.
+///
+/// [`Size::MAX_BYTES`]: super::Size::MAX_BYTES
+pub const MAX_BLOCK_PARTS_COUNT: u32 =
+    (super::Size::MAX_BYTES / BLOCK_PART_SIZE_BYTES as u64) as u32 + 1;
 
 /// Block parts header
 #[derive(
@@ -21,7 +35,8 @@ pub struct Header {
 
 tendermint_pb_modules! {
     use pb::types::{
-        CanonicalPartSetHeader as RawCanonicalPartSetHeader, PartSetHeader as RawPartSetHeader,
+        CanonicalPartSetHeader as RawCanonicalPartSetHeader, Part as RawPart,
+        PartSetHeader as RawPartSetHeader,
     };
     use crate::{
         error::Error,
@@ -29,7 +44,7 @@ tendermint_pb_modules! {
         prelude::*,
         Hash,
     };
-    use super::Header;
+    use super::{Header, Part};
 
     impl Protobuf<RawPartSetHeader> for Header {}
 
@@ -78,6 +93,33 @@ tendermint_pb_modules! {
             }
         }
     }
+
+    impl Protobuf<RawPart> for Part {}
+
+    impl TryFrom<RawPart> for Part {
+        type Error = Error;
+
+        fn try_from(value: RawPart) -> Result<Self, Self::Error> {
+            Ok(Self {
+                index: value.index,
+                bytes: value.bytes,
+                proof: value
+                    .proof
+                    .ok_or_else(|| Error::invalid_part("missing proof".to_string()))?
+                    .try_into()?,
+            })
+        }
+    }
+
+    impl From<Part> for RawPart {
+        fn from(value: Part) -> Self {
+            RawPart {
+                index: value.index,
+                bytes: value.bytes,
+                proof: Some(value.proof.into()),
+            }
+        }
+    }
 }
 
 impl Header {
@@ -96,3 +138,284 @@ impl Header {
         Ok(Header { total, hash })
     }
 }
+
+/// A part of a block, with the proof of its inclusion in the part set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Part {
+    /// Index of the part in the part set
+    pub index: u32,
+
+    /// Bytes of the part
+    pub bytes: Vec<u8>,
+
+    /// Merkle proof of the part in the part set
+    pub proof: Proof,
+}
+
+impl Part {
+    /// Performs basic validation of the part, without verifying its proof.
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        if self.bytes.len() > BLOCK_PART_SIZE_BYTES as usize {
+            return Err(Error::invalid_part(format!(
+                "too big: {} bytes, max: {}",
+                self.bytes.len(),
+                BLOCK_PART_SIZE_BYTES
+            )));
+        }
+        if u64::from(self.index) != self.proof.index {
+            return Err(Error::invalid_part(format!(
+                "index {} does not match the proof index {}",
+                self.index, self.proof.index
+            )));
+        }
+        self.proof.validate_basic()
+    }
+}
+
+/// Set of the parts of a block.
+///
+/// A part set is either built from the encoding of a block, to be split
+/// into parts, or from the [`Header`] of a block whose parts are received
+/// one by one, each of them being verified against the header before it is
+/// added to the set.
+#[derive(Clone, Debug)]
+pub struct PartSet {
+    header: Header,
+    parts: Vec<Option<Part>>,
+    count: u32,
+    byte_size: usize,
+}
+
+impl PartSet {
+    /// Splits `data` into parts of [`BLOCK_PART_SIZE_BYTES`] bytes.
+    #[cfg(feature = "rust-crypto")]
+    pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+        Self::from_data_with::<crate::crypto::default::Sha256>(data)
+    }
+
+    /// Splits `data` into parts of [`BLOCK_PART_SIZE_BYTES`] bytes, hashed
+    /// with a Merkle hasher provided by a crypto provider.
+    ///
+    /// The parts have the size expected by [`PartSet::add_part_with`], so
+    /// that they can be added to a part set built from the header of this one.
+    pub fn from_data_with<H>(data: &[u8]) -> Result<Self, Error>
+    where
+        H: MerkleHash + Default,
+    {
+        let chunks = data
+            .chunks(BLOCK_PART_SIZE_BYTES as usize)
+            .collect::<Vec<_>>();
+        let total = u32::try_from(chunks.len()).map_err(Error::integer_overflow)?;
+        let (root, proofs) = merkle::proofs_from_byte_vectors::<H>(&chunks);
+        let parts = chunks
+            .into_iter()
+            .zip(proofs)
+            .zip(0..)
+            .map(|((bytes, proof), index)| {
+                Some(Part {
+                    index,
+                    bytes: bytes.to_vec(),
+                    proof,
+                })
+            })
+            .collect();
+        let hash = if total == 0 {
+            Hash::None
+        } else {
+            Hash::Sha256(root)
+        };
+
+        Ok(Self {
+            header: Header { total, hash },
+            parts,
+            count: total,
+            byte_size: data.len(),
+        })
+    }
+
+    /// An empty part set, to be filled with the parts of the given header.
+    ///
+    /// Fails if the header has more than [`MAX_BLOCK_PARTS_COUNT`] parts.
+    pub fn from_header(header: Header) -> Result<Self, Error> {
+        if header.total > MAX_BLOCK_PARTS_COUNT {
+            return Err(Error::invalid_part_set_header(format!(
+                "{} parts exceed the maximum of {}",
+                header.total, MAX_BLOCK_PARTS_COUNT
+            )));
+        }
+        Ok(Self {
+            header,
+            parts: vec![None; header.total as usize],
+            count: 0,
+            byte_size: 0,
+        })
+    }
+
+    /// The header of this part set.
+    pub fn header(&self) -> Header {
+        self.header
+    }
+
+    /// The total number of parts of this part set.
+    pub fn total(&self) -> u32 {
+        self.header.total
+    }
+
+    /// The number of parts of this part set that were added so far.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// The total size of the parts of this part set added so far, in bytes.
+    pub fn byte_size(&self) -> usize {
+        self.byte_size
+    }
+
+    /// Returns `true` if all the parts of this part set were added.
+    pub fn is_complete(&self) -> bool {
+        self.count == self.header.total
+    }
+
+    /// The part at the given index, if it was added.
+    pub fn part(&self, index: u32) -> Option<&Part> {
+        self.parts.get(index as usize)?.as_ref()
+    }
+
+    /// The indices of the parts which were not added yet.
+    pub fn missing(&self) -> impl Iterator<Item = u32> + '_ {
+        self.parts
+            .iter()
+            .zip(0..)
+            .filter_map(|(part, index)| part.is_none().then_some(index))
+    }
+
+    /// Adds a part received from a peer, after verifying its proof against
+    /// the header of this part set.
+    ///
+    /// Returns `Ok(false)` if the part was already added.
+    #[cfg(feature = "rust-crypto")]
+    pub fn add_part(&mut self, part: Part) -> Result<bool, Error> {
+        self.add_part_with::<crate::crypto::default::Sha256>(part)
+    }
+
+    /// Adds a part received from a peer, after verifying its proof against
+    /// the header of this part set with a Merkle hasher provided by a crypto
+    /// provider.
+    ///
+    /// Returns `Ok(false)` if the part was already added.
+    pub fn add_part_with<H>(&mut self, part: Part) -> Result<bool, Error>
+    where
+        H: MerkleHash + Default,
+    {
+        let total = self.header.total;
+        if part.index >= total {
+            return Err(Error::invalid_part(format!(
+                "index {} is out of bounds of {} parts",
+                part.index, total
+            )));
+        }
+        if self.parts[part.index as usize].is_some() {
+            return Ok(false);
+        }
+        part.validate_basic()?;
+        if part.proof.total != u64::from(total) {
+            return Err(Error::invalid_part(format!(
+                "proof is for {} parts, expected {}",
+                part.proof.total, total
+            )));
+        }
+        // All the parts but the last one are full.
+        if part.index < total - 1 && part.bytes.len() != BLOCK_PART_SIZE_BYTES as usize {
+            return Err(Error::invalid_part(format!(
+                "expected {} bytes, got {}",
+                BLOCK_PART_SIZE_BYTES,
+                part.bytes.len()
+            )));
+        }
+        part.proof.verify::<H>(&self.header.hash, &part.bytes)?;
+
+        self.byte_size += part.bytes.len();
+        self.count += 1;
+        let index = part.index as usize;
+        self.parts[index] = Some(part);
+        Ok(true)
+    }
+
+    /// The data of this part set, once it is complete.
+    pub fn data(&self) -> Result<Vec<u8>, Error> {
+        if !self.is_complete() {
+            return Err(Error::incomplete_part_set(self.count, self.header.total));
+        }
+        let mut data = Vec::with_capacity(self.byte_size);
+        for part in self.parts.iter().flatten() {
+            data.extend_from_slice(&part.bytes);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(all(test, feature = "rust-crypto"))]
+mod tests {
+    use super::*;
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn reassembles_parts() {
+        let data = data(3 * BLOCK_PART_SIZE_BYTES as usize + 100);
+        let source = PartSet::from_data(&data).unwrap();
+        assert_eq!(source.total(), 4);
+        assert!(source.is_complete());
+        assert_eq!(source.data().unwrap(), data);
+
+        let mut part_set = PartSet::from_header(source.header()).unwrap();
+        assert!(!part_set.is_complete());
+        assert!(part_set.data().is_err());
+        for index in [3, 1, 0, 2] {
+            let part = source.part(index).unwrap().clone();
+            assert!(part_set.add_part(part.clone()).unwrap());
+            assert!(!part_set.add_part(part).unwrap());
+        }
+        assert!(part_set.is_complete());
+        assert_eq!(part_set.missing().count(), 0);
+        assert_eq!(part_set.byte_size(), data.len());
+        assert_eq!(part_set.data().unwrap(), data);
+    }
+
+    #[test]
+    fn rejects_invalid_parts() {
+        let data = data(2 * BLOCK_PART_SIZE_BYTES as usize);
+        let source = PartSet::from_data(&data).unwrap();
+        let mut part_set = PartSet::from_header(source.header()).unwrap();
+
+        let mut tampered = source.part(1).unwrap().clone();
+        tampered.bytes[0] ^= 1;
+        assert!(part_set.add_part(tampered).is_err());
+
+        let mut wrong_index = source.part(1).unwrap().clone();
+        wrong_index.index = 0;
+        assert!(part_set.add_part(wrong_index).is_err());
+
+        let mut out_of_bounds = source.part(1).unwrap().clone();
+        out_of_bounds.index = 2;
+        assert!(part_set.add_part(out_of_bounds).is_err());
+
+        // Parts of another set
+        let other = PartSet::from_data(&data[1..]).unwrap();
+        assert!(part_set.add_part(other.part(0).unwrap().clone()).is_err());
+
+        assert_eq!(part_set.count(), 0);
+        assert_eq!(part_set.missing().collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn rejects_too_many_parts() {
+        assert_eq!(MAX_BLOCK_PARTS_COUNT, 1601);
+        let header = Header::new(MAX_BLOCK_PARTS_COUNT, Hash::Sha256([0; 32])).unwrap();
+        assert!(PartSet::from_header(header).is_ok());
+        let header = Header::new(u32::MAX, Hash::Sha256([0; 32])).unwrap();
+        assert!(PartSet::from_header(header).is_err());
+    }
+}
@@ -21,6 +21,9 @@ pub struct Size {
 }
 
 impl Size {
+    /// The maximum size of a block in bytes.
+    pub const MAX_BYTES: u64 = 104_857_600;
+
     /// The default value for the `time_iota_ms` parameter.
     pub const fn default_time_iota_ms() -> i64 {
         1000
@@ -33,12 +33,38 @@ pub struct Channel {
     pub recently_sent: u64,
 }
 
-/// Channel collections
+/// Channel collections, as the byte IDs of the channels
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, Default)]
-pub struct Channels(String);
+pub struct Channels(#[serde(with = "serializers::bytes::hexstring")] Vec<u8>);
+
+impl Channels {
+    /// Construct `Channels` from the IDs of the channels
+    pub fn new(ids: impl Into<Vec<u8>>) -> Self {
+        Channels(ids.into())
+    }
+
+    /// IDs of the channels
+    pub fn ids(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Does this collection include the channel with the given ID?
+    pub fn contains(&self, id: Id) -> bool {
+        self.0.iter().any(|&ch| u64::from(ch) == id.value())
+    }
+}
+
+impl From<Vec<u8>> for Channels {
+    fn from(ids: Vec<u8>) -> Self {
+        Channels(ids)
+    }
+}
 
 impl Display for Channels {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        for id in &self.0 {
+            write!(f, "{id:02X}")?;
+        }
+        Ok(())
     }
 }
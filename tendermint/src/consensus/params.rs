@@ -3,7 +3,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    block, evidence, prelude::*, public_key, serializers::allow_empty_object::allow_empty_object,
+    block, error::Error, evidence, prelude::*, public_key,
+    serializers::allow_empty_object::allow_empty_object,
 };
 
 /// All consensus-relevant parameters that can be adjusted by the ABCI app.
@@ -29,6 +30,96 @@ pub struct Params {
     pub abci: AbciParams,
 }
 
+impl Params {
+    /// Checks that the parameters are within the bounds accepted by CometBFT.
+    ///
+    /// As in CometBFT, the public key types must be known, i.e. supported by
+    /// the enabled features. A negative `abci.vote_extensions_enable_height`
+    /// or an unknown key type name cannot be represented, and is rejected
+    /// when decoding the parameters.
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        if self.block.max_bytes == 0 {
+            return Err(Error::invalid_consensus_params(
+                "block.max_bytes must be greater than 0".to_string(),
+            ));
+        }
+        if self.block.max_bytes > block::Size::MAX_BYTES {
+            return Err(Error::invalid_consensus_params(format!(
+                "block.max_bytes is too big: {} > {}",
+                self.block.max_bytes,
+                block::Size::MAX_BYTES
+            )));
+        }
+        if self.block.max_gas < -1 {
+            return Err(Error::invalid_consensus_params(format!(
+                "block.max_gas must be greater or equal to -1, got {}",
+                self.block.max_gas
+            )));
+        }
+        if self.evidence.max_age_num_blocks == 0 {
+            return Err(Error::invalid_consensus_params(
+                "evidence.max_age_num_blocks must be greater than 0".to_string(),
+            ));
+        }
+        if self.evidence.max_age_duration.0.is_zero() {
+            return Err(Error::invalid_consensus_params(
+                "evidence.max_age_duration must be greater than 0".to_string(),
+            ));
+        }
+        if self.evidence.max_bytes < 0 {
+            return Err(Error::invalid_consensus_params(format!(
+                "evidence.max_bytes must be non negative, got {}",
+                self.evidence.max_bytes
+            )));
+        }
+        // The bound is valid as an `i64`, as checked above.
+        if self.evidence.max_bytes as u64 > self.block.max_bytes {
+            return Err(Error::invalid_consensus_params(format!(
+                "evidence.max_bytes is greater than block.max_bytes: {} > {}",
+                self.evidence.max_bytes, self.block.max_bytes
+            )));
+        }
+        if self.validator.pub_key_types.is_empty() {
+            return Err(Error::invalid_consensus_params(
+                "validator.pub_key_types must not be empty".to_string(),
+            ));
+        }
+        for (i, key_type) in self.validator.pub_key_types.iter().enumerate() {
+            // Without the "secp256k1" feature, validator keys of this type
+            // cannot be decoded.
+            if cfg!(not(feature = "secp256k1")) && *key_type == public_key::Algorithm::Secp256k1 {
+                return Err(Error::invalid_consensus_params(format!(
+                    "validator.pub_key_types[{i}], {key_type}, is an unknown public key type"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The default consensus parameters of CometBFT.
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            block: block::Size {
+                max_bytes: 22_020_096,
+                max_gas: -1,
+                time_iota_ms: block::Size::default_time_iota_ms(),
+            },
+            evidence: evidence::Params {
+                max_age_num_blocks: 100_000,
+                max_age_duration: evidence::Duration(core::time::Duration::from_secs(48 * 3600)),
+                max_bytes: 1_048_576,
+            },
+            validator: ValidatorParams {
+                pub_key_types: vec![public_key::Algorithm::Ed25519],
+            },
+            version: Some(VersionParams::default()),
+            abci: AbciParams::default(),
+        }
+    }
+}
+
 /// ValidatorParams restrict the public key types validators can use.
 ///
 /// [Tendermint documentation](https://docs.tendermint.com/master/spec/core/data_structures.html#validatorparams)
@@ -72,6 +163,10 @@ fn key_type(s: &str) -> public_key::Algorithm {
     if s == "Secp256k1" || s == "secp256k1" {
         return public_key::Algorithm::Secp256k1;
     }
+    #[cfg(feature = "bls12381")]
+    if s == "Bls12381" || s == "bls12_381" {
+        return public_key::Algorithm::Bls12381;
+    }
     public_key::Algorithm::Ed25519 // Todo: Shall we error out for invalid key types?
 }
 
@@ -181,6 +276,8 @@ mod v0_34 {
                     .map(|k| match k {
                         public_key::Algorithm::Ed25519 => "ed25519".to_string(),
                         public_key::Algorithm::Secp256k1 => "secp256k1".to_string(),
+                        #[cfg(feature = "bls12381")]
+                        public_key::Algorithm::Bls12381 => "bls12_381".to_string(),
                     })
                     .collect(),
             }
@@ -275,6 +372,8 @@ mod v0_37 {
                     .map(|k| match k {
                         public_key::Algorithm::Ed25519 => "ed25519".to_string(),
                         public_key::Algorithm::Secp256k1 => "secp256k1".to_string(),
+                        #[cfg(feature = "bls12381")]
+                        public_key::Algorithm::Bls12381 => "bls12_381".to_string(),
                     })
                     .collect(),
             }
@@ -370,6 +469,8 @@ mod v0_38 {
                     .map(|k| match k {
                         public_key::Algorithm::Ed25519 => "ed25519".to_string(),
                         public_key::Algorithm::Secp256k1 => "secp256k1".to_string(),
+                        #[cfg(feature = "bls12381")]
+                        public_key::Algorithm::Bls12381 => "bls12_381".to_string(),
                     })
                     .collect(),
             }
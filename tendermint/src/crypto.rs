@@ -7,6 +7,9 @@
 //! The abstract framework enabling this extensibility is provided by the
 //! `digest` and `signature` crates.
 
+#[cfg(feature = "bls12381")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bls12381")))]
+pub mod bls12381;
 pub mod ed25519;
 pub mod sha256;
 pub mod signature;
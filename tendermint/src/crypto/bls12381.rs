@@ -0,0 +1,206 @@
+//! BLS12-381 signatures, as used by CometBFT 1.x validators.
+//!
+//! Public keys are compressed G1 points and signatures compressed G2 points
+//! (the "minimal public key size" variant), with the ciphersuite of the
+//! proof-of-possession scheme. Messages longer than 32 bytes are signed by
+//! their SHA-256 digest.
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use blst::{min_pk, BLST_ERROR};
+use digest::Digest;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use crate::{crypto::signature::Error as SignatureError, prelude::*, Error, Signature};
+
+/// Size of a compressed public key, in bytes.
+pub const PUBLIC_KEY_SIZE: usize = 48;
+
+/// Size of a serialized secret key, in bytes.
+pub const SIGNING_KEY_SIZE: usize = 32;
+
+/// Size of a compressed signature, in bytes.
+pub const SIGNATURE_SIZE: usize = 96;
+
+/// Messages longer than this are hashed with SHA-256 before being signed.
+pub const MAX_MSG_LEN: usize = 32;
+
+/// Domain separation tag of the signatures.
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The message actually signed for `msg`.
+fn signed_message(msg: &[u8]) -> Cow<'_, [u8]> {
+    if msg.len() > MAX_MSG_LEN {
+        Cow::Owned(sha2::Sha256::digest(msg).to_vec())
+    } else {
+        Cow::Borrowed(msg)
+    }
+}
+
+/// BLS12-381 public key, checked to be a valid point of the G1 subgroup.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct VerificationKey([u8; PUBLIC_KEY_SIZE]);
+
+impl fmt::Display for VerificationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for VerificationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Display>::fmt(self, f)
+    }
+}
+
+impl VerificationKey {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Verify the signature of `msg` with this key.
+    ///
+    /// Only compressed signatures of [`SIGNATURE_SIZE`] bytes are accepted.
+    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> Result<(), SignatureError> {
+        if signature.len() != SIGNATURE_SIZE {
+            return Err(SignatureError::MalformedSignature);
+        }
+        let pk = min_pk::PublicKey::from_bytes(&self.0)
+            .map_err(|_| SignatureError::MalformedPublicKey)?;
+        let sig = min_pk::Signature::sig_validate(signature, true)
+            .map_err(|_| SignatureError::MalformedSignature)?;
+        match sig.verify(false, &signed_message(msg), DST, &[], &pk, false) {
+            BLST_ERROR::BLST_SUCCESS => Ok(()),
+            _ => Err(SignatureError::VerificationFailed),
+        }
+    }
+}
+
+impl TryFrom<&'_ [u8]> for VerificationKey {
+    type Error = Error;
+
+    fn try_from(slice: &'_ [u8]) -> Result<Self, Self::Error> {
+        let bytes = <[u8; PUBLIC_KEY_SIZE]>::try_from(slice)
+            .map_err(|_| Error::invalid_key("invalid bls12_381 key length".into()))?;
+        min_pk::PublicKey::key_validate(&bytes)
+            .map_err(|_| Error::invalid_key("malformed bls12_381 public key".into()))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl From<min_pk::PublicKey> for VerificationKey {
+    fn from(pk: min_pk::PublicKey) -> Self {
+        Self(pk.compress())
+    }
+}
+
+/// BLS12-381 secret key.
+#[derive(Clone)]
+pub struct SigningKey(min_pk::SecretKey);
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SigningKey").field(&"..").finish()
+    }
+}
+
+impl SigningKey {
+    /// Generate a new secret key from the given random number generator.
+    pub fn generate<R: RngCore + CryptoRng>(mut rng: R) -> Self {
+        let mut ikm = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(&mut *ikm);
+        Self(min_pk::SecretKey::key_gen(&*ikm, &[]).expect("key material is 32 bytes"))
+    }
+
+    /// Serialize this key as big-endian bytes.
+    pub fn to_bytes(&self) -> Zeroizing<[u8; SIGNING_KEY_SIZE]> {
+        Zeroizing::new(self.0.to_bytes())
+    }
+
+    pub fn verification_key(&self) -> VerificationKey {
+        self.0.sk_to_pk().into()
+    }
+
+    /// Sign `msg` with this key.
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        self.0.sign(&signed_message(msg), DST, &[]).into()
+    }
+}
+
+impl TryFrom<&'_ [u8]> for SigningKey {
+    type Error = Error;
+
+    fn try_from(slice: &'_ [u8]) -> Result<Self, Self::Error> {
+        if slice.len() != SIGNING_KEY_SIZE {
+            return Err(Error::invalid_key("invalid bls12_381 key length".into()));
+        }
+        min_pk::SecretKey::from_bytes(slice)
+            .map(Self)
+            .map_err(|_| Error::invalid_key("malformed bls12_381 secret key".into()))
+    }
+}
+
+impl From<min_pk::Signature> for Signature {
+    fn from(sig: min_pk::Signature) -> Signature {
+        Signature::try_from(&sig.compress()[..]).expect("BLS12-381 signatures are 96 bytes")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::try_from(&[seed; SIGNING_KEY_SIZE][..]).unwrap()
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let sk = signing_key(1);
+        let pk = sk.verification_key();
+        let pk = VerificationKey::try_from(pk.as_bytes()).unwrap();
+
+        for msg in [&b"short"[..], &[0x42; 100][..]] {
+            let sig = sk.sign(msg);
+            assert_eq!(sig.as_bytes().len(), SIGNATURE_SIZE);
+            pk.verify(msg, sig.as_bytes()).unwrap();
+            assert!(matches!(
+                pk.verify(b"other", sig.as_bytes()),
+                Err(SignatureError::VerificationFailed)
+            ));
+            assert!(matches!(
+                signing_key(2)
+                    .verification_key()
+                    .verify(msg, sig.as_bytes()),
+                Err(SignatureError::VerificationFailed)
+            ));
+            assert!(matches!(
+                pk.verify(msg, &sig.as_bytes()[..64]),
+                Err(SignatureError::MalformedSignature)
+            ));
+        }
+    }
+
+    #[test]
+    fn signing_key_roundtrip() {
+        let sk = signing_key(3);
+        let decoded = SigningKey::try_from(&sk.to_bytes()[..]).unwrap();
+        assert_eq!(decoded.verification_key(), sk.verification_key());
+    }
+
+    #[test]
+    fn rejects_invalid_public_keys() {
+        assert!(VerificationKey::try_from(&[0u8; 32][..]).is_err());
+        // Not a point on the curve
+        assert!(VerificationKey::try_from(&[0xAB; PUBLIC_KEY_SIZE][..]).is_err());
+        // Point at infinity
+        let mut infinity = [0u8; PUBLIC_KEY_SIZE];
+        infinity[0] = 0xC0;
+        assert!(VerificationKey::try_from(&infinity[..]).is_err());
+    }
+}
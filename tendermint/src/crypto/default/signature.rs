@@ -1,8 +1,16 @@
 //! The pure Rust implementation of signature verification functions.
 
 use crate::crypto::signature::Error;
+#[cfg(feature = "std")]
+use crate::crypto::signature::{verify_each, BatchError, BatchItem};
 use crate::{PublicKey, Signature};
 
+/// Signature verifier backed by pure Rust implementations.
+///
+/// With the `std` feature, batches of Ed25519 signatures are verified at
+/// once. Without it, [`verify_batch`] checks the signatures one by one.
+///
+/// [`verify_batch`]: crate::crypto::signature::Verifier::verify_batch
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct Verifier;
 
@@ -27,9 +35,47 @@ impl crate::crypto::signature::Verifier for Verifier {
                     .map_err(|_| Error::MalformedSignature)?;
                 pk.verify(msg, &sig).map_err(|_| Error::VerificationFailed)
             },
+            #[cfg(feature = "bls12381")]
+            PublicKey::Bls12381(pk) => pk.verify(msg, signature.as_bytes()),
             _ => Err(Error::UnsupportedKeyType),
         }
     }
+
+    /// Verifies the Ed25519 signatures of the batch at once, falling back to
+    /// verifying them one by one to find the invalid signature if the batch
+    /// fails, or if it has keys of other types.
+    ///
+    /// The random coefficients of the batch are drawn from the operating
+    /// system, so batches are only verified at once with the `std` feature.
+    /// Without it, this verifier checks the signatures one by one.
+    #[cfg(feature = "std")]
+    fn verify_batch(items: &[BatchItem<'_>]) -> Result<(), BatchError> {
+        if items.len() < 2 {
+            return verify_each::<Self>(items);
+        }
+
+        let mut batch = ed25519_consensus::batch::Verifier::new();
+        for item in items {
+            #[allow(unreachable_patterns)]
+            let pk = match item.public_key {
+                PublicKey::Ed25519(pk) => pk,
+                _ => return verify_each::<Self>(items),
+            };
+            let Ok(sig) = ed25519_consensus::Signature::try_from(item.signature.as_bytes()) else {
+                return verify_each::<Self>(items);
+            };
+            let pk = <[u8; 32]>::try_from(pk.as_bytes()).expect("Ed25519 keys are 32 bytes");
+            batch.queue((
+                ed25519_consensus::VerificationKeyBytes::from(pk),
+                sig,
+                item.msg,
+            ));
+        }
+
+        batch
+            .verify(rand_core::OsRng)
+            .or_else(|_| verify_each::<Self>(items))
+    }
 }
 
 #[cfg(test)]
@@ -192,7 +238,7 @@ mod tests {
             let public_key = PublicKey::from_raw_ed25519(public_key).unwrap();
             match public_key {
                 PublicKey::Ed25519(_) => {},
-                #[cfg(feature = "secp256k1")]
+                #[cfg(any(feature = "secp256k1", feature = "bls12381"))]
                 _ => panic!("expected public key to be Ed25519: {:?}", public_key),
             }
             let sig = Signature::try_from(sig).unwrap();
@@ -201,6 +247,61 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "bls12381")]
+    #[test]
+    fn rejects_bls12381_length_signatures_of_ed25519_keys() {
+        use crate::{crypto::signature::Error, signature::BLS12381_SIGNATURE_LENGTH};
+
+        let v = ED25519_TEST_VECTORS[0];
+        let public_key = PublicKey::from_raw_ed25519(v[0]).unwrap();
+        let mut sig = v[2].to_vec();
+        sig.resize(BLS12381_SIGNATURE_LENGTH, 0);
+        let sig = Signature::try_from(sig.as_slice()).unwrap();
+        assert!(matches!(
+            Verifier::verify(public_key, v[1], &sig),
+            Err(Error::MalformedSignature)
+        ));
+    }
+
+    #[test]
+    fn ed25519_batch_test_vectors() {
+        use crate::{crypto::signature::BatchItem, prelude::*};
+
+        let keys_and_sigs = ED25519_TEST_VECTORS
+            .iter()
+            .map(|v| {
+                (
+                    PublicKey::from_raw_ed25519(v[0]).unwrap(),
+                    Signature::try_from(v[2]).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut msgs = ED25519_TEST_VECTORS
+            .iter()
+            .map(|v| v[1].to_vec())
+            .collect::<Vec<_>>();
+        fn items<'a>(
+            keys_and_sigs: &'a [(PublicKey, Signature)],
+            msgs: &'a [Vec<u8>],
+        ) -> Vec<BatchItem<'a>> {
+            keys_and_sigs
+                .iter()
+                .zip(msgs)
+                .map(|((public_key, signature), msg)| BatchItem {
+                    public_key: *public_key,
+                    msg,
+                    signature,
+                })
+                .collect()
+        }
+
+        Verifier::verify_batch(&items(&keys_and_sigs, &msgs)).unwrap();
+
+        msgs[2].push(0);
+        let err = Verifier::verify_batch(&items(&keys_and_sigs, &msgs)).unwrap_err();
+        assert_eq!(err.index, 2);
+    }
+
     // Arbitrary "valid" tests taken from
     // https://github.com/google/wycheproof/blob/2196000605e45d91097147c9c71f26b72af58003/testvectors/ecdsa_secp256k1_sha256_test.json
     //
@@ -30,6 +30,55 @@ impl Display for Error {
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+/// A signature verified as part of a batch, with the key and message it is
+/// verified against.
+#[derive(Clone, Copy, Debug)]
+pub struct BatchItem<'a> {
+    pub public_key: PublicKey,
+    pub msg: &'a [u8],
+    pub signature: &'a Signature,
+}
+
+/// Failure to verify a batch of signatures, identifying the first invalid
+/// signature of the batch.
+#[derive(Debug)]
+pub struct BatchError {
+    /// Index of the invalid signature in the batch.
+    pub index: usize,
+    /// Why the signature is invalid.
+    pub error: Error,
+}
+
+impl Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "signature {} of the batch: {}", self.index, self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BatchError {}
+
 pub trait Verifier {
     fn verify(pubkey: PublicKey, msg: &[u8], signature: &Signature) -> Result<(), Error>;
+
+    /// Verifies a batch of signatures, failing with the first invalid one.
+    ///
+    /// The default implementation verifies the signatures one by one.
+    /// Implementations able to verify batches at once can override it, and
+    /// use [`verify_each`] to find the invalid signature of a failed batch.
+    fn verify_batch(items: &[BatchItem<'_>]) -> Result<(), BatchError> {
+        verify_each::<Self>(items)
+    }
+}
+
+/// Verifies the signatures of a batch one by one with `V`, failing with the
+/// first invalid one.
+pub fn verify_each<V>(items: &[BatchItem<'_>]) -> Result<(), BatchError>
+where
+    V: Verifier + ?Sized,
+{
+    items.iter().enumerate().try_for_each(|(index, item)| {
+        V::verify(item.public_key, item.msg, item.signature)
+            .map_err(|error| BatchError { index, error })
+    })
 }
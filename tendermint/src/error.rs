@@ -1,12 +1,12 @@
 //! Error types
 
-use alloc::string::String;
+use alloc::{boxed::Box, string::String};
 use core::num::TryFromIntError;
 
 use flex_error::{define_error, DisplayOnly};
 use serde::{Deserialize, Serialize};
 
-use crate::account;
+use crate::{account, evidence::DuplicateVoteEvidence};
 
 define_error! {
     #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -157,9 +157,33 @@ define_error! {
         InvalidEvidence
             |_| { format_args!("invalid evidence") },
 
+        EvidenceVerification
+            { detail: String }
+            |e| { format_args!("evidence verification failed: {}", e.detail) },
+
+        InvalidVote
+            { detail: String }
+            |e| { format_args!("invalid vote: {}", e.detail) },
+
+        InvalidVoteExtension
+            { detail: String }
+            |e| { format_args!("invalid vote extension: {}", e.detail) },
+
+        ConflictingVotes
+            { evidence: Box<DuplicateVoteEvidence> }
+            |e| { format_args!("conflicting votes from validator {}", e.evidence.vote_a.validator_address) },
+
         InvalidValidatorParams
             |_| { format_args!("invalid validator parameters") },
 
+        InvalidConsensusParams
+            { detail: String }
+            |e| { format_args!("invalid consensus parameters: {}", e.detail) },
+
+        InvalidGenesis
+            { detail: String }
+            |e| { format_args!("invalid genesis: {}", e.detail) },
+
         InvalidVersionParams
             |_| { format_args!("invalid version parameters") },
 
@@ -230,11 +254,27 @@ define_error! {
             [ DisplayOnly<TryFromIntError> ]
             |_| { "negative item index in proof" },
 
+        InvalidMerkleProof
+            { detail: String }
+            |e| { format_args!("invalid Merkle proof: {}", e.detail) },
+
+        InvalidPart
+            { detail: String }
+            |e| { format_args!("invalid block part: {}", e.detail) },
+
+        IncompletePartSet
+            { count: u32, total: u32 }
+            |e| { format_args!("part set is incomplete: {} of {} parts", e.count, e.total) },
+
         TotalVotingPowerMismatch
             |_| { "total voting power in validator set does not match the sum of participants' powers" },
 
         TotalVotingPowerOverflow
             |_| { "total voting power in validator set exceeds the allowed maximum" },
+
+        InvalidValidatorUpdate
+            { detail: String }
+            |e| { format_args!("invalid validator update: {}", e.detail) },
     }
 }
 
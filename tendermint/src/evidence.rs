@@ -1,18 +1,24 @@
 //! Evidence of malfeasance by validators (i.e. signing conflicting votes).
 
+mod verify;
+
 use core::slice;
 
 use serde::{Deserialize, Serialize};
 use tendermint_proto::google::protobuf::Duration as RawDuration;
+use tendermint_proto::v0_38::types::DuplicateVoteEvidence as RawDuplicateVoteEvidence;
 use tendermint_proto::Protobuf;
 
+pub use self::verify::LightClientAttackKind;
 use crate::{
     block::{signed_header::SignedHeader, Height},
+    crypto::Sha256,
     error::Error,
+    merkle::{self, MerkleHash},
     prelude::*,
     serializers, validator,
     vote::Power,
-    Time, Vote,
+    Hash, Time, Vote,
 };
 
 /// Evidence of malfeasance by validators (i.e. signing conflicting votes or light client attack).
@@ -38,7 +44,11 @@ impl From<DuplicateVoteEvidence> for Evidence {
 }
 
 /// Duplicate vote evidence
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    try_from = "RawDuplicateVoteEvidence",
+    into = "RawDuplicateVoteEvidence"
+)]
 pub struct DuplicateVoteEvidence {
     pub vote_a: Vote,
     pub vote_b: Vote,
@@ -111,6 +121,48 @@ impl List {
     pub fn iter(&self) -> slice::Iter<'_, Evidence> {
         self.0.iter()
     }
+
+    /// Computes the hash of this evidence list, as committed to by the
+    /// `evidence_hash` of block headers.
+    #[cfg(feature = "rust-crypto")]
+    pub fn hash(&self) -> Hash {
+        self.hash_with::<crate::crypto::default::Sha256>()
+    }
+
+    /// Hash this evidence list with a Merkle hasher provided by a crypto provider.
+    ///
+    /// The leaves of the tree are the protobuf encodings of the evidence,
+    /// without the `Evidence` wrapper.
+    pub fn hash_with<H>(&self) -> Hash
+    where
+        H: MerkleHash + Sha256 + Default,
+    {
+        use prost::Message as _;
+        use tendermint_proto::v0_38::types as raw;
+
+        let evidence_bytes = self
+            .0
+            .iter()
+            .map(|evidence| match evidence {
+                Evidence::DuplicateVote(ev) => {
+                    let mut raw = raw::DuplicateVoteEvidence::from(ev.as_ref().clone());
+                    // The block IDs of votes are not nullable in the Go encoding, which
+                    // encodes the nil block ID of votes for nil as an empty message.
+                    for vote in [&mut raw.vote_a, &mut raw.vote_b].into_iter().flatten() {
+                        vote.block_id.get_or_insert_with(|| raw::BlockId {
+                            hash: Vec::new(),
+                            part_set_header: Some(Default::default()),
+                        });
+                    }
+                    raw.encode_to_vec()
+                },
+                Evidence::LightClientAttack(ev) => {
+                    Protobuf::<raw::LightClientAttackEvidence>::encode_vec(ev.as_ref().clone())
+                },
+            })
+            .collect::<Vec<_>>();
+        Hash::Sha256(merkle::simple_hash_from_byte_vectors::<H>(&evidence_bytes))
+    }
 }
 
 impl AsRef<[Evidence]> for List {
@@ -214,7 +266,7 @@ tendermint_pb_modules! {
                 vote_a: Some(value.vote_a.into()),
                 vote_b: Some(value.vote_b.into()),
                 total_voting_power: value.total_voting_power.into(),
-                validator_power: value.total_voting_power.into(),
+                validator_power: value.validator_power.into(),
                 timestamp: Some(value.timestamp.into()),
             }
         }
@@ -0,0 +1,869 @@
+//! Verification of evidence against the state of the chain, following
+//! <https://github.com/cometbft/cometbft/blob/v0.38.x/evidence/verify.go>.
+
+use crate::{
+    account,
+    block::{signed_header::SignedHeader, Commit, CommitSig, Header},
+    chain,
+    crypto::{signature::Verifier, Sha256},
+    error::Error,
+    merkle::MerkleHash,
+    prelude::*,
+    trust_threshold::{TrustThreshold, TrustThresholdFraction},
+    validator,
+    vote::{self, ValidatorIndex},
+    PublicKey, Vote,
+};
+
+use super::{DuplicateVoteEvidence, LightClientAttackEvidence};
+
+/// Kind of a light client attack, determined by comparing the conflicting
+/// block with the trusted block at the same height.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LightClientAttackKind {
+    /// The conflicting header could not have been derived from the state of
+    /// the chain, i.e. the attackers forged the application state or the
+    /// validator set.
+    Lunatic,
+    /// The conflicting block was committed in the same round as the trusted
+    /// block, so the validators who signed both double signed.
+    Equivocation,
+    /// The conflicting block was committed in another round than the trusted
+    /// block. The misbehaving validators cannot be identified from the commits.
+    Amnesia,
+}
+
+fn verification_error(detail: impl Into<String>) -> Error {
+    Error::evidence_verification(detail.into())
+}
+
+impl DuplicateVoteEvidence {
+    /// Verifies this evidence against the chain ID and the validator set at
+    /// the height of the votes.
+    ///
+    /// Both votes must be signed by the same validator, for the same height,
+    /// round and vote type but for different blocks, and the powers recorded
+    /// in the evidence must match the validator set.
+    pub fn verify<V>(
+        &self,
+        chain_id: &chain::Id,
+        validator_set: &validator::Set,
+    ) -> Result<(), Error>
+    where
+        V: Verifier,
+    {
+        let (vote_a, vote_b) = self.votes();
+        let validator = validator_set
+            .validator(vote_a.validator_address)
+            .ok_or_else(|| {
+                verification_error(format!(
+                    "address {} was not a validator at height {}",
+                    vote_a.validator_address, vote_a.height
+                ))
+            })?;
+
+        if vote_a.height != vote_b.height
+            || vote_a.round != vote_b.round
+            || vote_a.vote_type != vote_b.vote_type
+        {
+            return Err(verification_error(format!(
+                "height, round and type do not match: {}/{}/{} vs. {}/{}/{}",
+                vote_a.height,
+                vote_a.round,
+                vote_a.vote_type,
+                vote_b.height,
+                vote_b.round,
+                vote_b.vote_type
+            )));
+        }
+        if vote_a.validator_address != vote_b.validator_address {
+            return Err(verification_error(format!(
+                "validator addresses do not match: {} vs. {}",
+                vote_a.validator_address, vote_b.validator_address
+            )));
+        }
+        if vote_a.block_id == vote_b.block_id {
+            return Err(verification_error(
+                "block IDs are the same, this is not a duplicate vote",
+            ));
+        }
+
+        if self.validator_power != validator.power {
+            return Err(verification_error(format!(
+                "validator power from evidence and our validator set does not match ({} != {})",
+                self.validator_power, validator.power
+            )));
+        }
+        if self.total_voting_power != validator_set.total_voting_power() {
+            return Err(verification_error(format!(
+                "total voting power from the evidence and our validator set does not match ({} != {})",
+                self.total_voting_power,
+                validator_set.total_voting_power()
+            )));
+        }
+
+        for vote in [vote_a, vote_b] {
+            let signature = vote
+                .signature
+                .as_ref()
+                .ok_or_else(|| verification_error("vote is not signed"))?;
+            let sign_bytes = vote.clone().into_signable_vec(chain_id.clone());
+            validator.verify_signature::<V>(&sign_bytes, signature)?;
+        }
+        Ok(())
+    }
+}
+
+impl LightClientAttackEvidence {
+    /// Whether the conflicting header could not have been derived from the
+    /// state of the chain, given the trusted header at the same height.
+    ///
+    /// This is the case when any of the hashes determined by the previous
+    /// block differs between both headers.
+    pub fn conflicting_header_is_invalid(&self, trusted_header: &Header) -> bool {
+        let conflicting_header = &self.conflicting_block.signed_header.header;
+        trusted_header.validators_hash != conflicting_header.validators_hash
+            || trusted_header.next_validators_hash != conflicting_header.next_validators_hash
+            || trusted_header.consensus_hash != conflicting_header.consensus_hash
+            || trusted_header.app_hash != conflicting_header.app_hash
+            || trusted_header.last_results_hash != conflicting_header.last_results_hash
+    }
+
+    /// Classifies the attack, given the trusted signed header at the height of
+    /// the conflicting block.
+    pub fn attack_kind(&self, trusted: &SignedHeader) -> LightClientAttackKind {
+        if self.conflicting_header_is_invalid(&trusted.header) {
+            LightClientAttackKind::Lunatic
+        } else if trusted.commit.round == self.conflicting_block.signed_header.commit.round {
+            LightClientAttackKind::Equivocation
+        } else {
+            LightClientAttackKind::Amnesia
+        }
+    }
+
+    /// Computes the validators who took part in the attack, sorted by
+    /// descending voting power then by address.
+    ///
+    /// These are the validators of `common_validators` who signed the
+    /// conflicting block in a lunatic attack, and the validators who signed
+    /// both blocks in an equivocation. No validators can be identified in an
+    /// amnesia attack.
+    pub fn get_byzantine_validators(
+        &self,
+        common_validators: &validator::Set,
+        trusted: &SignedHeader,
+    ) -> Vec<validator::Info> {
+        let conflicting_commit = &self.conflicting_block.signed_header.commit;
+        let mut validators = match self.attack_kind(trusted) {
+            LightClientAttackKind::Lunatic => conflicting_commit
+                .signatures
+                .iter()
+                .filter(|sig| sig.is_commit())
+                .filter_map(|sig| common_validators.validator(sig.validator_address()?))
+                .collect::<Vec<_>>(),
+            // The validator hashes are the same, so validators have the same
+            // index in both commits.
+            LightClientAttackKind::Equivocation => conflicting_commit
+                .signatures
+                .iter()
+                .zip(&trusted.commit.signatures)
+                .filter(|(sig_a, sig_b)| sig_a.is_commit() && sig_b.is_commit())
+                .filter_map(|(sig, _)| {
+                    self.conflicting_block
+                        .validator_set
+                        .validator(sig.validator_address()?)
+                })
+                .collect(),
+            LightClientAttackKind::Amnesia => Vec::new(),
+        };
+        validators.sort_by_key(|v| (core::cmp::Reverse(v.power), v.address));
+        validators
+    }
+
+    /// Verifies this evidence, given the signed header at the common height,
+    /// the trusted signed header at the height of the conflicting block and
+    /// the validator set at the common height.
+    #[cfg(feature = "rust-crypto")]
+    pub fn verify<V>(
+        &self,
+        common_header: &SignedHeader,
+        trusted: &SignedHeader,
+        common_validators: &validator::Set,
+    ) -> Result<(), Error>
+    where
+        V: Verifier,
+    {
+        self.verify_with::<V, crate::crypto::default::Sha256>(
+            common_header,
+            trusted,
+            common_validators,
+        )
+    }
+
+    /// Verifies this evidence, hashing headers with a Merkle hasher provided
+    /// by a crypto provider.
+    ///
+    /// The common header must be at the common height of the evidence, and
+    /// the conflicting block must be a consistent light block, whose header
+    /// commits to its validator set.
+    ///
+    /// When the common height differs from the height of the conflicting
+    /// block, validators of `common_validators` holding more than a third of
+    /// its voting power must have signed the conflicting block. Otherwise, the
+    /// conflicting header must be correctly derived from the state of the
+    /// chain. In both cases, the conflicting block must be committed by its
+    /// validator set, and the total voting power and byzantine validators
+    /// recorded in the evidence must match the ones computed from the common
+    /// validator set.
+    pub fn verify_with<V, H>(
+        &self,
+        common_header: &SignedHeader,
+        trusted: &SignedHeader,
+        common_validators: &validator::Set,
+    ) -> Result<(), Error>
+    where
+        V: Verifier,
+        H: MerkleHash + Sha256 + Default,
+    {
+        let conflicting = &self.conflicting_block.signed_header;
+        let chain_id = &trusted.header.chain_id;
+
+        if common_header.header.height != self.common_height {
+            return Err(verification_error(format!(
+                "common header is at height {}, evidence common height is {}",
+                common_header.header.height, self.common_height
+            )));
+        }
+        validate_light_block::<H>(chain_id, conflicting, &self.conflicting_block.validator_set)?;
+        let conflicting_hash = conflicting.header.hash_with::<H>();
+
+        if common_header.header.height != conflicting.header.height {
+            verify_commit_trusting::<V>(
+                chain_id,
+                common_validators,
+                &conflicting.commit,
+                TrustThresholdFraction::ONE_THIRD,
+            )
+            .map_err(|e| {
+                verification_error(format!(
+                    "skipping verification of conflicting block failed: {e}"
+                ))
+            })?;
+        } else if self.conflicting_header_is_invalid(&trusted.header) {
+            return Err(verification_error(
+                "common height is the same as conflicting block height so expected the \
+                 conflicting block to be correctly derived yet it wasn't",
+            ));
+        }
+
+        verify_commit_light::<V>(
+            chain_id,
+            &self.conflicting_block.validator_set,
+            &conflicting.commit,
+        )
+        .map_err(|e| verification_error(format!("invalid commit from conflicting block: {e}")))?;
+
+        if self.total_voting_power != common_validators.total_voting_power() {
+            return Err(verification_error(format!(
+                "total voting power from the evidence and our validator set does not match ({} != {})",
+                self.total_voting_power,
+                common_validators.total_voting_power()
+            )));
+        }
+
+        if conflicting.header.height > trusted.header.height {
+            if conflicting.header.time > trusted.header.time {
+                return Err(verification_error(format!(
+                    "conflicting block doesn't violate monotonically increasing time ({} is after {})",
+                    conflicting.header.time, trusted.header.time
+                )));
+            }
+        } else if trusted.header.hash_with::<H>() == conflicting_hash {
+            return Err(verification_error(format!(
+                "trusted header hash matches the evidence's conflicting header hash: {conflicting_hash}"
+            )));
+        }
+
+        let expected = self.get_byzantine_validators(common_validators, trusted);
+        if expected.len() != self.byzantine_validators.len() {
+            return Err(verification_error(format!(
+                "expected {} byzantine validators from evidence but got {}",
+                expected.len(),
+                self.byzantine_validators.len()
+            )));
+        }
+        for (idx, (expected, actual)) in expected.iter().zip(&self.byzantine_validators).enumerate()
+        {
+            if expected.address != actual.address || expected.power != actual.power {
+                return Err(verification_error(format!(
+                    "byzantine validator {idx} does not match: expected {} with power {}, got {} with power {}",
+                    expected.address, expected.power, actual.address, actual.power
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the signed header and validator set of a light block are
+/// consistent, as `LightBlock.ValidateBasic` does in Go: the commit is for
+/// the header, the header commits to the validator set, and the address of
+/// every validator is derived from its public key.
+fn validate_light_block<H>(
+    chain_id: &chain::Id,
+    signed_header: &SignedHeader,
+    validator_set: &validator::Set,
+) -> Result<(), Error>
+where
+    H: MerkleHash + Sha256 + Default,
+{
+    let header = &signed_header.header;
+    let commit = &signed_header.commit;
+    if &header.chain_id != chain_id {
+        return Err(verification_error(format!(
+            "header belongs to another chain {}, not {}",
+            header.chain_id, chain_id
+        )));
+    }
+    if commit.height != header.height {
+        return Err(verification_error(format!(
+            "header and commit height mismatch: {} vs {}",
+            header.height, commit.height
+        )));
+    }
+    let header_hash = header.hash_with::<H>();
+    if commit.block_id.hash != header_hash {
+        return Err(verification_error(format!(
+            "commit signs block {}, header is block {}",
+            commit.block_id.hash, header_hash
+        )));
+    }
+
+    if validator_set.validators().is_empty() {
+        return Err(verification_error("validator set is empty"));
+    }
+    for validator in validator_set.validators() {
+        if !address_matches::<H>(validator) {
+            return Err(verification_error(format!(
+                "validator address {} does not match its public key",
+                validator.address
+            )));
+        }
+    }
+    let validators_hash = validator_set.hash_with::<H>();
+    if validators_hash != header.validators_hash {
+        return Err(verification_error(format!(
+            "expected validator hash of header to match validator set hash ({} != {})",
+            header.validators_hash, validators_hash
+        )));
+    }
+    Ok(())
+}
+
+/// Whether the address of the validator is the one of its public key.
+fn address_matches<H>(validator: &validator::Info) -> bool
+where
+    H: Sha256,
+{
+    // SHA256(pk)[:20]
+    let sha256_address =
+        |bytes: &[u8]| H::digest(bytes)[..account::LENGTH] == *validator.address.as_bytes();
+    match validator.pub_key {
+        PublicKey::Ed25519(pk) => sha256_address(pk.as_bytes()),
+        #[cfg(feature = "secp256k1")]
+        PublicKey::Secp256k1(_) => account::Id::from(validator.pub_key) == validator.address,
+        #[cfg(feature = "bls12381")]
+        PublicKey::Bls12381(pk) => sha256_address(pk.as_bytes()),
+    }
+}
+
+/// Verifies that validators of `validator_set` holding more than `trust_threshold`
+/// of its voting power signed `commit`, looking up the signers by address.
+fn verify_commit_trusting<V>(
+    chain_id: &chain::Id,
+    validator_set: &validator::Set,
+    commit: &Commit,
+    trust_threshold: TrustThresholdFraction,
+) -> Result<(), Error>
+where
+    V: Verifier,
+{
+    let mut signers: Vec<(usize, validator::Info)> = Vec::new();
+    for (idx, sig) in commit.signatures.iter().enumerate() {
+        if !sig.is_commit() {
+            continue;
+        }
+        let validator = match sig
+            .validator_address()
+            .and_then(|address| validator_set.validator(address))
+        {
+            Some(validator) => validator,
+            None => continue,
+        };
+        if signers.iter().any(|(_, v)| v.address == validator.address) {
+            return Err(verification_error(format!(
+                "double vote from {}",
+                validator.address
+            )));
+        }
+        signers.push((idx, validator));
+    }
+    verify_commit_signers::<V>(chain_id, validator_set, commit, &signers, trust_threshold)
+}
+
+/// Verifies that validators of `validator_set` holding more than two thirds of
+/// its voting power signed `commit`, which must have a signature slot for
+/// every validator of the set.
+fn verify_commit_light<V>(
+    chain_id: &chain::Id,
+    validator_set: &validator::Set,
+    commit: &Commit,
+) -> Result<(), Error>
+where
+    V: Verifier,
+{
+    if validator_set.validators().len() != commit.signatures.len() {
+        return Err(verification_error(format!(
+            "invalid commit -- wrong set size: {} vs {}",
+            validator_set.validators().len(),
+            commit.signatures.len()
+        )));
+    }
+    let mut signers = Vec::new();
+    for (idx, (sig, validator)) in commit
+        .signatures
+        .iter()
+        .zip(validator_set.validators())
+        .enumerate()
+    {
+        if !sig.is_commit() {
+            continue;
+        }
+        if sig.validator_address() != Some(validator.address) {
+            return Err(verification_error(format!(
+                "wrong validator address in commit signature {idx}: expected {}",
+                validator.address
+            )));
+        }
+        signers.push((idx, validator.clone()));
+    }
+    verify_commit_signers::<V>(
+        chain_id,
+        validator_set,
+        commit,
+        &signers,
+        TrustThresholdFraction::TWO_THIRDS,
+    )
+}
+
+/// Verifies the signatures of the given signers of `commit`, identified by the
+/// index of their signature, until they hold enough voting power of
+/// `validator_set`.
+fn verify_commit_signers<V>(
+    chain_id: &chain::Id,
+    validator_set: &validator::Set,
+    commit: &Commit,
+    signers: &[(usize, validator::Info)],
+    trust_threshold: TrustThresholdFraction,
+) -> Result<(), Error>
+where
+    V: Verifier,
+{
+    let total = validator_set.total_voting_power().value();
+    let mut tallied = 0u64;
+    for (idx, validator) in signers {
+        let (vote, signature) = commit_vote(commit, *idx)?;
+        validator.verify_signature::<V>(&vote.into_signable_vec(chain_id.clone()), &signature)?;
+        tallied += validator.power();
+        if trust_threshold.is_enough_power(tallied, total) {
+            return Ok(());
+        }
+    }
+    Err(verification_error(format!(
+        "invalid commit -- insufficient voting power: got {tallied}, needed more than {trust_threshold} of {total}"
+    )))
+}
+
+/// Reconstructs the precommit for the block of `commit` at `idx`, with its signature.
+fn commit_vote(commit: &Commit, idx: usize) -> Result<(Vote, crate::Signature), Error> {
+    let (validator_address, timestamp, signature) = match &commit.signatures[idx] {
+        CommitSig::BlockIdFlagCommit {
+            validator_address,
+            timestamp,
+            signature,
+        } => (validator_address, timestamp, signature),
+        _ => {
+            return Err(verification_error(format!(
+                "commit signature {idx} is not for the committed block"
+            )))
+        },
+    };
+    let signature = signature
+        .clone()
+        .ok_or_else(|| verification_error(format!("commit signature {idx} is missing")))?;
+    let vote = Vote {
+        vote_type: vote::Type::Precommit,
+        height: commit.height,
+        round: commit.round,
+        block_id: Some(commit.block_id),
+        timestamp: Some(*timestamp),
+        validator_address: *validator_address,
+        validator_index: ValidatorIndex::try_from(idx)?,
+        signature: Some(signature.clone()),
+        extension: Default::default(),
+        extension_signature: None,
+    };
+    Ok((vote, signature))
+}
+
+#[cfg(all(test, feature = "rust-crypto"))]
+mod tests {
+    use ed25519_consensus::SigningKey;
+
+    use super::*;
+    use crate::{
+        block::{self, header::Version, Height, Round},
+        crypto::default::signature::Verifier as DefaultVerifier,
+        evidence::ConflictingBlock,
+        hash::AppHash,
+        Hash, PublicKey, Time,
+    };
+
+    const CHAIN_ID: &str = "test-chain";
+
+    fn signing_keys() -> Vec<SigningKey> {
+        (1..=4).map(|seed| SigningKey::from([seed; 32])).collect()
+    }
+
+    fn validator_set(keys: &[SigningKey]) -> validator::Set {
+        let validators = keys
+            .iter()
+            .map(|key| {
+                let pk = PublicKey::from(key.verification_key());
+                validator::Info::new(pk, 10u32.into())
+            })
+            .collect();
+        validator::Set::without_proposer(validators)
+    }
+
+    fn signing_key<'a>(keys: &'a [SigningKey], validator: &validator::Info) -> &'a SigningKey {
+        keys.iter()
+            .find(|key| PublicKey::from(key.verification_key()) == validator.pub_key)
+            .unwrap()
+    }
+
+    fn header(height: u32, time: i64, app_hash: u8, vals: &validator::Set) -> Header {
+        Header {
+            version: Version { block: 11, app: 0 },
+            chain_id: CHAIN_ID.parse().unwrap(),
+            height: height.into(),
+            time: Time::from_unix_timestamp(time, 0).unwrap(),
+            last_block_id: None,
+            last_commit_hash: None,
+            data_hash: None,
+            validators_hash: vals.hash(),
+            next_validators_hash: vals.hash(),
+            consensus_hash: Hash::Sha256([0x11; 32]),
+            app_hash: AppHash::try_from(vec![app_hash; 32]).unwrap(),
+            last_results_hash: None,
+            evidence_hash: None,
+            proposer_address: vals.validators()[0].address,
+        }
+    }
+
+    /// Signs `header` in `round` with the first `signers` validators of `vals`.
+    fn sign_header(
+        header: Header,
+        round: u16,
+        keys: &[SigningKey],
+        vals: &validator::Set,
+        signers: usize,
+    ) -> SignedHeader {
+        let block_id = block::Id {
+            hash: header.hash(),
+            part_set_header: Default::default(),
+        };
+        let mut commit = Commit {
+            height: header.height,
+            round: Round::from(round),
+            block_id,
+            signatures: vec![CommitSig::BlockIdFlagAbsent; vals.validators().len()],
+        };
+        for (idx, validator) in vals.validators().iter().enumerate().take(signers) {
+            let vote = Vote {
+                vote_type: vote::Type::Precommit,
+                height: header.height,
+                round: commit.round,
+                block_id: Some(block_id),
+                timestamp: Some(header.time),
+                validator_address: validator.address,
+                validator_index: ValidatorIndex::try_from(idx).unwrap(),
+                signature: None,
+                extension: Default::default(),
+                extension_signature: None,
+            };
+            let sign_bytes = vote.into_signable_vec(header.chain_id.clone());
+            let signature = signing_key(keys, validator).sign(&sign_bytes);
+            commit.signatures[idx] = CommitSig::BlockIdFlagCommit {
+                validator_address: validator.address,
+                timestamp: header.time,
+                signature: Some(signature.into()),
+            };
+        }
+        SignedHeader::new(header, commit).unwrap()
+    }
+
+    fn signed_vote(key: &SigningKey, validator: &validator::Info, block_hash: u8) -> Vote {
+        let mut vote = Vote {
+            vote_type: vote::Type::Prevote,
+            height: Height::from(10u32),
+            round: Round::from(0u16),
+            block_id: Some(block::Id {
+                hash: Hash::Sha256([block_hash; 32]),
+                part_set_header: Default::default(),
+            }),
+            timestamp: Some(Time::from_unix_timestamp(1_000, 0).unwrap()),
+            validator_address: validator.address,
+            validator_index: ValidatorIndex::try_from(0u32).unwrap(),
+            signature: None,
+            extension: Default::default(),
+            extension_signature: None,
+        };
+        let sign_bytes = vote.clone().into_signable_vec(CHAIN_ID.parse().unwrap());
+        vote.signature = Some(key.sign(&sign_bytes).into());
+        vote
+    }
+
+    #[test]
+    fn duplicate_vote_evidence() {
+        let keys = signing_keys();
+        let vals = validator_set(&keys);
+        let validator = &vals.validators()[0];
+        let key = signing_key(&keys, validator);
+        let chain_id = CHAIN_ID.parse().unwrap();
+
+        let mut evidence = DuplicateVoteEvidence::new(
+            signed_vote(key, validator, 1),
+            signed_vote(key, validator, 2),
+        )
+        .unwrap();
+        evidence.validator_power = validator.power;
+        evidence.total_voting_power = vals.total_voting_power();
+        evidence
+            .verify::<DefaultVerifier>(&chain_id, &vals)
+            .unwrap();
+
+        let mut wrong_power = evidence.clone();
+        wrong_power.validator_power = 1u32.into();
+        assert!(wrong_power
+            .verify::<DefaultVerifier>(&chain_id, &vals)
+            .is_err());
+
+        let mut same_block = evidence.clone();
+        same_block.vote_b = signed_vote(key, validator, 1);
+        assert!(same_block
+            .verify::<DefaultVerifier>(&chain_id, &vals)
+            .is_err());
+
+        let mut other_round = evidence.clone();
+        other_round.vote_b.round = Round::from(1u16);
+        assert!(other_round
+            .verify::<DefaultVerifier>(&chain_id, &vals)
+            .is_err());
+
+        let mut forged = evidence.clone();
+        forged.vote_b.signature = signed_vote(&keys[0], validator, 3).signature;
+        assert!(forged.verify::<DefaultVerifier>(&chain_id, &vals).is_err());
+
+        assert!(evidence
+            .verify::<DefaultVerifier>(&"other-chain".parse().unwrap(), &vals)
+            .is_err());
+    }
+
+    #[test]
+    fn equivocation_and_amnesia() {
+        let keys = signing_keys();
+        let vals = validator_set(&keys);
+        let trusted = sign_header(header(10, 1_000, 1, &vals), 0, &keys, &vals, 4);
+
+        // The conflicting header is correctly derived but commits another block.
+        let mut conflicting_header = header(10, 1_000, 1, &vals);
+        conflicting_header.data_hash = Some(Hash::Sha256([0x22; 32]));
+        let conflicting = sign_header(conflicting_header.clone(), 0, &keys, &vals, 3);
+        let mut evidence = LightClientAttackEvidence {
+            conflicting_block: ConflictingBlock {
+                signed_header: conflicting,
+                validator_set: vals.clone(),
+            },
+            common_height: Height::from(10u32),
+            byzantine_validators: Vec::new(),
+            total_voting_power: vals.total_voting_power(),
+            timestamp: trusted.header.time,
+        };
+        assert_eq!(
+            evidence.attack_kind(&trusted),
+            LightClientAttackKind::Equivocation
+        );
+        let byzantine = evidence.get_byzantine_validators(&vals, &trusted);
+        assert_eq!(byzantine, vals.validators()[..3].to_vec());
+        // The byzantine validators must be listed in the evidence.
+        assert!(evidence
+            .verify::<DefaultVerifier>(&trusted, &trusted, &vals)
+            .is_err());
+        evidence.byzantine_validators = byzantine;
+        evidence
+            .verify::<DefaultVerifier>(&trusted, &trusted, &vals)
+            .unwrap();
+
+        // Not enough validators signed the conflicting block.
+        let mut unsigned = evidence.clone();
+        unsigned.conflicting_block.signed_header =
+            sign_header(conflicting_header.clone(), 0, &keys, &vals, 2);
+        assert!(unsigned
+            .verify::<DefaultVerifier>(&trusted, &trusted, &vals)
+            .is_err());
+
+        let mut amnesia = evidence.clone();
+        amnesia.conflicting_block.signed_header =
+            sign_header(conflicting_header, 1, &keys, &vals, 4);
+        assert_eq!(
+            amnesia.attack_kind(&trusted),
+            LightClientAttackKind::Amnesia
+        );
+        assert!(amnesia.get_byzantine_validators(&vals, &trusted).is_empty());
+        assert!(amnesia
+            .verify::<DefaultVerifier>(&trusted, &trusted, &vals)
+            .is_err());
+        amnesia.byzantine_validators = Vec::new();
+        amnesia
+            .verify::<DefaultVerifier>(&trusted, &trusted, &vals)
+            .unwrap();
+
+        // The trusted block is no evidence of an attack.
+        let mut honest = evidence;
+        honest.conflicting_block.signed_header = trusted.clone();
+        honest.byzantine_validators = honest.get_byzantine_validators(&vals, &trusted);
+        assert!(honest
+            .verify::<DefaultVerifier>(&trusted, &trusted, &vals)
+            .is_err());
+    }
+
+    #[test]
+    fn lunatic_attack() {
+        let keys = signing_keys();
+        let vals = validator_set(&keys);
+        let common = sign_header(header(5, 500, 1, &vals), 0, &keys, &vals, 4);
+        let trusted = sign_header(header(10, 1_000, 1, &vals), 0, &keys, &vals, 4);
+
+        // Two validators forge the application state with a validator set of their own.
+        let lunatic_vals = validator_set(&keys[..2]);
+        let mut conflicting_header = header(10, 1_000, 9, &lunatic_vals);
+        conflicting_header.proposer_address = lunatic_vals.validators()[0].address;
+        let mut evidence = LightClientAttackEvidence {
+            conflicting_block: ConflictingBlock {
+                signed_header: sign_header(conflicting_header, 0, &keys, &lunatic_vals, 2),
+                validator_set: lunatic_vals.clone(),
+            },
+            common_height: Height::from(5u32),
+            byzantine_validators: Vec::new(),
+            total_voting_power: vals.total_voting_power(),
+            timestamp: common.header.time,
+        };
+        assert!(evidence.conflicting_header_is_invalid(&trusted.header));
+        assert_eq!(
+            evidence.attack_kind(&trusted),
+            LightClientAttackKind::Lunatic
+        );
+
+        evidence.byzantine_validators = evidence.get_byzantine_validators(&vals, &trusted);
+        assert_eq!(evidence.byzantine_validators.len(), 2);
+        for validator in &evidence.byzantine_validators {
+            assert!(lunatic_vals.validator(validator.address).is_some());
+        }
+        evidence
+            .verify::<DefaultVerifier>(&common, &trusted, &vals)
+            .unwrap();
+
+        // The total voting power must be the one of the common validator set.
+        let mut wrong_total = evidence.clone();
+        wrong_total.total_voting_power = lunatic_vals.total_voting_power();
+        assert!(wrong_total
+            .verify::<DefaultVerifier>(&common, &trusted, &vals)
+            .is_err());
+
+        // A lunatic header can't be verified from the same height.
+        let mut same_height = evidence.clone();
+        same_height.common_height = trusted.header.height;
+        assert!(same_height
+            .verify::<DefaultVerifier>(&trusted, &trusted, &vals)
+            .is_err());
+
+        // The common header must be at the common height.
+        assert!(evidence
+            .verify::<DefaultVerifier>(&trusted, &trusted, &vals)
+            .is_err());
+
+        // The validator set must be the one of the conflicting header.
+        let mut other_vals = evidence.clone();
+        other_vals.conflicting_block.validator_set = validator_set(&keys[2..]);
+        assert!(other_vals
+            .verify::<DefaultVerifier>(&common, &trusted, &vals)
+            .is_err());
+
+        // The attackers claim the address of an honest validator, which is
+        // not part of the validator set hash.
+        let honest = vals
+            .validators()
+            .iter()
+            .find(|v| lunatic_vals.validator(v.address).is_none())
+            .unwrap();
+        let mut forged = evidence.clone();
+        let mut forged_vals = lunatic_vals.validators().clone();
+        let impersonated = forged_vals[0].address;
+        forged_vals[0].address = honest.address;
+        forged.conflicting_block.validator_set = validator::Set::without_proposer(forged_vals);
+        for sig in &mut forged.conflicting_block.signed_header.commit.signatures {
+            if let CommitSig::BlockIdFlagCommit {
+                validator_address, ..
+            } = sig
+            {
+                if *validator_address == impersonated {
+                    *validator_address = honest.address;
+                }
+            }
+        }
+        forged.byzantine_validators = forged.get_byzantine_validators(&vals, &trusted);
+        assert!(forged
+            .byzantine_validators
+            .iter()
+            .any(|v| v.address == honest.address));
+        assert!(forged
+            .verify::<DefaultVerifier>(&common, &trusted, &vals)
+            .is_err());
+
+        // The commit must be at the height of the header.
+        let mut wrong_height = evidence.clone();
+        wrong_height.conflicting_block.signed_header.commit.height = Height::from(9u32);
+        assert!(wrong_height
+            .verify::<DefaultVerifier>(&common, &trusted, &vals)
+            .is_err());
+
+        // A third of the common validators didn't sign the conflicting block.
+        let single_vals = validator_set(&keys[..1]);
+        let mut single = evidence;
+        single.conflicting_block = ConflictingBlock {
+            signed_header: sign_header(
+                header(10, 1_000, 9, &single_vals),
+                0,
+                &keys,
+                &single_vals,
+                1,
+            ),
+            validator_set: single_vals,
+        };
+        single.byzantine_validators = single.get_byzantine_validators(&vals, &trusted);
+        assert!(single
+            .verify::<DefaultVerifier>(&common, &trusted, &vals)
+            .is_err());
+    }
+}
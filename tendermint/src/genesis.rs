@@ -2,7 +2,11 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::{chain, consensus, prelude::*, serializers, validator, AppHash, Time};
+#[cfg(feature = "rust-crypto")]
+use crate::error::Error;
+use crate::{
+    chain, consensus, crypto::Sha256, prelude::*, serializers, validator, AppHash, Hash, Time,
+};
 
 /// Genesis data
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -31,3 +35,226 @@ pub struct Genesis<AppState = serde_json::Value> {
     /// App state
     pub app_state: AppState,
 }
+
+impl<AppState> Genesis<AppState> {
+    /// Creates the genesis of a chain starting at height 1 with the default
+    /// consensus parameters, such as for a local testnet.
+    pub fn new(
+        chain_id: chain::Id,
+        genesis_time: Time,
+        validators: Vec<validator::Info>,
+        app_state: AppState,
+    ) -> Self {
+        Self {
+            genesis_time,
+            chain_id,
+            initial_height: 1,
+            consensus_params: consensus::Params::default(),
+            validators,
+            app_hash: AppHash::default(),
+            app_state,
+        }
+    }
+
+    /// Checks the genesis as CometBFT does before starting a chain from it.
+    ///
+    /// An initial height of 0 is accepted and stands for 1.
+    #[cfg(feature = "rust-crypto")]
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.initial_height < 0 {
+            return Err(Error::invalid_genesis(format!(
+                "initial height cannot be negative, got {}",
+                self.initial_height
+            )));
+        }
+        self.consensus_params.validate_basic()?;
+
+        let mut total_power = 0u64;
+        for (i, validator) in self.validators.iter().enumerate() {
+            if validator.power() == 0 {
+                return Err(Error::invalid_genesis(format!(
+                    "validator {} has no voting power",
+                    validator.address
+                )));
+            }
+            let address = crate::account::Id::from(validator.pub_key);
+            if validator.address != address {
+                return Err(Error::invalid_genesis(format!(
+                    "incorrect address for validator {}, should be {}",
+                    validator.address, address
+                )));
+            }
+            if self.validators[..i]
+                .iter()
+                .any(|other| other.address == validator.address)
+            {
+                return Err(Error::invalid_genesis(format!(
+                    "duplicate validator {}",
+                    validator.address
+                )));
+            }
+            total_power = total_power.saturating_add(validator.power());
+        }
+        if total_power > validator::Set::MAX_TOTAL_VOTING_POWER {
+            return Err(Error::invalid_genesis(format!(
+                "total voting power {} exceeds the maximum {}",
+                total_power,
+                validator::Set::MAX_TOTAL_VOTING_POWER
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Computes the hash of the contents of a genesis file, which CometBFT
+/// stores on the first start of a node to refuse to restart it with another
+/// genesis.
+///
+/// The hash is that of the file as is, as the JSON encoding of a [`Genesis`]
+/// depends on formatting.
+#[cfg(feature = "rust-crypto")]
+pub fn hash(genesis_json: impl AsRef<[u8]>) -> Hash {
+    hash_with::<crate::crypto::default::Sha256>(genesis_json)
+}
+
+/// Computes the hash of the contents of a genesis file with a hasher
+/// provided by a crypto provider.
+pub fn hash_with<H: Sha256>(genesis_json: impl AsRef<[u8]>) -> Hash {
+    Hash::Sha256(H::digest(genesis_json))
+}
+
+#[cfg(all(test, feature = "rust-crypto"))]
+mod tests {
+    use ed25519_consensus::SigningKey;
+
+    use super::*;
+    use crate::PublicKey;
+
+    fn validator(seed: u8, power: u32) -> validator::Info {
+        let key = SigningKey::from([seed; 32]);
+        validator::Info::new(PublicKey::from(key.verification_key()), power.into())
+    }
+
+    fn genesis() -> Genesis {
+        Genesis::new(
+            "test-chain".parse().unwrap(),
+            Time::unix_epoch(),
+            vec![validator(1, 10), validator(2, 20)],
+            serde_json::Value::Null,
+        )
+    }
+
+    #[test]
+    fn validate() {
+        let genesis = genesis();
+        genesis.validate().unwrap();
+
+        let json = serde_json::to_string(&genesis).unwrap();
+        let parsed: Genesis = serde_json::from_str(&json).unwrap();
+        parsed.validate().unwrap();
+        assert_eq!(parsed.validators, genesis.validators);
+        assert_eq!(parsed.consensus_params, genesis.consensus_params);
+
+        let mut invalid = genesis.clone();
+        invalid.initial_height = -1;
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = genesis.clone();
+        invalid.validators.push(validator(1, 10));
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = genesis.clone();
+        invalid.validators[0].power = 0u32.into();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = genesis.clone();
+        invalid.validators[0].address = invalid.validators[1].address;
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = genesis.clone();
+        invalid.validators[0].power = validator::Set::MAX_TOTAL_VOTING_POWER.try_into().unwrap();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = genesis.clone();
+        invalid.consensus_params.evidence.max_bytes =
+            invalid.consensus_params.block.max_bytes as i64 + 1;
+        assert!(invalid.validate().is_err());
+
+        #[cfg(not(feature = "secp256k1"))]
+        {
+            let mut invalid = genesis.clone();
+            invalid
+                .consensus_params
+                .validator
+                .pub_key_types
+                .push(crate::public_key::Algorithm::Secp256k1);
+            assert!(invalid.validate().is_err());
+        }
+
+        let mut invalid = genesis;
+        invalid.consensus_params.validator.pub_key_types.clear();
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn go_genesis() {
+        // Genesis of a CometBFT 0.38 testnet, which leaves `app_state` out.
+        let json = r#"{
+            "genesis_time": "2023-05-17T14:12:48.347696215Z",
+            "chain_id": "dockerchain",
+            "initial_height": "1",
+            "consensus_params": {
+                "block": { "max_bytes": "22020096", "max_gas": "-1" },
+                "evidence": {
+                    "max_age_num_blocks": "100000",
+                    "max_age_duration": "172800000000000",
+                    "max_bytes": "1048576"
+                },
+                "validator": { "pub_key_types": ["ed25519"] },
+                "version": { "app": "0" },
+                "abci": { "vote_extensions_enable_height": "0" }
+            },
+            "validators": [
+                {
+                    "address": "2DD9F44FD9067555C322243C3C913BA7B51D2BE0",
+                    "pub_key": {
+                        "type": "tendermint/PubKeyEd25519",
+                        "value": "bNNlGls5R25wC3Sd8720F/3+7IZBhXcD22MNFtPk/v0="
+                    },
+                    "power": "10",
+                    "name": ""
+                }
+            ],
+            "app_hash": ""
+        }"#;
+        let genesis: Genesis<Option<serde_json::Value>> = serde_json::from_str(json).unwrap();
+        genesis.validate().unwrap();
+
+        // CometBFT refuses negative vote extensions enable heights and
+        // unknown public key types, which do not even decode.
+        let negative = json.replace(
+            r#""vote_extensions_enable_height": "0""#,
+            r#""vote_extensions_enable_height": "-1""#,
+        );
+        assert!(serde_json::from_str::<Genesis<Option<serde_json::Value>>>(&negative).is_err());
+        let unknown = json.replace(r#"["ed25519"]"#, r#"["ed25519", "sr25519"]"#);
+        assert!(serde_json::from_str::<Genesis<Option<serde_json::Value>>>(&unknown).is_err());
+
+        let defaults = consensus::Params::default();
+        assert_eq!(genesis.consensus_params.block, defaults.block);
+        assert_eq!(genesis.consensus_params.evidence, defaults.evidence);
+        assert_eq!(genesis.consensus_params.validator, defaults.validator);
+        assert_eq!(genesis.consensus_params.version, defaults.version);
+    }
+
+    #[test]
+    fn hash_of_genesis_file() {
+        // SHA256 of the bytes of the file, as computed by `sha256sum`.
+        assert_eq!(
+            hash(b"{}\n"),
+            "CA3D163BAB055381827226140568F3BEF7EAAC187CEBD76878E0B63E9E442356"
+                .parse::<Hash>()
+                .unwrap()
+        );
+    }
+}
@@ -298,6 +298,8 @@ impl FromStr for AppHash {
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
     #[derive(Debug, serde::Deserialize)]
@@ -371,4 +373,49 @@ mod tests {
         assert_eq!(test.hash.as_ref(), expected_hash);
         assert_eq!(test.empty_hash, Hash::None);
     }
+
+    proptest! {
+        #[test]
+        fn hash_encodings_round_trip(
+            bytes in prop::option::of(any::<[u8; SHA256_HASH_SIZE]>())
+        ) {
+            let hash = bytes.map_or(Hash::None, Hash::Sha256);
+
+            // Empty hashes only deserialize where they are allowed.
+            let json = serde_json::to_string(&hash).unwrap();
+            match hash {
+                Hash::None => prop_assert!(serde_json::from_str::<Hash>(&json).is_err()),
+                _ => prop_assert_eq!(serde_json::from_str::<Hash>(&json).unwrap(), hash),
+            }
+
+            let json = allow_empty::serialize(&hash, serde_json::value::Serializer);
+            prop_assert_eq!(allow_empty::deserialize(json.unwrap()).unwrap(), hash);
+
+            let json = crate::serializers::hash::serialize(&hash, serde_json::value::Serializer);
+            let decoded = crate::serializers::hash::deserialize(json.unwrap()).unwrap();
+            prop_assert_eq!(decoded, hash);
+
+            let bytes = Protobuf::<Vec<u8>>::encode_vec(hash);
+            prop_assert_eq!(<Hash as Protobuf<Vec<u8>>>::decode_vec(&bytes).unwrap(), hash);
+        }
+
+        #[test]
+        fn app_hash_encodings_round_trip(bytes in prop::collection::vec(any::<u8>(), 0..64)) {
+            let app_hash = AppHash(bytes);
+
+            let json = crate::serializers::apphash::serialize(&app_hash, serde_json::value::Serializer);
+            let decoded = crate::serializers::apphash::deserialize(json.unwrap()).unwrap();
+            prop_assert_eq!(&decoded, &app_hash);
+
+            let json = crate::serializers::apphash_base64::serialize(
+                &app_hash,
+                serde_json::value::Serializer,
+            );
+            let decoded = crate::serializers::apphash_base64::deserialize(json.unwrap()).unwrap();
+            prop_assert_eq!(&decoded, &app_hash);
+
+            let bytes = Protobuf::<Vec<u8>>::encode_vec(app_hash.clone());
+            prop_assert_eq!(<AppHash as Protobuf<Vec<u8>>>::decode_vec(&bytes).unwrap(), app_hash);
+        }
+    }
 }
@@ -28,6 +28,72 @@ where
     hasher.hash_byte_vectors(byte_vecs)
 }
 
+/// Compute a simple Merkle root from vectors of arbitrary byte vectors, along
+/// with a proof of inclusion of each of them.
+pub fn proofs_from_byte_vectors<H>(byte_vecs: &[impl AsRef<[u8]>]) -> (Hash, Vec<Proof>)
+where
+    H: MerkleHash + Default,
+{
+    let mut hasher = H::default();
+    let mut leaf_hashes = vec![[0u8; HASH_SIZE]; byte_vecs.len()];
+    let mut aunts = vec![Vec::new(); byte_vecs.len()];
+    let root = build_trails(&mut hasher, byte_vecs, &mut leaf_hashes, &mut aunts);
+
+    let total = byte_vecs.len() as u64;
+    let proofs = leaf_hashes
+        .into_iter()
+        .zip(aunts)
+        .enumerate()
+        .map(|(index, (leaf_hash, aunts))| Proof {
+            total,
+            index: index as u64,
+            leaf_hash: crate::Hash::Sha256(leaf_hash),
+            aunts: aunts.into_iter().map(crate::Hash::Sha256).collect(),
+        })
+        .collect();
+    (root, proofs)
+}
+
+// Hashes the subtree of the given byte vectors like `hash_byte_vectors`,
+// recording the hash of each leaf and appending the hashes of its aunts in
+// the subtree, from the leaf's sibling up.
+fn build_trails<H: MerkleHash>(
+    hasher: &mut H,
+    byte_vecs: &[impl AsRef<[u8]>],
+    leaf_hashes: &mut [Hash],
+    aunts: &mut [Vec<Hash>],
+) -> Hash {
+    match byte_vecs.len() {
+        0 => hasher.empty_hash(),
+        1 => {
+            leaf_hashes[0] = hasher.leaf_hash(byte_vecs[0].as_ref());
+            leaf_hashes[0]
+        },
+        length => {
+            let split = length.next_power_of_two() / 2;
+            let left = build_trails(
+                hasher,
+                &byte_vecs[..split],
+                &mut leaf_hashes[..split],
+                &mut aunts[..split],
+            );
+            let right = build_trails(
+                hasher,
+                &byte_vecs[split..],
+                &mut leaf_hashes[split..],
+                &mut aunts[split..],
+            );
+            for trail in &mut aunts[..split] {
+                trail.push(right);
+            }
+            for trail in &mut aunts[split..] {
+                trail.push(left);
+            }
+            hasher.inner_hash(left, right)
+        },
+    }
+}
+
 /// Implementation of Merkle tree hashing for Tendermint.
 pub trait MerkleHash {
     // tmhash({})
@@ -192,6 +258,32 @@ mod tests {
         assert_eq!(node_hash, &hash);
     }
 
+    #[test]
+    fn test_proofs_from_byte_vectors() {
+        for total in 0..=9 {
+            let items = (0..total)
+                .map(|i| format!("item-{i}").into_bytes())
+                .collect::<Vec<_>>();
+            let (root, proofs) = proofs_from_byte_vectors::<Sha256>(&items);
+            assert_eq!(root, simple_hash_from_byte_vectors::<Sha256>(&items));
+            assert_eq!(proofs.len(), total);
+
+            for (i, proof) in proofs.iter().enumerate() {
+                assert_eq!(proof.total, total as u64);
+                assert_eq!(proof.index, i as u64);
+                let root = crate::Hash::Sha256(root);
+                proof.verify::<Sha256>(&root, &items[i]).unwrap();
+                assert!(proof.verify::<Sha256>(&root, b"other").is_err());
+
+                let mut tampered = proof.clone();
+                tampered.index = (tampered.index + 1) % tampered.total;
+                if total > 1 {
+                    assert!(tampered.verify::<Sha256>(&root, &items[i]).is_err());
+                }
+            }
+        }
+    }
+
     mod non_incremental {
         use super::*;
 
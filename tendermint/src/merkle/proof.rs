@@ -3,7 +3,11 @@
 use serde::{Deserialize, Serialize};
 use tendermint_proto::v0_37::crypto::Proof as RawProof;
 
-use crate::{prelude::*, serializers, Hash};
+use crate::{
+    merkle::{self, MerkleHash},
+    prelude::*,
+    serializers, Error, Hash,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(try_from = "RawProof", into = "RawProof")]
@@ -18,6 +22,107 @@ pub struct Proof {
     pub aunts: Vec<Hash>,
 }
 
+impl Proof {
+    /// Maximum number of aunts of a proof, limiting the depth of the trees
+    /// proofs are accepted for.
+    pub const MAX_AUNTS: usize = 100;
+
+    /// Performs basic validation of the proof, without computing any hash.
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        if self.index >= self.total {
+            return Err(Error::invalid_merkle_proof(format!(
+                "index {} is out of bounds of {} items",
+                self.index, self.total
+            )));
+        }
+        if self.leaf_hash.is_empty() {
+            return Err(Error::invalid_merkle_proof("empty leaf hash".to_string()));
+        }
+        if self.aunts.len() > Self::MAX_AUNTS {
+            return Err(Error::invalid_merkle_proof(format!(
+                "expected no more than {} aunts, got {}",
+                Self::MAX_AUNTS,
+                self.aunts.len()
+            )));
+        }
+        if self.aunts.iter().any(Hash::is_empty) {
+            return Err(Error::invalid_merkle_proof("empty aunt hash".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Computes the root hash of the tree from the leaf hash and the aunts
+    /// of this proof, if they are consistent with its index and total.
+    pub fn compute_root_hash<H>(&self) -> Option<Hash>
+    where
+        H: MerkleHash + Default,
+    {
+        let Hash::Sha256(leaf_hash) = self.leaf_hash else {
+            return None;
+        };
+        let aunts = self
+            .aunts
+            .iter()
+            .map(|aunt| match aunt {
+                Hash::Sha256(hash) => Some(*hash),
+                Hash::None => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        compute_hash_from_aunts(&mut H::default(), self.index, self.total, leaf_hash, &aunts)
+            .map(Hash::Sha256)
+    }
+
+    /// Verifies that `leaf` is the item at the index of this proof in the
+    /// tree with the given root hash.
+    pub fn verify<H>(&self, root_hash: &Hash, leaf: &[u8]) -> Result<(), Error>
+    where
+        H: MerkleHash + Default,
+    {
+        self.validate_basic()?;
+        if self.leaf_hash != Hash::Sha256(H::default().leaf_hash(leaf)) {
+            return Err(Error::invalid_merkle_proof(
+                "leaf hash does not match the leaf".to_string(),
+            ));
+        }
+        match self.compute_root_hash::<H>() {
+            Some(computed) if computed == *root_hash => Ok(()),
+            Some(_) => Err(Error::invalid_merkle_proof(
+                "computed root hash does not match the root hash".to_string(),
+            )),
+            None => Err(Error::invalid_merkle_proof(
+                "aunts are inconsistent with the index and total".to_string(),
+            )),
+        }
+    }
+}
+
+// Hashes the leaf hash with its aunts, from the leaf's sibling up, following
+// the split of the tree in `MerkleHash::hash_byte_vectors`.
+fn compute_hash_from_aunts<H: MerkleHash>(
+    hasher: &mut H,
+    index: u64,
+    total: u64,
+    leaf_hash: merkle::Hash,
+    aunts: &[merkle::Hash],
+) -> Option<merkle::Hash> {
+    if index >= total {
+        return None;
+    }
+    if total == 1 {
+        return aunts.is_empty().then_some(leaf_hash);
+    }
+    let (last, rest) = aunts.split_last()?;
+    let num_left = total.next_power_of_two() / 2;
+    if index < num_left {
+        let left = compute_hash_from_aunts(hasher, index, num_left, leaf_hash, rest)?;
+        Some(hasher.inner_hash(left, *last))
+    } else {
+        let right =
+            compute_hash_from_aunts(hasher, index - num_left, total - num_left, leaf_hash, rest)?;
+        Some(hasher.inner_hash(*last, right))
+    }
+}
+
 /// Merkle proof defined by the list of ProofOps
 /// <https://github.com/tendermint/tendermint/blob/c8483531d8e756f7fbb812db1dd16d841cdf298a/crypto/merkle/merkle.proto#L26>
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -1,4 +1,4 @@
-//! Node information (used in RPC responses)
+//! Node information (used in RPC responses and exchanged by peers)
 
 use core::fmt::{self, Display};
 
@@ -102,3 +102,74 @@ impl From<TxIndexStatus> for bool {
         }
     }
 }
+
+tendermint_pb_modules! {
+    use super::{Info, ListenAddress, OtherInfo, ProtocolVersionInfo, TxIndexStatus};
+    use crate::{channel::Channels, prelude::*, Error};
+    use pb::p2p::{
+        DefaultNodeInfo as RawDefaultNodeInfo, DefaultNodeInfoOther as RawDefaultNodeInfoOther,
+        ProtocolVersion as RawProtocolVersion,
+    };
+
+    impl Protobuf<RawDefaultNodeInfo> for Info {}
+
+    impl TryFrom<RawDefaultNodeInfo> for Info {
+        type Error = Error;
+
+        fn try_from(value: RawDefaultNodeInfo) -> Result<Self, Self::Error> {
+            let protocol_version = value.protocol_version.ok_or_else(Error::missing_version)?;
+            let other = value.other.unwrap_or_default();
+            let tx_index = match other.tx_index.as_str() {
+                "" | "on" => TxIndexStatus::On,
+                "off" => TxIndexStatus::Off,
+                status => {
+                    return Err(Error::parse(format!("invalid tx_index status: {status}")));
+                },
+            };
+
+            Ok(Info {
+                protocol_version: ProtocolVersionInfo {
+                    p2p: protocol_version.p2p,
+                    block: protocol_version.block,
+                    app: protocol_version.app,
+                },
+                id: value.default_node_id.parse()?,
+                listen_addr: ListenAddress::new(value.listen_addr),
+                network: value.network.try_into()?,
+                version: value.version.into(),
+                channels: Channels::new(value.channels),
+                moniker: value.moniker.parse()?,
+                other: OtherInfo {
+                    tx_index,
+                    rpc_address: other.rpc_address,
+                },
+            })
+        }
+    }
+
+    impl From<Info> for RawDefaultNodeInfo {
+        fn from(value: Info) -> Self {
+            RawDefaultNodeInfo {
+                protocol_version: Some(RawProtocolVersion {
+                    p2p: value.protocol_version.p2p,
+                    block: value.protocol_version.block,
+                    app: value.protocol_version.app,
+                }),
+                default_node_id: value.id.to_string(),
+                listen_addr: value.listen_addr.to_string(),
+                network: value.network.to_string(),
+                version: value.version.into(),
+                channels: value.channels.ids().to_vec(),
+                moniker: value.moniker.to_string(),
+                other: Some(RawDefaultNodeInfoOther {
+                    tx_index: match value.other.tx_index {
+                        TxIndexStatus::On => "on",
+                        TxIndexStatus::Off => "off",
+                    }
+                    .to_string(),
+                    rpc_address: value.other.rpc_address,
+                }),
+            }
+        }
+    }
+}
@@ -4,6 +4,9 @@ pub use crate::crypto::ed25519::SigningKey as Ed25519;
 #[cfg(feature = "secp256k1")]
 pub use k256::ecdsa::SigningKey as Secp256k1;
 
+#[cfg(feature = "bls12381")]
+pub use crate::crypto::bls12381::SigningKey as Bls12381;
+
 use crate::prelude::*;
 
 #[cfg(feature = "rust-crypto")]
@@ -18,6 +21,8 @@ use zeroize::Zeroizing;
 
 pub const ED25519_KEYPAIR_SIZE: usize = 64;
 pub const SECP256K1_KEY_SIZE: usize = 32;
+#[cfg(feature = "bls12381")]
+pub const BLS12381_KEY_SIZE: usize = crate::crypto::bls12381::SIGNING_KEY_SIZE;
 
 /// Private keys as parsed from configuration files
 #[cfg_attr(feature = "rust-crypto", derive(Serialize, Deserialize))]
@@ -43,6 +48,15 @@ pub enum PrivateKey {
         deserialize_with = "deserialize_secp256k1_privkey"
     )]
     Secp256k1(Secp256k1),
+
+    #[cfg(feature = "bls12381")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bls12381")))]
+    #[serde(
+        rename = "tendermint/PrivKeyBls12_381",
+        serialize_with = "serialize_bls12381_privkey",
+        deserialize_with = "deserialize_bls12381_privkey"
+    )]
+    Bls12381(Bls12381),
 }
 
 impl PrivateKey {
@@ -56,6 +70,11 @@ impl PrivateKey {
             PrivateKey::Secp256k1(signing_key) => {
                 PublicKey::Secp256k1(*signing_key.verifying_key())
             },
+
+            #[cfg(feature = "bls12381")]
+            PrivateKey::Bls12381(signing_key) => {
+                PublicKey::Bls12381(signing_key.verification_key())
+            },
         }
     }
 
@@ -66,6 +85,9 @@ impl PrivateKey {
 
             #[cfg(feature = "secp256k1")]
             PrivateKey::Secp256k1(_signing_key) => None,
+
+            #[cfg(feature = "bls12381")]
+            PrivateKey::Bls12381(_signing_key) => None,
         }
     }
 
@@ -116,6 +138,38 @@ where
     Ok(signing_key)
 }
 
+/// Serialize a BLS12-381 privkey as Base64
+#[cfg(feature = "bls12381")]
+fn serialize_bls12381_privkey<S>(signing_key: &Bls12381, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    Zeroizing::new(
+        String::from_utf8(Base64::default().encode(&signing_key.to_bytes()[..])).unwrap(),
+    )
+    .serialize(serializer)
+}
+
+/// Deserialize a BLS12-381 privkey from Base64
+#[cfg(feature = "bls12381")]
+fn deserialize_bls12381_privkey<'de, D>(deserializer: D) -> Result<Bls12381, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    use de::Error;
+    let string = Zeroizing::new(String::deserialize(deserializer)?);
+    let mut privkey_bytes = Zeroizing::new([0u8; BLS12381_KEY_SIZE]);
+    let decoded_len = Base64::default()
+        .decode_to_slice(string.as_bytes(), &mut *privkey_bytes)
+        .map_err(D::Error::custom)?;
+
+    if decoded_len != BLS12381_KEY_SIZE {
+        return Err(D::Error::custom("invalid bls12_381 privkey size"));
+    }
+
+    Bls12381::try_from(&privkey_bytes[..]).map_err(|_| D::Error::custom("invalid signing key"))
+}
+
 /// Serialize an Ed25519 keypair as Base64
 #[cfg(feature = "rust-crypto")]
 fn serialize_ed25519_keypair<S>(signing_key: &Ed25519, serializer: S) -> Result<S::Ok, S::Error>
@@ -2,7 +2,7 @@
 //!
 //! [ADR-063]: https://github.com/tendermint/tendermint/blob/main/docs/architecture/adr-063-privval-grpc.md
 
-use crate::prelude::*;
+use crate::{chain, prelude::*, Proposal, PublicKey, Vote};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RemoteSignerError {
@@ -10,6 +10,26 @@ pub struct RemoteSignerError {
     pub description: String,
 }
 
+/// Signs consensus messages on behalf of a validator, as answered to the requests of the
+/// privval protocol.
+///
+/// Implementations are responsible for refusing to sign conflicting messages.
+pub trait Signer {
+    /// Returns the public key of the validator on the given chain.
+    fn public_key(&self, chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError>;
+
+    /// Signs the vote for the given chain, returning it with its signature set, along with the
+    /// signature of its extension for non-nil precommits.
+    fn sign_vote(&mut self, chain_id: &chain::Id, vote: Vote) -> Result<Vote, RemoteSignerError>;
+
+    /// Signs the proposal for the given chain, returning it with its signature set.
+    fn sign_proposal(
+        &mut self,
+        chain_id: &chain::Id,
+        proposal: Proposal,
+    ) -> Result<Proposal, RemoteSignerError>;
+}
+
 // =============================================================================
 // Protobuf conversions
 // =============================================================================
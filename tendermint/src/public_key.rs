@@ -3,6 +3,9 @@
 #[cfg(feature = "secp256k1")]
 pub use k256::ecdsa::VerifyingKey as Secp256k1;
 
+#[cfg(feature = "bls12381")]
+pub use crate::crypto::bls12381::VerificationKey as Bls12381;
+
 mod pub_key_request;
 mod pub_key_response;
 
@@ -49,6 +52,16 @@ pub enum PublicKey {
         deserialize_with = "deserialize_secp256k1_base64"
     )]
     Secp256k1(Secp256k1),
+
+    /// BLS12-381 keys
+    #[cfg(feature = "bls12381")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bls12381")))]
+    #[serde(
+        rename = "tendermint/PubKeyBls12_381",
+        serialize_with = "serialize_bls12381_base64",
+        deserialize_with = "deserialize_bls12381_base64"
+    )]
+    Bls12381(Bls12381),
 }
 
 // Internal thunk type to facilitate deserialization from the raw Protobuf data
@@ -65,6 +78,8 @@ impl From<ProtobufPublicKeyWrapper> for PublicKey {
             ProtobufPublicKey::Ed25519 { ed25519 } => PublicKey::Ed25519(ed25519),
             #[cfg(feature = "secp256k1")]
             ProtobufPublicKey::Secp256k1 { secp256k1 } => PublicKey::Secp256k1(secp256k1),
+            #[cfg(feature = "bls12381")]
+            ProtobufPublicKey::Bls12381 { bls12381 } => PublicKey::Bls12381(bls12381),
         }
     }
 }
@@ -90,6 +105,16 @@ enum ProtobufPublicKey {
         )]
         secp256k1: Secp256k1,
     },
+
+    #[cfg(feature = "bls12381")]
+    #[serde(rename = "tendermint.crypto.PublicKey_Bls12381")]
+    Bls12381 {
+        #[serde(
+            serialize_with = "serialize_bls12381_base64",
+            deserialize_with = "deserialize_bls12381_base64"
+        )]
+        bls12381: Bls12381,
+    },
 }
 
 /// Custom deserialization for public keys to handle multiple potential JSON
@@ -121,42 +146,99 @@ tendermint_pb_modules! {
 
     impl Protobuf<RawPublicKey> for PublicKey {}
 
-    impl TryFrom<RawPublicKey> for PublicKey {
-        type Error = Error;
+    /// Decodes an Ed25519 or secp256k1 key, which every protocol version has.
+    pub(super) fn decode_key(value: &RawPublicKey) -> Result<PublicKey, Error> {
+        let sum = value
+            .sum
+            .as_ref()
+            .ok_or_else(|| Error::invalid_key("empty sum".to_string()))?;
+        if let Sum::Ed25519(b) = sum {
+            let key = Ed25519::try_from(&b[..])?;
+            return Ok(PublicKey::Ed25519(key));
+        }
+        #[cfg(feature = "secp256k1")]
+        if let Sum::Secp256k1(b) = sum {
+            return PublicKey::from_raw_secp256k1(b)
+                .ok_or_else(|| Error::invalid_key("malformed key".to_string()));
+        }
+        Err(Error::invalid_key("not an ed25519 key".to_string()))
+    }
 
-        fn try_from(value: RawPublicKey) -> Result<Self, Self::Error> {
-            let sum = &value
-                .sum
-                .ok_or_else(|| Error::invalid_key("empty sum".to_string()))?;
-            if let Sum::Ed25519(b) = sum {
-                let key = Ed25519::try_from(&b[..])?;
-                return Ok(PublicKey::Ed25519(key));
-            }
+    /// Encodes an Ed25519 or secp256k1 key, which every protocol version has.
+    /// Other keys are encoded as the empty key, whose decoding fails.
+    pub(super) fn encode_key(value: PublicKey) -> RawPublicKey {
+        match value {
+            PublicKey::Ed25519(ref pk) => RawPublicKey {
+                sum: Some(Sum::Ed25519(
+                    pk.as_bytes().to_vec(),
+                )),
+            },
             #[cfg(feature = "secp256k1")]
-            if let Sum::Secp256k1(b) = sum {
-                return Self::from_raw_secp256k1(b)
-                    .ok_or_else(|| Error::invalid_key("malformed key".to_string()));
-            }
-            Err(Error::invalid_key("not an ed25519 key".to_string()))
+            PublicKey::Secp256k1(ref pk) => RawPublicKey {
+                sum: Some(Sum::Secp256k1(
+                    pk.to_sec1_bytes().into(),
+                )),
+            },
+            #[cfg(feature = "bls12381")]
+            PublicKey::Bls12381(_) => RawPublicKey { sum: None },
         }
     }
+}
 
-    impl From<PublicKey> for RawPublicKey {
-        fn from(value: PublicKey) -> Self {
-            match value {
-                PublicKey::Ed25519(ref pk) => RawPublicKey {
-                    sum: Some(Sum::Ed25519(
-                        pk.as_bytes().to_vec(),
-                    )),
-                },
-                #[cfg(feature = "secp256k1")]
-                PublicKey::Secp256k1(ref pk) => RawPublicKey {
-                    sum: Some(Sum::Secp256k1(
-                        pk.to_sec1_bytes().into(),
-                    )),
-                },
-            }
+impl TryFrom<tendermint_proto::v0_34::crypto::PublicKey> for PublicKey {
+    type Error = Error;
+
+    fn try_from(value: tendermint_proto::v0_34::crypto::PublicKey) -> Result<Self, Self::Error> {
+        v0_34::decode_key(&value)
+    }
+}
+
+impl From<PublicKey> for tendermint_proto::v0_34::crypto::PublicKey {
+    fn from(value: PublicKey) -> Self {
+        v0_34::encode_key(value)
+    }
+}
+
+impl TryFrom<tendermint_proto::v0_37::crypto::PublicKey> for PublicKey {
+    type Error = Error;
+
+    fn try_from(value: tendermint_proto::v0_37::crypto::PublicKey) -> Result<Self, Self::Error> {
+        v0_37::decode_key(&value)
+    }
+}
+
+impl From<PublicKey> for tendermint_proto::v0_37::crypto::PublicKey {
+    fn from(value: PublicKey) -> Self {
+        v0_37::encode_key(value)
+    }
+}
+
+/// Only this protocol version has BLS12-381 keys.
+impl TryFrom<tendermint_proto::v0_38::crypto::PublicKey> for PublicKey {
+    type Error = Error;
+
+    fn try_from(value: tendermint_proto::v0_38::crypto::PublicKey) -> Result<Self, Self::Error> {
+        #[cfg(feature = "bls12381")]
+        if let Some(tendermint_proto::v0_38::crypto::public_key::Sum::Bls12381(b)) = &value.sum {
+            let key = Bls12381::try_from(&b[..])?;
+            return Ok(PublicKey::Bls12381(key));
         }
+        v0_38::decode_key(&value)
+    }
+}
+
+/// Only this protocol version has BLS12-381 keys.
+impl From<PublicKey> for tendermint_proto::v0_38::crypto::PublicKey {
+    fn from(value: PublicKey) -> Self {
+        #[cfg(feature = "bls12381")]
+        if let PublicKey::Bls12381(ref pk) = value {
+            return Self {
+                sum: Some(tendermint_proto::v0_38::crypto::public_key::Sum::Bls12381(
+                    pk.as_bytes().to_vec(),
+                )),
+            };
+        }
+        v0_38::encode_key(value)
     }
 }
 
@@ -170,6 +252,13 @@ impl PublicKey {
             .map(PublicKey::Secp256k1)
     }
 
+    /// From raw compressed BLS12-381 public key bytes
+    #[cfg(feature = "bls12381")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bls12381")))]
+    pub fn from_raw_bls12381(bytes: &[u8]) -> Option<PublicKey> {
+        Bls12381::try_from(bytes).map(PublicKey::Bls12381).ok()
+    }
+
     /// From raw Ed25519 public key bytes
     pub fn from_raw_ed25519(bytes: &[u8]) -> Option<PublicKey> {
         Ed25519::try_from(bytes).map(PublicKey::Ed25519).ok()
@@ -200,12 +289,24 @@ impl PublicKey {
         }
     }
 
+    /// Get BLS12-381 public key
+    #[cfg(feature = "bls12381")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bls12381")))]
+    pub fn bls12381(self) -> Option<Bls12381> {
+        match self {
+            PublicKey::Bls12381(pk) => Some(pk),
+            _ => None,
+        }
+    }
+
     /// Serialize this key as a byte vector.
     pub fn to_bytes(self) -> Vec<u8> {
         match self {
             PublicKey::Ed25519(pk) => pk.as_bytes().to_vec(),
             #[cfg(feature = "secp256k1")]
             PublicKey::Secp256k1(pk) => pk.to_sec1_bytes().into(),
+            #[cfg(feature = "bls12381")]
+            PublicKey::Bls12381(pk) => pk.as_bytes().to_vec(),
         }
     }
 
@@ -223,6 +324,12 @@ impl PublicKey {
                 key_bytes.extend(pk.to_sec1_bytes().as_ref());
                 key_bytes
             },
+            #[cfg(feature = "bls12381")]
+            PublicKey::Bls12381(ref pk) => {
+                let mut key_bytes = vec![0xD4, 0x87, 0xF3, 0xC9, 0x30];
+                key_bytes.extend(pk.as_bytes());
+                key_bytes
+            },
         };
         bech32::encode(hrp, backward_compatible_amino_prefixed_pubkey)
     }
@@ -246,6 +353,13 @@ impl From<Secp256k1> for PublicKey {
     }
 }
 
+#[cfg(feature = "bls12381")]
+impl From<Bls12381> for PublicKey {
+    fn from(pk: Bls12381) -> PublicKey {
+        PublicKey::Bls12381(pk)
+    }
+}
+
 #[cfg(feature = "rust-crypto")]
 impl From<ed25519_consensus::VerificationKey> for PublicKey {
     fn from(vk: ed25519_consensus::VerificationKey) -> PublicKey {
@@ -261,18 +375,27 @@ impl PartialOrd for PublicKey {
 
 impl Ord for PublicKey {
     fn cmp(&self, other: &Self) -> Ordering {
+        #[allow(unreachable_patterns)]
+        match (self, other) {
+            (PublicKey::Ed25519(a), PublicKey::Ed25519(b)) => a.as_bytes().cmp(b.as_bytes()),
+            #[cfg(feature = "secp256k1")]
+            (PublicKey::Secp256k1(a), PublicKey::Secp256k1(b)) => a.cmp(b),
+            #[cfg(feature = "bls12381")]
+            (PublicKey::Bls12381(a), PublicKey::Bls12381(b)) => a.as_bytes().cmp(b.as_bytes()),
+            // Keys of different types are ordered by type
+            _ => self.algorithm_rank().cmp(&other.algorithm_rank()),
+        }
+    }
+}
+
+impl PublicKey {
+    fn algorithm_rank(&self) -> u8 {
         match self {
-            PublicKey::Ed25519(a) => match other {
-                PublicKey::Ed25519(b) => a.as_bytes().cmp(b.as_bytes()),
-                #[cfg(feature = "secp256k1")]
-                PublicKey::Secp256k1(_) => Ordering::Less,
-            },
+            PublicKey::Ed25519(_) => 0,
             #[cfg(feature = "secp256k1")]
-            PublicKey::Secp256k1(a) => match other {
-                PublicKey::Ed25519(_) => Ordering::Greater,
-                #[cfg(feature = "secp256k1")]
-                PublicKey::Secp256k1(b) => a.cmp(b),
-            },
+            PublicKey::Secp256k1(_) => 1,
+            #[cfg(feature = "bls12381")]
+            PublicKey::Bls12381(_) => 2,
         }
     }
 }
@@ -294,6 +417,10 @@ impl TendermintKey {
             PublicKey::Ed25519(_) => Ok(TendermintKey::AccountKey(public_key)),
             #[cfg(feature = "secp256k1")]
             PublicKey::Secp256k1(_) => Ok(TendermintKey::AccountKey(public_key)),
+            #[cfg(feature = "bls12381")]
+            PublicKey::Bls12381(_) => Err(Error::invalid_key(
+                "bls12_381 keys can only be used as consensus keys".to_string(),
+            )),
         }
     }
 
@@ -304,9 +431,11 @@ impl TendermintKey {
             PublicKey::Ed25519(_) => Ok(TendermintKey::AccountKey(public_key)),
             #[cfg(feature = "secp256k1")]
             PublicKey::Secp256k1(_) => Ok(TendermintKey::AccountKey(public_key)),
+            #[cfg(feature = "bls12381")]
+            PublicKey::Bls12381(_) => Ok(TendermintKey::ConsensusKey(public_key)),
 
             _ => Err(Error::invalid_key(
-                "only ed25519 or secp256k1 consensus keys are supported".to_string(),
+                "only ed25519, secp256k1 or bls12_381 consensus keys are supported".to_string(),
             )),
         }
     }
@@ -328,6 +457,11 @@ pub enum Algorithm {
 
     /// secp256k1
     Secp256k1,
+
+    /// bls12_381
+    #[cfg(feature = "bls12381")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bls12381")))]
+    Bls12381,
 }
 
 impl Algorithm {
@@ -336,6 +470,8 @@ impl Algorithm {
         match self {
             Algorithm::Ed25519 => "ed25519",
             Algorithm::Secp256k1 => "secp256k1",
+            #[cfg(feature = "bls12381")]
+            Algorithm::Bls12381 => "bls12_381",
         }
     }
 }
@@ -353,6 +489,8 @@ impl FromStr for Algorithm {
         match s {
             "ed25519" => Ok(Algorithm::Ed25519),
             "secp256k1" => Ok(Algorithm::Secp256k1),
+            #[cfg(feature = "bls12381")]
+            "bls12_381" => Ok(Algorithm::Bls12381),
             _ => Err(Error::parse(format!("invalid algorithm: {s}"))),
         }
     }
@@ -393,6 +531,17 @@ where
         .serialize(serializer)
 }
 
+/// Serialize the bytes of a BLS12-381 public key as Base64. Used for serializing JSON
+#[cfg(feature = "bls12381")]
+fn serialize_bls12381_base64<S>(pk: &Bls12381, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    String::from_utf8(base64::encode(pk.as_bytes()))
+        .unwrap()
+        .serialize(serializer)
+}
+
 fn deserialize_ed25519_base64<'de, D>(deserializer: D) -> Result<Ed25519, D::Error>
 where
     D: Deserializer<'de>,
@@ -414,8 +563,20 @@ where
     Secp256k1::from_sec1_bytes(&bytes).map_err(|_| D::Error::custom("invalid secp256k1 key"))
 }
 
+#[cfg(feature = "bls12381")]
+fn deserialize_bls12381_base64<'de, D>(deserializer: D) -> Result<Bls12381, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use de::Error;
+    let encoded = String::deserialize(deserializer)?;
+    let bytes = base64::decode(encoded).map_err(D::Error::custom)?;
+    Bls12381::try_from(&bytes[..]).map_err(|_| D::Error::custom("invalid bls12_381 key"))
+}
+
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
     use subtle_encoding::hex;
 
     use super::{PublicKey, TendermintKey};
@@ -466,6 +627,15 @@ mod tests {
         );
     }
 
+    proptest! {
+        #[test]
+        fn ed25519_json_round_trips(bytes in any::<[u8; 32]>()) {
+            let pubkey = PublicKey::from_raw_ed25519(&bytes).unwrap();
+            let json = serde_json::to_string(&pubkey).unwrap();
+            prop_assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), pubkey);
+        }
+    }
+
     #[test]
     fn json_parsing() {
         let json_string = "{\"type\":\"tendermint/PubKeyEd25519\",\"value\":\"RblzMO4is5L1hZz6wo4kPbptzOyue6LTk4+lPhD1FRk=\"}";
@@ -483,6 +653,66 @@ mod tests {
         assert_eq!(reserialized_json.as_str(), json_string);
     }
 
+    #[test]
+    #[cfg(feature = "bls12381")]
+    fn bls12381_encodings() {
+        use tendermint_proto::{v0_38::crypto::PublicKey as RawPublicKey, Protobuf};
+
+        use crate::{account, crypto::bls12381::SigningKey};
+
+        let signing_key = SigningKey::try_from(&[1; 32][..]).unwrap();
+        let pubkey = PublicKey::Bls12381(signing_key.verification_key());
+        let bytes = pubkey.to_bytes();
+        assert_eq!(bytes.len(), 48);
+
+        let json = serde_json::to_string(&pubkey).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                "{{\"type\":\"tendermint/PubKeyBls12_381\",\"value\":\"{}\"}}",
+                String::from_utf8(subtle_encoding::base64::encode(&bytes)).unwrap()
+            )
+        );
+        assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), pubkey);
+
+        let raw = Protobuf::<RawPublicKey>::encode_vec(pubkey);
+        // Field 3, length-delimited, 48 bytes
+        assert_eq!(raw[..2], [0x1a, 0x30]);
+        assert_eq!(
+            <PublicKey as Protobuf<RawPublicKey>>::decode_vec(&raw).unwrap(),
+            pubkey
+        );
+
+        let digest = <sha2::Sha256 as sha2::Digest>::digest(&bytes);
+        assert_eq!(account::Id::from(pubkey).as_bytes(), &digest[..20]);
+        assert_eq!(
+            PublicKey::from_raw_bls12381(&bytes).unwrap().bls12381(),
+            pubkey.bls12381()
+        );
+        assert!(TendermintKey::new_consensus_key(pubkey).is_ok());
+        assert!(TendermintKey::new_account_key(pubkey).is_err());
+
+        // The v0.34 protocol has no BLS12-381 keys.
+        use tendermint_proto::v0_34::crypto::PublicKey as RawLegacyPublicKey;
+        let raw = Protobuf::<RawLegacyPublicKey>::encode_vec(pubkey);
+        assert!(<PublicKey as Protobuf<RawLegacyPublicKey>>::decode_vec(&raw).is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "bls12381"))]
+    fn rejects_bls12381_keys_without_feature() {
+        use tendermint_proto::v0_38::crypto::{public_key::Sum, PublicKey as RawPublicKey};
+
+        use crate::account;
+
+        let raw = RawPublicKey {
+            sum: Some(Sum::Bls12381(vec![0xa0; 48])),
+        };
+        assert!(PublicKey::try_from(raw.clone()).is_err());
+        assert!(account::Id::try_from(raw).is_err());
+        assert!("bls12_381".parse::<super::Algorithm>().is_err());
+    }
+
     tendermint_pb_modules! {
         use super::*;
         use pb::privval::PubKeyResponse as RawPubKeyResponse;
@@ -535,5 +765,17 @@ mod tests {
             ).unwrap();
             assert_eq!(decoded, msg);
         }
+
+        proptest! {
+            #[test]
+            fn ed25519_protobuf_round_trips(bytes in any::<[u8; 32]>()) {
+                use pb::crypto::PublicKey as RawPublicKey;
+
+                let pubkey = PublicKey::from_raw_ed25519(&bytes).unwrap();
+                let encoded = Protobuf::<RawPublicKey>::encode_vec(pubkey);
+                let decoded = <PublicKey as Protobuf<RawPublicKey>>::decode_vec(&encoded).unwrap();
+                prop_assert_eq!(decoded, pubkey);
+            }
+        }
     }
 }
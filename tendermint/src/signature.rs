@@ -10,9 +10,13 @@ use tendermint_proto::Protobuf;
 
 use crate::{error::Error, prelude::*};
 
-/// The expected length of all currently supported signatures, in bytes.
+/// The expected length of Ed25519 and secp256k1 signatures, in bytes.
 pub const SIGNATURE_LENGTH: usize = 64;
 
+/// The expected length of BLS12-381 signatures, in bytes.
+#[cfg(feature = "bls12381")]
+pub const BLS12381_SIGNATURE_LENGTH: usize = 96;
+
 /// Signatures
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Signature(Vec<u8>);
@@ -59,11 +63,21 @@ impl Signature {
     /// Create a new signature from the given byte array, if non-empty.
     ///
     /// If the given byte array is empty, returns `Ok(None)`.
+    ///
+    /// Signatures are decoded without knowing the type of the key which made
+    /// them, so with the `bls12381` feature, signatures of either
+    /// [`SIGNATURE_LENGTH`] or `BLS12381_SIGNATURE_LENGTH` bytes are
+    /// accepted. The length expected for the type of the key is checked when
+    /// the signature is verified.
     pub fn new<B: AsRef<[u8]>>(bytes: B) -> Result<Option<Self>, Error> {
         let bytes = bytes.as_ref();
         if bytes.is_empty() {
             return Ok(None);
         }
+        #[cfg(feature = "bls12381")]
+        if bytes.len() == BLS12381_SIGNATURE_LENGTH {
+            return Ok(Some(Self(bytes.to_vec())));
+        }
         if bytes.len() != SIGNATURE_LENGTH {
             return Err(Error::signature_invalid(format!(
                 "expected signature to be {} bytes long, but was {} bytes",
@@ -280,6 +280,16 @@ mod tests {
             prop_assert_eq!(time, decoded_time);
         }
 
+        #[test]
+        fn protobuf_decode_is_the_inverse_of_encode_within_reasonable_time_range(
+            datetime in pbt::time::arb_protobuf_safe_datetime()
+        ) {
+            let time: Time = datetime.try_into().unwrap();
+            let bytes = Protobuf::<Timestamp>::encode_vec(time);
+            let decoded_time = <Time as Protobuf<Timestamp>>::decode_vec(&bytes).unwrap();
+            prop_assert_eq!(time, decoded_time);
+        }
+
         #[test]
         fn serde_of_rfc3339_timestamps_is_safe(
             stamp in prop_oneof![
@@ -32,6 +32,10 @@ pub struct Set {
 impl Set {
     pub const MAX_TOTAL_VOTING_POWER: u64 = (i64::MAX / 8) as u64;
 
+    /// Maximal difference between proposer priorities, relative to the total
+    /// voting power.
+    const PRIORITY_WINDOW_SIZE_FACTOR: i64 = 2;
+
     /// Constructor
     pub fn new(validators: Vec<Info>, proposer: Option<Info>) -> Set {
         Self::try_from_parts(validators, proposer, 0).unwrap()
@@ -124,6 +128,262 @@ impl Set {
             .cloned()
     }
 
+    /// Returns the validator with the highest proposer priority, the one with
+    /// the lowest address among validators with the same priority.
+    pub fn find_proposer(&self) -> Option<&Info> {
+        self.validators
+            .iter()
+            .max_by_key(|v| (v.proposer_priority, core::cmp::Reverse(v.address)))
+    }
+
+    /// Advances the proposer priorities by `times` rounds, and makes the
+    /// proposer of the last round the proposer of this set.
+    ///
+    /// As in CometBFT, the priorities are first rescaled so that they differ by
+    /// at most twice the total voting power, and centered around zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is empty or `times` is zero.
+    pub fn increment_proposer_priority(&mut self, times: u32) {
+        assert!(!self.validators.is_empty(), "empty validator set");
+        assert!(
+            times > 0,
+            "cannot increment proposer priority a non-positive number of times"
+        );
+
+        self.rescale_priorities(Self::PRIORITY_WINDOW_SIZE_FACTOR * self.total_power());
+        self.shift_by_avg_proposer_priority();
+
+        let mut proposer = 0;
+        for _ in 0..times {
+            proposer = self.increment_proposer_priority_once();
+        }
+        self.proposer = Some(self.validators[proposer].clone());
+    }
+
+    /// Applies a set of changes to this validator set, as CometBFT does with
+    /// the validator updates returned by the application.
+    ///
+    /// A change with zero voting power removes the validator with the same
+    /// address, any other change adds or updates a validator. New validators
+    /// start with a priority of about -1.125 times the total voting power, so
+    /// that validators can't reset a negative priority by unbonding and
+    /// bonding again. The priorities are then rescaled and centered.
+    ///
+    /// The set is left unchanged if an error is returned.
+    pub fn update_with_change_set(&mut self, changes: &[Info]) -> Result<(), Error> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let (mut updates, removals) = Self::process_changes(changes)?;
+
+        let num_new_validators = updates
+            .iter()
+            .filter(|update| !self.has_address(update.address))
+            .count();
+        if num_new_validators == 0 && self.validators.len() == removals.len() {
+            return Err(Error::invalid_validator_update(
+                "applying the validator changes would result in empty set".into(),
+            ));
+        }
+
+        let removed_power = self.verify_removals(&removals)?;
+        let updated_total_power = self.verify_updates(&updates, removed_power)?;
+
+        for update in &mut updates {
+            update.proposer_priority = match self.validator(update.address) {
+                Some(validator) => validator.proposer_priority,
+                // Cannot overflow, as the updated total voting power is below
+                // twice the maximum total voting power.
+                None => (-(updated_total_power + (updated_total_power >> 3))).into(),
+            };
+        }
+
+        for update in updates {
+            match self
+                .validators
+                .iter_mut()
+                .find(|v| v.address == update.address)
+            {
+                Some(validator) => *validator = update,
+                None => self.validators.push(update),
+            }
+        }
+        self.validators
+            .retain(|v| !removals.iter().any(|removal| removal.address == v.address));
+
+        let total_voting_power = self.validators.iter().map(Info::power).sum::<u64>();
+        self.total_voting_power = total_voting_power.try_into()?;
+
+        self.rescale_priorities(Self::PRIORITY_WINDOW_SIZE_FACTOR * self.total_power());
+        self.shift_by_avg_proposer_priority();
+        Self::sort_validators(&mut self.validators);
+
+        Ok(())
+    }
+
+    /// Applies validator updates, such as the ones returned by the application
+    /// at the end of a block, with [`Set::update_with_change_set`].
+    #[cfg(feature = "rust-crypto")]
+    pub fn apply_updates(&mut self, updates: &[Update]) -> Result<(), Error> {
+        let changes = updates
+            .iter()
+            .map(|update| Info::new(update.pub_key, update.power))
+            .collect::<Vec<_>>();
+        self.update_with_change_set(&changes)
+    }
+
+    fn total_power(&self) -> i64 {
+        // Cannot overflow, as it is at most `MAX_TOTAL_VOTING_POWER`.
+        self.total_voting_power.value() as i64
+    }
+
+    fn has_address(&self, address: account::Id) -> bool {
+        self.validators.iter().any(|v| v.address == address)
+    }
+
+    /// Divides the proposer priorities so that they differ by at most
+    /// `diff_max`.
+    fn rescale_priorities(&mut self, diff_max: i64) {
+        if diff_max <= 0 || self.validators.is_empty() {
+            return;
+        }
+
+        // Computed with 128-bit integers, as the difference of two priorities
+        // can exceed `i64::MAX`.
+        let priorities = self.validators.iter().map(|v| v.proposer_priority.value());
+        let max = priorities.clone().max().unwrap_or_default();
+        let min = priorities.min().unwrap_or_default();
+        let diff = i128::from(max) - i128::from(min);
+        let diff_max = i128::from(diff_max);
+        if diff > diff_max {
+            let ratio = (diff + diff_max - 1) / diff_max;
+            for validator in &mut self.validators {
+                let priority = i128::from(validator.proposer_priority.value()) / ratio;
+                validator.proposer_priority = (priority as i64).into();
+            }
+        }
+    }
+
+    /// Centers the proposer priorities around zero.
+    fn shift_by_avg_proposer_priority(&mut self) {
+        if self.validators.is_empty() {
+            return;
+        }
+
+        let sum = self
+            .validators
+            .iter()
+            .map(|v| i128::from(v.proposer_priority.value()))
+            .sum::<i128>();
+        // The average of 64-bit integers fits in 64 bits. Go rounds the
+        // division of big integers towards negative infinity.
+        let avg = sum.div_euclid(self.validators.len() as i128) as i64;
+        for validator in &mut self.validators {
+            validator.proposer_priority = validator
+                .proposer_priority
+                .value()
+                .saturating_sub(avg)
+                .into();
+        }
+    }
+
+    /// Runs a single round of proposer selection, returning the index of the
+    /// proposer.
+    fn increment_proposer_priority_once(&mut self) -> usize {
+        for validator in &mut self.validators {
+            validator.proposer_priority = validator
+                .proposer_priority
+                .value()
+                .saturating_add(validator.power() as i64)
+                .into();
+        }
+
+        let proposer = self
+            .validators
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, v)| (v.proposer_priority, core::cmp::Reverse(v.address)))
+            .map(|(idx, _)| idx)
+            .expect("validator set is not empty");
+        let total_power = self.total_power();
+        let validator = &mut self.validators[proposer];
+        validator.proposer_priority = validator
+            .proposer_priority
+            .value()
+            .saturating_sub(total_power)
+            .into();
+        proposer
+    }
+
+    /// Splits changes into updates and removals, sorted by address.
+    fn process_changes(changes: &[Info]) -> Result<(Vec<Info>, Vec<Info>), Error> {
+        let mut changes = changes.to_vec();
+        changes.sort_by_key(|change| change.address);
+
+        if let Some(pair) = changes
+            .windows(2)
+            .find(|pair| pair[0].address == pair[1].address)
+        {
+            return Err(Error::invalid_validator_update(format!(
+                "duplicate entry for validator {}",
+                pair[0].address
+            )));
+        }
+        if let Some(change) = changes
+            .iter()
+            .find(|change| change.power() > Self::MAX_TOTAL_VOTING_POWER)
+        {
+            return Err(Error::invalid_validator_update(format!(
+                "to prevent clipping/overflow, voting power can't be higher than {}, got {}",
+                Self::MAX_TOTAL_VOTING_POWER,
+                change.power
+            )));
+        }
+
+        Ok(changes
+            .into_iter()
+            .partition(|change| !change.power.is_zero()))
+    }
+
+    /// Checks that the removed validators are part of this set, returning
+    /// their voting power.
+    fn verify_removals(&self, removals: &[Info]) -> Result<i64, Error> {
+        removals.iter().try_fold(0i64, |removed_power, removal| {
+            let validator = self.validator(removal.address).ok_or_else(|| {
+                Error::invalid_validator_update(format!(
+                    "failed to find validator {} to remove",
+                    removal.address
+                ))
+            })?;
+            Ok(removed_power + validator.power() as i64)
+        })
+    }
+
+    /// Checks that applying the updates never makes the total voting power
+    /// exceed the maximum, whatever the order in which they are applied,
+    /// returning the total voting power after the updates and before the
+    /// removals.
+    fn verify_updates(&self, updates: &[Info], removed_power: i64) -> Result<i64, Error> {
+        let delta = |update: &Info| match self.validator(update.address) {
+            Some(validator) => update.power() as i64 - validator.power() as i64,
+            None => update.power() as i64,
+        };
+        let mut deltas = updates.iter().map(delta).collect::<Vec<_>>();
+        deltas.sort_unstable();
+
+        let mut total_power_after_removals = self.total_power() - removed_power;
+        for delta in deltas {
+            total_power_after_removals += delta;
+            if total_power_after_removals > Self::MAX_TOTAL_VOTING_POWER as i64 {
+                return Err(Error::total_voting_power_overflow());
+            }
+        }
+        Ok(total_power_after_removals + removed_power)
+    }
+
     /// Compute the hash of this validator set.
     #[cfg(feature = "rust-crypto")]
     pub fn hash(&self) -> Hash {
@@ -311,6 +571,8 @@ tendermint_pb_modules! {
         }
     }
 
+    impl Protobuf<RawValidator> for Info {}
+
     impl TryFrom<RawValidator> for Info {
         type Error = Error;
 
@@ -760,4 +1022,203 @@ mod tests {
             "{err}"
         );
     }
+
+    /// A validator with the given name as address, as in the CometBFT tests.
+    fn named_validator(name: &[u8], power: u64) -> Info {
+        let mut address = [0; 20];
+        address[..name.len()].copy_from_slice(name);
+        Info {
+            address: account::Id::new(address),
+            pub_key: PublicKey::from_raw_ed25519(&[name[0]; 32]).unwrap(),
+            power: power.try_into().unwrap(),
+            name: None,
+            proposer_priority: ProposerPriority::default(),
+        }
+    }
+
+    /// Equivalent of CometBFT's `NewValidatorSet`.
+    fn new_validator_set(validators: &[Info]) -> Set {
+        let mut set = Set::without_proposer(Vec::new());
+        set.update_with_change_set(validators).unwrap();
+        set.increment_proposer_priority(1);
+        set
+    }
+
+    fn proposer_name(set: &Set) -> &'static str {
+        let proposer = set.proposer().as_ref().unwrap();
+        match &proposer.address.as_bytes()[..3] {
+            b"foo" => "foo",
+            b"bar" => "bar",
+            b"baz" => "baz",
+            _ => unreachable!(),
+        }
+    }
+
+    // https://github.com/cometbft/cometbft/blob/v0.38.x/types/validator_set_test.go
+    #[test]
+    fn proposer_selection() {
+        let mut set = new_validator_set(&[
+            named_validator(b"foo", 1000),
+            named_validator(b"bar", 300),
+            named_validator(b"baz", 330),
+        ]);
+        let mut proposers = Vec::new();
+        for _ in 0..99 {
+            proposers.push(proposer_name(&set));
+            set.increment_proposer_priority(1);
+        }
+        let expected = "foo baz foo bar foo foo baz foo bar foo foo baz foo foo bar foo baz foo foo bar \
+                        foo foo baz foo bar foo foo baz foo bar foo foo baz foo foo bar foo baz foo foo bar \
+                        foo baz foo foo bar foo baz foo foo bar foo baz foo foo foo baz bar foo foo foo baz \
+                        foo bar foo foo baz foo bar foo foo baz foo bar foo foo baz foo bar foo foo baz foo \
+                        foo bar foo baz foo foo bar foo baz foo foo bar foo baz foo foo";
+        assert_eq!(proposers.join(" "), expected);
+    }
+
+    #[test]
+    fn proposer_selection_by_address_and_power() {
+        let addresses = [b"\0\0\0", b"\0\0\x01", b"\0\0\x02"];
+        let validators = |powers: [u64; 3]| {
+            let mut set = new_validator_set(&[
+                named_validator(addresses[0], powers[0]),
+                named_validator(addresses[1], powers[1]),
+                named_validator(addresses[2], powers[2]),
+            ]);
+            move || {
+                let proposer = set.proposer().clone().unwrap();
+                set.increment_proposer_priority(1);
+                usize::from(proposer.address.as_bytes()[2])
+            }
+        };
+
+        // With equal voting powers, validators propose in the order of their addresses.
+        let mut next_proposer = validators([100, 100, 100]);
+        for i in 0..15 {
+            assert_eq!(next_proposer(), i % 3);
+        }
+
+        // Not enough voting power to propose twice in a row.
+        let mut next_proposer = validators([100, 100, 400]);
+        assert_eq!(next_proposer(), 2);
+        assert_eq!(next_proposer(), 0);
+
+        // Just enough voting power to propose twice in a row.
+        let mut next_proposer = validators([100, 100, 401]);
+        assert_eq!(next_proposer(), 2);
+        assert_eq!(next_proposer(), 2);
+        assert_eq!(next_proposer(), 0);
+
+        // Validators propose in proportion to their voting power.
+        let mut next_proposer = validators([4, 5, 3]);
+        let mut counts = [0; 3];
+        for _ in 0..120 {
+            counts[next_proposer()] += 1;
+        }
+        assert_eq!(counts, [40, 50, 30]);
+    }
+
+    #[test]
+    fn averaging_in_increment_proposer_priority() {
+        for (priorities, times, avg) in [
+            ([1, 2, 3], 1, 2),
+            ([10, -10, 1], 11, 0),
+            ([100, -10, 1], 1, 30),
+        ] {
+            let validators = [b"a", b"b", b"c"]
+                .into_iter()
+                .zip(priorities)
+                .map(|(name, priority)| Info {
+                    proposer_priority: ProposerPriority::from(priority),
+                    ..named_validator(name, 0)
+                })
+                .collect::<Vec<_>>();
+            let set = Set::without_proposer(validators);
+            assert_eq!(
+                set.find_proposer().unwrap().proposer_priority.value(),
+                priorities.into_iter().max().unwrap()
+            );
+            let mut incremented = set.clone();
+            incremented.increment_proposer_priority(times);
+            for validator in set.validators() {
+                let updated = incremented.validator(validator.address).unwrap();
+                assert_eq!(
+                    updated.proposer_priority.value(),
+                    validator.proposer_priority.value() - avg
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn update_with_change_set() {
+        let powers = |set: &Set| {
+            set.validators()
+                .iter()
+                .map(|v| (v.address.as_bytes()[1], v.power()))
+                .collect::<Vec<_>>()
+        };
+        let v = |n: u8, power: u64| named_validator(&[b'v', n], power);
+
+        // Voting power changes.
+        let mut set = new_validator_set(&[v(b'1', 10), v(b'2', 10)]);
+        set.update_with_change_set(&[v(b'2', 22), v(b'1', 11)])
+            .unwrap();
+        assert_eq!(powers(&set), [(b'2', 22), (b'1', 11)]);
+        assert_eq!(set.total_voting_power().value(), 33);
+
+        // New validators start with a priority of -1.125 times the updated
+        // total voting power, before centering.
+        let mut set = new_validator_set(&[v(b'2', 20), v(b'1', 10)]);
+        set.update_with_change_set(&[v(b'4', 40), v(b'3', 30)])
+            .unwrap();
+        assert_eq!(
+            powers(&set),
+            [(b'4', 40), (b'3', 30), (b'2', 20), (b'1', 10)]
+        );
+        let priorities = set
+            .validators()
+            .iter()
+            .map(|v| v.proposer_priority.value())
+            .collect::<Vec<_>>();
+        // Priorities before centering: -112, -112, -10, 10.
+        assert_eq!(priorities, [-56, -56, 46, 66]);
+        assert_eq!(priorities.iter().sum::<i64>(), 0);
+
+        // Removals.
+        let mut set = new_validator_set(&[v(b'3', 30), v(b'2', 20), v(b'1', 10)]);
+        set.update_with_change_set(&[v(b'2', 0), v(b'3', 0)])
+            .unwrap();
+        assert_eq!(powers(&set), [(b'1', 10)]);
+
+        // No false overflow errors when updates are applied in another order.
+        let max = Set::MAX_TOTAL_VOTING_POWER;
+        let mut set = new_validator_set(&[v(b'2', max - 1)]);
+        set.update_with_change_set(&[v(b'1', max - 1), v(b'2', 1)])
+            .unwrap();
+        assert_eq!(powers(&set), [(b'1', max - 1), (b'2', 1)]);
+    }
+
+    #[test]
+    fn update_with_change_set_errors() {
+        let v = |n: u8, power: u64| named_validator(&[b'v', n], power);
+        let set = new_validator_set(&[v(b'1', 10), v(b'2', 20)]);
+        let max = Set::MAX_TOTAL_VOTING_POWER;
+
+        for changes in [
+            // Duplicate entries
+            vec![v(b'1', 11), v(b'1', 12)],
+            vec![v(b'1', 0), v(b'1', 0)],
+            // Removal of an unknown validator
+            vec![v(b'3', 0)],
+            // Empty resulting set
+            vec![v(b'1', 0), v(b'2', 0)],
+            // Too much voting power
+            vec![v(b'3', max + 1)],
+            vec![v(b'3', max - 25)],
+        ] {
+            let mut updated = set.clone();
+            assert!(updated.update_with_change_set(&changes).is_err());
+            assert_eq!(updated, set);
+        }
+    }
 }
@@ -14,6 +14,12 @@ impl Display for Version {
     }
 }
 
+impl From<String> for Version {
+    fn from(value: String) -> Self {
+        Version(value)
+    }
+}
+
 impl From<Version> for String {
     fn from(value: Version) -> Self {
         value.0
@@ -4,16 +4,22 @@ mod canonical_vote;
 mod power;
 mod sign_vote;
 mod validator_index;
+mod vote_set;
 
 use core::{fmt, str::FromStr};
 
 use bytes::BufMut;
+use prost::Message as _;
 use serde::{Deserialize, Serialize};
-use tendermint_proto::v0_38::types::{CanonicalVote as RawCanonicalVote, Vote as RawVote};
+use tendermint_proto::v0_38::types::{
+    CanonicalVote as RawCanonicalVote, CanonicalVoteExtension as RawCanonicalVoteExtension,
+    Vote as RawVote,
+};
 use tendermint_proto::{Error as ProtobufError, Protobuf};
 
 pub use self::{
     canonical_vote::CanonicalVote, power::Power, sign_vote::*, validator_index::ValidatorIndex,
+    vote_set::VoteSet,
 };
 use crate::{
     account, block, chain::Id as ChainId, consensus::State, error::Error, hash, prelude::*,
@@ -111,7 +117,9 @@ mod v0_34 {
                 r#type: value.vote_type.into(),
                 height: value.height.into(),
                 round: value.round.into(),
-                block_id: value.block_id.map(Into::into),
+                // Go always encodes the block ID, filling it with the default
+                // for votes for nil.
+                block_id: Some(value.block_id.unwrap_or_default().into()),
                 timestamp: value.timestamp.map(Into::into),
                 validator_address: value.validator_address.into(),
                 validator_index: value.validator_index.into(),
@@ -162,7 +170,9 @@ mod v0_37 {
                 r#type: value.vote_type.into(),
                 height: value.height.into(),
                 round: value.round.into(),
-                block_id: value.block_id.map(Into::into),
+                // Go always encodes the block ID, filling it with the default
+                // for votes for nil.
+                block_id: Some(value.block_id.unwrap_or_default().into()),
                 timestamp: value.timestamp.map(Into::into),
                 validator_address: value.validator_address.into(),
                 validator_index: value.validator_index.into(),
@@ -213,7 +223,9 @@ mod v0_38 {
                 r#type: value.vote_type.into(),
                 height: value.height.into(),
                 round: value.round.into(),
-                block_id: value.block_id.map(Into::into),
+                // Go always encodes the block ID, filling it with the default
+                // for votes for nil.
+                block_id: Some(value.block_id.unwrap_or_default().into()),
                 timestamp: value.timestamp.map(Into::into),
                 validator_address: value.validator_address.into(),
                 validator_index: value.validator_index.into(),
@@ -270,6 +282,13 @@ impl Vote {
         Protobuf::<RawCanonicalVote>::encode_length_delimited_vec(canonical)
     }
 
+    /// Create signable vector from the extension of the Vote.
+    ///
+    /// Only the extensions of non-nil precommits are signed.
+    pub fn into_extension_signable_vec(self, chain_id: ChainId) -> Vec<u8> {
+        extension_sign_bytes(self.extension, self.height, self.round, &chain_id)
+    }
+
     /// Consensus state from this vote - This doesn't seem to be used anywhere.
     #[deprecated(
         since = "0.17.0",
@@ -285,6 +304,23 @@ impl Vote {
     }
 }
 
+/// Bytes signed by a validator for the vote extension of a precommit at the
+/// given height and round.
+pub(crate) fn extension_sign_bytes(
+    extension: Vec<u8>,
+    height: block::Height,
+    round: block::Round,
+    chain_id: &ChainId,
+) -> Vec<u8> {
+    RawCanonicalVoteExtension {
+        extension,
+        height: height.into(),
+        round: round.value().into(),
+        chain_id: chain_id.to_string(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
 /// SignedVote is the union of a canonicalized vote, the signature on
 /// the sign bytes of that vote and the id of the validator who signed it.
 pub struct SignedVote {
@@ -0,0 +1,768 @@
+//! Collection of the votes of a validator set for a given height, round and
+//! vote type, following
+//! <https://github.com/cometbft/cometbft/blob/v0.38.x/types/vote_set.go>.
+
+use alloc::collections::BTreeMap;
+
+use super::{Type, Vote};
+use crate::{
+    account, block, chain,
+    crypto::signature::Verifier,
+    error::Error,
+    evidence::DuplicateVoteEvidence,
+    node,
+    prelude::*,
+    validator::{self, Info},
+    Time,
+};
+
+/// Votes for the same block (or for nil).
+#[derive(Clone, Debug)]
+struct BlockVotes {
+    /// Whether a peer claims to have seen +2/3 votes for this block.
+    peer_maj23: bool,
+    /// Votes indexed by validator index.
+    votes: Vec<Option<Vote>>,
+    /// Voting power of the votes.
+    sum: u64,
+}
+
+impl BlockVotes {
+    fn new(peer_maj23: bool, num_validators: usize) -> Self {
+        Self {
+            peer_maj23,
+            votes: vec![None; num_validators],
+            sum: 0,
+        }
+    }
+}
+
+/// A set of votes of a validator set for a given height, round and vote type.
+///
+/// Votes are checked against the validator set when they are added. At most
+/// one vote per validator is counted towards the voting power of the set: a
+/// validator voting for two different blocks is reported with a
+/// [`DuplicateVoteEvidence`], and its second vote is only tracked if a peer
+/// claims that its block has +2/3 votes (see [`VoteSet::set_peer_maj23`]).
+#[derive(Clone, Debug)]
+pub struct VoteSet {
+    chain_id: chain::Id,
+    height: block::Height,
+    round: block::Round,
+    vote_type: Type,
+    validator_set: validator::Set,
+    extensions_enabled: bool,
+    /// The first vote of each validator, or its vote for the +2/3 majority.
+    votes: Vec<Option<Vote>>,
+    /// Voting power of `votes`.
+    sum: u64,
+    /// First block (or nil) which got +2/3 votes.
+    maj23: Option<Option<block::Id>>,
+    votes_by_block: BTreeMap<Option<block::Id>, BlockVotes>,
+    peer_maj23s: BTreeMap<node::Id, Option<block::Id>>,
+}
+
+impl VoteSet {
+    /// Creates an empty set of votes of `validator_set`.
+    ///
+    /// Votes must not carry any vote extension.
+    pub fn new(
+        chain_id: chain::Id,
+        height: block::Height,
+        round: block::Round,
+        vote_type: Type,
+        validator_set: validator::Set,
+    ) -> Self {
+        let num_validators = validator_set.validators().len();
+        Self {
+            chain_id,
+            height,
+            round,
+            vote_type,
+            validator_set,
+            extensions_enabled: false,
+            votes: vec![None; num_validators],
+            sum: 0,
+            maj23: None,
+            votes_by_block: BTreeMap::new(),
+            peer_maj23s: BTreeMap::new(),
+        }
+    }
+
+    /// Creates an empty set of precommits of `validator_set`, at a height
+    /// where vote extensions are enabled.
+    ///
+    /// The extension signatures of precommits for a block are verified along
+    /// with the votes.
+    pub fn new_extended(
+        chain_id: chain::Id,
+        height: block::Height,
+        round: block::Round,
+        validator_set: validator::Set,
+    ) -> Self {
+        Self {
+            extensions_enabled: true,
+            ..Self::new(chain_id, height, round, Type::Precommit, validator_set)
+        }
+    }
+
+    /// Chain ID of the votes.
+    pub fn chain_id(&self) -> &chain::Id {
+        &self.chain_id
+    }
+
+    /// Height of the votes.
+    pub fn height(&self) -> block::Height {
+        self.height
+    }
+
+    /// Round of the votes.
+    pub fn round(&self) -> block::Round {
+        self.round
+    }
+
+    /// Type of the votes.
+    pub fn vote_type(&self) -> Type {
+        self.vote_type
+    }
+
+    /// The validator set casting the votes.
+    pub fn validator_set(&self) -> &validator::Set {
+        &self.validator_set
+    }
+
+    /// Whether vote extensions are verified with the votes.
+    pub fn extensions_enabled(&self) -> bool {
+        self.extensions_enabled
+    }
+
+    /// Adds a vote to the set, after checking it and its signature against
+    /// the validator set.
+    ///
+    /// Returns `Ok(false)` if the vote is already in the set. A validator
+    /// voting for another block than in a previous vote is reported with an
+    /// [`ErrorDetail::ConflictingVotes`][crate::error::ErrorDetail::ConflictingVotes]
+    /// error carrying the evidence of the double vote, whose timestamp is to
+    /// be set to the time of the block at the height of the votes.
+    pub fn add_vote<V>(&mut self, vote: Vote) -> Result<bool, Error>
+    where
+        V: Verifier,
+    {
+        if vote.height != self.height
+            || vote.round != self.round
+            || vote.vote_type != self.vote_type
+        {
+            return Err(Error::invalid_vote(format!(
+                "expected {}/{}/{}, but got {}/{}/{}",
+                self.height, self.round, self.vote_type, vote.height, vote.round, vote.vote_type
+            )));
+        }
+
+        let index = vote.validator_index.value() as usize;
+        let validator = self.validator_set.validators().get(index).ok_or_else(|| {
+            Error::invalid_vote(format!(
+                "cannot find validator {} in validator set of size {}",
+                index,
+                self.validator_set.validators().len()
+            ))
+        })?;
+        if vote.validator_address != validator.address {
+            return Err(Error::invalid_vote(format!(
+                "validator address {} does not match address {} for validator index {}",
+                vote.validator_address, validator.address, index
+            )));
+        }
+
+        if let Some(existing) = self.get_vote(index, &vote.block_id) {
+            if existing.signature == vote.signature {
+                return Ok(false);
+            }
+            return Err(Error::invalid_vote(format!(
+                "non-deterministic signature for the vote of validator {}",
+                vote.validator_address
+            )));
+        }
+
+        self.verify_vote::<V>(&vote, validator)?;
+
+        let power = validator.power();
+        match self.add_verified_vote(vote.clone(), power) {
+            (added, None) => Ok(added),
+            (_, Some(conflicting)) => {
+                let mut evidence = DuplicateVoteEvidence::new(conflicting, vote)?;
+                if evidence.vote_b.block_id < evidence.vote_a.block_id {
+                    core::mem::swap(&mut evidence.vote_a, &mut evidence.vote_b);
+                }
+                evidence.validator_power = power.try_into()?;
+                evidence.total_voting_power = self.validator_set.total_voting_power();
+                Err(Error::conflicting_votes(Box::new(evidence)))
+            },
+        }
+    }
+
+    /// Records that a peer claims to have seen +2/3 votes for `block_id`, or
+    /// for nil if it is `None`.
+    ///
+    /// Conflicting votes for that block are then tracked, so that the +2/3
+    /// majority can be observed if the claim is true.
+    pub fn set_peer_maj23(
+        &mut self,
+        peer_id: node::Id,
+        block_id: Option<block::Id>,
+    ) -> Result<(), Error> {
+        if let Some(existing) = self.peer_maj23s.get(&peer_id) {
+            if *existing == block_id {
+                return Ok(());
+            }
+            return Err(Error::invalid_vote(format!(
+                "conflicting +2/3 majority claims from peer {peer_id}"
+            )));
+        }
+        self.peer_maj23s.insert(peer_id, block_id);
+
+        let num_validators = self.votes.len();
+        self.votes_by_block
+            .entry(block_id)
+            .or_insert_with(|| BlockVotes::new(true, num_validators))
+            .peer_maj23 = true;
+        Ok(())
+    }
+
+    /// Returns the vote of the validator at `index`, preferring its vote for
+    /// the +2/3 majority.
+    pub fn get_by_index(&self, index: usize) -> Option<&Vote> {
+        self.votes.get(index)?.as_ref()
+    }
+
+    /// Returns the vote of the validator with the given address, preferring
+    /// its vote for the +2/3 majority.
+    pub fn get_by_address(&self, address: account::Id) -> Option<&Vote> {
+        let index = self
+            .validator_set
+            .validators()
+            .iter()
+            .position(|v| v.address == address)?;
+        self.get_by_index(index)
+    }
+
+    /// Voting power of the validators who voted.
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Voting power of the tracked votes for `block_id`, or for nil if it is
+    /// `None`.
+    pub fn voting_power_for(&self, block_id: &Option<block::Id>) -> u64 {
+        self.votes_by_block
+            .get(block_id)
+            .map_or(0, |block_votes| block_votes.sum)
+    }
+
+    /// Returns the first block which got +2/3 votes, with `Some(None)` if
+    /// +2/3 voted for nil.
+    pub fn two_thirds_majority(&self) -> Option<Option<block::Id>> {
+        self.maj23
+    }
+
+    /// Whether a block, or nil, got +2/3 votes.
+    pub fn has_two_thirds_majority(&self) -> bool {
+        self.maj23.is_some()
+    }
+
+    /// Whether validators with more than 2/3 of the voting power voted, for
+    /// any blocks.
+    pub fn has_two_thirds_any(&self) -> bool {
+        self.sum > self.total_power() * 2 / 3
+    }
+
+    /// Whether all validators voted.
+    pub fn has_all(&self) -> bool {
+        self.sum == self.total_power()
+    }
+
+    /// Builds the commit of the block which got +2/3 precommits.
+    ///
+    /// Returns `None` if these are not precommits or no block got +2/3 of
+    /// them. Precommits for other blocks are recorded as absent.
+    pub fn make_commit(&self) -> Option<block::Commit> {
+        self.make_extended_commit()
+            .map(|extended| extended.to_commit())
+    }
+
+    /// Builds the commit of the block which got +2/3 precommits, with the
+    /// vote extensions of the precommits.
+    ///
+    /// Returns `None` if these are not precommits or no block got +2/3 of
+    /// them. Precommits for other blocks are recorded as absent.
+    pub fn make_extended_commit(&self) -> Option<block::ExtendedCommit> {
+        let block_id = self.commit_block_id()?;
+        let extended_signatures = self
+            .votes
+            .iter()
+            .map(|vote| match vote {
+                Some(vote) if vote.block_id.is_none() => block::CommitSig::BlockIdFlagNil {
+                    validator_address: vote.validator_address,
+                    timestamp: vote.timestamp.unwrap_or_else(Time::unix_epoch),
+                    signature: vote.signature.clone(),
+                }
+                .into(),
+                Some(vote) if vote.block_id == Some(block_id) => block::ExtendedCommitSig {
+                    commit_sig: block::CommitSig::BlockIdFlagCommit {
+                        validator_address: vote.validator_address,
+                        timestamp: vote.timestamp.unwrap_or_else(Time::unix_epoch),
+                        signature: vote.signature.clone(),
+                    },
+                    extension: vote.extension.clone(),
+                    extension_signature: vote.extension_signature.clone(),
+                },
+                _ => block::CommitSig::BlockIdFlagAbsent.into(),
+            })
+            .collect();
+        Some(block::ExtendedCommit {
+            height: self.height,
+            round: self.round,
+            block_id,
+            extended_signatures,
+        })
+    }
+
+    /// The block which got +2/3 precommits.
+    fn commit_block_id(&self) -> Option<block::Id> {
+        if self.vote_type != Type::Precommit {
+            return None;
+        }
+        self.maj23.flatten()
+    }
+
+    fn total_power(&self) -> u64 {
+        self.validator_set.total_voting_power().value()
+    }
+
+    /// Returns the vote of the validator at `index` for `block_id`.
+    fn get_vote(&self, index: usize, block_id: &Option<block::Id>) -> Option<&Vote> {
+        if let Some(Some(existing)) = self.votes.get(index) {
+            if existing.block_id == *block_id {
+                return Some(existing);
+            }
+        }
+        self.votes_by_block
+            .get(block_id)?
+            .votes
+            .get(index)?
+            .as_ref()
+    }
+
+    /// Verifies the signatures of a vote of `validator`.
+    fn verify_vote<V>(&self, vote: &Vote, validator: &Info) -> Result<(), Error>
+    where
+        V: Verifier,
+    {
+        let signature = vote
+            .signature
+            .as_ref()
+            .ok_or_else(|| Error::invalid_vote("vote is not signed".into()))?;
+        let sign_bytes = vote.clone().into_signable_vec(self.chain_id.clone());
+        validator.verify_signature::<V>(&sign_bytes, signature)?;
+
+        if !self.extensions_enabled {
+            if !vote.extension.is_empty() || vote.extension_signature.is_some() {
+                return Err(Error::invalid_vote(
+                    "unexpected vote extension data present in vote".into(),
+                ));
+            }
+        } else if vote.is_precommit() && vote.block_id.is_some() {
+            let extension_signature = vote
+                .extension_signature
+                .as_ref()
+                .ok_or_else(|| Error::invalid_vote("vote extension signature is missing".into()))?;
+            let extension_sign_bytes = vote
+                .clone()
+                .into_extension_signable_vec(self.chain_id.clone());
+            validator.verify_signature::<V>(&extension_sign_bytes, extension_signature)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a verified vote, returning whether it was added and the
+    /// conflicting vote of the same validator, if any.
+    fn add_verified_vote(&mut self, vote: Vote, power: u64) -> (bool, Option<Vote>) {
+        let index = vote.validator_index.value() as usize;
+        let block_id = vote.block_id;
+
+        let conflicting = match &self.votes[index] {
+            Some(existing) => {
+                let conflicting = existing.clone();
+                // Replace the vote if it is for the +2/3 majority.
+                if self.maj23 == Some(block_id) {
+                    self.votes[index] = Some(vote.clone());
+                }
+                Some(conflicting)
+            },
+            None => {
+                self.votes[index] = Some(vote.clone());
+                self.sum += power;
+                None
+            },
+        };
+
+        let num_validators = self.votes.len();
+        let block_votes = match self.votes_by_block.get_mut(&block_id) {
+            // There's a conflict and no peer claims that this block is special.
+            Some(block_votes) if conflicting.is_some() && !block_votes.peer_maj23 => {
+                return (false, conflicting)
+            },
+            Some(block_votes) => block_votes,
+            // The block is not tracked, so forget about the conflicting vote.
+            None if conflicting.is_some() => return (false, conflicting),
+            None => self
+                .votes_by_block
+                .entry(block_id)
+                .or_insert_with(|| BlockVotes::new(false, num_validators)),
+        };
+
+        let quorum = self.validator_set.total_voting_power().value() * 2 / 3 + 1;
+        let previous_sum = block_votes.sum;
+        if block_votes.votes[index].is_none() {
+            block_votes.votes[index] = Some(vote);
+            block_votes.sum += power;
+        }
+
+        // Only the first block reaching the quorum is the +2/3 majority, whose
+        // votes take precedence over conflicting ones.
+        if previous_sum < quorum && quorum <= block_votes.sum && self.maj23.is_none() {
+            self.maj23 = Some(block_id);
+            for (index, vote) in block_votes.votes.iter().enumerate() {
+                if vote.is_some() {
+                    self.votes[index] = vote.clone();
+                }
+            }
+        }
+
+        (true, conflicting)
+    }
+}
+
+#[cfg(all(test, feature = "rust-crypto"))]
+mod tests {
+    use ed25519_consensus::SigningKey;
+
+    use super::*;
+    use crate::{
+        crypto::default::signature::Verifier as DefaultVerifier, error::ErrorDetail, Hash,
+        PublicKey,
+    };
+
+    const CHAIN_ID: &str = "test-chain";
+
+    struct Validators {
+        keys: Vec<SigningKey>,
+        set: validator::Set,
+    }
+
+    impl Validators {
+        fn new(powers: &[u32]) -> Self {
+            let keys = (1..=powers.len() as u8)
+                .map(|seed| SigningKey::from([seed; 32]))
+                .collect::<Vec<_>>();
+            let set = validator::Set::without_proposer(
+                keys.iter()
+                    .zip(powers)
+                    .map(|(key, power)| {
+                        Info::new(PublicKey::from(key.verification_key()), (*power).into())
+                    })
+                    .collect(),
+            );
+            Self { keys, set }
+        }
+
+        fn vote(&self, index: usize, vote_type: Type, block: Option<u8>) -> Vote {
+            let validator = &self.set.validators()[index];
+            let mut vote = Vote {
+                vote_type,
+                height: 10u32.into(),
+                round: 1u16.into(),
+                block_id: block.map(block_id),
+                timestamp: Some(Time::from_unix_timestamp(1_000, 0).unwrap()),
+                validator_address: validator.address,
+                validator_index: (index as u32).try_into().unwrap(),
+                signature: None,
+                extension: Vec::new(),
+                extension_signature: None,
+            };
+            let sign_bytes = vote.clone().into_signable_vec(CHAIN_ID.parse().unwrap());
+            vote.signature = Some(self.key(index).sign(&sign_bytes).into());
+            vote
+        }
+
+        fn key(&self, index: usize) -> &SigningKey {
+            let validator = &self.set.validators()[index];
+            self.keys
+                .iter()
+                .find(|key| PublicKey::from(key.verification_key()) == validator.pub_key)
+                .unwrap()
+        }
+
+        fn extended_vote(&self, index: usize, block: Option<u8>) -> Vote {
+            let mut vote = Vote {
+                extension: vec![index as u8; 4],
+                ..self.vote(index, Type::Precommit, block)
+            };
+            if block.is_some() {
+                let sign_bytes = vote
+                    .clone()
+                    .into_extension_signable_vec(CHAIN_ID.parse().unwrap());
+                vote.extension_signature = Some(self.key(index).sign(&sign_bytes).into());
+            } else {
+                vote.extension = Vec::new();
+            }
+            vote
+        }
+
+        fn vote_set(&self, vote_type: Type) -> VoteSet {
+            VoteSet::new(
+                CHAIN_ID.parse().unwrap(),
+                10u32.into(),
+                1u16.into(),
+                vote_type,
+                self.set.clone(),
+            )
+        }
+    }
+
+    fn block_id(byte: u8) -> block::Id {
+        block::Id {
+            hash: Hash::Sha256([byte; 32]),
+            part_set_header: block::parts::Header::new(1, Hash::Sha256([byte; 32])).unwrap(),
+        }
+    }
+
+    #[test]
+    fn two_thirds_majority() {
+        let validators = Validators::new(&[10, 10, 10, 10]);
+        let mut votes = validators.vote_set(Type::Prevote);
+
+        assert!(votes
+            .add_vote::<DefaultVerifier>(validators.vote(0, Type::Prevote, Some(1)))
+            .unwrap());
+        // Adding a vote twice is a no-op.
+        assert!(!votes
+            .add_vote::<DefaultVerifier>(validators.vote(0, Type::Prevote, Some(1)))
+            .unwrap());
+        votes
+            .add_vote::<DefaultVerifier>(validators.vote(1, Type::Prevote, None))
+            .unwrap();
+        votes
+            .add_vote::<DefaultVerifier>(validators.vote(2, Type::Prevote, Some(1)))
+            .unwrap();
+        assert!(!votes.has_two_thirds_majority());
+        assert!(votes.has_two_thirds_any());
+        assert_eq!(votes.sum(), 30);
+        assert_eq!(votes.voting_power_for(&Some(block_id(1))), 20);
+
+        votes
+            .add_vote::<DefaultVerifier>(validators.vote(3, Type::Prevote, Some(1)))
+            .unwrap();
+        assert_eq!(votes.two_thirds_majority(), Some(Some(block_id(1))));
+        assert!(votes.has_all());
+        // Prevotes can't make a commit.
+        assert!(votes.make_commit().is_none());
+    }
+
+    #[test]
+    fn invalid_votes() {
+        let validators = Validators::new(&[10, 10, 10, 10]);
+        let mut votes = validators.vote_set(Type::Prevote);
+
+        // Wrong type
+        assert!(votes
+            .add_vote::<DefaultVerifier>(validators.vote(0, Type::Precommit, Some(1)))
+            .is_err());
+
+        // Wrong validator index
+        let mut vote = validators.vote(0, Type::Prevote, Some(1));
+        vote.validator_index = 1u32.try_into().unwrap();
+        assert!(votes.add_vote::<DefaultVerifier>(vote).is_err());
+
+        // Invalid signature
+        let mut vote = validators.vote(0, Type::Prevote, Some(1));
+        vote.signature = validators.vote(1, Type::Prevote, Some(1)).signature;
+        assert!(votes.add_vote::<DefaultVerifier>(vote).is_err());
+
+        // Unexpected vote extension
+        let mut vote = validators.vote(0, Type::Prevote, Some(1));
+        vote.extension = b"extension".to_vec();
+        assert!(votes.add_vote::<DefaultVerifier>(vote).is_err());
+
+        assert_eq!(votes.sum(), 0);
+    }
+
+    #[test]
+    fn conflicting_votes() {
+        let validators = Validators::new(&[10, 10, 10, 10]);
+        let mut votes = validators.vote_set(Type::Prevote);
+
+        votes
+            .add_vote::<DefaultVerifier>(validators.vote(0, Type::Prevote, Some(2)))
+            .unwrap();
+        let err = votes
+            .add_vote::<DefaultVerifier>(validators.vote(0, Type::Prevote, Some(1)))
+            .unwrap_err();
+        let evidence = match err.detail() {
+            ErrorDetail::ConflictingVotes(e) => e.evidence.clone(),
+            _ => panic!("unexpected error: {err}"),
+        };
+        assert_eq!(evidence.vote_a.block_id, Some(block_id(1)));
+        assert_eq!(evidence.vote_b.block_id, Some(block_id(2)));
+        assert_eq!(evidence.validator_power.value(), 10);
+        assert_eq!(evidence.total_voting_power.value(), 40);
+        evidence
+            .verify::<DefaultVerifier>(&CHAIN_ID.parse().unwrap(), &validators.set)
+            .unwrap();
+
+        // The conflicting vote was not counted.
+        assert_eq!(votes.sum(), 10);
+        assert_eq!(votes.voting_power_for(&Some(block_id(1))), 0);
+        assert_eq!(votes.get_by_index(0).unwrap().block_id, Some(block_id(2)));
+    }
+
+    #[test]
+    fn conflicting_votes_for_peer_majority() {
+        let validators = Validators::new(&[10, 10, 10, 10]);
+        let mut votes = validators.vote_set(Type::Precommit);
+        let peer = node::Id::new([1; 20]);
+
+        votes
+            .add_vote::<DefaultVerifier>(validators.vote(0, Type::Precommit, Some(2)))
+            .unwrap();
+        votes.set_peer_maj23(peer, Some(block_id(1))).unwrap();
+        assert!(votes.set_peer_maj23(peer, Some(block_id(2))).is_err());
+
+        // The conflicting vote is tracked, as a peer claims that its block
+        // has a +2/3 majority.
+        assert!(votes
+            .add_vote::<DefaultVerifier>(validators.vote(0, Type::Precommit, Some(1)))
+            .is_err());
+        for index in 1..3 {
+            votes
+                .add_vote::<DefaultVerifier>(validators.vote(index, Type::Precommit, Some(1)))
+                .unwrap();
+        }
+        votes
+            .add_vote::<DefaultVerifier>(validators.vote(3, Type::Precommit, None))
+            .unwrap();
+        assert_eq!(votes.two_thirds_majority(), Some(Some(block_id(1))));
+        assert_eq!(votes.get_by_index(0).unwrap().block_id, Some(block_id(1)));
+
+        let commit = votes.make_commit().unwrap();
+        assert_eq!(commit.height, votes.height());
+        assert_eq!(commit.round, votes.round());
+        assert_eq!(commit.block_id, block_id(1));
+        assert!(commit.signatures[..3]
+            .iter()
+            .all(block::CommitSig::is_commit));
+        assert!(commit.signatures[3].is_nil());
+        assert_eq!(
+            commit.signatures[0].validator_address(),
+            Some(validators.set.validators()[0].address)
+        );
+    }
+
+    #[test]
+    fn extended_commit() {
+        use tendermint_proto::{v0_38::types::ExtendedCommit as RawExtendedCommit, Protobuf};
+
+        use crate::abci::types::{
+            BlockSignatureInfo, ExtendedCommitInfo, ExtendedVoteInfo, Validator,
+        };
+
+        let validators = Validators::new(&[10, 10, 10, 10]);
+        let chain_id = CHAIN_ID.parse().unwrap();
+        let mut votes =
+            VoteSet::new_extended(chain_id, 10u32.into(), 1u16.into(), validators.set.clone());
+
+        // Precommits for a block must carry a signed extension.
+        assert!(votes
+            .add_vote::<DefaultVerifier>(validators.vote(0, Type::Precommit, Some(1)))
+            .is_err());
+        let mut vote = validators.extended_vote(0, Some(1));
+        vote.extension = b"forged".to_vec();
+        assert!(votes.add_vote::<DefaultVerifier>(vote).is_err());
+
+        for index in 0..3 {
+            votes
+                .add_vote::<DefaultVerifier>(validators.extended_vote(index, Some(1)))
+                .unwrap();
+        }
+        votes
+            .add_vote::<DefaultVerifier>(validators.extended_vote(3, None))
+            .unwrap();
+
+        let extended = votes.make_extended_commit().unwrap();
+        assert_eq!(extended.to_commit(), votes.make_commit().unwrap());
+        assert_eq!(extended.extended_signatures[2].extension, vec![2; 4]);
+        extended.ensure_extensions(true).unwrap();
+        assert!(extended.ensure_extensions(false).is_err());
+        extended
+            .verify_extensions::<DefaultVerifier>(votes.chain_id(), &validators.set)
+            .unwrap();
+
+        let encoded = Protobuf::<RawExtendedCommit>::encode_vec(extended.clone());
+        let decoded =
+            <block::ExtendedCommit as Protobuf<RawExtendedCommit>>::decode_vec(&encoded).unwrap();
+        assert_eq!(decoded, extended);
+
+        let mut forged = extended.clone();
+        forged.extended_signatures[1].extension = b"forged".to_vec();
+        assert!(forged
+            .verify_extensions::<DefaultVerifier>(votes.chain_id(), &validators.set)
+            .is_err());
+
+        // The same checks apply to the commit info provided to `PrepareProposal`.
+        let info = ExtendedCommitInfo {
+            round: extended.round,
+            votes: extended
+                .extended_signatures
+                .iter()
+                .zip(validators.set.validators())
+                .map(|(sig, validator)| ExtendedVoteInfo {
+                    validator: Validator {
+                        address: validator.address.as_bytes().try_into().unwrap(),
+                        power: validator.power,
+                    },
+                    sig_info: BlockSignatureInfo::Flag(if sig.commit_sig.is_commit() {
+                        block::BlockIdFlag::Commit
+                    } else {
+                        block::BlockIdFlag::Nil
+                    }),
+                    vote_extension: sig.extension.clone().into(),
+                    extension_signature: sig.extension_signature.clone(),
+                })
+                .collect(),
+        };
+        info.verify_vote_extensions::<DefaultVerifier>(
+            votes.chain_id(),
+            votes.height(),
+            &validators.set,
+        )
+        .unwrap();
+        assert!(info
+            .verify_vote_extensions::<DefaultVerifier>(
+                votes.chain_id(),
+                votes.height().increment(),
+                &validators.set,
+            )
+            .is_err());
+
+        let mut insufficient = info;
+        insufficient.votes[0].sig_info = BlockSignatureInfo::Flag(block::BlockIdFlag::Absent);
+        insufficient.votes[0].vote_extension = Default::default();
+        insufficient.votes[0].extension_signature = None;
+        assert!(insufficient
+            .verify_vote_extensions::<DefaultVerifier>(
+                votes.chain_id(),
+                votes.height(),
+                &validators.set,
+            )
+            .is_err());
+    }
+}
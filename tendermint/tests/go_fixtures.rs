@@ -0,0 +1,194 @@
+//! Checks that blocks served by Go nodes of each supported CometBFT version
+//! survive a round trip through the domain types, both in JSON and in
+//! protobuf encoding.
+//!
+//! Next to the JSON document of each block is its protobuf encoding by Go,
+//! which is the single part its block ID commits to, and the genesis
+//! validators of the chain of the blocks at heights 1 and 10.
+#![cfg(feature = "rust-crypto")]
+
+use std::{fs, path::PathBuf};
+
+use pretty_assertions::assert_eq;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tendermint::{
+    block::{self, Block},
+    crypto::default::Sha256,
+    merkle, validator, Hash,
+};
+use tendermint_proto::Protobuf;
+
+/// The `result` of the `block` RPC endpoint.
+#[derive(Deserialize)]
+struct Fixture {
+    block_id: Value,
+    block: Value,
+}
+
+fn fixtures_dir(version: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("support")
+        .join("serialization")
+        .join(version)
+}
+
+fn fixtures(version: &str) -> Vec<(PathBuf, Fixture)> {
+    let mut paths = fs::read_dir(fixtures_dir(version))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "json")
+                && path
+                    .file_stem()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .starts_with("block")
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty(), "no fixtures for {version}");
+    paths
+        .into_iter()
+        .map(|path| {
+            let fixture = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            (path, fixture)
+        })
+        .collect()
+}
+
+fn check_fixtures<RawBlock>(version: &str)
+where
+    RawBlock: prost::Message + Default + Serialize + DeserializeOwned + From<Block>,
+    Block: Protobuf<RawBlock> + TryFrom<RawBlock, Error = tendermint::Error>,
+{
+    for (path_buf, fixture) in fixtures(version) {
+        let path = path_buf.display();
+        let block_id: block::Id = serde_json::from_value(fixture.block_id.clone()).unwrap();
+        let raw: RawBlock = serde_json::from_value(fixture.block.clone()).unwrap();
+        let block = Block::try_from(raw).unwrap();
+        let go_bytes = fs::read(path_buf.with_extension("pb")).unwrap();
+
+        // The block fits in a single part, whose hash is the root of the part
+        // set: the fixture is byte for byte the encoding of Go.
+        assert_eq!(block_id.part_set_header.total, 1, "{path}: part count");
+        assert_eq!(
+            Hash::Sha256(merkle::simple_hash_from_byte_vectors::<Sha256>(&[
+                &go_bytes
+            ])),
+            block_id.part_set_header.hash,
+            "{path}: Go protobuf encoding"
+        );
+        assert_eq!(
+            <Block as Protobuf<RawBlock>>::decode_vec(&go_bytes).unwrap(),
+            block,
+            "{path}: protobuf decoding"
+        );
+
+        // JSON: re-encoding the domain types in the dialect of the version
+        // gives back the document of Go.
+        assert_eq!(
+            serde_json::to_value(block_id).unwrap(),
+            fixture.block_id,
+            "{path}: block ID"
+        );
+        assert_eq!(
+            serde_json::to_value(RawBlock::from(block.clone())).unwrap(),
+            fixture.block,
+            "{path}: block"
+        );
+
+        // Protobuf: the domain type encodes to the same bytes as Go.
+        assert_eq!(
+            Protobuf::<RawBlock>::encode_vec(block.clone()),
+            go_bytes,
+            "{path}: protobuf encoding"
+        );
+
+        // The block ID computed by Go commits to both the header and the
+        // protobuf encoding of the whole block, split into parts.
+        assert_eq!(block.header.hash(), block_id.hash, "{path}: header hash");
+        assert_eq!(
+            block.make_part_set().unwrap().header(),
+            block_id.part_set_header,
+            "{path}: part set header"
+        );
+    }
+}
+
+fn check_validators<RawValidator>(version: &str)
+where
+    RawValidator: prost::Message + Default + From<validator::Info>,
+    validator::Info: Protobuf<RawValidator> + TryFrom<RawValidator, Error = tendermint::Error>,
+{
+    let path = fixtures_dir(version).join("validators.json");
+    let value: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    let validators: Vec<validator::Info> = serde_json::from_value(value.clone()).unwrap();
+
+    // JSON: the validators re-encode to the genesis document of Go.
+    assert_eq!(
+        serde_json::to_value(&validators).unwrap(),
+        value,
+        "{version}: validators"
+    );
+
+    // Protobuf: everything but the name, which Go does not encode, survives a
+    // round trip.
+    for info in &validators {
+        let bytes = Protobuf::<RawValidator>::encode_vec(info.clone());
+        assert_eq!(
+            <validator::Info as Protobuf<RawValidator>>::decode_vec(&bytes).unwrap(),
+            validator::Info {
+                name: None,
+                ..info.clone()
+            },
+            "{version}: validator {}",
+            info.address
+        );
+    }
+
+    // The genesis validators sign the first block, whose header commits to
+    // the hash Go computed for the set.
+    let (_, first) = fixtures(version)
+        .into_iter()
+        .find(|(path, _)| path.ends_with("block_at_height_1.json"))
+        .unwrap();
+    let header: block::Header = serde_json::from_value(first.block["header"].clone()).unwrap();
+    assert_eq!(
+        validator::Set::without_proposer(validators).hash(),
+        header.validators_hash,
+        "{version}: validators hash"
+    );
+}
+
+#[test]
+fn v0_34_blocks() {
+    check_fixtures::<tendermint_proto::v0_34::types::Block>("v0_34");
+}
+
+#[test]
+fn v0_37_blocks() {
+    check_fixtures::<tendermint_proto::v0_37::types::Block>("v0_37");
+}
+
+#[test]
+fn v0_38_blocks() {
+    check_fixtures::<tendermint_proto::v0_38::types::Block>("v0_38");
+}
+
+#[test]
+fn v0_34_validators() {
+    check_validators::<tendermint_proto::v0_34::types::Validator>("v0_34");
+}
+
+#[test]
+fn v0_37_validators() {
+    check_validators::<tendermint_proto::v0_37::types::Validator>("v0_37");
+}
+
+#[test]
+fn v0_38_validators() {
+    check_validators::<tendermint_proto::v0_38::types::Validator>("v0_38");
+}
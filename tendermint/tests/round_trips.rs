@@ -0,0 +1,149 @@
+//! Property tests of the round trips of the domain types through the
+//! protobuf and JSON encodings of each supported CometBFT version.
+
+use std::fmt::{Debug, Display};
+
+use proptest::{collection::vec, option, prelude::*};
+use serde::{de::DeserializeOwned, Serialize};
+use tendermint::{
+    block::{Commit, Header},
+    validator, Block, Proposal, Vote,
+};
+use tendermint_pbt_gen::tendermint::{
+    arb_block, arb_commit, arb_header, arb_proposal, arb_signature, arb_validator_info, arb_vote,
+};
+use tendermint_proto::Protobuf;
+
+fn protobuf_round_trip<T, Raw>(value: &T) -> Result<(), TestCaseError>
+where
+    T: Protobuf<Raw> + Debug + PartialEq,
+    Raw: prost::Message + Default + From<T>,
+    <T as TryFrom<Raw>>::Error: Display,
+{
+    let bytes = Protobuf::<Raw>::encode_vec(value.clone());
+    let decoded = <T as Protobuf<Raw>>::decode_vec(&bytes).unwrap();
+    prop_assert_eq!(&decoded, value);
+    Ok(())
+}
+
+fn json_round_trip<T, Raw>(value: &T) -> Result<(), TestCaseError>
+where
+    T: Clone + Debug + PartialEq + TryFrom<Raw>,
+    Raw: Serialize + DeserializeOwned + From<T>,
+    <T as TryFrom<Raw>>::Error: Debug,
+{
+    let json = serde_json::to_string(&Raw::from(value.clone())).unwrap();
+    let raw: Raw = serde_json::from_str(&json).unwrap();
+    prop_assert_eq!(&T::try_from(raw).unwrap(), value);
+    Ok(())
+}
+
+fn round_trip<T, Raw>(value: &T) -> Result<(), TestCaseError>
+where
+    T: Protobuf<Raw> + Debug + PartialEq,
+    Raw: prost::Message + Default + Serialize + DeserializeOwned + From<T>,
+    <T as TryFrom<Raw>>::Error: Debug + Display,
+{
+    protobuf_round_trip::<T, Raw>(value)?;
+    json_round_trip::<T, Raw>(value)
+}
+
+fn domain_json_round_trip<T>(value: &T) -> Result<(), TestCaseError>
+where
+    T: Debug + PartialEq + Serialize + DeserializeOwned,
+{
+    let json = serde_json::to_string(value).unwrap();
+    prop_assert_eq!(&serde_json::from_str::<T>(&json).unwrap(), value);
+    Ok(())
+}
+
+macro_rules! round_trip_tests {
+    ($version:ident) => {
+        mod $version {
+            use super::*;
+            use tendermint_proto::$version::types as pb;
+
+            proptest! {
+                #[test]
+                fn header(header in arb_header()) {
+                    round_trip::<Header, pb::Header>(&header)?;
+                }
+
+                #[test]
+                fn vote(vote in arb_vote()) {
+                    round_trip::<Vote, pb::Vote>(&vote)?;
+                }
+
+                #[test]
+                fn commit(commit in arb_commit()) {
+                    round_trip::<Commit, pb::Commit>(&commit)?;
+                }
+
+                #[test]
+                fn block(block in arb_block()) {
+                    round_trip::<Block, pb::Block>(&block)?;
+                }
+
+                #[test]
+                fn validator_info(info in arb_validator_info()) {
+                    round_trip::<validator::Info, pb::Validator>(&info)?;
+                }
+
+                #[test]
+                fn proposal(proposal in arb_proposal()) {
+                    protobuf_round_trip::<Proposal, pb::Proposal>(&proposal)?;
+                }
+            }
+        }
+    };
+}
+
+round_trip_tests!(v0_34);
+round_trip_tests!(v0_37);
+round_trip_tests!(v0_38);
+
+proptest! {
+    #[test]
+    fn v0_38_vote_with_extension(
+        vote in arb_vote(),
+        extension in vec(any::<u8>(), 1..=64),
+        extension_signature in option::of(arb_signature()),
+    ) {
+        let vote = Vote {
+            extension,
+            extension_signature,
+            ..vote
+        };
+        round_trip::<Vote, tendermint_proto::v0_38::types::Vote>(&vote)?;
+    }
+
+    #[test]
+    fn header_json(header in arb_header()) {
+        domain_json_round_trip(&header)?;
+    }
+
+    #[test]
+    fn vote_json(vote in arb_vote()) {
+        domain_json_round_trip(&vote)?;
+    }
+
+    #[test]
+    fn commit_json(commit in arb_commit()) {
+        domain_json_round_trip(&commit)?;
+    }
+
+    #[test]
+    fn block_json(block in arb_block()) {
+        domain_json_round_trip(&block)?;
+    }
+
+    #[test]
+    fn validator_info_json(info in arb_validator_info()) {
+        // The proposer priority is not part of the JSON encoding of validators.
+        let info = validator::Info {
+            proposer_priority: Default::default(),
+            ..info
+        };
+        domain_json_round_trip(&info)?;
+    }
+}
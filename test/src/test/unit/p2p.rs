@@ -1 +1,18 @@
+use std::sync::Once;
+
+mod blocksync;
+mod mconnection;
+mod node_info;
+mod pex;
+mod privval;
 mod secret_connection;
+mod statesync;
+mod transport;
+
+/// Installs the handler `eyre` needs to report the errors of the transports.
+fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        eyre::set_hook(Box::new(eyre::DefaultHandler::default_with)).unwrap();
+    });
+}
@@ -0,0 +1,324 @@
+use std::{net::SocketAddr, thread, time::Duration};
+
+use prost::Message as _;
+use rand_core::OsRng;
+use tendermint::{
+    account,
+    block::{self, parts::Header as PartSetHeader, CommitSig, Height},
+    chain,
+    crypto::default::Sha256,
+    node,
+    public_key::PublicKey,
+    validator,
+    vote::{self, ValidatorIndex},
+    Block, Signature, Vote,
+};
+use tendermint_p2p::{
+    blocksync::{verify_block, BlockSync, BlockSyncConfig},
+    error::{Error, ErrorDetail},
+    secret_connection,
+    transport::{
+        memory::{MemoryConnection, MemoryEndpoint, MemoryIncoming, MemoryNetwork},
+        BindInfo, ConnectInfo, Connection, Endpoint, StreamId, StreamSend, Transport,
+    },
+};
+use tendermint_proto::v0_38::blocksync::{
+    message::Sum, BlockResponse, Message as RawMessage, StatusResponse,
+};
+use tendermint_testgen::{light_chain::LightChain, validator::generate_validators, Validator};
+
+use super::init;
+
+fn chain_id() -> chain::Id {
+    "test-chain".parse().unwrap()
+}
+
+/// Blocks 1 to `length` of a chain, and the validator set signing them.
+///
+/// The generated headers do not commit to the contents of the blocks, so they are completed and
+/// signed again by the validators.
+fn blocks(length: u64) -> (Vec<Block>, validator::Set) {
+    let light_blocks = LightChain::default_with_length(length)
+        .light_blocks
+        .into_iter()
+        .map(|light_block| tendermint_testgen::Generator::generate(&light_block).unwrap())
+        .collect::<Vec<_>>();
+    let validators = light_blocks[0].validators.clone();
+    let keys = ["1", "2"].map(|id| {
+        let key = Validator::new(id).get_private_key().unwrap();
+        ed25519_consensus::SigningKey::try_from(key).unwrap()
+    });
+
+    let mut blocks = Vec::<Block>::new();
+    let mut last_commit = None;
+    for light_block in light_blocks {
+        let mut block = Block::new(
+            light_block.signed_header.header,
+            Vec::new(),
+            Default::default(),
+            last_commit.take(),
+        );
+        block.header.last_block_id = blocks.last().map(|previous| block::Id {
+            hash: previous.header.hash(),
+            part_set_header: PartSetHeader::new(1, previous.header.hash()).unwrap(),
+        });
+        block.header.data_hash = Some(block.data_hash_with::<Sha256>());
+        block.header.evidence_hash = Some(block.evidence_hash_with::<Sha256>());
+        block.header.last_commit_hash = Some(block.last_commit_hash_with::<Sha256>());
+
+        let mut commit = light_block.signed_header.commit;
+        commit.block_id = block::Id {
+            hash: block.header.hash(),
+            part_set_header: PartSetHeader::new(1, block.header.hash()).unwrap(),
+        };
+        for (index, sig) in commit.signatures.iter_mut().enumerate() {
+            if let CommitSig::BlockIdFlagCommit {
+                validator_address,
+                timestamp,
+                signature,
+            } = sig
+            {
+                let vote = Vote {
+                    vote_type: vote::Type::Precommit,
+                    height: commit.height,
+                    round: commit.round,
+                    block_id: Some(commit.block_id),
+                    timestamp: Some(*timestamp),
+                    validator_address: *validator_address,
+                    validator_index: ValidatorIndex::try_from(index).unwrap(),
+                    signature: None,
+                    extension: Vec::new(),
+                    extension_signature: None,
+                };
+                let key = keys
+                    .iter()
+                    .find(|key| {
+                        account::Id::from(
+                            PublicKey::from_raw_ed25519(key.verification_key().as_bytes()).unwrap(),
+                        ) == *validator_address
+                    })
+                    .unwrap();
+                let sign_bytes = vote.into_signable_vec(chain_id());
+                *signature = Some(key.sign(&sign_bytes).into());
+            }
+        }
+        last_commit = Some(commit);
+        blocks.push(block);
+    }
+    (blocks, validators)
+}
+
+/// Replaces the signatures of the last commit of the block.
+fn tamper(mut block: Block) -> Block {
+    for sig in &mut block.last_commit.as_mut().unwrap().signatures {
+        if let CommitSig::BlockIdFlagCommit { signature, .. } = sig {
+            *signature = Signature::new([1; 64]).unwrap();
+        }
+    }
+    block
+}
+
+#[test]
+fn test_verify_block() {
+    let (blocks, validators) = blocks(4);
+    verify_block(&chain_id(), &blocks[1], &blocks[2], &validators).unwrap();
+
+    let invalid = |block: &Block, next: &Block, validators: &validator::Set| {
+        let err = verify_block(&chain_id(), block, next, validators).unwrap_err();
+        assert!(
+            matches!(err.detail(), ErrorDetail::InvalidBlock(_)),
+            "{err}"
+        );
+    };
+    invalid(&blocks[1], &blocks[3], &validators);
+    invalid(&blocks[2], &blocks[1], &validators);
+    invalid(&blocks[1], &tamper(blocks[2].clone()), &validators);
+    let mut block = blocks[1].clone();
+    block.data = vec![b"tx".to_vec()];
+    invalid(&block, &blocks[2], &validators);
+    let others = validator::Set::without_proposer(
+        generate_validators(&[Validator::new("3").voting_power(50)]).unwrap(),
+    );
+    invalid(&blocks[1], &blocks[2], &others);
+    let err = verify_block(
+        &"other-chain".parse().unwrap(),
+        &blocks[1],
+        &blocks[2],
+        &validators,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("chain test-chain"), "{err}");
+}
+
+/// Serves blocks over the blocksync stream of the connection, until it is closed. Silent peers
+/// report their blocks, but do not send them.
+fn serve(conn: &MemoryConnection, blocks: &[Block], silent: bool) {
+    let (read, send) = conn.open_bidirectional(StreamId::BlockSync).unwrap();
+    for msg in read {
+        let Ok(msg) = msg else { break };
+        let reply = match RawMessage::decode(msg.as_slice()).unwrap().sum.unwrap() {
+            Sum::StatusRequest(_) => Sum::StatusResponse(StatusResponse {
+                height: blocks.len() as i64,
+                base: 1,
+            }),
+            Sum::BlockRequest(request) if !silent => Sum::BlockResponse(BlockResponse {
+                block: Some(blocks[request.height as usize - 1].clone().into()),
+                ext_commit: None,
+            }),
+            _ => continue,
+        };
+        if send
+            .send(RawMessage { sum: Some(reply) }.encode_to_vec())
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+fn bind(network: &MemoryNetwork) -> (MemoryEndpoint, MemoryIncoming, node::Id) {
+    let key = ed25519_consensus::SigningKey::new(OsRng);
+    let public_key = PublicKey::from_raw_ed25519(key.verification_key().as_bytes()).unwrap();
+    let id = secret_connection::PublicKey::from(&key).peer_id();
+    let (endpoint, incoming) = network
+        .transport(key)
+        .bind(BindInfo {
+            advertise_addrs: "127.0.0.1:0",
+            bind_addrs: "127.0.0.1:0",
+            public_key,
+        })
+        .unwrap();
+    (endpoint, incoming, id)
+}
+
+/// Blocks delivered by a sync, the peers removed with the reason, and the ids of all peers.
+type Synced = (
+    Vec<Result<Block, Error>>,
+    Vec<(node::Id, Error)>,
+    Vec<node::Id>,
+);
+
+/// Blocks delivered by a client syncing from peers serving the given blocks, and the peers it
+/// removed.
+fn sync(
+    peers: Vec<(Vec<Block>, bool)>,
+    validators: validator::Set,
+    config: BlockSyncConfig,
+) -> Synced {
+    let network = MemoryNetwork::new();
+    let (client_endpoint, _, _) = bind(&network);
+    let mut client = BlockSync::new(chain_id(), Height::from(1_u32), validators, config);
+
+    let mut ids = Vec::new();
+    let mut servers = Vec::new();
+    for (blocks, silent) in peers {
+        let (endpoint, mut incoming, id) = bind(&network);
+        let addr = Endpoint::<SocketAddr>::listen_addrs(&endpoint)[0];
+        servers.push(thread::spawn(move || {
+            let conn = incoming.next().unwrap().unwrap();
+            serve(&conn, &blocks, silent);
+            drop(endpoint);
+        }));
+        let conn = client_endpoint
+            .connect(ConnectInfo { addrs: addr, id })
+            .unwrap();
+        client.add_peer(id, conn).unwrap();
+        ids.push(id);
+    }
+
+    let delivered = client.by_ref().collect();
+    let removed = client.take_removed_peers();
+    // Dropping the client closes the connections to the peers.
+    drop(client);
+    for server in servers {
+        server.join().unwrap();
+    }
+    (delivered, removed, ids)
+}
+
+fn heights(delivered: &[Result<Block, Error>]) -> Vec<u64> {
+    delivered
+        .iter()
+        .map(|block| block.as_ref().unwrap().header.height.value())
+        .collect()
+}
+
+#[test]
+fn test_blocksync_delivers_blocks_in_order() {
+    init();
+    let (blocks, validators) = blocks(30);
+    let config = BlockSyncConfig {
+        max_pending_requests_per_peer: 4,
+        ..BlockSyncConfig::default()
+    };
+    let (delivered, removed, _) = sync(
+        vec![(blocks.clone(), false), (blocks, false)],
+        validators,
+        config,
+    );
+
+    // The last block cannot be verified without the next one.
+    assert_eq!(heights(&delivered), (1..30).collect::<Vec<_>>());
+    assert!(removed.is_empty());
+}
+
+#[test]
+fn test_blocksync_stops_at_stop_height() {
+    init();
+    let (blocks, validators) = blocks(20);
+    let config = BlockSyncConfig {
+        stop_height: Some(Height::from(10_u32)),
+        ..BlockSyncConfig::default()
+    };
+    let (delivered, removed, _) = sync(vec![(blocks, false)], validators, config);
+
+    assert_eq!(heights(&delivered), (1..=10).collect::<Vec<_>>());
+    assert!(removed.is_empty());
+}
+
+#[test]
+fn test_blocksync_removes_peer_with_invalid_blocks() {
+    init();
+    let (mut blocks, validators) = blocks(10);
+    blocks[4] = tamper(blocks[4].clone());
+    let (delivered, removed, ids) = sync(
+        vec![(blocks, false)],
+        validators,
+        BlockSyncConfig::default(),
+    );
+
+    // Blocks up to the one whose commit is invalid are delivered.
+    assert_eq!(heights(&delivered[..3]), vec![1, 2, 3]);
+    assert_eq!(delivered.len(), 4);
+    let err = delivered[3].as_ref().unwrap_err();
+    assert!(matches!(err.detail(), ErrorDetail::NoBlockSyncPeers(_)));
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].0, ids[0]);
+    assert!(matches!(
+        removed[0].1.detail(),
+        ErrorDetail::InvalidBlock(_)
+    ));
+}
+
+#[test]
+fn test_blocksync_removes_peer_not_sending_blocks() {
+    init();
+    let (blocks, validators) = blocks(10);
+    let config = BlockSyncConfig {
+        request_timeout: Duration::from_millis(200),
+        ..BlockSyncConfig::default()
+    };
+    let (delivered, removed, ids) = sync(
+        vec![(blocks.clone(), true), (blocks, false)],
+        validators,
+        config,
+    );
+
+    assert_eq!(heights(&delivered), (1..10).collect::<Vec<_>>());
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].0, ids[0]);
+    assert!(matches!(
+        removed[0].1.detail(),
+        ErrorDetail::BlockRequestTimeout(_)
+    ));
+}
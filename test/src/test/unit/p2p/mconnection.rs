@@ -0,0 +1,266 @@
+use std::{
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use rand_core::OsRng;
+use tendermint::channel;
+use tendermint_p2p::{
+    mconnection::{ChannelDescriptor, MConnection, MConnectionConfig},
+    secret_connection::{SecretConnection, Version},
+};
+
+const CHANNEL_A: channel::Id = channel::Id(0x20);
+const CHANNEL_B: channel::Id = channel::Id(0x30);
+
+fn secret_connection(stream: TcpStream) -> SecretConnection<TcpStream> {
+    let private_key = ed25519_consensus::SigningKey::new(OsRng);
+    SecretConnection::new(stream, private_key, Version::V0_34).unwrap()
+}
+
+/// Connects two `MConnection`s over TCP, with the given channels on each side.
+fn connect(
+    channels1: &[ChannelDescriptor],
+    channels2: &[ChannelDescriptor],
+    config: &MConnectionConfig,
+) -> (MConnection, MConnection) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let accept = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        (secret_connection(stream.try_clone().unwrap()), stream)
+    });
+    let stream1 = TcpStream::connect(addr).unwrap();
+    let conn1 = secret_connection(stream1.try_clone().unwrap());
+    let (conn2, stream2) = accept.join().unwrap();
+
+    (
+        MConnection::new(conn1, stream1, channels1, config).unwrap(),
+        MConnection::new(conn2, stream2, channels2, config).unwrap(),
+    )
+}
+
+fn channels() -> Vec<ChannelDescriptor> {
+    vec![
+        ChannelDescriptor::new(CHANNEL_A),
+        ChannelDescriptor {
+            priority: 5,
+            send_queue_capacity: 10,
+            recv_queue_capacity: 10,
+            ..ChannelDescriptor::new(CHANNEL_B)
+        },
+    ]
+}
+
+#[test]
+fn test_multiplexes_channels() {
+    let config = MConnectionConfig {
+        max_packet_msg_payload_size: 100,
+        ..MConnectionConfig::default()
+    };
+    let (conn1, conn2) = connect(&channels(), &channels(), &config);
+
+    // Split into many packets, interleaved with those of the other channel.
+    let long_message = (0..10_000_u32).map(|i| i as u8).collect::<Vec<_>>();
+    let sender_a = conn1.sender(CHANNEL_A).unwrap();
+    let sender_b = conn1.sender(CHANNEL_B).unwrap();
+    sender_a.send(long_message.clone()).unwrap();
+    for i in 0..5_u8 {
+        sender_b.send(vec![i; 3]).unwrap();
+    }
+
+    let mut receiver_a = conn2.receiver(CHANNEL_A).unwrap();
+    let mut receiver_b = conn2.receiver(CHANNEL_B).unwrap();
+    assert_eq!(receiver_a.next().unwrap().unwrap(), long_message);
+    for i in 0..5_u8 {
+        assert_eq!(receiver_b.next().unwrap().unwrap(), vec![i; 3]);
+    }
+
+    conn2
+        .sender(CHANNEL_B)
+        .unwrap()
+        .send(b"ack".to_vec())
+        .unwrap();
+    assert_eq!(
+        conn1.receiver(CHANNEL_B).unwrap().next().unwrap().unwrap(),
+        b"ack"
+    );
+}
+
+#[test]
+fn test_rejects_invalid_channels() {
+    let config = MConnectionConfig::default();
+    let (conn, _peer) = connect(&channels(), &channels(), &config);
+
+    assert!(conn.sender(channel::Id(0x40)).is_err());
+    assert!(conn.receiver(channel::Id(0x40)).is_err());
+    assert!(conn.receiver(CHANNEL_A).is_ok());
+    assert!(conn.receiver(CHANNEL_A).is_err());
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let accept = thread::spawn(move || secret_connection(listener.accept().unwrap().0));
+    let stream = TcpStream::connect(addr).unwrap();
+    let secret = secret_connection(stream.try_clone().unwrap());
+    let _peer = accept.join().unwrap();
+
+    // Channel IDs are a single byte on the wire.
+    let result = MConnection::new(
+        secret,
+        stream,
+        &[ChannelDescriptor::new(channel::Id(0x100))],
+        &config,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_fails_on_message_for_unknown_channel() {
+    let config = MConnectionConfig::default();
+    let (conn1, conn2) = connect(&channels(), &channels()[..1], &config);
+
+    let mut receiver = conn2.receiver(CHANNEL_A).unwrap();
+    conn1
+        .sender(CHANNEL_B)
+        .unwrap()
+        .send(b"?".to_vec())
+        .unwrap();
+
+    assert!(receiver.next().unwrap().is_err());
+    assert!(receiver.next().is_none());
+    assert!(conn2
+        .sender(CHANNEL_A)
+        .unwrap()
+        .send(b"!".to_vec())
+        .is_err());
+}
+
+#[test]
+fn test_fails_on_message_over_capacity() {
+    let config = MConnectionConfig {
+        max_packet_msg_payload_size: 100,
+        ..MConnectionConfig::default()
+    };
+    let small = [ChannelDescriptor {
+        recv_message_capacity: 1_000,
+        ..ChannelDescriptor::new(CHANNEL_A)
+    }];
+    let (conn1, conn2) = connect(&channels(), &small, &config);
+
+    let mut receiver = conn2.receiver(CHANNEL_A).unwrap();
+    let sender = conn1.sender(CHANNEL_A).unwrap();
+    sender.send(vec![0; 1_000]).unwrap();
+    sender.send(vec![0; 1_001]).unwrap();
+
+    assert_eq!(receiver.next().unwrap().unwrap().len(), 1_000);
+    assert!(receiver.next().unwrap().is_err());
+}
+
+#[test]
+fn test_try_send_when_queue_full() {
+    // At 1 byte per second, the send routine is stuck on the first packet.
+    let config = MConnectionConfig {
+        send_rate: 1,
+        ..MConnectionConfig::default()
+    };
+    let (conn, _peer) = connect(&channels(), &channels(), &config);
+
+    let sender = conn.sender(CHANNEL_A).unwrap();
+    let queued = (0..3)
+        .map(|i| sender.try_send(vec![i; 10]).unwrap())
+        .collect::<Vec<_>>();
+    assert!(queued[0]);
+    assert!(!queued[2]);
+}
+
+#[test]
+fn test_fails_when_messages_are_not_received() {
+    let config = MConnectionConfig::default();
+    let (conn1, conn2) = connect(&channels(), &channels(), &config);
+
+    // The receiver is taken but not read from, so the queue of the channel fills up.
+    let mut receiver = conn2.receiver(CHANNEL_A).unwrap();
+    let sender = conn1.sender(CHANNEL_A).unwrap();
+    for i in 0..3_u8 {
+        sender.send(vec![i; 10]).unwrap();
+    }
+    thread::sleep(Duration::from_millis(200));
+
+    assert_eq!(receiver.next().unwrap().unwrap(), vec![0; 10]);
+    assert!(receiver.next().unwrap().is_err());
+}
+
+#[test]
+fn test_keeps_reading_when_channel_not_received() {
+    let config = MConnectionConfig {
+        ping_interval: Duration::from_millis(20),
+        pong_timeout: Duration::from_millis(200),
+        ..MConnectionConfig::default()
+    };
+    let (conn1, conn2) = connect(&channels(), &channels(), &config);
+
+    // Nobody takes the receiver of the flooded channel on the peer.
+    let sender = conn1.sender(CHANNEL_A).unwrap();
+    for i in 0..50_u8 {
+        sender.send(vec![i; 10]).unwrap();
+    }
+    let mut receiver = conn1.receiver(CHANNEL_B).unwrap();
+    thread::sleep(Duration::from_millis(500));
+
+    // The pongs of the peer kept the connection alive, and the other channels still work.
+    conn2
+        .sender(CHANNEL_B)
+        .unwrap()
+        .send(b"pong".to_vec())
+        .unwrap();
+    assert_eq!(receiver.next().unwrap().unwrap(), b"pong");
+    conn1
+        .sender(CHANNEL_B)
+        .unwrap()
+        .send(b"ping".to_vec())
+        .unwrap();
+    assert_eq!(
+        conn2.receiver(CHANNEL_B).unwrap().next().unwrap().unwrap(),
+        b"ping"
+    );
+}
+
+#[test]
+fn test_keeps_alive_with_pings() {
+    let config = MConnectionConfig {
+        ping_interval: Duration::from_millis(20),
+        pong_timeout: Duration::from_millis(500),
+        ..MConnectionConfig::default()
+    };
+    let (conn1, conn2) = connect(&channels(), &channels(), &config);
+
+    thread::sleep(Duration::from_millis(200));
+
+    conn1
+        .sender(CHANNEL_A)
+        .unwrap()
+        .send(b"ping".to_vec())
+        .unwrap();
+    assert_eq!(
+        conn2.receiver(CHANNEL_A).unwrap().next().unwrap().unwrap(),
+        b"ping"
+    );
+}
+
+#[test]
+fn test_close_ends_receivers() {
+    let config = MConnectionConfig::default();
+    let (conn1, conn2) = connect(&channels(), &channels(), &config);
+
+    let mut local = conn1.receiver(CHANNEL_A).unwrap();
+    let mut remote = conn2.receiver(CHANNEL_A).unwrap();
+    let sender = conn1.sender(CHANNEL_A).unwrap();
+    conn1.close().unwrap();
+
+    assert!(local.next().is_none());
+    // The peer sees the connection end between two packets, which is not a failure.
+    assert!(remote.next().is_none());
+    assert!(sender.send(b"gone".to_vec()).is_err());
+}
@@ -0,0 +1,187 @@
+use std::{
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use rand_core::OsRng;
+use tendermint::{
+    channel::Channels,
+    node::{
+        self,
+        info::{ListenAddress, OtherInfo, ProtocolVersionInfo, TxIndexStatus},
+    },
+};
+use tendermint_p2p::{
+    error::{Error, ErrorDetail},
+    node_info::{check_compatible, exchange, exchange_async, validate},
+    secret_connection::{self, AsyncSecretConnection, SecretConnection, Version},
+};
+use tokio::io::AsyncWriteExt as _;
+
+fn node_info(id: node::Id) -> node::Info {
+    node::Info {
+        protocol_version: ProtocolVersionInfo {
+            p2p: 8,
+            block: 11,
+            app: 1,
+        },
+        id,
+        listen_addr: ListenAddress::new("tcp://0.0.0.0:26656".to_owned()),
+        network: "test-chain".parse().unwrap(),
+        version: "0.38.0".to_owned().into(),
+        channels: Channels::new([0x20, 0x21, 0x40]),
+        moniker: "node".parse().unwrap(),
+        other: OtherInfo {
+            tx_index: TxIndexStatus::Off,
+            rpc_address: "tcp://0.0.0.0:26657".to_owned(),
+        },
+    }
+}
+
+fn random_id() -> node::Id {
+    secret_connection::PublicKey::from(&ed25519_consensus::SigningKey::new(OsRng)).peer_id()
+}
+
+/// Exchanges node info between two peers, each building its node info from its own ID.
+fn exchange_with(
+    info1: impl FnOnce(node::Id) -> node::Info + Send + 'static,
+    info2: impl FnOnce(node::Id) -> node::Info,
+) -> (Result<node::Info, Error>, Result<node::Info, Error>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let peer1 = thread::spawn(move || {
+        let key = ed25519_consensus::SigningKey::new(OsRng);
+        let id = secret_connection::PublicKey::from(&key).peer_id();
+        let (stream, _) = listener.accept().unwrap();
+        let mut conn = SecretConnection::new(stream, key, Version::V0_34).unwrap();
+        exchange(&mut conn, &info1(id))
+    });
+
+    let key = ed25519_consensus::SigningKey::new(OsRng);
+    let id = secret_connection::PublicKey::from(&key).peer_id();
+    let mut conn =
+        SecretConnection::new(TcpStream::connect(addr).unwrap(), key, Version::V0_34).unwrap();
+    let result2 = exchange(&mut conn, &info2(id));
+    (peer1.join().unwrap(), result2)
+}
+
+#[test]
+fn test_exchange() {
+    let (result1, result2) = exchange_with(node_info, node_info);
+    let (remote1, remote2) = (result1.unwrap(), result2.unwrap());
+
+    assert_eq!(remote1, node_info(remote1.id));
+    assert_eq!(remote2, node_info(remote2.id));
+    assert_ne!(remote1.id, remote2.id);
+}
+
+#[test]
+fn test_exchange_rejects_incompatible_network() {
+    let (result1, result2) = exchange_with(node_info, |id| node::Info {
+        network: "other-chain".parse().unwrap(),
+        ..node_info(id)
+    });
+
+    for result in [result1, result2] {
+        assert!(matches!(
+            result.unwrap_err().detail(),
+            ErrorDetail::IncompatibleNetwork(_)
+        ));
+    }
+}
+
+#[test]
+fn test_exchange_rejects_impersonation() {
+    let (result1, _) = exchange_with(node_info, |_| node_info(random_id()));
+
+    assert!(matches!(
+        result1.unwrap_err().detail(),
+        ErrorDetail::RemotePeerMismatch(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_exchange_async_rejects_overlong_length() {
+    let (io1, io2) = tokio::io::duplex(4096);
+    let key = ed25519_consensus::SigningKey::new(OsRng);
+    let id = secret_connection::PublicKey::from(&key).peer_id();
+    let (conn1, conn2) = tokio::join!(
+        AsyncSecretConnection::new(io1, key, Version::V0_34),
+        AsyncSecretConnection::new(
+            io2,
+            ed25519_consensus::SigningKey::new(OsRng),
+            Version::V0_34
+        ),
+    );
+    let (mut conn1, mut conn2) = (conn1.unwrap(), conn2.unwrap());
+
+    // A length prefix that does not fit in 64 bits.
+    let peer = async {
+        conn2.write_all(&[0xff; 11]).await.unwrap();
+        conn2.flush().await.unwrap();
+    };
+    let info = node_info(id);
+    let (result, ()) = tokio::join!(exchange_async(&mut conn1, &info), peer);
+
+    assert!(matches!(
+        result.unwrap_err().detail(),
+        ErrorDetail::Protocol(_)
+    ));
+}
+
+#[test]
+fn test_check_compatible() {
+    let local = node_info(random_id());
+    let remote = node_info(random_id());
+    assert!(check_compatible(&local, &remote).is_ok());
+
+    let mut other = remote.clone();
+    other.protocol_version.block = 10;
+    assert!(matches!(
+        check_compatible(&local, &other).unwrap_err().detail(),
+        ErrorDetail::IncompatibleBlockVersion(e) if e.local == 11 && e.remote == 10
+    ));
+
+    let mut other = remote.clone();
+    other.protocol_version.p2p = 7;
+    assert!(matches!(
+        check_compatible(&local, &other).unwrap_err().detail(),
+        ErrorDetail::IncompatibleP2pVersion(_)
+    ));
+
+    // Different app versions are fine.
+    let mut other = remote.clone();
+    other.protocol_version.app = 2;
+    assert!(check_compatible(&local, &other).is_ok());
+
+    let mut other = remote.clone();
+    other.channels = Channels::new([0x30, 0x38]);
+    assert!(matches!(
+        check_compatible(&local, &other).unwrap_err().detail(),
+        ErrorDetail::NoCommonChannels(_)
+    ));
+
+    // Nodes without channels talk to anybody.
+    let mut crawler = local;
+    crawler.channels = Channels::default();
+    assert!(check_compatible(&crawler, &other).is_ok());
+}
+
+#[test]
+fn test_validate() {
+    let info = node_info(random_id());
+    assert!(validate(&info).is_ok());
+
+    let mut invalid = info.clone();
+    invalid.channels = Channels::new([0x20, 0x21, 0x20]);
+    assert!(validate(&invalid).is_err());
+
+    let mut invalid = info.clone();
+    invalid.channels = Channels::new((0..17).collect::<Vec<u8>>());
+    assert!(validate(&invalid).is_err());
+
+    let mut invalid = info;
+    invalid.moniker = "".parse().unwrap();
+    assert!(validate(&invalid).is_err());
+}
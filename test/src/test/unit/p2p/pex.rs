@@ -0,0 +1,280 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use rand_core::OsRng;
+use tendermint::{node, public_key::PublicKey};
+use tendermint_p2p::{
+    error::ErrorDetail,
+    pex::{AddrBook, NetAddress, PexConfig, PexReactor},
+    secret_connection,
+    transport::{memory::MemoryNetwork, BindInfo, ConnectInfo, Endpoint, Transport},
+};
+
+use super::init;
+
+fn random_id() -> node::Id {
+    secret_connection::PublicKey::from(&ed25519_consensus::SigningKey::new(OsRng)).peer_id()
+}
+
+fn net_address(ip: &str) -> NetAddress {
+    NetAddress::new(random_id(), format!("{ip}:26656").parse().unwrap())
+}
+
+fn reactor(book: AddrBook, config: PexConfig) -> PexReactor {
+    PexReactor::new(Arc::new(Mutex::new(book)), config)
+}
+
+#[test]
+fn test_net_address_encodings() {
+    let addr = net_address("1.2.3.4");
+
+    let s = addr.to_string();
+    assert_eq!(s, format!("{}@1.2.3.4:26656", addr.id));
+    assert_eq!(s.parse::<NetAddress>().unwrap(), addr);
+    assert!("1.2.3.4:26656".parse::<NetAddress>().is_err());
+
+    let raw = tendermint_proto::v0_38::p2p::NetAddress::from(addr);
+    assert_eq!(raw.ip, "1.2.3.4");
+    assert_eq!(NetAddress::try_from(raw.clone()).unwrap(), addr);
+    let out_of_range = tendermint_proto::v0_38::p2p::NetAddress { port: 65536, ..raw };
+    assert!(NetAddress::try_from(out_of_range).is_err());
+
+    assert!(addr.is_routable());
+    for ip in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "192.168.1.1",
+        "0.0.0.0",
+        "[::1]",
+        "[fe80::1]",
+    ] {
+        assert!(!net_address(ip).is_routable(), "{ip}");
+    }
+}
+
+#[test]
+fn test_addr_book_add_and_mark() {
+    let mut book = AddrBook::new(true);
+    let src = net_address("5.6.7.8");
+    let ours = net_address("9.9.9.9");
+    let private = net_address("9.9.9.10");
+    book.add_our_address(ours);
+    book.add_private_ids([private.id]);
+
+    assert!(book.add_address(ours, src).is_err());
+    assert!(book.add_address(private, src).is_err());
+    assert!(book.add_address(net_address("127.0.0.1"), src).is_err());
+    assert!(book.is_empty());
+    assert!(book.pick_address(50).is_none());
+
+    let addr = net_address("1.2.3.4");
+    book.add_address(addr, src).unwrap();
+    book.add_address(addr, src).unwrap();
+    assert_eq!(book.size(), 1);
+    assert!(book.has_address(addr.id));
+    assert!(!book.is_good(addr.id));
+    assert_eq!(book.pick_address(100), Some(addr));
+
+    book.mark_attempt(addr.id);
+    assert!(book.last_attempt(addr.id).is_some());
+    book.mark_good(addr.id);
+    assert!(book.is_good(addr.id));
+    assert_eq!(book.size(), 1);
+    assert_eq!(book.pick_address(0), Some(addr));
+
+    book.mark_bad(addr.id, Duration::from_secs(3600));
+    assert!(!book.has_address(addr.id));
+    assert!(book.is_banned(addr.id));
+    assert!(book.add_address(addr, src).is_err());
+    book.reinstate_bad_peers();
+    assert!(book.is_banned(addr.id));
+
+    let banned_briefly = net_address("1.2.3.5");
+    book.add_address(banned_briefly, src).unwrap();
+    book.mark_bad(banned_briefly.id, Duration::ZERO);
+    book.reinstate_bad_peers();
+    assert!(book.has_address(banned_briefly.id));
+    assert!(!book.is_good(banned_briefly.id));
+}
+
+#[test]
+fn test_addr_book_selection() {
+    let mut book = AddrBook::new(false);
+    assert!(book.selection().is_empty());
+
+    // Spread over many groups, so that no bucket overflows.
+    for i in 0..400 {
+        let addr = net_address(&format!("{}.{}.1.1", 1 + i / 200, i % 200));
+        book.add_address(addr, addr).unwrap();
+    }
+    assert_eq!(book.size(), 400);
+    assert!(book.need_more_addrs());
+
+    let selection = book.selection();
+    // 23% of the book.
+    assert_eq!(selection.len(), 92);
+    assert!(selection.iter().all(|addr| book.has_address(addr.id)));
+    let mut ids = selection.iter().map(|addr| addr.id).collect::<Vec<_>>();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), selection.len());
+}
+
+#[test]
+fn test_addr_book_persistence() {
+    let path = std::env::temp_dir().join(format!("addrbook-{}.json", random_id()));
+    let good = net_address("1.2.3.4");
+    let new = net_address("5.6.7.8");
+
+    let mut book = AddrBook::load(&path, true).unwrap();
+    assert!(book.is_empty());
+    book.add_address(good, new).unwrap();
+    book.add_address(new, good).unwrap();
+    book.mark_good(good.id);
+    book.save().unwrap();
+
+    let loaded = AddrBook::load(&path, true).unwrap();
+    assert_eq!(loaded.size(), 2);
+    assert!(loaded.is_good(good.id));
+    assert!(loaded.has_address(new.id));
+    assert!(!loaded.is_good(new.id));
+
+    std::fs::write(&path, "{\"key\": \"00\", \"addrs\": 1}").unwrap();
+    let err = AddrBook::load(&path, true).err().unwrap();
+    assert!(matches!(err.detail(), ErrorDetail::InvalidAddrBook(_)));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_reactor_request_and_serve() {
+    let server_addr = net_address("1.1.1.1");
+    let client_addr = net_address("2.2.2.2");
+    let mut server_book = AddrBook::new(true);
+    let known = (0..5)
+        .map(|i| net_address(&format!("3.3.3.{i}")))
+        .collect::<Vec<_>>();
+    for &addr in &known {
+        server_book.add_address(addr, addr).unwrap();
+    }
+    let server = reactor(server_book, PexConfig::default());
+    let client = reactor(AddrBook::new(true), PexConfig::default());
+
+    assert!(server.add_peer(client_addr, false).is_none());
+    let request = client.add_peer(server_addr, true).unwrap();
+    // A single request is pending at a time.
+    assert!(client.request_addrs(server_addr.id).is_none());
+
+    let reply = server.receive(client_addr.id, &request).unwrap().unwrap();
+    assert!(client.receive(server_addr.id, &reply).unwrap().is_none());
+    let client_book = client.book().lock().unwrap();
+    assert!(known.iter().all(|addr| client_book.has_address(addr.id)));
+    assert!(client_book.is_good(server_addr.id));
+    drop(client_book);
+    assert!(server.book().lock().unwrap().has_address(client_addr.id));
+
+    // Addresses must be requested, and requests must not come too often.
+    let err = client.receive(server_addr.id, &reply).unwrap_err();
+    assert!(matches!(err.detail(), ErrorDetail::UnsolicitedPexAddrs(_)));
+    let err = server.receive(client_addr.id, &request).unwrap_err();
+    assert!(matches!(
+        err.detail(),
+        ErrorDetail::PexRequestTooFrequent(_)
+    ));
+
+    let err = server.receive(random_id(), &request).unwrap_err();
+    assert!(matches!(err.detail(), ErrorDetail::UnknownPeer(_)));
+    let err = server.receive(client_addr.id, b"\xff").unwrap_err();
+    assert!(matches!(err.detail(), ErrorDetail::InvalidPexMessage(_)));
+}
+
+fn listen_addr<E: Endpoint<SocketAddr>>(endpoint: &E) -> SocketAddr {
+    endpoint.listen_addrs()[0]
+}
+
+fn bind_info(public_key: PublicKey) -> BindInfo<&'static str> {
+    BindInfo {
+        advertise_addrs: "127.0.0.1:0",
+        bind_addrs: "127.0.0.1:0",
+        public_key,
+    }
+}
+
+#[test]
+fn test_seed_serves_and_crawls() {
+    init();
+    let network = MemoryNetwork::new();
+    let keypair = || {
+        let key = ed25519_consensus::SigningKey::new(OsRng);
+        let public_key = PublicKey::from_raw_ed25519(key.verification_key().as_bytes()).unwrap();
+        let id = secret_connection::PublicKey::from(&key).peer_id();
+        (key, public_key, id)
+    };
+    let (seed_key, seed_public_key, seed_id) = keypair();
+    let (node_key, node_public_key, node_id) = keypair();
+    let (seed_endpoint, mut seed_incoming) = network
+        .transport(seed_key)
+        .bind(bind_info(seed_public_key))
+        .unwrap();
+    let (node_endpoint, mut node_incoming) = network
+        .transport(node_key)
+        .bind(bind_info(node_public_key))
+        .unwrap();
+    let seed_addr = NetAddress::new(seed_id, listen_addr(&seed_endpoint));
+    let node_addr = NetAddress::new(node_id, listen_addr(&node_endpoint));
+
+    let mut seed_book = AddrBook::new(false);
+    seed_book.add_our_address(seed_addr);
+    let known = net_address("4.4.4.4");
+    seed_book.add_address(known, known).unwrap();
+    let seed_config = PexConfig {
+        seed_mode: true,
+        ..PexConfig::default()
+    };
+    let seed = reactor(seed_book, seed_config);
+    let node = reactor(AddrBook::new(false), PexConfig::default());
+
+    // The seed answers the request of the node, and disconnects.
+    thread::scope(|scope| {
+        let served = scope.spawn(|| {
+            let conn = seed_incoming.next().unwrap().unwrap();
+            seed.serve(&conn, node_addr, false)
+        });
+        let conn = node_endpoint
+            .connect(ConnectInfo {
+                addrs: seed_addr.addr,
+                id: seed_addr.id,
+            })
+            .unwrap();
+        node.serve(&conn, seed_addr, true).unwrap();
+        served.join().unwrap().unwrap();
+    });
+    let node_book = node.book().lock().unwrap();
+    assert!(node_book.has_address(known.id));
+    assert!(node_book.is_good(seed_addr.id));
+    drop(node_book);
+
+    // The seed learnt the address of the node, and crawls it. The node answers with what it
+    // learnt from the seed, and keeps the connection.
+    assert!(!seed.book().lock().unwrap().is_good(node_addr.id));
+    thread::scope(|scope| {
+        let served = scope.spawn(|| {
+            let conn = node_incoming.next().unwrap().unwrap();
+            node.serve(&conn, seed_addr, false)
+        });
+        // The other known address cannot be connected to.
+        assert_eq!(seed.crawl(&seed_endpoint), 2);
+        served.join().unwrap().unwrap();
+    });
+    let seed_book = seed.book().lock().unwrap();
+    assert!(seed_book.is_good(node_addr.id));
+    assert!(!seed_book.is_good(known.id));
+    assert!(seed_book.last_attempt(known.id).is_some());
+    drop(seed_book);
+
+    // Addresses are not crawled again right away.
+    assert_eq!(seed.crawl(&seed_endpoint), 0);
+}
@@ -0,0 +1,281 @@
+use std::{os::unix::net::UnixStream, thread};
+
+use rand_core::OsRng;
+use tendermint::{
+    account, chain,
+    privval::RemoteSignerError,
+    proposal::{self, Proposal},
+    public_key::PubKeyRequest,
+    vote::{self, ValidatorIndex, Vote},
+    PublicKey, Signature, Time,
+};
+use tendermint_p2p::{
+    error::ErrorDetail,
+    privval::{
+        read_message, write_message, Address, Listener, Request, Signer, SignerClient, SignerServer,
+    },
+    secret_connection,
+};
+
+/// Signs with an in-memory key, on a single chain.
+struct TestSigner {
+    key: ed25519_consensus::SigningKey,
+    chain_id: chain::Id,
+}
+
+impl TestSigner {
+    fn new() -> Self {
+        Self {
+            key: ed25519_consensus::SigningKey::new(OsRng),
+            chain_id: chain_id(),
+        }
+    }
+
+    fn check_chain(&self, chain_id: &chain::Id) -> Result<(), RemoteSignerError> {
+        if *chain_id == self.chain_id {
+            Ok(())
+        } else {
+            Err(RemoteSignerError {
+                code: 1,
+                description: format!("unknown chain {chain_id}"),
+            })
+        }
+    }
+}
+
+impl Signer for TestSigner {
+    fn public_key(&self, chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError> {
+        self.check_chain(chain_id)?;
+        Ok(PublicKey::from_raw_ed25519(self.key.verification_key().as_bytes()).unwrap())
+    }
+
+    fn sign_vote(
+        &mut self,
+        chain_id: &chain::Id,
+        mut vote: Vote,
+    ) -> Result<Vote, RemoteSignerError> {
+        self.check_chain(chain_id)?;
+        let sign_bytes = vote.clone().into_signable_vec(chain_id.clone());
+        vote.signature = Signature::new(self.key.sign(&sign_bytes).to_bytes()).unwrap();
+        Ok(vote)
+    }
+
+    fn sign_proposal(
+        &mut self,
+        chain_id: &chain::Id,
+        mut proposal: Proposal,
+    ) -> Result<Proposal, RemoteSignerError> {
+        self.check_chain(chain_id)?;
+        let sign_bytes = proposal.clone().into_signable_vec(chain_id.clone());
+        proposal.signature = Signature::new(self.key.sign(&sign_bytes).to_bytes()).unwrap();
+        Ok(proposal)
+    }
+}
+
+fn chain_id() -> chain::Id {
+    "test-chain".parse().unwrap()
+}
+
+fn vote() -> Vote {
+    Vote {
+        vote_type: vote::Type::Prevote,
+        height: 10_u32.into(),
+        round: 1_u8.into(),
+        block_id: None,
+        timestamp: Some(Time::from_unix_timestamp(1_700_000_000, 0).unwrap()),
+        validator_address: account::Id::new([1; 20]),
+        validator_index: ValidatorIndex::try_from(0_u32).unwrap(),
+        signature: None,
+        extension: Vec::new(),
+        extension_signature: None,
+    }
+}
+
+fn proposal() -> Proposal {
+    Proposal {
+        msg_type: proposal::Type::Proposal,
+        height: 10_u32.into(),
+        round: 1_u8.into(),
+        pol_round: None,
+        block_id: None,
+        timestamp: Some(Time::from_unix_timestamp(1_700_000_000, 0).unwrap()),
+        signature: None,
+    }
+}
+
+fn verify(public_key: PublicKey, sign_bytes: &[u8], signature: &Signature) {
+    let key =
+        ed25519_consensus::VerificationKey::try_from(public_key.to_bytes().as_slice()).unwrap();
+    let signature = ed25519_consensus::Signature::try_from(signature.as_bytes()).unwrap();
+    key.verify(&signature, sign_bytes).unwrap();
+}
+
+/// Exercises every request against a [`TestSigner`].
+fn check_client<IoHandler: std::io::Read + std::io::Write>(client: &mut SignerClient<IoHandler>) {
+    let public_key = client.public_key(&chain_id()).unwrap();
+
+    let signed = client.sign_vote(&chain_id(), vote()).unwrap();
+    let sign_bytes = vote().into_signable_vec(chain_id());
+    verify(public_key, &sign_bytes, signed.signature.as_ref().unwrap());
+
+    let signed = client.sign_proposal(&chain_id(), proposal()).unwrap();
+    let sign_bytes = proposal().into_signable_vec(chain_id());
+    verify(public_key, &sign_bytes, signed.signature.as_ref().unwrap());
+
+    client.ping().unwrap();
+
+    let err = client
+        .sign_vote(&"other-chain".parse().unwrap(), vote())
+        .unwrap_err();
+    match err.detail() {
+        ErrorDetail::RemoteSigner(e) => {
+            assert_eq!(e.code, 1);
+            assert_eq!(e.description, "unknown chain other-chain");
+        },
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn test_address_parsing() {
+    let id =
+        secret_connection::PublicKey::from(&ed25519_consensus::SigningKey::new(OsRng)).peer_id();
+    for s in [
+        format!("tcp://{id}@127.0.0.1:26659"),
+        "tcp://127.0.0.1:26659".to_owned(),
+        "unix:///tmp/privval.sock".to_owned(),
+    ] {
+        let addr = s.parse::<Address>().unwrap();
+        assert_eq!(addr.to_string(), s);
+    }
+    assert_eq!(
+        format!("tcp://{id}@127.0.0.1:26659")
+            .parse::<Address>()
+            .unwrap(),
+        Address::Tcp {
+            peer_id: Some(id),
+            addr: "127.0.0.1:26659".parse().unwrap(),
+        }
+    );
+    for s in [
+        "127.0.0.1:26659",
+        "tcp://nobody@127.0.0.1:1",
+        "tcp://localhost",
+    ] {
+        assert!(s.parse::<Address>().is_err(), "{s}");
+    }
+}
+
+#[test]
+fn test_signer_dials_node_over_unix_socket() {
+    let path = std::env::temp_dir().join(format!("privval-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let addr = Address::Unix(path.clone());
+    let identity = ed25519_consensus::SigningKey::new(OsRng);
+    let listener = Listener::bind(&addr, identity.clone()).unwrap();
+    assert_eq!(listener.local_addr().unwrap(), addr);
+
+    let signer = thread::spawn(move || {
+        let mut server = SignerServer::new(TestSigner::new());
+        server.dial(&addr, &identity).map(|()| server)
+    });
+    let mut client = SignerClient::accept(&listener).unwrap();
+    assert!(client.inner().peer_id().is_none());
+    check_client(&mut client);
+
+    // The signer stops serving once the node disconnects.
+    drop(client);
+    signer.join().unwrap().unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_binds_over_stale_unix_socket() {
+    let path = std::env::temp_dir().join(format!("privval-stale-{}.sock", std::process::id()));
+    let addr = Address::Unix(path.clone());
+
+    // The socket file outlives the listener, as after a crash.
+    drop(Listener::bind(&addr, ed25519_consensus::SigningKey::new(OsRng)).unwrap());
+    assert!(path.exists());
+
+    Listener::bind(&addr, ed25519_consensus::SigningKey::new(OsRng)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_node_dials_signer_over_secret_connection() {
+    let signer_identity = ed25519_consensus::SigningKey::new(OsRng);
+    let node_identity = ed25519_consensus::SigningKey::new(OsRng);
+    let signer_id = secret_connection::PublicKey::from(&signer_identity).peer_id();
+    let node_id = secret_connection::PublicKey::from(&node_identity).peer_id();
+
+    let listen_addr = Address::Tcp {
+        peer_id: Some(node_id),
+        addr: "127.0.0.1:0".parse().unwrap(),
+    };
+    let listener = Listener::bind(&listen_addr, signer_identity).unwrap();
+    let Address::Tcp { addr, .. } = listener.local_addr().unwrap() else {
+        unreachable!()
+    };
+
+    let signer = thread::spawn(move || {
+        let mut server = SignerServer::new(TestSigner::new());
+        // A node with another identity is rejected, before the expected one is served.
+        assert!(server.accept(&listener).is_err());
+        server.accept(&listener)
+    });
+
+    let other_identity = ed25519_consensus::SigningKey::new(OsRng);
+    let addr = Address::Tcp {
+        peer_id: Some(signer_id),
+        addr,
+    };
+    // The signer rejects the connection once the handshake is done.
+    if let Ok(mut client) = SignerClient::connect(&addr, &other_identity) {
+        assert!(client.ping().is_err());
+    }
+
+    let mut client = SignerClient::connect(&addr, &node_identity).unwrap();
+    assert_eq!(client.inner().peer_id(), Some(signer_id));
+    check_client(&mut client);
+    drop(client);
+    signer.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_signer_rejects_unexpected_messages() {
+    let (mut node, signer) = UnixStream::pair().unwrap();
+    let server = thread::spawn(move || SignerServer::new(TestSigner::new()).serve(signer));
+
+    write_message(&mut node, tendermint_p2p::privval::Response::Ping).unwrap();
+    let err = server.join().unwrap().unwrap_err();
+    assert!(matches!(
+        err.detail(),
+        ErrorDetail::UnexpectedPrivvalMessage(_)
+    ));
+
+    // Requests are answered in order.
+    let (node, signer) = UnixStream::pair().unwrap();
+    let server = thread::spawn(move || SignerServer::new(TestSigner::new()).serve(signer));
+    let mut client = SignerClient::new(node);
+    let request = Request::PubKey(PubKeyRequest {
+        chain_id: chain_id(),
+    });
+    for _ in 0..3 {
+        assert!(client.request(request.clone()).is_ok());
+    }
+    drop(client);
+    server.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_rejects_overlong_message_length() {
+    // The bits of the tenth byte past the 64th bit of the length do not fit.
+    let mut overflowing: &[u8] = &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x02];
+    assert!(read_message(&mut overflowing).is_err());
+
+    // The largest length fits, and is then rejected for its size.
+    let mut largest: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    let err = read_message(&mut largest).unwrap_err();
+    assert!(matches!(err.detail(), ErrorDetail::MessageTooLarge(_)));
+}
@@ -13,6 +13,7 @@ use tendermint_proto::v0_38 as proto;
 
 use crate::pipe;
 
+mod async_connection;
 mod nonce;
 mod public_key;
 
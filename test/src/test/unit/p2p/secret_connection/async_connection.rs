@@ -0,0 +1,136 @@
+use std::{
+    io::{Read as _, Write as _},
+    net::TcpListener as StdTcpListener,
+    thread,
+};
+
+use rand_core::OsRng;
+use tendermint_p2p::secret_connection::{
+    AsyncSecretConnection, PublicKey, SecretConnection, Version,
+};
+use tokio::{
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+    net::{TcpListener, TcpStream},
+};
+
+fn private_key() -> ed25519_consensus::SigningKey {
+    ed25519_consensus::SigningKey::new(OsRng)
+}
+
+/// Longer than a single frame, and not a multiple of the frame size.
+fn long_message() -> Vec<u8> {
+    (0..5_000_u32).map(|i| (i % 251) as u8).collect()
+}
+
+#[tokio::test]
+async fn test_async_read_write() {
+    // Both peers write their handshake messages before reading, so the pipe must hold a frame.
+    let (io1, io2) = tokio::io::duplex(2048);
+    let (key1, key2) = (private_key(), private_key());
+    let (pubkey1, pubkey2) = (PublicKey::from(&key1), PublicKey::from(&key2));
+
+    let (conn1, conn2) = tokio::join!(
+        AsyncSecretConnection::new(io1, key1, Version::V0_34),
+        AsyncSecretConnection::new(io2, key2, Version::V0_34),
+    );
+    let (mut conn1, mut conn2) = (conn1.unwrap(), conn2.unwrap());
+    assert_eq!(conn1.remote_pubkey(), pubkey2);
+    assert_eq!(conn2.remote_pubkey(), pubkey1);
+
+    let message = long_message();
+    let writer = async {
+        conn1.write_all(&message).await.unwrap();
+        conn1.shutdown().await.unwrap();
+    };
+    let reader = async {
+        let mut buf = Vec::new();
+        conn2.read_to_end(&mut buf).await.unwrap();
+        buf
+    };
+    let ((), received) = tokio::join!(writer, reader);
+    assert_eq!(received, message);
+}
+
+#[tokio::test]
+async fn test_async_split_full_duplex() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let accept = async {
+        let (stream, _) = listener.accept().await.unwrap();
+        AsyncSecretConnection::new(stream, private_key(), Version::V0_34).await
+    };
+    let connect = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        AsyncSecretConnection::new(stream, private_key(), Version::V0_34).await
+    };
+    let (conn1, conn2) = tokio::join!(accept, connect);
+
+    let message = long_message();
+    let (mut send1, mut recv1) = conn1.unwrap().split_with(TcpStream::into_split);
+    let (mut send2, mut recv2) = conn2.unwrap().split();
+
+    // Both peers send before receiving anything.
+    let peer1 = async {
+        send1.write_all(&message).await.unwrap();
+        send1.flush().await.unwrap();
+        let mut buf = vec![0; message.len()];
+        recv1.read_exact(&mut buf).await.unwrap();
+        buf
+    };
+    let peer2 = async {
+        send2.write_all(&message).await.unwrap();
+        send2.flush().await.unwrap();
+        let mut buf = vec![0; message.len()];
+        recv2.read_exact(&mut buf).await.unwrap();
+        buf
+    };
+    let (received1, received2) = tokio::join!(peer1, peer2);
+    assert_eq!(received1, message);
+    assert_eq!(received2, message);
+}
+
+#[tokio::test]
+async fn test_async_interop_with_blocking() {
+    let listener = StdTcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let message = long_message();
+    let echoed = message.clone();
+
+    // Blocking peer, echoing what it receives.
+    let blocking = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut conn = SecretConnection::new(stream, private_key(), Version::V0_34).unwrap();
+        let mut buf = vec![0; echoed.len()];
+        conn.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, echoed);
+        conn.write_all(&buf).unwrap();
+    });
+
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let mut conn = AsyncSecretConnection::new(stream, private_key(), Version::V0_34)
+        .await
+        .unwrap();
+    conn.write_all(&message).await.unwrap();
+    conn.flush().await.unwrap();
+    let mut buf = vec![0; message.len()];
+    conn.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, message);
+
+    blocking.join().unwrap();
+}
+
+#[tokio::test]
+async fn test_async_end_of_stream() {
+    let (io1, io2) = tokio::io::duplex(4096);
+    let (conn1, conn2) = tokio::join!(
+        AsyncSecretConnection::new(io1, private_key(), Version::V0_34),
+        AsyncSecretConnection::new(io2, private_key(), Version::V0_34),
+    );
+    let (conn1, mut conn2) = (conn1.unwrap(), conn2.unwrap());
+
+    // Ending the stream between frames ends the connection cleanly.
+    drop(conn1);
+    let mut buf = [0; 16];
+    assert_eq!(conn2.read(&mut buf).await.unwrap(), 0);
+}
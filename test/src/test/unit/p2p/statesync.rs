@@ -0,0 +1,369 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use prost::Message as _;
+use rand_core::OsRng;
+use tendermint::{block::Height, node, public_key::PublicKey, AppHash};
+use tendermint_abci::{Application, ClientBuilder, ServerBuilder};
+use tendermint_config::StatesyncConfig;
+use tendermint_p2p::{
+    error::{Error, ErrorDetail},
+    secret_connection,
+    statesync::{AppHashSource, Snapshot, StateSync},
+    transport::{
+        memory::{MemoryConnection, MemoryEndpoint, MemoryIncoming, MemoryNetwork},
+        BindInfo, ConnectInfo, Connection, Endpoint, StreamId, StreamSend, Transport,
+    },
+};
+use tendermint_proto::v0_38::{
+    abci::{
+        response_apply_snapshot_chunk, response_offer_snapshot, RequestApplySnapshotChunk,
+        RequestInfo, RequestOfferSnapshot, ResponseApplySnapshotChunk, ResponseInfo,
+        ResponseOfferSnapshot,
+    },
+    statesync::{message::Sum, ChunkResponse, Message as RawMessage, SnapshotsResponse},
+};
+
+use super::init;
+
+const CHUNKS: u32 = 4;
+
+fn chunk(index: u32) -> Vec<u8> {
+    format!("chunk-{index}").into_bytes()
+}
+
+/// App hash of the state restored from the chunks.
+fn app_hash() -> AppHash {
+    AppHash::try_from((0..CHUNKS).flat_map(chunk).collect::<Vec<_>>()).unwrap()
+}
+
+fn snapshot(height: u32) -> Snapshot {
+    Snapshot {
+        height: Height::from(height),
+        format: 1,
+        chunks: CHUNKS,
+        hash: vec![height as u8; 32],
+        metadata: Vec::new(),
+    }
+}
+
+/// App hashes of the heights of a fake light client.
+struct AppHashes(HashMap<Height, AppHash>);
+
+impl AppHashSource for AppHashes {
+    fn app_hash(&mut self, height: Height) -> Result<AppHash, Error> {
+        self.0
+            .get(&height)
+            .cloned()
+            .ok_or_else(|| Error::unverified_app_hash(height, "no such height".to_owned()))
+    }
+}
+
+/// Source of app hashes whose light client cannot reach its peers.
+struct Unreachable;
+
+impl AppHashSource for Unreachable {
+    fn app_hash(&mut self, height: Height) -> Result<AppHash, Error> {
+        Err(Error::app_hash_unavailable(
+            height,
+            "no witnesses left".to_owned(),
+        ))
+    }
+}
+
+#[derive(Default)]
+struct AppState {
+    offered: Vec<Height>,
+    height: i64,
+    chunks: Vec<Vec<u8>>,
+    rejected: Vec<String>,
+}
+
+/// Application restoring the snapshots of format 1 whose chunks are the expected ones,
+/// rejecting the senders of other chunks.
+#[derive(Clone, Default)]
+struct SnapshotApp(Arc<Mutex<AppState>>);
+
+impl Application for SnapshotApp {
+    fn info(&self, _request: RequestInfo) -> ResponseInfo {
+        let state = self.0.lock().unwrap();
+        ResponseInfo {
+            last_block_height: state.height,
+            last_block_app_hash: state.chunks.concat().into(),
+            ..Default::default()
+        }
+    }
+
+    fn offer_snapshot(&self, request: RequestOfferSnapshot) -> ResponseOfferSnapshot {
+        let snapshot = request.snapshot.unwrap();
+        let mut state = self.0.lock().unwrap();
+        state
+            .offered
+            .push(Height::try_from(snapshot.height).unwrap());
+        let result = if snapshot.format != 1 {
+            response_offer_snapshot::Result::RejectFormat
+        } else if request.app_hash != app_hash().as_bytes() {
+            response_offer_snapshot::Result::Reject
+        } else {
+            state.height = snapshot.height as i64;
+            state.chunks.clear();
+            response_offer_snapshot::Result::Accept
+        };
+        ResponseOfferSnapshot {
+            result: result.into(),
+        }
+    }
+
+    fn apply_snapshot_chunk(
+        &self,
+        request: RequestApplySnapshotChunk,
+    ) -> ResponseApplySnapshotChunk {
+        let mut state = self.0.lock().unwrap();
+        if request.chunk != chunk(request.index) {
+            state.rejected.push(request.sender.clone());
+            return ResponseApplySnapshotChunk {
+                result: response_apply_snapshot_chunk::Result::Retry.into(),
+                refetch_chunks: vec![request.index],
+                reject_senders: vec![request.sender],
+            };
+        }
+        state.chunks.push(request.chunk.to_vec());
+        ResponseApplySnapshotChunk {
+            result: response_apply_snapshot_chunk::Result::Accept.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Serves snapshots over the statesync streams of the connection, until it is closed.
+/// Corrupt peers send invalid chunks.
+fn serve(conn: &MemoryConnection, snapshots: &[Snapshot], corrupt: bool) {
+    let (snapshot_read, snapshot_send) = conn.open_bidirectional(StreamId::Snapshot).unwrap();
+    let (chunk_read, chunk_send) = conn.open_bidirectional(StreamId::Chunk).unwrap();
+
+    let snapshots = snapshots.to_vec();
+    let snapshot_server = thread::spawn(move || {
+        for msg in snapshot_read {
+            let Ok(msg) = msg else { break };
+            let Some(Sum::SnapshotsRequest(_)) = RawMessage::decode(msg.as_slice()).unwrap().sum
+            else {
+                continue;
+            };
+            for snapshot in &snapshots {
+                let raw = tendermint_proto::v0_38::abci::Snapshot::from(snapshot.clone());
+                let response = Sum::SnapshotsResponse(SnapshotsResponse {
+                    height: raw.height,
+                    format: raw.format,
+                    chunks: raw.chunks,
+                    hash: raw.hash.to_vec(),
+                    metadata: raw.metadata.to_vec(),
+                });
+                if snapshot_send
+                    .send(
+                        RawMessage {
+                            sum: Some(response),
+                        }
+                        .encode_to_vec(),
+                    )
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+    });
+
+    for msg in chunk_read {
+        let Ok(msg) = msg else { break };
+        let Some(Sum::ChunkRequest(request)) = RawMessage::decode(msg.as_slice()).unwrap().sum
+        else {
+            continue;
+        };
+        let chunk = if corrupt {
+            b"corrupt".to_vec()
+        } else {
+            chunk(request.index)
+        };
+        let response = Sum::ChunkResponse(ChunkResponse {
+            height: request.height,
+            format: request.format,
+            index: request.index,
+            chunk,
+            missing: false,
+        });
+        if chunk_send
+            .send(
+                RawMessage {
+                    sum: Some(response),
+                }
+                .encode_to_vec(),
+            )
+            .is_err()
+        {
+            break;
+        }
+    }
+    snapshot_server.join().unwrap();
+}
+
+fn bind(network: &MemoryNetwork) -> (MemoryEndpoint, MemoryIncoming, node::Id) {
+    let key = ed25519_consensus::SigningKey::new(OsRng);
+    let public_key = PublicKey::from_raw_ed25519(key.verification_key().as_bytes()).unwrap();
+    let id = secret_connection::PublicKey::from(&key).peer_id();
+    let (endpoint, incoming) = network
+        .transport(key)
+        .bind(BindInfo {
+            advertise_addrs: "127.0.0.1:0",
+            bind_addrs: "127.0.0.1:0",
+            public_key,
+        })
+        .unwrap();
+    (endpoint, incoming, id)
+}
+
+fn config() -> StatesyncConfig {
+    StatesyncConfig {
+        enable: true,
+        rpc_servers: Vec::new(),
+        trust_height: 0,
+        trust_hash: String::new(),
+        trust_period: "168h0m0s".to_owned(),
+        discovery_time: Duration::from_millis(300).into(),
+        temp_dir: String::new(),
+        chunk_request_timeout: Duration::from_secs(2).into(),
+        chunk_fetchers: 2,
+    }
+}
+
+/// Result of a restore, the state of the application, and the IDs of the peers.
+type Synced = (
+    Result<(Snapshot, AppHash), Error>,
+    Arc<Mutex<AppState>>,
+    Vec<node::Id>,
+);
+
+/// Result of a client restoring the application from peers serving the given snapshots,
+/// the state of the application, and the IDs of the peers.
+fn sync<A: AppHashSource>(peers: Vec<(Vec<Snapshot>, bool)>, app_hashes: A) -> Synced {
+    let app = SnapshotApp::default();
+    let state = app.0.clone();
+    let server = ServerBuilder::default().bind("127.0.0.1:0", app).unwrap();
+    let server_addr = server.local_addr();
+    thread::spawn(move || server.listen().map_err(|e| e.to_string()));
+    let mut abci = ClientBuilder::default().connect(server_addr).unwrap();
+
+    let network = MemoryNetwork::new();
+    let (client_endpoint, _, _) = bind(&network);
+    let mut client = StateSync::new(&config(), app_hashes);
+
+    let mut ids = Vec::new();
+    let mut servers = Vec::new();
+    for (snapshots, corrupt) in peers {
+        let (endpoint, mut incoming, id) = bind(&network);
+        let addr = Endpoint::<SocketAddr>::listen_addrs(&endpoint)[0];
+        servers.push(thread::spawn(move || {
+            let conn = incoming.next().unwrap().unwrap();
+            serve(&conn, &snapshots, corrupt);
+            drop(endpoint);
+        }));
+        let conn = client_endpoint
+            .connect(ConnectInfo { addrs: addr, id })
+            .unwrap();
+        client.add_peer(id, conn).unwrap();
+        ids.push(id);
+    }
+
+    let result = client.sync(&mut abci);
+    // Dropping the client closes the connections to the peers.
+    drop(client);
+    for server in servers {
+        server.join().unwrap();
+    }
+    (result, state, ids)
+}
+
+#[test]
+fn test_statesync_restores_latest_snapshot() {
+    init();
+    let app_hashes = AppHashes(
+        [
+            (Height::from(5_u32), app_hash()),
+            (Height::from(10_u32), app_hash()),
+        ]
+        .into(),
+    );
+    let (result, state, _) = sync(
+        vec![
+            (vec![snapshot(5), snapshot(10)], false),
+            (vec![snapshot(10)], false),
+        ],
+        app_hashes,
+    );
+
+    let (restored, hash) = result.unwrap();
+    assert_eq!(restored, snapshot(10));
+    assert_eq!(hash, app_hash());
+    let state = state.lock().unwrap();
+    assert_eq!(state.offered, vec![Height::from(10_u32)]);
+    assert_eq!(state.chunks.concat(), app_hash().as_bytes());
+}
+
+#[test]
+fn test_statesync_skips_snapshots_without_app_hash() {
+    init();
+    let app_hashes = AppHashes([(Height::from(5_u32), app_hash())].into());
+    let (result, state, _) = sync(vec![(vec![snapshot(5), snapshot(10)], false)], app_hashes);
+
+    assert_eq!(result.unwrap().0, snapshot(5));
+    assert_eq!(state.lock().unwrap().offered, vec![Height::from(5_u32)]);
+}
+
+#[test]
+fn test_statesync_refetches_chunks_of_rejected_senders() {
+    init();
+    let app_hashes = AppHashes([(Height::from(10_u32), app_hash())].into());
+    let (result, state, ids) = sync(
+        vec![(vec![snapshot(10)], true), (vec![snapshot(10)], false)],
+        app_hashes,
+    );
+
+    assert_eq!(result.unwrap().0, snapshot(10));
+    let state = state.lock().unwrap();
+    assert_eq!(state.chunks.concat(), app_hash().as_bytes());
+    assert!(!state.rejected.is_empty());
+    assert!(state
+        .rejected
+        .iter()
+        .all(|sender| *sender == ids[0].to_string()));
+}
+
+#[test]
+fn test_statesync_fails_without_snapshots() {
+    init();
+    let (result, state, _) = sync(
+        vec![(Vec::new(), false), (vec![snapshot(10)], false)],
+        AppHashes(HashMap::new()),
+    );
+
+    let err = result.unwrap_err();
+    assert!(matches!(err.detail(), ErrorDetail::NoSnapshots(_)), "{err}");
+    assert!(state.lock().unwrap().offered.is_empty());
+}
+
+#[test]
+fn test_statesync_fails_when_app_hashes_unavailable() {
+    init();
+    let (result, state, _) = sync(vec![(vec![snapshot(5), snapshot(10)], false)], Unreachable);
+
+    let err = result.unwrap_err();
+    assert!(
+        matches!(err.detail(), ErrorDetail::AppHashUnavailable(_)),
+        "{err}"
+    );
+    assert!(state.lock().unwrap().offered.is_empty());
+}
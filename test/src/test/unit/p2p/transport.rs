@@ -0,0 +1,297 @@
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+};
+
+use rand_core::OsRng;
+use tendermint::{node, public_key::PublicKey};
+use tendermint_p2p::{
+    secret_connection,
+    transport::{
+        memory::MemoryNetwork, tcp::TcpTransport, BindInfo, ConnectInfo, Connection, Direction,
+        Endpoint, StreamId, StreamSend, Transport,
+    },
+};
+
+use super::init;
+
+fn keypair() -> (ed25519_consensus::SigningKey, PublicKey, node::Id) {
+    let private_key = ed25519_consensus::SigningKey::new(OsRng);
+    let public_key =
+        PublicKey::from_raw_ed25519(private_key.verification_key().as_bytes()).unwrap();
+    let id = secret_connection::PublicKey::from(&private_key).peer_id();
+    (private_key, public_key, id)
+}
+
+fn bind_info(public_key: PublicKey) -> BindInfo<&'static str> {
+    BindInfo {
+        advertise_addrs: "127.0.0.1:0",
+        bind_addrs: "127.0.0.1:0",
+        public_key,
+    }
+}
+
+fn listen_addr<E: Endpoint<SocketAddr>>(endpoint: &E) -> SocketAddr {
+    endpoint.listen_addrs()[0]
+}
+
+/// Sends a message each way over the PEX stream of the given connections.
+fn exchange<C: Connection>(a: &C, b: &C, msg: &[u8])
+where
+    C::Error: std::fmt::Debug,
+{
+    let (mut a_read, a_send) = a.open_bidirectional(StreamId::Pex).unwrap();
+    let (mut b_read, b_send) = b.open_bidirectional(StreamId::Pex).unwrap();
+
+    a_send.send(msg).unwrap();
+    assert_eq!(b_read.next().unwrap().unwrap(), msg);
+    b_send.send(msg).unwrap();
+    assert_eq!(a_read.next().unwrap().unwrap(), msg);
+}
+
+#[test]
+fn test_tcp_connect() {
+    init();
+    let (key1, public_key1, id1) = keypair();
+    let (key2, public_key2, id2) = keypair();
+    let (endpoint1, _) = TcpTransport::new(key1)
+        .bind(bind_info(public_key1))
+        .unwrap();
+    let (endpoint2, mut incoming2) = TcpTransport::new(key2)
+        .bind(bind_info(public_key2))
+        .unwrap();
+    let addr2 = listen_addr(&endpoint2);
+
+    let accept = thread::spawn(move || incoming2.next().unwrap().unwrap());
+    let outgoing = endpoint1
+        .connect(ConnectInfo {
+            addrs: addr2,
+            id: id2,
+        })
+        .unwrap();
+    let incoming = accept.join().unwrap();
+
+    assert!(matches!(outgoing, Direction::Outgoing(_)));
+    assert!(incoming.is_incoming());
+    assert_eq!(outgoing.public_key(), public_key2);
+    assert_eq!(incoming.public_key(), public_key1);
+    assert_eq!(outgoing.inner().remote_id(), id2);
+    assert_eq!(incoming.inner().remote_id(), id1);
+    assert_eq!(outgoing.remote_addr(), addr2);
+    assert_eq!(outgoing.local_addr(), incoming.remote_addr());
+    assert_eq!(outgoing.advertised_addrs(), vec![addr2]);
+
+    exchange(&outgoing, &incoming, b"The Queen's Gambit");
+}
+
+#[test]
+fn test_tcp_connect_rejects_unexpected_peer() {
+    init();
+    let (key1, public_key1, _) = keypair();
+    let (key2, public_key2, _) = keypair();
+    let (_, _, other_id) = keypair();
+    let (endpoint1, _) = TcpTransport::new(key1)
+        .bind(bind_info(public_key1))
+        .unwrap();
+    let (endpoint2, mut incoming2) = TcpTransport::new(key2)
+        .bind(bind_info(public_key2))
+        .unwrap();
+    let addr2 = listen_addr(&endpoint2);
+
+    let accept = thread::spawn(move || incoming2.next().unwrap());
+    let result = endpoint1.connect(ConnectInfo {
+        addrs: addr2,
+        id: other_id,
+    });
+    accept.join().unwrap().unwrap();
+
+    let err = result.err().unwrap().to_string();
+    assert!(err.contains("expected remote peer"), "{err}");
+}
+
+#[test]
+fn test_tcp_connect_tries_next_address_after_failed_handshake() {
+    init();
+    let (key1, public_key1, _) = keypair();
+    let (key2, public_key2, id2) = keypair();
+    let (endpoint1, _) = TcpTransport::new(key1)
+        .bind(bind_info(public_key1))
+        .unwrap();
+    let (endpoint2, mut incoming2) = TcpTransport::new(key2)
+        .bind(bind_info(public_key2))
+        .unwrap();
+    let addr2 = listen_addr(&endpoint2);
+
+    // Accepts a connection and closes it before any handshake.
+    let closing = TcpListener::bind("127.0.0.1:0").unwrap();
+    let closing_addr = closing.local_addr().unwrap();
+    thread::spawn(move || drop(closing.accept()));
+
+    let accept = thread::spawn(move || incoming2.next().unwrap().unwrap());
+    let outgoing = endpoint1
+        .connect(ConnectInfo {
+            addrs: &[closing_addr, addr2][..],
+            id: id2,
+        })
+        .unwrap();
+    let incoming = accept.join().unwrap();
+
+    assert_eq!(outgoing.remote_addr(), addr2);
+    exchange(&outgoing, &incoming, b"Zugzwang");
+}
+
+#[test]
+fn test_tcp_incoming_not_held_up_by_slow_handshake() {
+    init();
+    let (key1, public_key1, _) = keypair();
+    let (key2, public_key2, id2) = keypair();
+    let (endpoint1, _) = TcpTransport::new(key1)
+        .bind(bind_info(public_key1))
+        .unwrap();
+    let (endpoint2, mut incoming2) = TcpTransport::new(key2)
+        .bind(bind_info(public_key2))
+        .unwrap();
+    let addr2 = listen_addr(&endpoint2);
+
+    // Connects without ever starting the handshake.
+    let _silent = TcpStream::connect(addr2).unwrap();
+
+    let accept = thread::spawn(move || incoming2.next().unwrap().unwrap());
+    let outgoing = endpoint1
+        .connect(ConnectInfo {
+            addrs: addr2,
+            id: id2,
+        })
+        .unwrap();
+    let incoming = accept.join().unwrap();
+
+    assert_eq!(outgoing.local_addr(), incoming.remote_addr());
+}
+
+#[test]
+fn test_bind_rejects_foreign_public_key() {
+    init();
+    let (key, _, _) = keypair();
+    let (_, other_public_key, _) = keypair();
+
+    assert!(TcpTransport::new(key.clone())
+        .bind(bind_info(other_public_key))
+        .is_err());
+    assert!(MemoryNetwork::new()
+        .transport(key)
+        .bind(bind_info(other_public_key))
+        .is_err());
+}
+
+#[test]
+fn test_tcp_incoming_ends_when_endpoint_dropped() {
+    init();
+    let (key, public_key, _) = keypair();
+    let (endpoint, mut incoming) = TcpTransport::new(key).bind(bind_info(public_key)).unwrap();
+
+    let accept = thread::spawn(move || incoming.next().is_none());
+    drop(endpoint);
+    assert!(accept.join().unwrap());
+}
+
+#[test]
+fn test_memory_connect() {
+    init();
+    let network = MemoryNetwork::new();
+    let (key1, public_key1, id1) = keypair();
+    let (key2, public_key2, id2) = keypair();
+    let (endpoint1, _) = network
+        .transport(key1)
+        .bind(bind_info(public_key1))
+        .unwrap();
+    let (endpoint2, mut incoming2) = network
+        .transport(key2)
+        .bind(bind_info(public_key2))
+        .unwrap();
+    let addr2 = listen_addr(&endpoint2);
+    assert_ne!(listen_addr(&endpoint1), addr2);
+
+    let accept = thread::spawn(move || incoming2.next().unwrap().unwrap());
+    let outgoing = endpoint1
+        .connect(ConnectInfo {
+            addrs: addr2,
+            id: id2,
+        })
+        .unwrap();
+    let incoming = accept.join().unwrap();
+
+    assert!(!outgoing.is_incoming());
+    assert!(incoming.is_incoming());
+    assert_eq!(outgoing.inner().remote_id(), id2);
+    assert_eq!(incoming.inner().remote_id(), id1);
+    assert_eq!(outgoing.remote_addr(), addr2);
+    assert_eq!(outgoing.local_addr(), incoming.remote_addr());
+
+    // Longer than a single `SecretConnection` frame.
+    let mut long_message = [0x5a; 4096];
+    long_message[4095] = 0xa5;
+    exchange(&outgoing, &incoming, &long_message);
+}
+
+#[test]
+fn test_memory_connect_refused() {
+    init();
+    let network = MemoryNetwork::new();
+    let (key, public_key, _) = keypair();
+    let (_, _, other_id) = keypair();
+    let (endpoint, _) = network.transport(key).bind(bind_info(public_key)).unwrap();
+
+    let addr: SocketAddr = "127.0.0.1:26656".parse().unwrap();
+    let result = endpoint.connect(ConnectInfo {
+        addrs: addr,
+        id: other_id,
+    });
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_memory_incoming_ends_when_endpoint_dropped() {
+    init();
+    let network = MemoryNetwork::new();
+    let (key, public_key, _) = keypair();
+    let (endpoint, mut incoming) = network.transport(key).bind(bind_info(public_key)).unwrap();
+
+    drop(endpoint);
+    assert!(incoming.next().is_none());
+}
+
+#[test]
+fn test_close_ends_streams() {
+    init();
+    let network = MemoryNetwork::new();
+    let (key1, public_key1, _) = keypair();
+    let (key2, public_key2, id2) = keypair();
+    let (endpoint1, _) = network
+        .transport(key1)
+        .bind(bind_info(public_key1))
+        .unwrap();
+    let (endpoint2, mut incoming2) = network
+        .transport(key2)
+        .bind(bind_info(public_key2))
+        .unwrap();
+    let addr2 = listen_addr(&endpoint2);
+
+    let accept = thread::spawn(move || incoming2.next().unwrap().unwrap());
+    let outgoing = endpoint1
+        .connect(ConnectInfo {
+            addrs: addr2,
+            id: id2,
+        })
+        .unwrap();
+    let incoming = accept.join().unwrap();
+
+    let (mut read, _) = incoming.open_bidirectional(StreamId::Pex).unwrap();
+    assert!(incoming.open_bidirectional(StreamId::Pex).is_err());
+    let (_, send) = outgoing.open_bidirectional(StreamId::Pex).unwrap();
+    send.send(b"bye").unwrap();
+    outgoing.close().unwrap();
+
+    assert_eq!(read.next().unwrap().unwrap(), b"bye");
+    assert!(read.next().is_none());
+    assert!(send.send(b"gone").is_err());
+}
@@ -29,7 +29,7 @@ pub const TENDERMINT_VERSIONS: &[TendermintVersion] = &[
     TendermintVersion {
         repo: "https://github.com/cometbft/cometbft",
         ident: "v0_38",
-        commitish: "v0.38.12",
+        commitish: "v0.38.17",
     },
 ];
 
@@ -40,6 +40,7 @@ const TYPE_TAG: &str = r#"#[serde(tag = "type", content = "value")]"#;
 /// Predefined custom attributes for field annotations
 const QUOTED: &str = r#"#[serde(with = "crate::serializers::from_str")]"#;
 const QUOTED_WITH_DEFAULT: &str = r#"#[serde(with = "crate::serializers::from_str", default)]"#;
+const QUOTED_OMIT_DEFAULT: &str = r#"#[serde(with = "crate::serializers::from_str", default, skip_serializing_if = "crate::serializers::is_default")]"#;
 const QUOTED_ALLOW_NULL: &str = r#"#[serde(with = "crate::serializers::from_str_allow_null")]"#;
 const DEFAULT: &str = r#"#[serde(default)]"#;
 const HEXSTRING: &str = r#"#[serde(with = "crate::serializers::bytes::hexstring")]"#;
@@ -51,12 +52,14 @@ const SKIP_SERIALIZING: &str = "#[serde(skip_serializing)]";
 const RENAME_ALL_PASCALCASE: &str = r#"#[serde(rename_all = "PascalCase")]"#;
 const NULLABLEVECARRAY: &str = r#"#[serde(with = "crate::serializers::txs")]"#;
 const NULLABLE: &str = r#"#[serde(with = "crate::serializers::nullable")]"#;
+const ALLOW_NULL: &str = r#"#[serde(with = "crate::serializers::allow_null")]"#;
 const ALIAS_POWER_QUOTED: &str =
     r#"#[serde(alias = "power", with = "crate::serializers::from_str")]"#;
 const PART_SET_HEADER_TOTAL: &str =
     r#"#[serde(with = "crate::serializers::part_set_header_total")]"#;
 const RENAME_EDPUBKEY: &str = r#"#[serde(rename = "tendermint/PubKeyEd25519", with = "crate::serializers::bytes::base64string")]"#;
 const RENAME_SECPPUBKEY: &str = r#"#[serde(rename = "tendermint/PubKeySecp256k1", with = "crate::serializers::bytes::base64string")]"#;
+const RENAME_BLSPUBKEY: &str = r#"#[serde(rename = "tendermint/PubKeyBls12_381", with = "crate::serializers::bytes::base64string")]"#;
 const RENAME_SRPUBKEY: &str = r#"#[serde(rename = "tendermint/PubKeySr25519", with = "crate::serializers::bytes::base64string")]"#;
 const RENAME_DUPLICATEVOTE: &str = r#"#[serde(rename = "tendermint/DuplicateVoteEvidence")]"#;
 const RENAME_LIGHTCLIENTATTACK: &str =
@@ -145,7 +148,7 @@ pub static CUSTOM_FIELD_ATTRIBUTES: &[(&str, &str)] = &[
         QUOTED_WITH_DEFAULT,
     ),
     (".tendermint.version.Consensus.block", QUOTED),
-    (".tendermint.version.Consensus.app", QUOTED_WITH_DEFAULT),
+    (".tendermint.version.Consensus.app", QUOTED_OMIT_DEFAULT),
     (".tendermint.abci.ResponseInfo.data", DEFAULT),
     (".tendermint.abci.ResponseInfo.version", DEFAULT),
     (
@@ -180,9 +183,9 @@ pub static CUSTOM_FIELD_ATTRIBUTES: &[(&str, &str)] = &[
     (".tendermint.types.Header.evidence_hash", HEXSTRING),
     (".tendermint.types.Header.proposer_address", HEXSTRING),
     (".tendermint.types.Data.txs", NULLABLEVECARRAY),
-    (".tendermint.types.EvidenceList.evidence", NULLABLE),
+    (".tendermint.types.EvidenceList.evidence", ALLOW_NULL),
     (".tendermint.types.Commit.height", QUOTED),
-    (".tendermint.types.Commit.signatures", NULLABLE),
+    (".tendermint.types.Commit.signatures", ALLOW_NULL),
     (".tendermint.types.CommitSig.validator_address", HEXSTRING),
     (".tendermint.types.CommitSig.timestamp", OPTIONAL),
     (".tendermint.types.CommitSig.signature", BASE64STRING),
@@ -274,6 +277,7 @@ pub static CUSTOM_FIELD_ATTRIBUTES_V_037: &[(&str, &str)] = &[
 ];
 
 pub static CUSTOM_FIELD_ATTRIBUTES_V_038: &[(&str, &str)] = &[
+    (".tendermint.crypto.PublicKey.sum.bls12381", RENAME_BLSPUBKEY),
     (
         ".tendermint.types.DuplicateVoteEvidence.total_voting_power",
         ALIAS_TOTAL_VOTING_POWER_QUOTED,